@@ -1,109 +1,61 @@
 use {
     crate::Poetry,
     koto::runtime::{
-        external_error, get_external_instance, is_external_instance, make_external_value,
-        type_as_string, visit_external_value, ExternalValue, Value, ValueIterator,
+        external_error, koto_methods, visit_external_value, KotoObject, Value, ValueIterator,
         ValueIteratorOutput, ValueMap,
     },
-    std::fmt,
 };
 
 pub fn make_module() -> ValueMap {
-    use Value::{Map, Str};
-
     let mut result = ValueMap::new();
 
-    result.add_fn("new", {
-        |vm, args| match vm.get_args(args) {
-            [Str(text)] => {
-                let mut poetry = Poetry::default();
-                poetry.add_links(text);
-                Ok(Map(KotoPoetry::make_value_map(poetry)))
-            }
-            [unexpected] => external_error!(
-                "poetry.new: Expected a String as argument, found '{}'",
-                type_as_string(&unexpected),
-            ),
-            _ => external_error!("poetry.new: Expected a String as argument"),
-        }
+    result.add_typed_fn("new", |text: String| {
+        let mut poetry = Poetry::default();
+        poetry.add_links(&text);
+        KotoPoetry::make_value_map(poetry)
     });
 
     result
 }
 
-#[derive(Debug)]
+#[derive(Debug, KotoObject)]
+#[koto(runtime = "koto::runtime", type_name = "Poetry")]
 pub struct KotoPoetry(Poetry);
 
+#[koto_methods(runtime = "koto::runtime")]
 impl KotoPoetry {
     fn make_value_map(poetry: Poetry) -> ValueMap {
-        use Value::*;
-
-        let mut result = ValueMap::default();
-
-        result.add_instance_fn("add_links", |vm, args| {
-            let args = vm.get_args(args);
-            get_external_instance!(args, "poetry", "next_word", Self, poetry, {
-                match args {
-                    [_, Str(text)] => {
-                        poetry.0.add_links(text);
-                        Ok(Empty)
-                    }
-                    _ => external_error!("poetry.add_links: Expected a String as argument"),
-                }
-            })
-        });
+        let mut result = Self(poetry).into_value_map();
 
+        // The iterator needs access to the Poetry map, so it's added manually
         result.add_instance_fn("iter", |vm, args| match vm.get_args(args) {
-            [Map(poetry_map)] => {
-                if is_external_instance::<KotoPoetry>(poetry_map) {
-                    let poetry_map = poetry_map.clone();
-
-                    let iter = move || match visit_external_value(
-                        &poetry_map,
-                        |poetry: &mut KotoPoetry| {
-                            let result = poetry
-                                .0
-                                .next_word()
-                                .map_or(Empty, |word| Str(word.as_ref().into()));
-                            Ok(result)
-                        },
-                    ) {
-                        Ok(result) => Some(Ok(ValueIteratorOutput::Value(result))),
-                        Err(error) => Some(Err(error)),
-                    };
+            [Value::Map(poetry_map)] => {
+                let poetry_map = poetry_map.clone();
+
+                let iter = move || match visit_external_value(
+                    &poetry_map,
+                    |poetry: &mut KotoPoetry| Ok(poetry.next_word()),
+                ) {
+                    Ok(result) => Some(Ok(ValueIteratorOutput::Value(result))),
+                    Err(error) => Some(Err(error)),
+                };
 
-                    Ok(Iterator(ValueIterator::make_external(iter)))
-                } else {
-                    external_error!("poetry.iter: Missing Poetry instance")
-                }
+                Ok(Value::Iterator(ValueIterator::make_external(iter)))
             }
             _ => external_error!("poetry.iter: Expected Poetry instance as argument"),
         });
 
-        result.add_instance_fn("next_word", |vm, args| {
-            let args = vm.get_args(args);
-            get_external_instance!(args, "poetry", "next_word", Self, poetry, {
-                let result = match poetry.0.next_word() {
-                    Some(word) => Str(word.as_ref().into()),
-                    None => Empty,
-                };
-                Ok(result)
-            })
-        });
-
-        result.insert(Value::ExternalDataId, make_external_value(Self(poetry)));
         result
     }
-}
 
-impl ExternalValue for KotoPoetry {
-    fn value_type(&self) -> String {
-        "Poetry".to_string()
+    fn add_links(&mut self, text: String) {
+        self.0.add_links(&text);
     }
-}
 
-impl fmt::Display for KotoPoetry {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Poetry")
+    fn next_word(&mut self) -> Value {
+        match self.0.next_word() {
+            Some(word) => Value::Str(word.as_ref().into()),
+            None => Value::Empty,
+        }
     }
 }
@@ -16,7 +16,7 @@ fn run_script(script: &str, path: Option<PathBuf>, should_fail_at_runtime: bool)
     prelude.add_map("tempfile", koto_tempfile::make_module());
     prelude.add_map("toml", koto_toml::make_module());
 
-    match koto.compile(&script) {
+    match koto.compile(script) {
         Ok(_) => match koto.run() {
             Ok(_) => {
                 if should_fail_at_runtime {
@@ -6,6 +6,8 @@ use {
     toml::Value as Toml,
 };
 
+// Deserializing via toml::Value rather than koto_serialize::DeserializableValue allows datetimes
+// to be converted into strings, rather than toml's internal datetime representation.
 fn toml_to_koto_value(value: &Toml) -> Result<Value, String> {
     let result = match value {
         Toml::Boolean(b) => Value::Bool(*b),
@@ -15,7 +17,7 @@ fn toml_to_koto_value(value: &Toml) -> Result<Value, String> {
         Toml::Array(a) => {
             match a
                 .iter()
-                .map(|entry| toml_to_koto_value(entry))
+                .map(toml_to_koto_value)
                 .collect::<Result<ValueVec, String>>()
             {
                 Ok(result) => Value::List(ValueList::with_data(result)),
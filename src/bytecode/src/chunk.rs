@@ -1,13 +1,34 @@
 use {
     crate::InstructionReader,
-    koto_parser::{ConstantPool, Span},
-    std::{path::PathBuf, sync::Arc},
+    koto_parser::{ConstantIndex, ConstantPool, Span},
+    std::{ops::Range, path::PathBuf, sync::Arc},
 };
 
+/// Debug information for a frame in a Koto program, e.g. a function body
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameDebugInfo {
+    /// The range of instructions that make up the frame
+    pub ip_range: Range<usize>,
+    /// The span of the frame's definition in the source, e.g. a function's span
+    pub span: Span,
+    /// The name of the frame's function, as an id in the chunk's constant pool
+    ///
+    /// Functions are named after the id they're assigned to, e.g. `foo = |x| x * x`,
+    /// or the key they're given in a map. Anonymous functions and the chunk's top-level frame
+    /// don't have a name.
+    pub function_name: Option<ConstantIndex>,
+    /// The frame's local values, as (register, id) pairs
+    ///
+    /// The registers are relative to the frame's register base, and the ids refer to strings in
+    /// the chunk's constant pool.
+    pub locals: Vec<(u16, ConstantIndex)>,
+}
+
 /// Debug information for a Koto program
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct DebugInfo {
-    source_map: Vec<(usize, Span)>,
+    pub(crate) source_map: Vec<(usize, Span)>,
+    pub(crate) frames: Vec<FrameDebugInfo>,
     /// The source of the program that the debug info was derived from
     pub source: String,
 }
@@ -27,18 +48,39 @@ impl DebugInfo {
 
     /// Returns a source span for a given instruction pointer
     pub fn get_source_span(&self, ip: usize) -> Option<Span> {
+        self.get_source_span_and_range(ip).map(|(span, _)| span)
+    }
+
+    /// Returns a source span for a given instruction pointer, along with the range of
+    /// instructions that the span applies to
+    pub fn get_source_span_and_range(&self, ip: usize) -> Option<(Span, Range<usize>)> {
         // Find the last entry with an ip less than or equal to the input
-        // an upper_bound would nice here, but this isn't currently a performance sensitive function
-        // so a scan through the entries will do.
-        let mut result = None;
-        for entry in self.source_map.iter() {
-            if entry.0 <= ip {
-                result = Some(entry.1);
-            } else {
-                break;
-            }
-        }
-        result
+        let index = self
+            .source_map
+            .partition_point(|(entry_ip, _)| *entry_ip <= ip)
+            .checked_sub(1)?;
+        let (start, span) = self.source_map[index];
+        let end = self
+            .source_map
+            .get(index + 1)
+            .map_or(usize::MAX, |(next_ip, _)| *next_ip);
+        Some((span, start..end))
+    }
+
+    /// Returns the entries in the source map as (ip, span) pairs, sorted by ip
+    ///
+    /// Each entry's span applies to the instructions up until the next entry's ip.
+    pub fn source_map(&self) -> &[(usize, Span)] {
+        &self.source_map
+    }
+
+    /// Returns the debug info for the innermost frame containing the given instruction pointer
+    pub fn get_frame_info(&self, ip: usize) -> Option<&FrameDebugInfo> {
+        // Frames are added when they're finished being compiled, so nested frames appear before
+        // the frames that contain them.
+        self.frames
+            .iter()
+            .find(|frame| frame.ip_range.contains(&ip))
     }
 }
 
@@ -132,7 +174,7 @@ pub fn chunk_to_string_annotated(chunk: Arc<Chunk>, source_lines: &[&str]) -> St
                 .line
                 .min(source_lines.len() as u32)
                 .max(1) as usize;
-            result += &format!("|{}| {}\n", line.to_string(), source_lines[line - 1]);
+            result += &format!("|{}| {}\n", line, source_lines[line - 1]);
             span = Some(instruction_span);
         }
 
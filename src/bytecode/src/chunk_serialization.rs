@@ -0,0 +1,383 @@
+use {
+    crate::{Chunk, DebugInfo, FrameDebugInfo, Instruction, InstructionReader},
+    koto_parser::{Constant, ConstantIndex, ConstantPool, ConstantPoolBuilder, Position, Span},
+    std::{convert::TryInto, path::PathBuf, sync::Arc},
+};
+
+// The header that starts every serialized chunk
+const MAGIC: &[u8; 4] = b"KOTO";
+
+// The version of the serialization format
+//
+// This should be incremented whenever the layout of serialized chunks or the bytecode changes.
+// The crate version is also included in the header, but it only changes between releases, so
+// bytecode changes made in between releases need to be covered by the format version.
+const FORMAT_VERSION: u32 = 6;
+
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const CONSTANT_F64: u8 = 0;
+const CONSTANT_I64: u8 = 1;
+const CONSTANT_STR: u8 = 2;
+
+impl Chunk {
+    /// Serializes the chunk into a binary format that can be loaded with [Chunk::from_bytes]
+    ///
+    /// The serialized data starts with a version header, chunks can only be loaded by the same
+    /// version of Koto that produced them.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ChunkWriter::default();
+
+        writer.write_raw(MAGIC);
+        writer.write_u32(FORMAT_VERSION);
+        writer.write_str(CRATE_VERSION);
+
+        match &self.source_path {
+            Some(path) => {
+                writer.write_u8(1);
+                writer.write_str(&path.to_string_lossy());
+            }
+            None => writer.write_u8(0),
+        }
+
+        writer.write_bytes(&self.bytes);
+
+        writer.write_len(self.constants.len());
+        for constant in self.constants.iter() {
+            match constant {
+                Constant::F64(n) => {
+                    writer.write_u8(CONSTANT_F64);
+                    writer.write_u64(n.to_bits());
+                }
+                Constant::I64(n) => {
+                    writer.write_u8(CONSTANT_I64);
+                    writer.write_u64(n as u64);
+                }
+                Constant::Str(s) => {
+                    writer.write_u8(CONSTANT_STR);
+                    writer.write_str(s);
+                }
+            }
+        }
+
+        writer.write_len(self.debug_info.source_map.len());
+        for (ip, span) in self.debug_info.source_map.iter() {
+            writer.write_len(*ip);
+            writer.write_span(span);
+        }
+        writer.write_len(self.debug_info.frames.len());
+        for frame in self.debug_info.frames.iter() {
+            writer.write_len(frame.ip_range.start);
+            writer.write_len(frame.ip_range.end);
+            writer.write_span(&frame.span);
+            match frame.function_name {
+                Some(id) => {
+                    writer.write_u8(1);
+                    writer.write_u32(id);
+                }
+                None => writer.write_u8(0),
+            }
+            writer.write_len(frame.locals.len());
+            for (register, id) in frame.locals.iter() {
+                writer.write_u16(*register);
+                writer.write_u32(*id);
+            }
+        }
+        writer.write_str(&self.debug_info.source);
+
+        writer.bytes
+    }
+
+    /// Deserializes a chunk that was produced by [Chunk::to_bytes]
+    ///
+    /// The bytecode is checked before being returned, so that instructions with invalid
+    /// constants or jump targets are reported as errors rather than causing the runtime to panic.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = ChunkReader { bytes, position: 0 };
+
+        if reader.read_raw(MAGIC.len())? != MAGIC {
+            return Err("Missing Koto bytecode header".into());
+        }
+
+        let format_version = reader.read_u32()?;
+        let crate_version = reader.read_str()?;
+        if format_version != FORMAT_VERSION || crate_version != CRATE_VERSION {
+            return Err(format!(
+                "Incompatible bytecode version (compiled with Koto {}, expected {})",
+                crate_version, CRATE_VERSION
+            ));
+        }
+
+        let source_path = match reader.read_u8()? {
+            0 => None,
+            1 => Some(PathBuf::from(reader.read_str()?)),
+            other => return Err(format!("Invalid source path marker ({})", other)),
+        };
+
+        let chunk_bytes = reader.read_bytes()?.to_vec();
+
+        let constant_count = reader.read_len()?;
+        let mut constants = ConstantPoolBuilder::new();
+        for _ in 0..constant_count {
+            match reader.read_u8()? {
+                CONSTANT_F64 => constants.add_f64(f64::from_bits(reader.read_u64()?)),
+                CONSTANT_I64 => constants.add_i64(reader.read_u64()? as i64),
+                CONSTANT_STR => constants.add_string(reader.read_str()?),
+                other => return Err(format!("Invalid constant type ({})", other)),
+            };
+        }
+        let constants = constants.build();
+        if constants.len() != constant_count {
+            return Err("Duplicate entries found in constant pool".into());
+        }
+
+        let mut debug_info = DebugInfo::default();
+        let source_map_len = reader.read_len()?;
+        for _ in 0..source_map_len {
+            let ip = reader.read_len()?;
+            let span = reader.read_span()?;
+            debug_info.source_map.push((ip, span));
+        }
+        let frame_count = reader.read_len()?;
+        for _ in 0..frame_count {
+            let ip_range = reader.read_len()?..reader.read_len()?;
+            let span = reader.read_span()?;
+            let function_name = match reader.read_u8()? {
+                0 => None,
+                1 => Some(reader.read_u32()?),
+                other => return Err(format!("Invalid function name marker ({})", other)),
+            };
+            let local_count = reader.read_len()?;
+            let mut locals = Vec::with_capacity(local_count.min(u16::MAX as usize + 1));
+            for _ in 0..local_count {
+                locals.push((reader.read_u16()?, reader.read_u32()?));
+            }
+            debug_info.frames.push(FrameDebugInfo {
+                ip_range,
+                span,
+                function_name,
+                locals,
+            });
+        }
+        debug_info.source = reader.read_str()?.to_string();
+
+        if reader.position != bytes.len() {
+            return Err("Unexpected data at the end of the bytecode".into());
+        }
+
+        let chunk = Arc::new(Chunk::new(chunk_bytes, constants, source_path, debug_info));
+        validate_chunk(chunk.clone())?;
+
+        Ok(Arc::try_unwrap(chunk).unwrap_or_else(|chunk| (*chunk).clone()))
+    }
+}
+
+// Checks that the chunk's instructions can be decoded, and that their operands are valid
+fn validate_chunk(chunk: Arc<Chunk>) -> Result<(), String> {
+    use Instruction::*;
+
+    let constants = &chunk.constants;
+    let bytes_len = chunk.bytes.len();
+
+    for frame in chunk.debug_info.frames.iter() {
+        if let Some(id) = frame.function_name {
+            check_string_constant(constants, id)?;
+        }
+        for (_, id) in frame.locals.iter() {
+            check_string_constant(constants, *id)?;
+        }
+    }
+
+    let mut reader = InstructionReader::new(chunk.clone());
+    let mut ip = reader.ip;
+
+    while let Some(instruction) = reader.next() {
+        let next_ip = reader.ip;
+
+        let check_jump = |offset: usize| match next_ip.checked_add(offset) {
+            Some(target) if target <= bytes_len => Ok(()),
+            _ => Err(format!(
+                "Invalid jump offset ({}) at position {}",
+                offset, ip
+            )),
+        };
+        let check_jump_back = |offset: usize| {
+            if offset <= next_ip {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Invalid jump offset ({}) at position {}",
+                    offset, ip
+                ))
+            }
+        };
+
+        match instruction {
+            Error { message } => return Err(message),
+            LoadFloat { constant, .. } => match constants.get(constant) {
+                Some(Constant::F64(_)) => {}
+                _ => return Err(format!("Invalid float constant ({})", constant)),
+            },
+            LoadInt { constant, .. } => match constants.get(constant) {
+                Some(Constant::I64(_)) => {}
+                _ => return Err(format!("Invalid int constant ({})", constant)),
+            },
+            LoadString { constant, .. }
+            | LoadGlobal { constant, .. }
+            | Import { constant, .. }
+            | Debug { constant, .. } => check_string_constant(constants, constant)?,
+            SetGlobal { global, .. } => check_string_constant(constants, global)?,
+            MapInsert { key, .. } | Access { key, .. } => check_string_constant(constants, key)?,
+            Function {
+                arg_count,
+                arg_names_ip,
+                size,
+                ..
+            } => {
+                for arg in 0..arg_count as usize {
+                    let arg_ip = arg_names_ip + arg * 4;
+                    let id =
+                        u32::from_le_bytes(chunk.bytes[arg_ip..arg_ip + 4].try_into().unwrap());
+                    check_string_constant(constants, id)?;
+                }
+                check_jump(size)?;
+            }
+            Jump { offset } | JumpIf { offset, .. } => check_jump(offset)?,
+            JumpIfArgProvided { register, offset } => {
+                // The runtime tracks omitted args with a 64 bit mask
+                if register >= 64 {
+                    return Err(format!(
+                        "Invalid argument register ({}) at position {}",
+                        register, ip
+                    ));
+                }
+                check_jump(offset)?;
+            }
+            JumpBack { offset } | JumpBackIf { offset, .. } => check_jump_back(offset)?,
+            IterNext { jump_offset, .. }
+            | IterNextTemp { jump_offset, .. }
+            | IterNextQuiet { jump_offset, .. } => check_jump(jump_offset)?,
+            TryStart { catch_offset, .. } => check_jump(catch_offset)?,
+            _ => {}
+        }
+
+        ip = next_ip;
+    }
+
+    Ok(())
+}
+
+fn check_string_constant(constants: &ConstantPool, id: ConstantIndex) -> Result<(), String> {
+    match constants.get(id) {
+        Some(Constant::Str(_)) => Ok(()),
+        _ => Err(format!("Invalid string constant ({})", id)),
+    }
+}
+
+#[derive(Default)]
+struct ChunkWriter {
+    bytes: Vec<u8>,
+}
+
+impl ChunkWriter {
+    fn write_raw(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn write_u8(&mut self, n: u8) {
+        self.bytes.push(n);
+    }
+
+    fn write_u16(&mut self, n: u16) {
+        self.write_raw(&n.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.write_raw(&n.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.write_raw(&n.to_le_bytes());
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write_u64(len as u64);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_len(bytes.len());
+        self.write_raw(bytes);
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    fn write_span(&mut self, span: &Span) {
+        self.write_u32(span.start.line);
+        self.write_u32(span.start.column);
+        self.write_u32(span.end.line);
+        self.write_u32(span.end.column);
+    }
+}
+
+struct ChunkReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ChunkReader<'a> {
+    fn read_raw(&mut self, len: usize) -> Result<&'a [u8], String> {
+        match self.position.checked_add(len) {
+            Some(end) if end <= self.bytes.len() => {
+                let result = &self.bytes[self.position..end];
+                self.position = end;
+                Ok(result)
+            }
+            _ => Err("Unexpected end of bytecode".into()),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.read_raw(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.read_raw(2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.read_raw(4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.read_raw(8)?.try_into().unwrap()))
+    }
+
+    fn read_len(&mut self) -> Result<usize, String> {
+        let len = self.read_u64()?;
+        len.try_into()
+            .map_err(|_| format!("Invalid length in bytecode ({})", len))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.read_len()?;
+        self.read_raw(len)
+    }
+
+    fn read_str(&mut self) -> Result<&'a str, String> {
+        std::str::from_utf8(self.read_bytes()?).map_err(|e| format!("Invalid string: {}", e))
+    }
+
+    fn read_span(&mut self) -> Result<Span, String> {
+        let start = Position {
+            line: self.read_u32()?,
+            column: self.read_u32()?,
+        };
+        let end = Position {
+            line: self.read_u32()?,
+            column: self.read_u32()?,
+        };
+        Ok(Span { start, end })
+    }
+}
@@ -1,8 +1,9 @@
 use {
-    crate::{DebugInfo, FunctionFlags, Op, TypeId},
+    crate::{DebugInfo, FrameDebugInfo, FunctionFlags, Op, TypeId},
     koto_parser::{
         AssignOp, AssignTarget, Ast, AstFor, AstIf, AstIndex, AstNode, AstOp, AstTry,
-        ConstantIndex, Function, LookupNode, MatchArm, Node, Scope, Span, SwitchArm,
+        ConstantIndex, Function, LookupNode, MapKey, MatchArm, Node, Scope, Span, StringNode,
+        SwitchArm,
     },
     smallvec::SmallVec,
     std::{convert::TryFrom, error, fmt},
@@ -78,21 +79,25 @@ enum LocalRegister {
 #[derive(Clone, Debug, Default)]
 struct Frame {
     loop_stack: Vec<Loop>,
-    register_stack: Vec<u8>,
+    register_stack: Vec<u16>,
     local_registers: Vec<LocalRegister>,
-    temporary_base: u8,
-    temporary_count: u8,
+    temporary_base: u16,
+    temporary_count: u16,
     last_op: Option<Op>, // used to decide if an additional return instruction is needed
+    // True when calls in tail position can be compiled as tail calls, see Op::TailCall
+    allow_tail_calls: bool,
+    // Set when the next node to be compiled is in tail position, see Compiler::compile_tail_node
+    tail_position: bool,
 }
 
 impl Frame {
-    fn new(local_count: u8, args: &[Arg], captures: &[ConstantIndex]) -> Self {
+    fn new(local_count: u16, args: &[Arg], captures: &[ConstantIndex]) -> Self {
         let temporary_base = local_count
-            + captures.len() as u8
+            + captures.len() as u16
             + args
                 .iter()
                 .filter(|arg| matches!(arg, Arg::Placeholder))
-                .count() as u8;
+                .count() as u16;
 
         // First, assign registers to the 'top-level' args, including placeholder registers
         let mut local_registers = Vec::with_capacity(args.len() + captures.len());
@@ -119,11 +124,11 @@ impl Frame {
         }
     }
 
-    fn push_register(&mut self) -> Result<u8, String> {
+    fn push_register(&mut self) -> Result<u16, String> {
         let new_register = self.temporary_base + self.temporary_count;
         self.temporary_count += 1;
 
-        if new_register == u8::MAX {
+        if new_register == u16::MAX {
             Err("Reached maximum number of registers".to_string())
         } else {
             self.register_stack.push(new_register);
@@ -131,7 +136,7 @@ impl Frame {
         }
     }
 
-    fn get_local_register(&self, index: ConstantIndex) -> Option<u8> {
+    fn get_local_register(&self, index: ConstantIndex) -> Option<u16> {
         self.local_registers
             .iter()
             .position(|local_register| {
@@ -142,10 +147,10 @@ impl Frame {
                 };
                 *register_index == index
             })
-            .map(|position| position as u8)
+            .map(|position| position as u16)
     }
 
-    fn get_local_assigned_register(&self, index: ConstantIndex) -> Option<u8> {
+    fn get_local_assigned_register(&self, index: ConstantIndex) -> Option<u16> {
         self.local_registers
             .iter()
             .position(|local_register| {
@@ -153,10 +158,10 @@ impl Frame {
                     LocalRegister::Assigned(assigned_index) if *assigned_index == index
                 )
             })
-            .map(|position| position as u8)
+            .map(|position| position as u16)
     }
 
-    fn get_local_reserved_register(&self, index: ConstantIndex) -> Option<u8> {
+    fn get_local_reserved_register(&self, index: ConstantIndex) -> Option<u16> {
         self.local_registers
             .iter()
             .position(|local_register| {
@@ -164,10 +169,10 @@ impl Frame {
                     LocalRegister::Reserved(assigned_index, _) if *assigned_index == index
                 )
             })
-            .map(|position| position as u8)
+            .map(|position| position as u16)
     }
 
-    fn reserve_local_register(&mut self, local: ConstantIndex) -> Result<u8, String> {
+    fn reserve_local_register(&mut self, local: ConstantIndex) -> Result<u16, String> {
         match self.get_local_assigned_register(local) {
             Some(assigned) => Ok(assigned),
             None => {
@@ -180,14 +185,14 @@ impl Frame {
                     return Err("reserve_local_register: Locals overflowed".to_string());
                 }
 
-                Ok(new_local_register as u8)
+                Ok(new_local_register as u16)
             }
         }
     }
 
     fn defer_op_until_register_is_committed(
         &mut self,
-        reserved_register: u8,
+        reserved_register: u16,
         bytes: Vec<u8>,
     ) -> Result<(), String> {
         match self.local_registers.get_mut(reserved_register as usize) {
@@ -202,7 +207,7 @@ impl Frame {
         }
     }
 
-    fn commit_local_register(&mut self, local_register: u8) -> Result<Vec<u8>, String> {
+    fn commit_local_register(&mut self, local_register: u16) -> Result<Vec<u8>, String> {
         let local_register = local_register as usize;
         let (index, deferred_ops) = match self.local_registers.get(local_register) {
             Some(LocalRegister::Assigned(_)) => {
@@ -221,7 +226,7 @@ impl Frame {
         Ok(deferred_ops)
     }
 
-    fn assign_local_register(&mut self, local: ConstantIndex) -> Result<u8, String> {
+    fn assign_local_register(&mut self, local: ConstantIndex) -> Result<u16, String> {
         let local_register = match self.get_local_assigned_register(local) {
             Some(assigned) => assigned,
             None => match self.get_local_reserved_register(local) {
@@ -244,7 +249,7 @@ impl Frame {
                         return Err("assign_local_register: Locals overflowed".to_string());
                     }
 
-                    new_local_register as u8
+                    new_local_register as u16
                 }
             },
         };
@@ -252,7 +257,7 @@ impl Frame {
         Ok(local_register)
     }
 
-    fn pop_register(&mut self) -> Result<u8, String> {
+    fn pop_register(&mut self) -> Result<u16, String> {
         let register = match self.register_stack.pop() {
             Some(register) => register,
             None => {
@@ -271,7 +276,7 @@ impl Frame {
         Ok(register)
     }
 
-    fn peek_register(&self, n: usize) -> Result<u8, String> {
+    fn peek_register(&self, n: usize) -> Result<u16, String> {
         self.register_stack
             .get(self.register_stack.len() - n - 1)
             .cloned()
@@ -286,12 +291,12 @@ impl Frame {
         Ok(())
     }
 
-    fn next_temporary_register(&self) -> u8 {
+    fn next_temporary_register(&self) -> u16 {
         self.temporary_count + self.temporary_base
     }
 
-    fn available_registers_count(&self) -> u8 {
-        u8::MAX - self.next_temporary_register()
+    fn available_registers_count(&self) -> u16 {
+        u16::MAX - self.next_temporary_register()
     }
 
     fn captures_for_nested_frame(
@@ -321,26 +326,26 @@ enum ResultRegister {
     // The result can be any temporary register, or an assigned register
     Any,
     // The result must be placed in the specified register
-    Fixed(u8),
+    Fixed(u16),
 }
 
 // While compiling a node, ResultRegister::Any might cause a temporary register to be allocated,
 // so the result register should be determined before other temporary registers are allocated.
 #[derive(Clone, Copy, Debug)]
 struct CompileResult {
-    register: u8,
+    register: u16,
     is_temporary: bool,
 }
 
 impl CompileResult {
-    fn with_assigned(register: u8) -> Self {
+    fn with_assigned(register: u16) -> Self {
         Self {
             register,
             is_temporary: false,
         }
     }
 
-    fn with_temporary(register: u8) -> Self {
+    fn with_temporary(register: u16) -> Self {
         Self {
             register,
             is_temporary: true,
@@ -350,11 +355,34 @@ impl CompileResult {
 
 type CompileNodeResult = Result<Option<CompileResult>, CompilerError>;
 
+// Returns true if the op is followed by a jump offset
+fn op_has_offset(op: Op) -> bool {
+    use Op::*;
+    matches!(
+        op,
+        Function
+            | Jump
+            | JumpTrue
+            | JumpFalse
+            | JumpBack
+            | JumpBackFalse
+            | JumpIfArgProvided
+            | IterNext
+            | IterNextTemp
+            | IterNextQuiet
+            | TryStart
+    )
+}
+
 /// The settings used by the [Compiler]
-#[derive(Default)]
+#[derive(Clone, Copy, Default)]
 pub struct CompilerSettings {
     /// Causes all top level identifiers to be exported to global
     pub repl_mode: bool,
+    /// Folds constant expressions and removes unreachable branches before compiling
+    ///
+    /// The AST is optimized by the [Loader](crate::Loader), see [fold_constants](crate::fold_constants).
+    pub fold_constants: bool,
 }
 
 /// The compiler used by the Koto language
@@ -365,6 +393,12 @@ pub struct Compiler {
     frame_stack: Vec<Frame>,
     span_stack: Vec<Span>,
     settings: CompilerSettings,
+    // The name of the function that's about to be compiled, taken from its assignment target
+    function_name: Option<ConstantIndex>,
+    // True when all jump offsets should be encoded with 4 bytes, see Op::Wide
+    wide_offsets: bool,
+    // Set when a jump offset didn't fit in 2 bytes
+    offset_overflow: bool,
 }
 
 impl Compiler {
@@ -372,8 +406,25 @@ impl Compiler {
         ast: &Ast,
         settings: CompilerSettings,
     ) -> Result<(Vec<u8>, DebugInfo), CompilerError> {
+        let mut compiler = Self::compile_with_offset_size(ast, settings, false)?;
+
+        // Jump offsets are encoded with 2 bytes by default, if one of them overflowed then the
+        // chunk is compiled again with wide offsets.
+        if compiler.offset_overflow {
+            compiler = Self::compile_with_offset_size(ast, settings, true)?;
+        }
+
+        Ok((compiler.bytes, compiler.debug_info))
+    }
+
+    fn compile_with_offset_size(
+        ast: &Ast,
+        settings: CompilerSettings,
+        wide_offsets: bool,
+    ) -> Result<Self, CompilerError> {
         let mut compiler = Compiler {
             settings,
+            wide_offsets,
             ..Default::default()
         };
 
@@ -381,7 +432,7 @@ impl Compiler {
             compiler.compile_node(ResultRegister::None, entry_point, ast)?;
         }
 
-        Ok((compiler.bytes, compiler.debug_info))
+        Ok(compiler)
     }
 
     fn compile_node(
@@ -394,6 +445,12 @@ impl Compiler {
 
         self.span_stack.push(*ast.span(node.span));
 
+        // The tail position flag is taken so that it doesn't get applied to the node's children
+        let tail_position = match self.frame_stack.last_mut() {
+            Some(frame) => std::mem::take(&mut frame.tail_position),
+            None => false,
+        };
+
         let result = match &node.node {
             Node::Empty => {
                 let result = self.get_result_register(result_register)?;
@@ -404,7 +461,7 @@ impl Compiler {
             }
             Node::Id(index) => self.compile_load_id(result_register, *index)?,
             Node::Lookup(lookup) => {
-                self.compile_lookup(result_register, lookup, None, None, ast)?
+                self.compile_lookup(result_register, lookup, None, None, ast, tail_position)?
             }
             Node::BoolTrue => {
                 let result = self.get_result_register(result_register)?;
@@ -455,10 +512,13 @@ impl Compiler {
                 }
                 result
             }
-            Node::Num2(elements) => self.compile_make_num2(result_register, &elements, ast)?,
-            Node::Num4(elements) => self.compile_make_num4(result_register, &elements, ast)?,
-            Node::List(elements) => self.compile_make_list(result_register, &elements, ast)?,
-            Node::Map(entries) => self.compile_make_map(result_register, &entries, ast)?,
+            Node::InterpolatedString(nodes) => {
+                self.compile_interpolated_string(result_register, nodes, ast)?
+            }
+            Node::Num2(elements) => self.compile_make_num2(result_register, elements, ast)?,
+            Node::Num4(elements) => self.compile_make_num4(result_register, elements, ast)?,
+            Node::List(elements) => self.compile_make_list(result_register, elements, ast)?,
+            Node::Map(entries) => self.compile_make_map(result_register, entries, ast)?,
             Node::Range {
                 start,
                 end,
@@ -542,10 +602,22 @@ impl Compiler {
                 result
             }
             Node::MainBlock { body, local_count } => {
-                self.compile_frame(*local_count as u8, body, &[], &[], ast, true)?;
+                let local_count = match u16::try_from(*local_count) {
+                    Ok(x) => x,
+                    Err(_) => {
+                        return compiler_error!(
+                            self,
+                            "Script has too many locals: {}",
+                            local_count
+                        );
+                    }
+                };
+                self.compile_frame(local_count, body, &[], &[], &[], ast, true)?;
                 None
             }
-            Node::Block(expressions) => self.compile_block(result_register, expressions, ast)?,
+            Node::Block(expressions) => {
+                self.compile_block(result_register, expressions, ast, tail_position)?
+            }
             Node::Tuple(elements) => {
                 self.compile_make_tuple(result_register, elements, false, ast)?
             }
@@ -560,7 +632,14 @@ impl Compiler {
                         if let Some(function_register) =
                             self.frame().get_local_assigned_register(*id)
                         {
-                            self.compile_call(result_register, function_register, args, None, ast)?
+                            self.compile_call(
+                                result_register,
+                                function_register,
+                                args,
+                                None,
+                                ast,
+                                tail_position,
+                            )?
                         } else {
                             let result = self.get_result_register(result_register)?;
                             let call_result_register = if let Some(result) = result {
@@ -578,6 +657,7 @@ impl Compiler {
                                 args,
                                 None,
                                 ast,
+                                tail_position,
                             )?;
 
                             self.pop_register()?; // function_register
@@ -586,10 +666,11 @@ impl Compiler {
                     }
                     Node::Lookup(function_lookup) => self.compile_lookup(
                         result_register,
-                        &function_lookup,
+                        function_lookup,
                         Some(&LookupNode::Call(args.clone())),
                         None,
                         ast,
+                        tail_position,
                     )?,
                     _ => {
                         return compiler_error!(self, "Call: unexpected node at index {}", function)
@@ -611,14 +692,17 @@ impl Compiler {
             Node::BinaryOp { op, lhs, rhs } => {
                 self.compile_binary_op(result_register, *op, *lhs, *rhs, ast)?
             }
-            Node::If(ast_if) => self.compile_if(result_register, ast_if, ast)?,
+            Node::If(ast_if) => self.compile_if(result_register, ast_if, ast, tail_position)?,
             Node::Match { expression, arms } => {
-                self.compile_match(result_register, *expression, arms, ast)?
+                self.compile_match(result_register, *expression, arms, ast, tail_position)?
             }
-            Node::Switch(arms) => self.compile_switch(result_register, arms, ast)?,
+            Node::Switch(arms) => self.compile_switch(result_register, arms, ast, tail_position)?,
             Node::Ellipsis(_) => {
                 return compiler_error!(self, "Ellipsis found outside of match patterns")
             }
+            Node::NamedArg { .. } => {
+                return compiler_error!(self, "Named argument found outside of call arguments")
+            }
             Node::Wildcard => None,
             Node::For(ast_for) => self.compile_for(result_register, ast_for, ast)?,
             Node::While { condition, body } => {
@@ -662,8 +746,14 @@ impl Compiler {
                 }
             },
             Node::ReturnExpression(expression) => {
+                let allow_tail_calls = self.frame().allow_tail_calls;
                 let expression_register = self
-                    .compile_node(ResultRegister::Any, ast.node(*expression), ast)?
+                    .compile_tail_node(
+                        ResultRegister::Any,
+                        ast.node(*expression),
+                        ast,
+                        allow_tail_calls,
+                    )?
                     .unwrap();
 
                 match result_register {
@@ -710,6 +800,23 @@ impl Compiler {
             Node::Try(try_expression) => {
                 self.compile_try_expression(result_register, try_expression, ast)?
             }
+            Node::Throw(expression) => {
+                // The throw never produces a value,
+                // but a result register is still needed when the throw is used as an expression.
+                let result = self.get_result_register(result_register)?;
+
+                let expression_register = self
+                    .compile_node(ResultRegister::Any, ast.node(*expression), ast)?
+                    .unwrap();
+
+                self.push_op(Throw, &[expression_register.register]);
+
+                if expression_register.is_temporary {
+                    self.pop_register()?;
+                }
+
+                result
+            }
             Node::Debug {
                 expression_string,
                 expression,
@@ -750,34 +857,74 @@ impl Compiler {
         Ok(result)
     }
 
+    // Compiles a node that's in tail position when `tail_position` is true
+    //
+    // A node is in tail position when its result is returned from the function, which allows
+    // calls in tail position to reuse the function's frame.
+    fn compile_tail_node(
+        &mut self,
+        result_register: ResultRegister,
+        node: &AstNode,
+        ast: &Ast,
+        tail_position: bool,
+    ) -> CompileNodeResult {
+        self.frame_mut().tail_position = tail_position;
+        self.compile_node(result_register, node, ast)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn compile_frame(
         &mut self,
-        local_count: u8,
+        local_count: u16,
         expressions: &[AstIndex],
         args: &[AstIndex],
+        default_args: &[(u16, AstIndex)],
         captures: &[ConstantIndex],
         ast: &Ast,
         allow_implicit_return: bool,
     ) -> Result<(), CompilerError> {
+        let frame_start_ip = self.bytes.len();
+
         self.frame_stack.push(Frame::new(
             local_count,
             &self.collect_args(args, ast)?,
             captures,
         ));
 
+        // Tail calls replace the current frame, so they're only used in functions that return
+        // their result, i.e. not in generators, or in the main block at the bottom of the stack.
+        let allow_tail_calls = allow_implicit_return && self.frame_stack.len() > 1;
+        self.frame_mut().allow_tail_calls = allow_tail_calls;
+
+        // Default arg values are used when the arg wasn't provided by the caller
+        for (arg_register, default_value) in default_args.iter() {
+            self.push_op(Op::JumpIfArgProvided, &[*arg_register]);
+            let default_jump_ip = self.push_offset_placeholder();
+            self.compile_node(
+                ResultRegister::Fixed(*arg_register),
+                ast.node(*default_value),
+                ast,
+            )?;
+            self.update_offset_placeholder(default_jump_ip);
+        }
+
         // unpack nested args
         for (arg_index, arg) in args.iter().enumerate() {
             match &ast.node(*arg).node {
                 Node::List(nested_args) => {
-                    let list_register = arg_index as u8;
-                    self.push_op(Op::CheckType, &[list_register, TypeId::List as u8]);
-                    self.push_op(Op::CheckSize, &[list_register, nested_args.len() as u8]);
+                    let list_register = arg_index as u16;
+                    self.push_op(Op::CheckType, &[list_register]);
+                    self.push_bytes(&[TypeId::List as u8]);
+                    self.push_op(Op::CheckSize, &[list_register]);
+                    self.push_bytes(&[nested_args.len() as u8]);
                     self.compile_unpack_nested_args(list_register, nested_args, ast)?;
                 }
                 Node::Tuple(nested_args) => {
-                    let tuple_register = arg_index as u8;
-                    self.push_op(Op::CheckType, &[tuple_register, TypeId::Tuple as u8]);
-                    self.push_op(Op::CheckSize, &[tuple_register, nested_args.len() as u8]);
+                    let tuple_register = arg_index as u16;
+                    self.push_op(Op::CheckType, &[tuple_register]);
+                    self.push_bytes(&[TypeId::Tuple as u8]);
+                    self.push_op(Op::CheckSize, &[tuple_register]);
+                    self.push_bytes(&[nested_args.len() as u8]);
                     self.compile_unpack_nested_args(tuple_register, nested_args, ast)?;
                 }
                 _ => {}
@@ -790,7 +937,8 @@ impl Compiler {
             ResultRegister::None
         };
 
-        let block_result = self.compile_block(result_register, expressions, ast)?;
+        let block_result =
+            self.compile_block(result_register, expressions, ast, allow_tail_calls)?;
 
         if let Some(result) = block_result {
             if self.frame().last_op != Some(Op::Return) {
@@ -806,6 +954,25 @@ impl Compiler {
             self.pop_register()?;
         }
 
+        let locals = self
+            .frame()
+            .local_registers
+            .iter()
+            .enumerate()
+            .filter_map(|(register, local)| match local {
+                LocalRegister::Assigned(id) | LocalRegister::Reserved(id, _) => {
+                    Some((register as u16, *id))
+                }
+                LocalRegister::Allocated => None,
+            })
+            .collect();
+        self.debug_info.frames.push(FrameDebugInfo {
+            ip_range: frame_start_ip..self.bytes.len(),
+            span: self.span(),
+            function_name: None,
+            locals,
+        });
+
         self.frame_stack.pop();
 
         Ok(())
@@ -874,7 +1041,7 @@ impl Compiler {
 
     fn compile_unpack_nested_args(
         &mut self,
-        container_register: u8,
+        container_register: u16,
         args: &[AstIndex],
         ast: &Ast,
     ) -> Result<(), CompilerError> {
@@ -883,30 +1050,28 @@ impl Compiler {
                 Node::Wildcard => {}
                 Node::Id(constant_index) => {
                     let local_register = self.assign_local_register(*constant_index)?;
-                    self.push_op(
-                        Op::ValueIndex,
-                        &[local_register, container_register, arg_index as u8],
-                    );
+                    self.push_op(Op::ValueIndex, &[local_register, container_register]);
+                    self.push_bytes(&[arg_index as u8]);
                 }
                 Node::List(nested_args) => {
                     let list_register = self.push_register()?;
-                    self.push_op(
-                        Op::ValueIndex,
-                        &[list_register, container_register, arg_index as u8],
-                    );
-                    self.push_op(Op::CheckType, &[list_register, TypeId::List as u8]);
-                    self.push_op(Op::CheckSize, &[list_register, nested_args.len() as u8]);
+                    self.push_op(Op::ValueIndex, &[list_register, container_register]);
+                    self.push_bytes(&[arg_index as u8]);
+                    self.push_op(Op::CheckType, &[list_register]);
+                    self.push_bytes(&[TypeId::List as u8]);
+                    self.push_op(Op::CheckSize, &[list_register]);
+                    self.push_bytes(&[nested_args.len() as u8]);
                     self.compile_unpack_nested_args(list_register, nested_args, ast)?;
                     self.pop_register()?; // list_register
                 }
                 Node::Tuple(nested_args) => {
                     let tuple_register = self.push_register()?;
-                    self.push_op(
-                        Op::ValueIndex,
-                        &[tuple_register, container_register, arg_index as u8],
-                    );
-                    self.push_op(Op::CheckType, &[tuple_register, TypeId::Tuple as u8]);
-                    self.push_op(Op::CheckSize, &[tuple_register, nested_args.len() as u8]);
+                    self.push_op(Op::ValueIndex, &[tuple_register, container_register]);
+                    self.push_bytes(&[arg_index as u8]);
+                    self.push_op(Op::CheckType, &[tuple_register]);
+                    self.push_bytes(&[TypeId::Tuple as u8]);
+                    self.push_op(Op::CheckSize, &[tuple_register]);
+                    self.push_bytes(&[nested_args.len() as u8]);
                     self.compile_unpack_nested_args(tuple_register, nested_args, ast)?;
                     self.pop_register()?; // tuple_register
                 }
@@ -922,6 +1087,7 @@ impl Compiler {
         result_register: ResultRegister,
         expressions: &[AstIndex],
         ast: &Ast,
+        tail_position: bool,
     ) -> CompileNodeResult {
         use Op::SetEmpty;
 
@@ -938,13 +1104,20 @@ impl Compiler {
                     Some(CompileResult::with_temporary(register))
                 }
             },
-            [expression] => self.compile_node(result_register, ast.node(*expression), ast)?,
+            [expression] => {
+                self.compile_tail_node(result_register, ast.node(*expression), ast, tail_position)?
+            }
             [expressions @ .., last_expression] => {
                 for expression in expressions.iter() {
                     self.compile_node(ResultRegister::None, ast.node(*expression), ast)?;
                 }
 
-                self.compile_node(result_register, ast.node(*last_expression), ast)?
+                self.compile_tail_node(
+                    result_register,
+                    ast.node(*last_expression),
+                    ast,
+                    tail_position,
+                )?
             }
         };
 
@@ -963,7 +1136,7 @@ impl Compiler {
         &mut self,
         target: &AssignTarget,
         ast: &Ast,
-    ) -> Result<Option<u8>, CompilerError> {
+    ) -> Result<Option<u16>, CompilerError> {
         let result = match self.scope_for_assign_target(target) {
             Scope::Local => match &ast.node(target.target_index).node {
                 Node::Id(constant_index) => Some(self.reserve_local_register(*constant_index)?),
@@ -996,7 +1169,13 @@ impl Compiler {
 
         let value_register = match op {
             AssignOp::Equal => {
-                self.compile_node(value_result_register, ast.node(expression), ast)?
+                let expression_node = ast.node(expression);
+                if let (Node::Id(id), Node::Function(_)) =
+                    (&ast.node(target.target_index).node, &expression_node.node)
+                {
+                    self.function_name = Some(*id);
+                }
+                self.compile_node(value_result_register, expression_node, ast)?
             }
             AssignOp::Add => self.compile_binary_op(
                 value_result_register,
@@ -1058,10 +1237,11 @@ impl Compiler {
             Node::Lookup(lookup) => {
                 self.compile_lookup(
                     ResultRegister::None,
-                    &lookup,
+                    lookup,
                     None,
                     Some(value_register.register),
                     ast,
+                    false,
                 )?;
             }
             Node::Wildcard => {}
@@ -1117,20 +1297,23 @@ impl Compiler {
                             None => return compiler_error!(self, "Missing register for target"),
                         };
                         // Get the value for the target by index
-                        self.push_op(ValueIndex, &[local_register, rhs.register, i as u8]);
+                        self.push_op(ValueIndex, &[local_register, rhs.register]);
+                        self.push_bytes(&[i as u8]);
                         // Commit the register now that it's assigned
                         self.commit_local_register(local_register)?;
                     }
                     Node::Lookup(lookup) => {
                         let register = self.push_register()?;
 
-                        self.push_op(ValueIndex, &[register, rhs.register, i as u8]);
+                        self.push_op(ValueIndex, &[register, rhs.register]);
+                        self.push_bytes(&[i as u8]);
                         self.compile_lookup(
                             ResultRegister::None,
-                            &lookup,
+                            lookup,
                             None,
                             Some(register),
                             ast,
+                            false,
                         )?;
 
                         self.pop_register()?;
@@ -1191,20 +1374,22 @@ impl Compiler {
         Ok(result)
     }
 
-    fn compile_set_global(&mut self, id: ConstantIndex, register: u8) {
+    fn compile_set_global(&mut self, id: ConstantIndex, register: u16) {
         if id <= u8::MAX as u32 {
-            self.push_op(Op::SetGlobal, &[id as u8, register]);
+            self.push_op(Op::SetGlobal, &[register]);
+            self.push_bytes(&[id as u8]);
         } else {
-            self.push_op(Op::SetGlobalLong, &id.to_le_bytes());
-            self.push_bytes(&[register]);
+            self.push_op(Op::SetGlobalLong, &[register]);
+            self.push_bytes(&id.to_le_bytes());
         }
     }
 
-    fn compile_load_global(&mut self, result_register: u8, id: ConstantIndex) {
+    fn compile_load_global(&mut self, result_register: u16, id: ConstantIndex) {
         use Op::*;
 
         if id <= u8::MAX as u32 {
-            self.push_op(LoadGlobal, &[result_register, id as u8]);
+            self.push_op(LoadGlobal, &[result_register]);
+            self.push_bytes(&[id as u8]);
         } else {
             self.push_op(LoadGlobalLong, &[result_register]);
             self.push_bytes(&id.to_le_bytes());
@@ -1280,7 +1465,8 @@ impl Compiler {
                     self.push_op(Copy, &[result.register, *single_item]);
                 }
                 _ => {
-                    self.push_op(MakeList, &[result.register, imported.len() as u8]);
+                    self.push_op(MakeList, &[result.register]);
+                    self.push_bytes(&[imported.len() as u8]);
                     for item in imported.iter() {
                         self.push_op(ListPushValue, &[result.register, *item]);
                     }
@@ -1293,7 +1479,7 @@ impl Compiler {
 
     fn compile_import_item(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         item: &[ConstantIndex],
     ) -> Result<(), CompilerError> {
         match item {
@@ -1311,7 +1497,7 @@ impl Compiler {
         Ok(())
     }
 
-    fn compile_import_id(&mut self, result_register: u8, id: ConstantIndex) {
+    fn compile_import_id(&mut self, result_register: u16, id: ConstantIndex) {
         use Op::*;
 
         if let Some(local_register) = self.frame().get_local_assigned_register(id) {
@@ -1321,7 +1507,8 @@ impl Compiler {
         } else {
             // If the id isn't a local then it needs to be imported
             if id <= u8::MAX as u32 {
-                self.push_op(Import, &[result_register, id as u8]);
+                self.push_op(Import, &[result_register]);
+                self.push_bytes(&[id as u8]);
             } else {
                 self.push_op(ImportLong, &[result_register]);
                 self.push_bytes(&id.to_le_bytes());
@@ -1422,7 +1609,7 @@ impl Compiler {
                 self.compile_op(result_register, op, lhs_node, rhs_node, ast)
             }
             Less | LessOrEqual | Greater | GreaterOrEqual | Equal | NotEqual => {
-                self.compile_comparison_op(result_register, op, &lhs_node, &rhs_node, ast)
+                self.compile_comparison_op(result_register, op, lhs_node, rhs_node, ast)
             }
             And | Or => self.compile_logic_op(result_register, op, lhs, rhs, ast),
         }
@@ -1667,14 +1854,8 @@ impl Compiler {
                 }
 
                 let first_element_register = self.peek_register(elements.len() - 1)?;
-                self.push_op(
-                    Op::MakeNum2,
-                    &[
-                        result.register,
-                        elements.len() as u8,
-                        first_element_register,
-                    ],
-                );
+                self.push_op(Op::MakeNum2, &[result.register, first_element_register]);
+                self.push_bytes(&[elements.len() as u8]);
 
                 self.truncate_register_stack(stack_count)?;
                 Some(result)
@@ -1719,14 +1900,8 @@ impl Compiler {
                 }
 
                 let first_element_register = self.peek_register(elements.len() - 1)?;
-                self.push_op(
-                    Op::MakeNum4,
-                    &[
-                        result.register,
-                        elements.len() as u8,
-                        first_element_register,
-                    ],
-                );
+                self.push_op(Op::MakeNum4, &[result.register, first_element_register]);
+                self.push_bytes(&[elements.len() as u8]);
 
                 self.truncate_register_stack(stack_count)?;
 
@@ -1752,6 +1927,10 @@ impl Compiler {
         temp_tuple: bool,
         ast: &Ast,
     ) -> CompileNodeResult {
+        if elements.len() > u8::MAX as usize {
+            return compiler_error!(self, "Too many tuple elements: {}", elements.len());
+        }
+
         let result = self.get_result_register(result_register)?;
         let stack_count = self.frame().register_stack.len();
 
@@ -1768,16 +1947,12 @@ impl Compiler {
             let start_register = self.peek_register(elements.len() - 1)?;
 
             if temp_tuple {
-                self.push_op(
-                    Op::MakeTempTuple,
-                    &[result.register, start_register as u8, elements.len() as u8],
-                );
+                self.push_op(Op::MakeTempTuple, &[result.register, start_register]);
+                self.push_bytes(&[elements.len() as u8]);
             // If we're making a temp tuple then the registers need to be kept around
             } else {
-                self.push_op(
-                    Op::MakeTuple,
-                    &[result.register, start_register as u8, elements.len() as u8],
-                );
+                self.push_op(Op::MakeTuple, &[result.register, start_register]);
+                self.push_bytes(&[elements.len() as u8]);
                 self.truncate_register_stack(stack_count)?;
             }
 
@@ -1789,6 +1964,58 @@ impl Compiler {
         Ok(result)
     }
 
+    fn compile_interpolated_string(
+        &mut self,
+        result_register: ResultRegister,
+        nodes: &[StringNode],
+        ast: &Ast,
+    ) -> CompileNodeResult {
+        let segment_count = match u16::try_from(nodes.len()) {
+            Ok(count) => count,
+            Err(_) => return compiler_error!(self, "Too many segments in interpolated string"),
+        };
+
+        let result = self.get_result_register(result_register)?;
+        let stack_count = self.frame().register_stack.len();
+
+        for node in nodes.iter() {
+            let node_register = self.push_register()?;
+
+            match node {
+                StringNode::Literal(constant) => {
+                    self.load_constant(
+                        node_register,
+                        *constant,
+                        Op::LoadString,
+                        Op::LoadStringLong,
+                    );
+                }
+                StringNode::Expression(expression) => {
+                    self.compile_node(
+                        ResultRegister::Fixed(node_register),
+                        ast.node(*expression),
+                        ast,
+                    )?;
+                }
+            }
+        }
+
+        let result = if let Some(result) = result {
+            let start_register = self.peek_register(nodes.len() - 1)?;
+
+            self.push_op(Op::MakeString, &[result.register, start_register]);
+            self.push_bytes(&segment_count.to_le_bytes());
+
+            Some(result)
+        } else {
+            None
+        };
+
+        self.truncate_register_stack(stack_count)?;
+
+        Ok(result)
+    }
+
     fn compile_make_list(
         &mut self,
         result_register: ResultRegister,
@@ -1802,10 +2029,11 @@ impl Compiler {
                 // TODO take ranges into account when determining size hint
                 let size_hint = elements.len();
                 if size_hint <= u8::MAX as usize {
-                    self.push_op(MakeList, &[result.register, size_hint as u8]);
+                    self.push_op(MakeList, &[result.register]);
+                    self.push_bytes(&[size_hint as u8]);
                 } else {
                     self.push_op(MakeListLong, &[result.register]);
-                    self.push_bytes(&size_hint.to_le_bytes());
+                    self.push_bytes(&(size_hint as u32).to_le_bytes());
                 }
 
                 match elements {
@@ -1823,7 +2051,9 @@ impl Compiler {
                         }
                     }
                     _ => {
-                        let max_batch_size = self.frame().available_registers_count() as usize;
+                        // The number of values pushed by ListPushValues is limited to u8::MAX
+                        let max_batch_size =
+                            self.frame().available_registers_count().min(u8::MAX as u16) as usize;
                         for elements_batch in elements.chunks(max_batch_size) {
                             let stack_count = self.frame().register_stack.len();
                             let start_register = self.frame().next_temporary_register();
@@ -1839,8 +2069,9 @@ impl Compiler {
 
                             self.push_op_without_span(
                                 ListPushValues,
-                                &[result.register, start_register, elements_batch.len() as u8],
+                                &[result.register, start_register],
                             );
+                            self.push_bytes(&[elements_batch.len() as u8]);
 
                             self.truncate_register_stack(stack_count)?;
                         }
@@ -1864,7 +2095,7 @@ impl Compiler {
     fn compile_make_map(
         &mut self,
         result_register: ResultRegister,
-        entries: &[(ConstantIndex, Option<AstIndex>)],
+        entries: &[(MapKey, Option<AstIndex>)],
         ast: &Ast,
     ) -> CompileNodeResult {
         use Op::*;
@@ -1873,30 +2104,50 @@ impl Compiler {
             Some(result) => {
                 let size_hint = entries.len();
                 if size_hint <= u8::MAX as usize {
-                    self.push_op(MakeMap, &[result.register, size_hint as u8]);
+                    self.push_op(MakeMap, &[result.register]);
+                    self.push_bytes(&[size_hint as u8]);
                 } else {
                     self.push_op(MakeMapLong, &[result.register]);
-                    self.push_bytes(&size_hint.to_le_bytes());
+                    self.push_bytes(&(size_hint as u32).to_le_bytes());
                 }
 
                 for (key, maybe_value_node) in entries.iter() {
-                    let value = match maybe_value_node {
-                        Some(value_node) => {
+                    let value = match (key, maybe_value_node) {
+                        (_, Some(value_node)) => {
                             let value_node = ast.node(*value_node);
+                            if let (MapKey::Str(key), Node::Function(_)) = (key, &value_node.node) {
+                                self.function_name = Some(*key);
+                            }
                             self.compile_node(ResultRegister::Any, value_node, ast)?
                                 .unwrap()
                         }
-                        None => match self.frame().get_local_assigned_register(*key) {
-                            Some(register) => CompileResult::with_assigned(register),
-                            None => {
-                                let register = self.push_register()?;
-                                self.compile_load_global(register, *key);
-                                CompileResult::with_temporary(register)
+                        (MapKey::Str(key), None) => {
+                            match self.frame().get_local_assigned_register(*key) {
+                                Some(register) => CompileResult::with_assigned(register),
+                                None => {
+                                    let register = self.push_register()?;
+                                    self.compile_load_global(register, *key);
+                                    CompileResult::with_temporary(register)
+                                }
                             }
-                        },
+                        }
+                        (MapKey::Meta(id), None) => {
+                            return compiler_error!(self, "Missing value for meta key {}", id)
+                        }
                     };
 
-                    self.compile_map_insert(result.register, value.register, *key);
+                    match key {
+                        MapKey::Str(key) => {
+                            self.compile_map_insert(result.register, value.register, *key)
+                        }
+                        MapKey::Meta(id) => {
+                            self.push_op_without_span(
+                                MetaInsert,
+                                &[result.register, value.register],
+                            );
+                            self.push_bytes(&[*id as u8]);
+                        }
+                    }
 
                     if value.is_temporary {
                         self.pop_register()?;
@@ -1926,6 +2177,9 @@ impl Compiler {
         function: &Function,
         ast: &Ast,
     ) -> CompileNodeResult {
+        // Take the function's name before compiling the body, which might contain other functions
+        let function_name = self.function_name.take();
+
         if let Some(result) = self.get_result_register(result_register)? {
             let arg_count = match u8::try_from(function.args.len()) {
                 Ok(x) => x,
@@ -1956,14 +2210,46 @@ impl Compiler {
                 generator: function.is_generator,
             };
 
-            self.push_op(
-                Op::Function,
-                &[result.register, arg_count, capture_count, flags.as_byte()],
-            );
+            // The default values apply to the trailing args, before any variadic arg
+            let optional_arg_count = function.default_args.len() as u8;
+            let first_default_arg = arg_count - function.is_variadic as u8 - optional_arg_count;
+            let default_args = function
+                .default_args
+                .iter()
+                .enumerate()
+                .map(|(i, default_value)| (first_default_arg as u16 + i as u16, *default_value))
+                .collect::<Vec<_>>();
+            // The VM tracks which args were omitted by the caller with a 64 bit mask
+            if let Some((last_arg_register, _)) = default_args.last() {
+                if *last_arg_register >= 64 {
+                    return compiler_error!(
+                        self,
+                        "Default values are only supported for the first 64 arguments"
+                    );
+                }
+            }
+
+            self.push_op(Op::Function, &[result.register]);
+            self.push_bytes(&[
+                arg_count,
+                optional_arg_count,
+                capture_count,
+                flags.as_byte(),
+            ]);
+
+            // The arg names are included so that named args can be matched at runtime,
+            // with u32::MAX being used for args without a name, e.g. wildcards.
+            for arg in function.args.iter() {
+                let arg_name = match &ast.node(*arg).node {
+                    Node::Id(id) => *id,
+                    _ => u32::MAX,
+                };
+                self.push_bytes(&arg_name.to_le_bytes());
+            }
 
             let function_size_ip = self.push_offset_placeholder();
 
-            let local_count = match u8::try_from(function.local_count) {
+            let local_count = match u16::try_from(function.local_count) {
                 Ok(x) => x,
                 Err(_) => {
                     return compiler_error!(
@@ -1980,8 +2266,9 @@ impl Compiler {
                 Node::Block(expressions) => {
                     self.compile_frame(
                         local_count,
-                        &expressions,
+                        expressions,
                         &function.args,
+                        &default_args,
                         &captures,
                         ast,
                         allow_implicit_return,
@@ -1992,6 +2279,7 @@ impl Compiler {
                         local_count,
                         &[function.body],
                         &function.args,
+                        &default_args,
                         &captures,
                         ast,
                         allow_implicit_return,
@@ -1999,24 +2287,31 @@ impl Compiler {
                 }
             };
 
+            // The function's frame info is the last to have been added by compile_frame
+            if let Some(frame_info) = self.debug_info.frames.last_mut() {
+                frame_info.function_name = function_name;
+            }
+
             self.update_offset_placeholder(function_size_ip);
 
             for (i, capture) in captures.iter().enumerate() {
                 if let Some(local_register) = self.frame().get_local_reserved_register(*capture) {
+                    let mut capture_op =
+                        self.encode_op(Op::Capture, &[result.register, local_register]);
+                    capture_op.push(i as u8);
                     self.frame_mut()
-                        .defer_op_until_register_is_committed(
-                            local_register,
-                            vec![Op::Capture as u8, result.register, i as u8, local_register],
-                        )
+                        .defer_op_until_register_is_committed(local_register, capture_op)
                         .map_err(|e| self.make_error(e))?;
                 } else if let Some(local_register) =
                     self.frame().get_local_assigned_register(*capture)
                 {
-                    self.push_op(Op::Capture, &[result.register, i as u8, local_register]);
+                    self.push_op(Op::Capture, &[result.register, local_register]);
+                    self.push_bytes(&[i as u8]);
                 } else {
                     let capture_register = self.push_register()?;
                     self.compile_load_global(capture_register, *capture);
-                    self.push_op(Op::Capture, &[result.register, i as u8, capture_register]);
+                    self.push_op(Op::Capture, &[result.register, capture_register]);
+                    self.push_bytes(&[i as u8]);
                     self.pop_register()?;
                 }
             }
@@ -2032,8 +2327,9 @@ impl Compiler {
         result_register: ResultRegister,
         (root_node, mut next_node_index): &(LookupNode, Option<AstIndex>),
         add_node_to_end_of_lookup: Option<&LookupNode>,
-        set_value: Option<u8>,
+        set_value: Option<u16>,
         ast: &Ast,
+        tail_position: bool,
     ) -> CompileNodeResult {
         use Op::*;
 
@@ -2045,7 +2341,7 @@ impl Compiler {
 
         // Keep track of a register for each lookup node.
         // This produces a lookup chain, allowing lookup operations to access parent containers.
-        let mut node_registers = SmallVec::<[u8; 4]>::new();
+        let mut node_registers = SmallVec::<[u16; 4]>::new();
 
         // At the end of the lookup we'll pop the whole stack,
         // so we don't need to keep track of how many temporary registers we use.
@@ -2133,6 +2429,7 @@ impl Compiler {
                             &args,
                             parent_register,
                             ast,
+                            tail_position,
                         )?;
                     } else {
                         let node_register = self.push_register()?;
@@ -2143,6 +2440,7 @@ impl Compiler {
                             &args,
                             parent_register,
                             ast,
+                            false,
                         )?;
                     }
                 }
@@ -2181,18 +2479,20 @@ impl Compiler {
         Ok(result)
     }
 
-    fn compile_map_insert(&mut self, map_register: u8, value_register: u8, key: ConstantIndex) {
+    fn compile_map_insert(&mut self, map_register: u16, value_register: u16, key: ConstantIndex) {
         if key <= u8::MAX as u32 {
-            self.push_op_without_span(Op::MapInsert, &[map_register, value_register, key as u8]);
+            self.push_op_without_span(Op::MapInsert, &[map_register, value_register]);
+            self.push_bytes(&[key as u8]);
         } else {
             self.push_op_without_span(Op::MapInsertLong, &[map_register, value_register]);
             self.push_bytes(&key.to_le_bytes());
         }
     }
 
-    fn compile_access(&mut self, result_register: u8, value_register: u8, key: ConstantIndex) {
+    fn compile_access(&mut self, result_register: u16, value_register: u16, key: ConstantIndex) {
         if key <= u8::MAX as u32 {
-            self.push_op(Op::Access, &[result_register, value_register, key as u8]);
+            self.push_op(Op::Access, &[result_register, value_register]);
+            self.push_bytes(&[key as u8]);
         } else {
             self.push_op(Op::AccessLong, &[result_register, value_register]);
             self.push_bytes(&key.to_le_bytes());
@@ -2202,13 +2502,29 @@ impl Compiler {
     fn compile_call(
         &mut self,
         result_register: ResultRegister,
-        function_register: u8,
+        function_register: u16,
         args: &[AstIndex],
-        parent: Option<u8>,
+        parent: Option<u16>,
         ast: &Ast,
+        tail_call: bool,
     ) -> CompileNodeResult {
         use Op::*;
 
+        if args.len() > u8::MAX as usize {
+            return compiler_error!(self, "Too many call arguments: {}", args.len());
+        }
+
+        let mut positional_args = Vec::with_capacity(args.len());
+        let mut named_args = Vec::new();
+        for arg in args.iter() {
+            match &ast.node(*arg).node {
+                Node::NamedArg { name, value } => {
+                    named_args.push((MapKey::Str(*name), Some(*value)))
+                }
+                _ => positional_args.push(*arg),
+            }
+        }
+
         let result = self.get_result_register(result_register)?;
         let stack_count = self.frame().register_stack.len();
 
@@ -2216,11 +2532,23 @@ impl Compiler {
         // (it's decided at runtime if the parent value will be used or not).
         let frame_base = self.push_register()?;
 
-        for arg in args.iter() {
+        for arg in positional_args.iter() {
             let arg_register = self.push_register()?;
             self.compile_node(ResultRegister::Fixed(arg_register), ast.node(*arg), ast)?;
         }
 
+        // Named args are collected into a map that follows the positional args,
+        // the VM then matches them to the function's args by name.
+        let has_named_args = !named_args.is_empty();
+        if has_named_args {
+            let named_args_register = self.push_register()?;
+            self.compile_make_map(ResultRegister::Fixed(named_args_register), &named_args, ast)?;
+        }
+
+        // Calls with named args aren't compiled as tail calls
+        let tail_call = tail_call && !has_named_args;
+        let arg_count = positional_args.len() as u8;
+
         let call_result_register = if let Some(result) = result {
             result.register
         } else {
@@ -2232,27 +2560,30 @@ impl Compiler {
 
         match parent {
             Some(parent_register) => {
+                let op = match (tail_call, has_named_args) {
+                    (true, _) => TailCallChild,
+                    (false, true) => CallChildNamed,
+                    (false, false) => CallChild,
+                };
                 self.push_op(
-                    CallChild,
+                    op,
                     &[
                         call_result_register,
                         function_register,
                         frame_base,
-                        args.len() as u8,
                         parent_register,
                     ],
                 );
+                self.push_bytes(&[arg_count]);
             }
             None => {
-                self.push_op(
-                    Call,
-                    &[
-                        call_result_register,
-                        function_register,
-                        frame_base,
-                        args.len() as u8,
-                    ],
-                );
+                let op = match (tail_call, has_named_args) {
+                    (true, _) => TailCall,
+                    (false, true) => CallNamed,
+                    (false, false) => Call,
+                };
+                self.push_op(op, &[call_result_register, function_register, frame_base]);
+                self.push_bytes(&[arg_count]);
             }
         }
 
@@ -2266,6 +2597,7 @@ impl Compiler {
         result_register: ResultRegister,
         ast_if: &AstIf,
         ast: &Ast,
+        tail_position: bool,
     ) -> CompileNodeResult {
         use Op::*;
 
@@ -2295,7 +2627,12 @@ impl Compiler {
             self.pop_register()?;
         }
 
-        self.compile_node(expression_result_register, ast.node(*then_node), ast)?;
+        self.compile_tail_node(
+            expression_result_register,
+            ast.node(*then_node),
+            ast,
+            tail_position,
+        )?;
 
         let if_jump_ip = {
             if !else_if_blocks.is_empty() || else_node.is_some() || result.is_some() {
@@ -2325,7 +2662,12 @@ impl Compiler {
                         self.pop_register()?;
                     }
 
-                    self.compile_node(expression_result_register, ast.node(*else_if_node), ast)?;
+                    self.compile_tail_node(
+                        expression_result_register,
+                        ast.node(*else_if_node),
+                        ast,
+                        tail_position,
+                    )?;
 
                     self.push_op_without_span(Jump, &[]);
                     let else_if_jump_ip = self.push_offset_placeholder();
@@ -2339,7 +2681,12 @@ impl Compiler {
 
         // Else - either compile the else block, or set the result to empty
         if let Some(else_node) = else_node {
-            self.compile_node(expression_result_register, ast.node(*else_node), ast)?;
+            self.compile_tail_node(
+                expression_result_register,
+                ast.node(*else_node),
+                ast,
+                tail_position,
+            )?;
         } else if let Some(result) = result {
             self.push_op_without_span(SetEmpty, &[result.register]);
         }
@@ -2361,6 +2708,7 @@ impl Compiler {
         result_register: ResultRegister,
         arms: &[SwitchArm],
         ast: &Ast,
+        tail_position: bool,
     ) -> CompileNodeResult {
         let result = self.get_result_register(result_register)?;
 
@@ -2393,7 +2741,12 @@ impl Compiler {
                 ResultRegister::None
             };
 
-            self.compile_node(body_result_register, ast.node(arm.expression), ast)?;
+            self.compile_tail_node(
+                body_result_register,
+                ast.node(arm.expression),
+                ast,
+                tail_position,
+            )?;
 
             if !is_last_arm {
                 self.push_op_without_span(Op::Jump, &[]);
@@ -2420,6 +2773,7 @@ impl Compiler {
         match_expression: AstIndex,
         arms: &[MatchArm],
         ast: &Ast,
+        tail_position: bool,
     ) -> CompileNodeResult {
         let result = self.get_result_register(result_register)?;
 
@@ -2446,6 +2800,7 @@ impl Compiler {
                 arm,
                 is_last_arm,
                 ast,
+                tail_position,
             )? {
                 result_jump_placeholders.push(placeholder);
             }
@@ -2460,14 +2815,16 @@ impl Compiler {
         Ok(result)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn compile_match_arm(
         &mut self,
         result: Option<CompileResult>,
-        match_register: u8,
+        match_register: u16,
         match_len: usize,
         arm: &MatchArm,
         is_last_arm: bool,
         ast: &Ast,
+        tail_position: bool,
     ) -> Result<Option<usize>, CompilerError> {
         let mut jumps = MatchJumpPlaceholders::default();
 
@@ -2582,7 +2939,12 @@ impl Compiler {
             ResultRegister::None
         };
 
-        self.compile_node(body_result_register, ast.node(arm.expression), ast)?;
+        self.compile_tail_node(
+            body_result_register,
+            ast.node(arm.expression),
+            ast,
+            tail_position,
+        )?;
 
         let result_jump_placeholder = if !is_last_arm {
             self.push_op_without_span(Op::Jump, &[]);
@@ -2628,6 +2990,7 @@ impl Compiler {
                 | Node::Float(_)
                 | Node::Int(_)
                 | Node::Str(_)
+                | Node::InterpolatedString(_)
                 | Node::Lookup(_) => {
                     let pattern = self.push_register()?;
                     self.compile_node(ResultRegister::Fixed(pattern), pattern_node, ast)?;
@@ -2635,10 +2998,8 @@ impl Compiler {
 
                     if match_is_container {
                         let element = self.push_register()?;
-                        self.push_op(
-                            ValueIndex,
-                            &[element, params.match_register, pattern_index as u8],
-                        );
+                        self.push_op(ValueIndex, &[element, params.match_register]);
+                        self.push_bytes(&[pattern_index as u8]);
                         self.push_op(Equal, &[comparison, pattern, element]);
                         self.pop_register()?; // element
                     } else {
@@ -2671,10 +3032,8 @@ impl Compiler {
                 Node::Id(id) => {
                     let id_register = self.assign_local_register(*id)?;
                     if match_is_container {
-                        self.push_op(
-                            ValueIndex,
-                            &[id_register, params.match_register, pattern_index as u8],
-                        );
+                        self.push_op(ValueIndex, &[id_register, params.match_register]);
+                        self.push_bytes(&[pattern_index as u8]);
                     } else {
                         self.push_op(Copy, &[id_register, params.match_register]);
                     }
@@ -2722,10 +3081,8 @@ impl Compiler {
                             // We want to assign the slice containing all but the first three items
                             // to the given id.
                             let id_register = self.assign_local_register(*id)?;
-                            self.push_op(
-                                SliceFrom,
-                                &[id_register, params.match_register, pattern_index as u8],
-                            );
+                            self.push_op(SliceFrom, &[id_register, params.match_register]);
+                            self.push_bytes(&[pattern_index as u8]);
                         }
 
                         if !params.is_last_alternative {
@@ -2743,7 +3100,8 @@ impl Compiler {
                             // the given id.
                             let id_register = self.assign_local_register(*id)?;
                             let to_index = -(arm_patterns.len() as i8 - 1) as u8;
-                            self.push_op(SliceTo, &[id_register, params.match_register, to_index]);
+                            self.push_op(SliceTo, &[id_register, params.match_register]);
+                            self.push_bytes(&[to_index]);
                         }
 
                         index_from_end = true;
@@ -2776,10 +3134,8 @@ impl Compiler {
         let value_register = if let Some(pattern_index) = pattern_index {
             // Place the nested container into a register
             let value_register = self.push_register()?;
-            self.push_op(
-                ValueIndex,
-                &[value_register, params.match_register, pattern_index as u8],
-            );
+            self.push_op(ValueIndex, &[value_register, params.match_register]);
+            self.push_bytes(&[pattern_index as u8]);
             value_register
         } else {
             params.match_register
@@ -2801,12 +3157,12 @@ impl Compiler {
         }
 
         let first_or_last_pattern_is_ellipsis = {
-            let first_is_ellipsis = nested_patterns.first().map_or(false, |first| {
-                matches!(ast.node(*first).node, Node::Ellipsis(_))
-            });
-            let last_is_ellipsis = nested_patterns.last().map_or(false, |last| {
-                matches!(ast.node(*last).node, Node::Ellipsis(_))
-            });
+            let first_is_ellipsis = nested_patterns
+                .first()
+                .is_some_and(|first| matches!(ast.node(*first).node, Node::Ellipsis(_)));
+            let last_is_ellipsis = nested_patterns
+                .last()
+                .is_some_and(|last| matches!(ast.node(*last).node, Node::Ellipsis(_)));
             if nested_patterns.len() > 1 && first_is_ellipsis && last_is_ellipsis {
                 return compiler_error!(self, "Only one ellipsis is allowed in a match pattern");
             }
@@ -2821,10 +3177,12 @@ impl Compiler {
             let patterns_len = nested_patterns.len() as u8;
 
             let comparison_op = if first_or_last_pattern_is_ellipsis {
-                self.push_op(SetNumberU8, &[expected_register, patterns_len - 1]);
+                self.push_op(SetNumberU8, &[expected_register]);
+                self.push_bytes(&[patterns_len - 1]);
                 GreaterOrEqual
             } else {
-                self.push_op(SetNumberU8, &[expected_register, patterns_len]);
+                self.push_op(SetNumberU8, &[expected_register]);
+                self.push_bytes(&[patterns_len]);
                 Equal
             };
             self.push_op(
@@ -2855,7 +3213,7 @@ impl Compiler {
                 ..params
             },
             true, // match_is_container
-            &nested_patterns,
+            nested_patterns,
             ast,
         )?;
 
@@ -2913,16 +3271,16 @@ impl Compiler {
             [] => return compiler_error!(self, "Missing argument in for loop"),
             [None] => {
                 // e.g. for _ in 0..10
-                self.push_op_without_span(IterNextQuiet, &[iterator_register as u8]);
+                self.push_op_without_span(IterNextQuiet, &[iterator_register]);
                 self.push_loop_jump_placeholder()?;
             }
             [Some(arg)] => {
                 // e.g. for i in 0..10
                 let arg_register = self.assign_local_register(*arg)?;
-                self.push_op_without_span(IterNext, &[arg_register, iterator_register as u8]);
+                self.push_op_without_span(IterNext, &[arg_register, iterator_register]);
                 self.push_loop_jump_placeholder()?;
             }
-            [args @ ..] => {
+            args => {
                 // e.g. for a, b, c in list_of_lists()
                 // e.g. for key, value in map
 
@@ -2936,10 +3294,8 @@ impl Compiler {
                 for (i, maybe_arg) in args.iter().enumerate() {
                     if let Some(arg) = maybe_arg {
                         let arg_register = self.assign_local_register(*arg)?;
-                        self.push_op_without_span(
-                            ValueIndex,
-                            &[arg_register, temp_register, i as u8],
-                        );
+                        self.push_op_without_span(ValueIndex, &[arg_register, temp_register]);
+                        self.push_bytes(&[i as u8]);
                     }
                 }
 
@@ -2969,14 +3325,12 @@ impl Compiler {
         self.truncate_register_stack(stack_count)?;
 
         if self.settings.repl_mode && self.frame_stack.len() == 1 {
-            for maybe_arg in args.iter() {
-                if let Some(arg) = maybe_arg {
-                    let arg_register = match self.frame().get_local_assigned_register(*arg) {
-                        Some(register) => register,
-                        None => return compiler_error!(self, "Missing arg register"),
-                    };
-                    self.compile_set_global(*arg, arg_register);
-                }
+            for arg in args.iter().flatten() {
+                let arg_register = match self.frame().get_local_assigned_register(*arg) {
+                    Some(register) => register,
+                    None => return compiler_error!(self, "Missing arg register"),
+                };
+                self.compile_set_global(*arg, arg_register);
             }
         }
 
@@ -3044,13 +3398,14 @@ impl Compiler {
 
     fn load_constant(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         index: ConstantIndex,
         short_op: Op,
         long_op: Op,
     ) {
         if index <= u8::MAX as u32 {
-            self.push_op(short_op, &[result_register, index as u8]);
+            self.push_op(short_op, &[result_register]);
+            self.push_bytes(&[index as u8]);
         } else {
             self.push_op(long_op, &[result_register]);
             self.push_bytes(&index.to_le_bytes());
@@ -3069,18 +3424,39 @@ impl Compiler {
         Ok(result)
     }
 
-    fn push_jump_back_op(&mut self, op: Op, bytes: &[u8], target_ip: usize) {
-        let offset = self.bytes.len() + 3 + bytes.len() - target_ip;
-        self.push_op_without_span(op, bytes);
-        self.push_bytes(&(offset as u16).to_le_bytes());
+    fn push_jump_back_op(&mut self, op: Op, registers: &[u16], target_ip: usize) {
+        self.push_op_without_span(op, registers);
+
+        let offset = self.bytes.len() + self.offset_size() - target_ip;
+        if self.wide_offsets {
+            self.push_bytes(&(offset as u32).to_le_bytes());
+        } else {
+            if offset > u16::MAX as usize {
+                self.offset_overflow = true;
+            }
+            self.push_bytes(&(offset as u16).to_le_bytes());
+        }
     }
 
     fn push_offset_placeholder(&mut self) -> usize {
         let offset_ip = self.bytes.len();
-        self.push_bytes(&[0, 0]);
+        if self.wide_offsets {
+            self.push_bytes(&[0; 4]);
+        } else {
+            self.push_bytes(&[0; 2]);
+        }
         offset_ip
     }
 
+    // The number of bytes used to encode jump offsets
+    fn offset_size(&self) -> usize {
+        if self.wide_offsets {
+            4
+        } else {
+            2
+        }
+    }
+
     fn current_loop(&self) -> Result<&Loop, CompilerError> {
         self.frame()
             .loop_stack
@@ -3100,23 +3476,66 @@ impl Compiler {
     }
 
     fn update_offset_placeholder(&mut self, offset_ip: usize) {
-        let offset = self.bytes.len() - offset_ip - 2;
-        let offset_bytes = (offset as u16).to_le_bytes();
-        self.bytes[offset_ip] = offset_bytes[0];
-        self.bytes[offset_ip + 1] = offset_bytes[1];
+        let offset_size = self.offset_size();
+        let offset = self.bytes.len() - offset_ip - offset_size;
+
+        if self.wide_offsets {
+            let offset_bytes = (offset as u32).to_le_bytes();
+            self.bytes[offset_ip..offset_ip + offset_size].copy_from_slice(&offset_bytes);
+        } else {
+            // If the offset doesn't fit then the chunk will be recompiled with wide offsets
+            if offset > u16::MAX as usize {
+                self.offset_overflow = true;
+            }
+            let offset_bytes = (offset as u16).to_le_bytes();
+            self.bytes[offset_ip..offset_ip + offset_size].copy_from_slice(&offset_bytes);
+        }
     }
 
-    fn push_op(&mut self, op: Op, bytes: &[u8]) {
+    // Pushes an op along with its register operands
+    //
+    // Any other operands should be pushed afterwards with push_bytes, or for jump offsets,
+    // with push_offset_placeholder.
+    fn push_op(&mut self, op: Op, registers: &[u16]) {
         self.debug_info.push(self.bytes.len(), self.span());
-        self.push_op_without_span(op, bytes);
+        self.push_op_without_span(op, registers);
     }
 
-    fn push_op_without_span(&mut self, op: Op, bytes: &[u8]) {
-        self.bytes.push(op as u8);
-        self.bytes.extend_from_slice(bytes);
+    fn push_op_without_span(&mut self, op: Op, registers: &[u16]) {
+        let bytes = self.encode_op(op, registers);
+        self.bytes.extend_from_slice(&bytes);
         self.frame_mut().last_op = Some(op);
     }
 
+    // Encodes an op and its register operands, adding the Wide prefix when needed
+    //
+    // Ops with jump offsets use the prefix when the compiler is using wide offsets, which
+    // causes the offset to be encoded with 4 bytes. If an op needs wide registers and has an
+    // offset while the compiler isn't using wide offsets, then the chunk is recompiled.
+    fn encode_op(&mut self, op: Op, registers: &[u16]) -> Vec<u8> {
+        let has_offset = op_has_offset(op);
+        let wide_registers = registers.iter().any(|register| *register > u8::MAX as u16);
+
+        if wide_registers && has_offset && !self.wide_offsets {
+            self.offset_overflow = true;
+        }
+
+        let mut result = Vec::with_capacity(2 + registers.len() * 2);
+
+        if wide_registers || (has_offset && self.wide_offsets) {
+            result.push(Op::Wide as u8);
+            result.push(op as u8);
+            for register in registers {
+                result.extend_from_slice(&register.to_le_bytes());
+            }
+        } else {
+            result.push(op as u8);
+            result.extend(registers.iter().map(|register| *register as u8));
+        }
+
+        result
+    }
+
     fn push_bytes(&mut self, bytes: &[u8]) {
         self.bytes.extend_from_slice(bytes);
     }
@@ -3129,19 +3548,19 @@ impl Compiler {
         self.frame_stack.last_mut().expect("Frame stack is empty")
     }
 
-    fn push_register(&mut self) -> Result<u8, CompilerError> {
+    fn push_register(&mut self) -> Result<u16, CompilerError> {
         self.frame_mut()
             .push_register()
             .map_err(|e| self.make_error(e))
     }
 
-    fn pop_register(&mut self) -> Result<u8, CompilerError> {
+    fn pop_register(&mut self) -> Result<u16, CompilerError> {
         self.frame_mut()
             .pop_register()
             .map_err(|e| self.make_error(e))
     }
 
-    fn peek_register(&mut self, n: usize) -> Result<u8, CompilerError> {
+    fn peek_register(&mut self, n: usize) -> Result<u16, CompilerError> {
         self.frame_mut()
             .peek_register(n)
             .map_err(|e| self.make_error(e))
@@ -3153,19 +3572,19 @@ impl Compiler {
             .map_err(|e| self.make_error(e))
     }
 
-    fn assign_local_register(&mut self, local: ConstantIndex) -> Result<u8, CompilerError> {
+    fn assign_local_register(&mut self, local: ConstantIndex) -> Result<u16, CompilerError> {
         self.frame_mut()
             .assign_local_register(local)
             .map_err(|e| self.make_error(e))
     }
 
-    fn reserve_local_register(&mut self, local: ConstantIndex) -> Result<u8, CompilerError> {
+    fn reserve_local_register(&mut self, local: ConstantIndex) -> Result<u16, CompilerError> {
         self.frame_mut()
             .reserve_local_register(local)
             .map_err(|e| self.make_error(e))
     }
 
-    fn commit_local_register(&mut self, register: u8) -> Result<u8, CompilerError> {
+    fn commit_local_register(&mut self, register: u16) -> Result<u16, CompilerError> {
         let deferred_ops = self
             .frame_mut()
             .commit_local_register(register)
@@ -3204,7 +3623,7 @@ struct MatchJumpPlaceholders {
 }
 
 struct MatchArmParameters<'a> {
-    match_register: u8,
+    match_register: u16,
     is_last_alternative: bool,
     has_last_pattern: bool,
     jumps: &'a mut MatchJumpPlaceholders,
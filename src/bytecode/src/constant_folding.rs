@@ -0,0 +1,468 @@
+use {
+    koto_parser::{
+        AssignTarget, Ast, AstIf, AstIndex, AstNode, AstOp, ConstantPool, ConstantPoolBuilder,
+        Node, SwitchArm,
+    },
+    std::collections::HashSet,
+};
+
+/// Folds constant expressions in the AST, and removes branches that can never be taken
+///
+/// Arithmetic, comparison, and logic operations with literal operands are evaluated and replaced
+/// with their results, which are added to the constant pool if necessary. `if` and `switch`
+/// expressions with literal conditions are reduced to the branches that will be taken.
+///
+/// Operations that would fail or behave differently at runtime (e.g. integer overflow, mixed
+/// types, non-bool conditions) are left untouched so that they produce the same result or error.
+/// Unreachable branches that assign local values are also kept, so that the locals remain in
+/// scope for the rest of the function.
+///
+/// The existing constants keep their indices, so the returned pool is a superset of the input.
+pub fn fold_constants(ast: &mut Ast, constants: ConstantPool) -> ConstantPool {
+    let mut folder = ConstantFolder {
+        constants: ConstantPoolBuilder::from_pool(&constants),
+        chained_comparisons: HashSet::new(),
+    };
+
+    // Comparisons with a comparison as their rhs are compiled as chains, e.g. `a < b < c` is
+    // equivalent to `(a < b) and (b < c)`, so the rhs of a chain can't be folded on its own.
+    for node in ast.nodes() {
+        if let Node::BinaryOp { op, rhs, .. } = node.node {
+            if is_comparison(op) && is_comparison_node(ast.node(rhs)) {
+                folder.chained_comparisons.insert(rhs);
+            }
+        }
+    }
+
+    // Child nodes are pushed to the AST before their parents, so visiting the nodes in order
+    // allows folded results to propagate up through nested expressions.
+    for index in 0..ast.nodes().len() {
+        let index = index as AstIndex;
+        if let Some(folded) = folder.fold_node(index, ast) {
+            ast.set_node(index, folded);
+        }
+    }
+
+    folder.constants.build()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Literal {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+struct ConstantFolder {
+    constants: ConstantPoolBuilder,
+    chained_comparisons: HashSet<AstIndex>,
+}
+
+impl ConstantFolder {
+    fn fold_node(&mut self, index: AstIndex, ast: &Ast) -> Option<Node> {
+        if self.chained_comparisons.contains(&index) {
+            return None;
+        }
+
+        match &ast.node(index).node {
+            Node::BinaryOp { op, lhs, rhs } => self.fold_binary_op(*op, *lhs, *rhs, ast),
+            Node::Negate(expression) => {
+                let result = match self.literal(*expression, ast)? {
+                    Literal::Bool(b) => Literal::Bool(!b),
+                    Literal::Int(n) => Literal::Int(n.checked_neg()?),
+                    Literal::Float(n) => Literal::Float(-n),
+                    Literal::Str(_) => return None,
+                };
+                Some(self.literal_node(result))
+            }
+            Node::If(ast_if) => self.fold_if(ast_if, ast),
+            Node::Switch(arms) => self.fold_switch(arms, ast),
+            _ => None,
+        }
+    }
+
+    fn fold_binary_op(
+        &mut self,
+        op: AstOp,
+        lhs: AstIndex,
+        rhs: AstIndex,
+        ast: &Ast,
+    ) -> Option<Node> {
+        use {AstOp::*, Literal::*};
+
+        let lhs_literal = self.literal(lhs, ast)?;
+
+        if is_comparison(op) {
+            return self.fold_comparison(op, lhs_literal, rhs, ast);
+        }
+
+        // The logic operators short-circuit, so only the lhs needs to be known
+        match (op, &lhs_literal) {
+            (And, Bool(false)) | (Or, Bool(true)) => return Some(self.literal_node(lhs_literal)),
+            (And, Bool(true)) | (Or, Bool(false)) => return Some(ast.node(rhs).node.clone()),
+            (And, _) | (Or, _) => return None,
+            _ => {}
+        }
+
+        let result = match (lhs_literal, self.literal(rhs, ast)?) {
+            (Int(a), Int(b)) => match op {
+                Add => Int(a.checked_add(b)?),
+                Subtract => Int(a.checked_sub(b)?),
+                Multiply => Int(a.checked_mul(b)?),
+                Divide => float_result(a as f64 / b as f64)?,
+                Modulo => Int(a.checked_rem(b)?),
+                _ => return None,
+            },
+            (Int(a), Float(b)) => fold_float_op(op, a as f64, b)?,
+            (Float(a), Int(b)) => fold_float_op(op, a, b as f64)?,
+            (Float(a), Float(b)) => fold_float_op(op, a, b)?,
+            (Str(a), Str(b)) if op == Add => Str(a + &b),
+            _ => return None,
+        };
+
+        Some(self.literal_node(result))
+    }
+
+    // Folds a comparison, along with any comparisons that are chained with it
+    fn fold_comparison(
+        &mut self,
+        op: AstOp,
+        lhs: Literal,
+        rhs: AstIndex,
+        ast: &Ast,
+    ) -> Option<Node> {
+        let mut op = op;
+        let mut lhs = lhs;
+        let mut rhs = rhs;
+        let mut result = true;
+
+        loop {
+            match &ast.node(rhs).node {
+                Node::BinaryOp {
+                    op: rhs_op,
+                    lhs: rhs_lhs,
+                    rhs: rhs_rhs,
+                } if is_comparison(*rhs_op) => {
+                    let rhs_lhs = self.literal(*rhs_lhs, ast)?;
+                    result &= compare(op, &lhs, &rhs_lhs)?;
+                    op = *rhs_op;
+                    lhs = rhs_lhs;
+                    rhs = *rhs_rhs;
+                }
+                _ => {
+                    result &= compare(op, &lhs, &self.literal(rhs, ast)?)?;
+                    break;
+                }
+            }
+        }
+
+        Some(self.literal_node(Literal::Bool(result)))
+    }
+
+    fn fold_if(&mut self, ast_if: &AstIf, ast: &Ast) -> Option<Node> {
+        let mut branches = Vec::with_capacity(ast_if.else_if_blocks.len() + 1);
+        branches.push((ast_if.condition, ast_if.then_node));
+        branches.extend(ast_if.else_if_blocks.iter().cloned());
+
+        let mut else_node = ast_if.else_node;
+        let mut remaining = Vec::with_capacity(branches.len());
+        let mut removed = Vec::new();
+
+        for (i, (condition, node)) in branches.iter().enumerate() {
+            match self.literal(*condition, ast) {
+                Some(Literal::Bool(true)) => {
+                    // Any following branches are unreachable
+                    removed.extend(branches[i + 1..].iter().map(|(_, node)| *node));
+                    removed.extend(else_node);
+                    else_node = Some(*node);
+                    break;
+                }
+                Some(Literal::Bool(false)) => removed.push(*node),
+                _ => remaining.push((*condition, *node)),
+            }
+        }
+
+        if removed.is_empty() && remaining.len() == branches.len() {
+            return None;
+        }
+
+        if removed.iter().any(|node| assigns_locals(*node, ast)) {
+            return None;
+        }
+
+        if remaining.is_empty() {
+            return Some(match else_node {
+                Some(else_node) => ast.node(else_node).node.clone(),
+                None => Node::Empty,
+            });
+        }
+
+        let (condition, then_node) = remaining.remove(0);
+        Some(Node::If(AstIf {
+            condition,
+            then_node,
+            else_if_blocks: remaining,
+            else_node,
+        }))
+    }
+
+    fn fold_switch(&mut self, arms: &[SwitchArm], ast: &Ast) -> Option<Node> {
+        let mut remaining = Vec::with_capacity(arms.len());
+        let mut removed = Vec::new();
+        let mut changed = false;
+
+        for (i, arm) in arms.iter().enumerate() {
+            match arm
+                .condition
+                .and_then(|condition| self.literal(condition, ast))
+            {
+                Some(Literal::Bool(true)) => {
+                    // The arm always matches, so any following arms are unreachable
+                    remaining.push(SwitchArm {
+                        condition: None,
+                        expression: arm.expression,
+                    });
+                    removed.extend(arms[i + 1..].iter().map(|arm| arm.expression));
+                    changed = true;
+                    break;
+                }
+                Some(Literal::Bool(false)) => {
+                    removed.push(arm.expression);
+                    changed = true;
+                }
+                _ => remaining.push(arm.clone()),
+            }
+        }
+
+        if !changed {
+            return None;
+        }
+
+        if removed.iter().any(|node| assigns_locals(*node, ast)) {
+            return None;
+        }
+
+        match remaining.first() {
+            None => Some(Node::Empty),
+            Some(SwitchArm {
+                condition: None,
+                expression,
+            }) => Some(ast.node(*expression).node.clone()),
+            Some(_) => Some(Node::Switch(remaining)),
+        }
+    }
+
+    fn literal(&self, index: AstIndex, ast: &Ast) -> Option<Literal> {
+        let constants = self.constants.pool();
+
+        let result = match &ast.node(index).node {
+            Node::BoolTrue => Literal::Bool(true),
+            Node::BoolFalse => Literal::Bool(false),
+            Node::Number0 => Literal::Int(0),
+            Node::Number1 => Literal::Int(1),
+            Node::Int(constant) => Literal::Int(constants.get_i64(*constant)),
+            Node::Float(constant) => Literal::Float(constants.get_f64(*constant)),
+            Node::Str(constant) => Literal::Str(constants.get_str(*constant).to_string()),
+            _ => return None,
+        };
+
+        Some(result)
+    }
+
+    fn literal_node(&mut self, literal: Literal) -> Node {
+        match literal {
+            Literal::Bool(true) => Node::BoolTrue,
+            Literal::Bool(false) => Node::BoolFalse,
+            Literal::Int(0) => Node::Number0,
+            Literal::Int(1) => Node::Number1,
+            Literal::Int(n) => Node::Int(self.constants.add_i64(n)),
+            Literal::Float(n) => Node::Float(self.constants.add_f64(n)),
+            Literal::Str(s) => Node::Str(self.constants.add_string(&s)),
+        }
+    }
+}
+
+// Returns true if the node, or any of its child nodes, assigns a value to a local
+//
+// Locals are reserved by the compiler when their assignments are compiled, so removing a branch
+// containing an assignment would change the meaning of later accesses to the local.
+// Nested functions have their own scope and are skipped, with the exception of their default
+// argument values.
+fn assigns_locals(index: AstIndex, ast: &Ast) -> bool {
+    use koto_parser::{LookupNode, Scope, StringNode};
+
+    let any = |nodes: &[AstIndex]| nodes.iter().any(|node| assigns_locals(*node, ast));
+    let assigns_local_id = |target: &AssignTarget| {
+        target.scope == Scope::Local && matches!(ast.node(target.target_index).node, Node::Id(_))
+    };
+
+    match &ast.node(index).node {
+        Node::Assign {
+            target, expression, ..
+        } => {
+            assigns_local_id(target)
+                || assigns_locals(target.target_index, ast)
+                || assigns_locals(*expression, ast)
+        }
+        Node::MultiAssign {
+            targets,
+            expression,
+        } => {
+            targets
+                .iter()
+                .any(|target| assigns_local_id(target) || assigns_locals(target.target_index, ast))
+                || assigns_locals(*expression, ast)
+        }
+        // Imports, loop args, match patterns, and catch args all assign locals
+        Node::Import { .. } | Node::Match { .. } | Node::Ellipsis(Some(_)) => true,
+        Node::For(ast_for) => {
+            ast_for.args.iter().any(Option::is_some)
+                || assigns_locals(ast_for.range, ast)
+                || assigns_locals(ast_for.body, ast)
+        }
+        Node::Try(ast_try) => {
+            ast_try.catch_arg.is_some()
+                || assigns_locals(ast_try.try_block, ast)
+                || assigns_locals(ast_try.catch_block, ast)
+                || ast_try
+                    .finally_block
+                    .iter()
+                    .any(|block| assigns_locals(*block, ast))
+        }
+        Node::Function(function) => any(&function.default_args),
+        Node::Lookup((lookup, next)) => {
+            let lookup_assigns = match lookup {
+                LookupNode::Root(node) | LookupNode::Index(node) => assigns_locals(*node, ast),
+                LookupNode::Call(args) => any(args),
+                LookupNode::Id(_) => false,
+            };
+            lookup_assigns || next.iter().any(|next| assigns_locals(*next, ast))
+        }
+        Node::InterpolatedString(nodes) => nodes.iter().any(|node| match node {
+            StringNode::Expression(expression) => assigns_locals(*expression, ast),
+            StringNode::Literal(_) => false,
+        }),
+        Node::Num2(nodes)
+        | Node::Num4(nodes)
+        | Node::List(nodes)
+        | Node::Tuple(nodes)
+        | Node::TempTuple(nodes)
+        | Node::Block(nodes)
+        | Node::MainBlock { body: nodes, .. } => any(nodes),
+        Node::Map(entries) => entries
+            .iter()
+            .any(|(_, value)| value.iter().any(|value| assigns_locals(*value, ast))),
+        Node::Call { function, args } => assigns_locals(*function, ast) || any(args),
+        Node::Range { start, end, .. } => any(&[*start, *end]),
+        Node::BinaryOp { lhs, rhs, .. } => any(&[*lhs, *rhs]),
+        Node::If(ast_if) => {
+            assigns_locals(ast_if.condition, ast)
+                || assigns_locals(ast_if.then_node, ast)
+                || ast_if
+                    .else_if_blocks
+                    .iter()
+                    .any(|(condition, node)| any(&[*condition, *node]))
+                || ast_if
+                    .else_node
+                    .iter()
+                    .any(|node| assigns_locals(*node, ast))
+        }
+        Node::Switch(arms) => arms.iter().any(|arm| {
+            arm.condition
+                .iter()
+                .any(|condition| assigns_locals(*condition, ast))
+                || assigns_locals(arm.expression, ast)
+        }),
+        Node::While { condition, body } | Node::Until { condition, body } => {
+            any(&[*condition, *body])
+        }
+        Node::RangeFrom { start: node }
+        | Node::RangeTo { end: node, .. }
+        | Node::NamedArg { value: node, .. }
+        | Node::Loop { body: node }
+        | Node::ReturnExpression(node)
+        | Node::Negate(node)
+        | Node::Throw(node)
+        | Node::Yield(node)
+        | Node::Debug {
+            expression: node, ..
+        } => assigns_locals(*node, ast),
+        Node::Empty
+        | Node::Id(_)
+        | Node::BoolTrue
+        | Node::BoolFalse
+        | Node::Number0
+        | Node::Number1
+        | Node::Int(_)
+        | Node::Float(_)
+        | Node::Str(_)
+        | Node::RangeFull
+        | Node::Wildcard
+        | Node::Ellipsis(None)
+        | Node::Break
+        | Node::Continue
+        | Node::Return => false,
+    }
+}
+
+fn fold_float_op(op: AstOp, a: f64, b: f64) -> Option<Literal> {
+    use AstOp::*;
+
+    match op {
+        Add => float_result(a + b),
+        Subtract => float_result(a - b),
+        Multiply => float_result(a * b),
+        Divide => float_result(a / b),
+        Modulo => float_result(a % b),
+        _ => None,
+    }
+}
+
+fn compare(op: AstOp, lhs: &Literal, rhs: &Literal) -> Option<bool> {
+    use {std::cmp::Ordering, AstOp::*, Literal::*};
+
+    let ordering = match (lhs, rhs) {
+        (Int(a), Int(b)) => a.cmp(b),
+        (Int(a), Float(b)) => (*a as f64).partial_cmp(b)?,
+        (Float(a), Int(b)) => a.partial_cmp(&(*b as f64))?,
+        (Float(a), Float(b)) => a.partial_cmp(b)?,
+        (Str(a), Str(b)) => a.cmp(b),
+        (Bool(a), Bool(b)) if matches!(op, Equal | NotEqual) => a.cmp(b),
+        _ => return None,
+    };
+
+    let result = match op {
+        Equal => ordering == Ordering::Equal,
+        NotEqual => ordering != Ordering::Equal,
+        Less => ordering == Ordering::Less,
+        LessOrEqual => ordering != Ordering::Greater,
+        Greater => ordering == Ordering::Greater,
+        GreaterOrEqual => ordering != Ordering::Less,
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+fn is_comparison(op: AstOp) -> bool {
+    use AstOp::*;
+
+    matches!(
+        op,
+        Equal | NotEqual | Less | LessOrEqual | Greater | GreaterOrEqual
+    )
+}
+
+fn is_comparison_node(node: &AstNode) -> bool {
+    matches!(node.node, Node::BinaryOp { op, .. } if is_comparison(op))
+}
+
+// Infinite and NaN results are left to be produced at runtime
+fn float_result(n: f64) -> Option<Literal> {
+    if n.is_finite() {
+        Some(Literal::Float(n))
+    } else {
+        None
+    }
+}
@@ -1,6 +1,6 @@
 use {
     crate::{Chunk, Op},
-    koto_parser::ConstantIndex,
+    koto_parser::{ConstantIndex, MetaKeyId},
     std::{convert::TryInto, fmt, sync::Arc},
 };
 
@@ -63,179 +63,187 @@ pub enum Instruction {
         message: String,
     },
     Copy {
-        target: u8,
-        source: u8,
+        target: u16,
+        source: u16,
     },
     SetEmpty {
-        register: u8,
+        register: u16,
     },
     SetBool {
-        register: u8,
+        register: u16,
         value: bool,
     },
     SetNumber {
-        register: u8,
+        register: u16,
         value: i64,
     },
     LoadFloat {
-        register: u8,
+        register: u16,
         constant: ConstantIndex,
     },
     LoadInt {
-        register: u8,
+        register: u16,
         constant: ConstantIndex,
     },
     LoadString {
-        register: u8,
+        register: u16,
         constant: ConstantIndex,
     },
     LoadGlobal {
-        register: u8,
+        register: u16,
         constant: ConstantIndex,
     },
     SetGlobal {
         global: ConstantIndex,
-        source: u8,
+        source: u16,
     },
     Import {
-        register: u8,
+        register: u16,
         constant: ConstantIndex,
     },
     MakeTuple {
-        register: u8,
-        start: u8,
+        register: u16,
+        start: u16,
         count: u8,
     },
     MakeTempTuple {
-        register: u8,
-        start: u8,
+        register: u16,
+        start: u16,
         count: u8,
     },
+    MakeString {
+        register: u16,
+        start: u16,
+        count: u16,
+    },
     MakeList {
-        register: u8,
+        register: u16,
         size_hint: usize,
     },
     MakeMap {
-        register: u8,
+        register: u16,
         size_hint: usize,
     },
     MakeNum2 {
-        register: u8,
+        register: u16,
         count: u8,
-        element_register: u8,
+        element_register: u16,
     },
     MakeNum4 {
-        register: u8,
+        register: u16,
         count: u8,
-        element_register: u8,
+        element_register: u16,
     },
     Range {
-        register: u8,
-        start: u8,
-        end: u8,
+        register: u16,
+        start: u16,
+        end: u16,
     },
     RangeInclusive {
-        register: u8,
-        start: u8,
-        end: u8,
+        register: u16,
+        start: u16,
+        end: u16,
     },
     RangeTo {
-        register: u8,
-        end: u8,
+        register: u16,
+        end: u16,
     },
     RangeToInclusive {
-        register: u8,
-        end: u8,
+        register: u16,
+        end: u16,
     },
     RangeFrom {
-        register: u8,
-        start: u8,
+        register: u16,
+        start: u16,
     },
     RangeFull {
-        register: u8,
+        register: u16,
     },
     MakeIterator {
-        register: u8,
-        iterable: u8,
+        register: u16,
+        iterable: u16,
     },
     Function {
-        register: u8,
+        register: u16,
         arg_count: u8,
+        optional_arg_count: u8,
         capture_count: u8,
         instance_function: bool,
         variadic: bool,
         generator: bool,
+        // The position in the chunk of the function's arg names, see [Op::Function]
+        arg_names_ip: usize,
         size: usize,
     },
     Capture {
-        function: u8,
+        function: u16,
         target: u8,
-        source: u8,
+        source: u16,
     },
     Negate {
-        register: u8,
-        source: u8,
+        register: u16,
+        source: u16,
     },
     Add {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Subtract {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Multiply {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Divide {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Modulo {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Less {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     LessOrEqual {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Greater {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     GreaterOrEqual {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Equal {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     NotEqual {
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
     },
     Jump {
         offset: usize,
     },
     JumpIf {
-        register: u8,
+        register: u16,
         offset: usize,
         jump_condition: bool,
     },
@@ -243,114 +251,130 @@ pub enum Instruction {
         offset: usize,
     },
     JumpBackIf {
-        register: u8,
+        register: u16,
         offset: usize,
         jump_condition: bool,
     },
+    JumpIfArgProvided {
+        register: u16,
+        offset: usize,
+    },
     Call {
-        result: u8,
-        function: u8,
-        frame_base: u8,
+        result: u16,
+        function: u16,
+        frame_base: u16,
         arg_count: u8,
+        tail_call: bool,
+        named_args: bool,
     },
     CallChild {
-        result: u8,
-        function: u8,
-        frame_base: u8,
+        result: u16,
+        function: u16,
+        frame_base: u16,
         arg_count: u8,
-        parent: u8,
+        parent: u16,
+        tail_call: bool,
+        named_args: bool,
     },
     Return {
-        register: u8,
+        register: u16,
     },
     Yield {
-        register: u8,
+        register: u16,
     },
     Size {
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
     },
     IterNext {
-        register: u8,
-        iterator: u8,
+        register: u16,
+        iterator: u16,
         jump_offset: usize,
     },
     IterNextTemp {
-        register: u8,
-        iterator: u8,
+        register: u16,
+        iterator: u16,
         jump_offset: usize,
     },
     IterNextQuiet {
-        iterator: u8,
+        iterator: u16,
         jump_offset: usize,
     },
     ValueIndex {
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
         index: i8,
     },
     SliceFrom {
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
         index: i8,
     },
     SliceTo {
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
         index: i8,
     },
     IsTuple {
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
     },
     IsList {
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
     },
     ListPushValue {
-        list: u8,
-        value: u8,
+        list: u16,
+        value: u16,
     },
     ListPushValues {
-        list: u8,
-        values_start: u8,
+        list: u16,
+        values_start: u16,
         count: u8,
     },
     ListUpdate {
-        list: u8,
-        index: u8,
-        value: u8,
+        list: u16,
+        index: u16,
+        value: u16,
     },
     Index {
-        register: u8,
-        value: u8,
-        index: u8,
+        register: u16,
+        value: u16,
+        index: u16,
     },
     MapInsert {
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
         key: ConstantIndex,
     },
+    MetaInsert {
+        register: u16,
+        value: u16,
+        id: MetaKeyId,
+    },
     Access {
-        register: u8,
-        map: u8,
+        register: u16,
+        map: u16,
         key: ConstantIndex,
     },
     TryStart {
-        arg_register: u8,
+        arg_register: u16,
         catch_offset: usize,
     },
     TryEnd,
+    Throw {
+        register: u16,
+    },
     Debug {
-        register: u8,
+        register: u16,
         constant: ConstantIndex,
     },
     CheckType {
-        register: u8,
+        register: u16,
         type_id: TypeId,
     },
     CheckSize {
-        register: u8,
+        register: u16,
         size: usize,
     },
 }
@@ -359,7 +383,7 @@ impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Instruction::*;
         match self {
-            Error { message } => unreachable!(message),
+            Error { message } => unreachable!("{}", message),
             Copy { .. } => write!(f, "Copy"),
             SetEmpty { .. } => write!(f, "SetEmpty"),
             SetBool { .. } => write!(f, "SetBool"),
@@ -372,6 +396,7 @@ impl fmt::Display for Instruction {
             Import { .. } => write!(f, "Import"),
             MakeTuple { .. } => write!(f, "MakeTuple"),
             MakeTempTuple { .. } => write!(f, "MakeTempTuple"),
+            MakeString { .. } => write!(f, "MakeString"),
             MakeList { .. } => write!(f, "MakeList"),
             MakeMap { .. } => write!(f, "MakeMap"),
             MakeNum2 { .. } => write!(f, "MakeNum2"),
@@ -401,6 +426,7 @@ impl fmt::Display for Instruction {
             JumpIf { .. } => write!(f, "JumpIf"),
             JumpBack { .. } => write!(f, "JumpBack"),
             JumpBackIf { .. } => write!(f, "JumpBackIf"),
+            JumpIfArgProvided { .. } => write!(f, "JumpIfArgProvided"),
             Call { .. } => write!(f, "Call"),
             CallChild { .. } => write!(f, "CallChild"),
             Return { .. } => write!(f, "Return"),
@@ -419,9 +445,11 @@ impl fmt::Display for Instruction {
             ListUpdate { .. } => write!(f, "ListUpdate"),
             Index { .. } => write!(f, "Index"),
             MapInsert { .. } => write!(f, "MapInsert"),
+            MetaInsert { .. } => write!(f, "MetaInsert"),
             Access { .. } => write!(f, "Access"),
             TryStart { .. } => write!(f, "TryStart"),
             TryEnd => write!(f, "TryEnd"),
+            Throw { .. } => write!(f, "Throw"),
             Debug { .. } => write!(f, "Debug"),
             CheckType { .. } => write!(f, "CheckType"),
             CheckSize { .. } => write!(f, "CheckSize"),
@@ -433,7 +461,7 @@ impl fmt::Debug for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Instruction::*;
         match self {
-            Error { message } => unreachable!(message),
+            Error { message } => unreachable!("{}", message),
             Copy { target, source } => write!(f, "Copy\t\tresult: {}\tsource: {}", target, source),
             SetEmpty { register } => write!(f, "SetEmpty\tresult: {}", register),
             SetBool { register, value } => {
@@ -482,6 +510,15 @@ impl fmt::Debug for Instruction {
                 "MakeTempTuple\tresult: {}\tstart: {}\tcount: {}",
                 register, start, count
             ),
+            MakeString {
+                register,
+                start,
+                count,
+            } => write!(
+                f,
+                "MakeString\tresult: {}\tstart: {}\tcount: {}",
+                register, start, count
+            ),
             MakeList {
                 register,
                 size_hint,
@@ -550,16 +587,25 @@ impl fmt::Debug for Instruction {
             Function {
                 register,
                 arg_count,
+                optional_arg_count,
                 capture_count,
                 instance_function,
                 variadic,
                 generator,
                 size,
+                ..
             } => write!(
                 f,
                 "Function\tresult: {}\targs: {}\t\tcaptures: {}\tsize: {}\n\
-                     \t\t\tinstance: {}\tvariadic: {}\tgenerator: {}",
-                register, arg_count, capture_count, size, instance_function, variadic, generator,
+                     \t\t\tinstance: {}\tvariadic: {}\tgenerator: {}\toptional args: {}",
+                register,
+                arg_count,
+                capture_count,
+                size,
+                instance_function,
+                variadic,
+                generator,
+                optional_arg_count,
             ),
             Capture {
                 function,
@@ -648,15 +694,30 @@ impl fmt::Debug for Instruction {
                 "JumpBackIf\tresult: {}\toffset: {}\tcondition: {}",
                 register, offset, jump_condition
             ),
+            JumpIfArgProvided { register, offset } => write!(
+                f,
+                "JumpIfArgProvided\tregister: {}\toffset: {}",
+                register, offset
+            ),
             Call {
                 result,
                 function,
                 frame_base,
                 arg_count,
+                tail_call,
+                named_args,
             } => write!(
                 f,
-                "Call\t\tresult: {}\tfunction: {}\tframe base: {}\targs: {}",
-                result, function, frame_base, arg_count
+                "{}\t\tresult: {}\tfunction: {}\tframe base: {}\targs: {}",
+                match (tail_call, named_args) {
+                    (true, _) => "TailCall",
+                    (false, true) => "CallNamed",
+                    (false, false) => "Call",
+                },
+                result,
+                function,
+                frame_base,
+                arg_count
             ),
             CallChild {
                 result,
@@ -664,10 +725,21 @@ impl fmt::Debug for Instruction {
                 parent,
                 frame_base,
                 arg_count,
+                tail_call,
+                named_args,
             } => write!(
                 f,
-                "CallChild\tresult: {}\tfunction: {}\tframe_base: {}\n\t\t\targs: {}\t\tparent: {}",
-                result, function, frame_base, arg_count, parent
+                "{}\tresult: {}\tfunction: {}\tframe_base: {}\n\t\t\targs: {}\t\tparent: {}",
+                match (tail_call, named_args) {
+                    (true, _) => "TailCallChild",
+                    (false, true) => "CallChildNamed",
+                    (false, false) => "CallChild",
+                },
+                result,
+                function,
+                frame_base,
+                arg_count,
+                parent
             ),
             Return { register } => write!(f, "Return\t\tresult: {}", register),
             Yield { register } => write!(f, "Yield\t\tresult: {}", register),
@@ -766,6 +838,15 @@ impl fmt::Debug for Instruction {
                 "MapInsert\tmap: {}\t\tvalue: {}\tkey: {}",
                 register, value, key
             ),
+            MetaInsert {
+                register,
+                value,
+                id,
+            } => write!(
+                f,
+                "MetaInsert\tmap: {}\t\tvalue: {}\tid: {}",
+                register, value, id
+            ),
             Access { register, map, key } => write!(
                 f,
                 "Access\t\tresult: {}\tmap: {}\t\tkey: {}",
@@ -780,6 +861,7 @@ impl fmt::Debug for Instruction {
                 arg_register, catch_offset
             ),
             TryEnd => write!(f, "TryEnd"),
+            Throw { register } => write!(f, "Throw\t\tregister: {}", register),
             Debug { register, constant } => {
                 write!(f, "Debug\t\tregister: {}\tconstant: {}", register, constant)
             }
@@ -860,7 +942,7 @@ impl Iterator for InstructionReader {
             }};
         }
 
-        let op = match self.chunk.bytes.get(self.ip) {
+        let mut op = match self.chunk.bytes.get(self.ip) {
             Some(byte) => Op::from(*byte),
             None => return None,
         };
@@ -868,359 +950,456 @@ impl Iterator for InstructionReader {
 
         self.ip += 1;
 
+        // The Wide prefix applies to the instruction that follows it
+        let wide = op == Op::Wide;
+        if wide {
+            op = Op::from(get_byte!());
+        }
+
+        macro_rules! get_register {
+            () => {{
+                if wide {
+                    get_u16!()
+                } else {
+                    get_byte!() as u16
+                }
+            }};
+        }
+
+        macro_rules! get_offset {
+            () => {{
+                if wide {
+                    get_u32!() as usize
+                } else {
+                    get_u16!() as usize
+                }
+            }};
+        }
+
         match op {
             Op::Copy => Some(Copy {
-                target: get_byte!(),
-                source: get_byte!(),
+                target: get_register!(),
+                source: get_register!(),
             }),
             Op::SetEmpty => Some(SetEmpty {
-                register: get_byte!(),
+                register: get_register!(),
             }),
             Op::SetFalse => Some(SetBool {
-                register: get_byte!(),
+                register: get_register!(),
                 value: false,
             }),
             Op::SetTrue => Some(SetBool {
-                register: get_byte!(),
+                register: get_register!(),
                 value: true,
             }),
             Op::Set0 => Some(SetNumber {
-                register: get_byte!(),
+                register: get_register!(),
                 value: 0,
             }),
             Op::Set1 => Some(SetNumber {
-                register: get_byte!(),
+                register: get_register!(),
                 value: 1,
             }),
             Op::SetNumberU8 => Some(SetNumber {
-                register: get_byte!(),
+                register: get_register!(),
                 value: get_byte!() as i64,
             }),
             Op::LoadFloat => Some(LoadFloat {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_byte!() as ConstantIndex,
             }),
             Op::LoadFloatLong => Some(LoadFloat {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_u32!() as ConstantIndex,
             }),
             Op::LoadInt => Some(LoadInt {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_byte!() as ConstantIndex,
             }),
             Op::LoadIntLong => Some(LoadInt {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_u32!() as ConstantIndex,
             }),
             Op::LoadString => Some(LoadString {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_byte!() as ConstantIndex,
             }),
             Op::LoadStringLong => Some(LoadString {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_u32!() as ConstantIndex,
             }),
             Op::LoadGlobal => Some(LoadGlobal {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_byte!() as ConstantIndex,
             }),
             Op::LoadGlobalLong => Some(LoadGlobal {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_u32!() as ConstantIndex,
             }),
             Op::SetGlobal => Some(SetGlobal {
+                source: get_register!(),
                 global: get_byte!() as ConstantIndex,
-                source: get_byte!(),
             }),
             Op::SetGlobalLong => Some(SetGlobal {
+                source: get_register!(),
                 global: get_u32!() as ConstantIndex,
-                source: get_byte!(),
             }),
             Op::Import => Some(Import {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_byte!() as ConstantIndex,
             }),
             Op::ImportLong => Some(Import {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_u32!() as ConstantIndex,
             }),
             Op::MakeTuple => Some(MakeTuple {
-                register: get_byte!(),
-                start: get_byte!(),
+                register: get_register!(),
+                start: get_register!(),
                 count: get_byte!(),
             }),
             Op::MakeTempTuple => Some(MakeTempTuple {
-                register: get_byte!(),
-                start: get_byte!(),
+                register: get_register!(),
+                start: get_register!(),
                 count: get_byte!(),
             }),
+            Op::MakeString => Some(MakeString {
+                register: get_register!(),
+                start: get_register!(),
+                count: get_u16!(),
+            }),
             Op::MakeList => Some(MakeList {
-                register: get_byte!(),
+                register: get_register!(),
                 size_hint: get_byte!() as usize,
             }),
             Op::MakeListLong => Some(MakeList {
-                register: get_byte!(),
+                register: get_register!(),
                 size_hint: get_u32!() as usize,
             }),
             Op::MakeMap => Some(MakeMap {
-                register: get_byte!(),
+                register: get_register!(),
                 size_hint: get_byte!() as usize,
             }),
             Op::MakeMapLong => Some(MakeMap {
-                register: get_byte!(),
+                register: get_register!(),
                 size_hint: get_u32!() as usize,
             }),
             Op::MakeNum2 => Some(MakeNum2 {
-                register: get_byte!(),
+                register: get_register!(),
+                element_register: get_register!(),
                 count: get_byte!(),
-                element_register: get_byte!(),
             }),
             Op::MakeNum4 => Some(MakeNum4 {
-                register: get_byte!(),
+                register: get_register!(),
+                element_register: get_register!(),
                 count: get_byte!(),
-                element_register: get_byte!(),
             }),
             Op::Range => Some(Range {
-                register: get_byte!(),
-                start: get_byte!(),
-                end: get_byte!(),
+                register: get_register!(),
+                start: get_register!(),
+                end: get_register!(),
             }),
             Op::RangeInclusive => Some(RangeInclusive {
-                register: get_byte!(),
-                start: get_byte!(),
-                end: get_byte!(),
+                register: get_register!(),
+                start: get_register!(),
+                end: get_register!(),
             }),
             Op::RangeTo => Some(RangeTo {
-                register: get_byte!(),
-                end: get_byte!(),
+                register: get_register!(),
+                end: get_register!(),
             }),
             Op::RangeToInclusive => Some(RangeToInclusive {
-                register: get_byte!(),
-                end: get_byte!(),
+                register: get_register!(),
+                end: get_register!(),
             }),
             Op::RangeFrom => Some(RangeFrom {
-                register: get_byte!(),
-                start: get_byte!(),
+                register: get_register!(),
+                start: get_register!(),
             }),
             Op::RangeFull => Some(RangeFull {
-                register: get_byte!(),
+                register: get_register!(),
             }),
             Op::MakeIterator => Some(MakeIterator {
-                register: get_byte!(),
-                iterable: get_byte!(),
+                register: get_register!(),
+                iterable: get_register!(),
             }),
             Op::Function => {
-                let register = get_byte!();
+                let register = get_register!();
                 let arg_count = get_byte!();
+                let optional_arg_count = get_byte!();
                 let capture_count = get_byte!();
                 let flags = FunctionFlags::from_byte(get_byte!());
-                let size = get_u16!() as usize;
+                let arg_names_ip = self.ip;
+                for _ in 0..arg_count {
+                    get_u32!();
+                }
+                let size = get_offset!();
 
                 Some(Function {
                     register,
                     arg_count,
+                    optional_arg_count,
                     capture_count,
                     instance_function: flags.instance_function,
                     variadic: flags.variadic,
                     generator: flags.generator,
+                    arg_names_ip,
                     size,
                 })
             }
             Op::Capture => Some(Capture {
-                function: get_byte!(),
+                function: get_register!(),
+                source: get_register!(),
                 target: get_byte!(),
-                source: get_byte!(),
             }),
             Op::Negate => Some(Negate {
-                register: get_byte!(),
-                source: get_byte!(),
+                register: get_register!(),
+                source: get_register!(),
             }),
             Op::Add => Some(Add {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Subtract => Some(Subtract {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Multiply => Some(Multiply {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Divide => Some(Divide {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Modulo => Some(Modulo {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Less => Some(Less {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::LessOrEqual => Some(LessOrEqual {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Greater => Some(Greater {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::GreaterOrEqual => Some(GreaterOrEqual {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Equal => Some(Equal {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::NotEqual => Some(NotEqual {
-                register: get_byte!(),
-                lhs: get_byte!(),
-                rhs: get_byte!(),
+                register: get_register!(),
+                lhs: get_register!(),
+                rhs: get_register!(),
             }),
             Op::Jump => Some(Jump {
-                offset: get_u16!() as usize,
+                offset: get_offset!(),
+            }),
+            Op::JumpIfArgProvided => Some(JumpIfArgProvided {
+                register: get_register!(),
+                offset: get_offset!(),
             }),
             Op::JumpTrue => Some(JumpIf {
-                register: get_byte!(),
-                offset: get_u16!() as usize,
+                register: get_register!(),
+                offset: get_offset!(),
                 jump_condition: true,
             }),
             Op::JumpFalse => Some(JumpIf {
-                register: get_byte!(),
-                offset: get_u16!() as usize,
+                register: get_register!(),
+                offset: get_offset!(),
                 jump_condition: false,
             }),
             Op::JumpBack => Some(JumpBack {
-                offset: get_u16!() as usize,
+                offset: get_offset!(),
             }),
             Op::JumpBackFalse => Some(JumpBackIf {
-                register: get_byte!(),
-                offset: get_u16!() as usize,
+                register: get_register!(),
+                offset: get_offset!(),
                 jump_condition: false,
             }),
             Op::Call => Some(Call {
-                result: get_byte!(),
-                function: get_byte!(),
-                frame_base: get_byte!(),
+                result: get_register!(),
+                function: get_register!(),
+                frame_base: get_register!(),
                 arg_count: get_byte!(),
+                tail_call: false,
+                named_args: false,
             }),
             Op::CallChild => Some(CallChild {
-                result: get_byte!(),
-                function: get_byte!(),
-                frame_base: get_byte!(),
+                result: get_register!(),
+                function: get_register!(),
+                frame_base: get_register!(),
+                parent: get_register!(),
+                arg_count: get_byte!(),
+                tail_call: false,
+                named_args: false,
+            }),
+            Op::TailCall => Some(Call {
+                result: get_register!(),
+                function: get_register!(),
+                frame_base: get_register!(),
+                arg_count: get_byte!(),
+                tail_call: true,
+                named_args: false,
+            }),
+            Op::TailCallChild => Some(CallChild {
+                result: get_register!(),
+                function: get_register!(),
+                frame_base: get_register!(),
+                parent: get_register!(),
                 arg_count: get_byte!(),
-                parent: get_byte!(),
+                tail_call: true,
+                named_args: false,
+            }),
+            Op::CallNamed => Some(Call {
+                result: get_register!(),
+                function: get_register!(),
+                frame_base: get_register!(),
+                arg_count: get_byte!(),
+                tail_call: false,
+                named_args: true,
+            }),
+            Op::CallChildNamed => Some(CallChild {
+                result: get_register!(),
+                function: get_register!(),
+                frame_base: get_register!(),
+                parent: get_register!(),
+                arg_count: get_byte!(),
+                tail_call: false,
+                named_args: true,
             }),
             Op::Return => Some(Return {
-                register: get_byte!(),
+                register: get_register!(),
             }),
             Op::Yield => Some(Yield {
-                register: get_byte!(),
+                register: get_register!(),
             }),
             Op::Size => Some(Size {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
             }),
             Op::IterNext => Some(IterNext {
-                register: get_byte!(),
-                iterator: get_byte!(),
-                jump_offset: get_u16!() as usize,
+                register: get_register!(),
+                iterator: get_register!(),
+                jump_offset: get_offset!(),
             }),
             Op::IterNextTemp => Some(IterNextTemp {
-                register: get_byte!(),
-                iterator: get_byte!(),
-                jump_offset: get_u16!() as usize,
+                register: get_register!(),
+                iterator: get_register!(),
+                jump_offset: get_offset!(),
             }),
             Op::IterNextQuiet => Some(IterNextQuiet {
-                iterator: get_byte!(),
-                jump_offset: get_u16!() as usize,
+                iterator: get_register!(),
+                jump_offset: get_offset!(),
             }),
             Op::ValueIndex => Some(ValueIndex {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
                 index: get_byte!() as i8,
             }),
             Op::SliceFrom => Some(SliceFrom {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
                 index: get_byte!() as i8,
             }),
             Op::SliceTo => Some(SliceTo {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
                 index: get_byte!() as i8,
             }),
             Op::IsTuple => Some(IsTuple {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
             }),
             Op::IsList => Some(IsList {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
             }),
             Op::ListPushValue => Some(ListPushValue {
-                list: get_byte!(),
-                value: get_byte!(),
+                list: get_register!(),
+                value: get_register!(),
             }),
             Op::ListPushValues => Some(ListPushValues {
-                list: get_byte!(),
-                values_start: get_byte!(),
+                list: get_register!(),
+                values_start: get_register!(),
                 count: get_byte!(),
             }),
             Op::ListUpdate => Some(ListUpdate {
-                list: get_byte!(),
-                index: get_byte!(),
-                value: get_byte!(),
+                list: get_register!(),
+                index: get_register!(),
+                value: get_register!(),
             }),
             Op::Index => Some(Index {
-                register: get_byte!(),
-                value: get_byte!(),
-                index: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
+                index: get_register!(),
             }),
             Op::MapInsert => Some(MapInsert {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
                 key: get_byte!() as ConstantIndex,
             }),
             Op::MapInsertLong => Some(MapInsert {
-                register: get_byte!(),
-                value: get_byte!(),
+                register: get_register!(),
+                value: get_register!(),
                 key: get_u32!() as ConstantIndex,
             }),
+            Op::MetaInsert => {
+                let register = get_register!();
+                let value = get_register!();
+                match MetaKeyId::from_byte(get_byte!()) {
+                    Ok(id) => Some(MetaInsert {
+                        register,
+                        value,
+                        id,
+                    }),
+                    Err(byte) => Some(Error {
+                        message: format!("Unexpected value for MetaInsert id: {}", byte),
+                    }),
+                }
+            }
             Op::Access => Some(Access {
-                register: get_byte!(),
-                map: get_byte!(),
+                register: get_register!(),
+                map: get_register!(),
                 key: get_byte!() as ConstantIndex,
             }),
             Op::AccessLong => Some(Access {
-                register: get_byte!(),
-                map: get_byte!(),
+                register: get_register!(),
+                map: get_register!(),
                 key: get_u32!() as ConstantIndex,
             }),
             Op::TryStart => Some(TryStart {
-                arg_register: get_byte!(),
-                catch_offset: get_u16!() as usize,
+                arg_register: get_register!(),
+                catch_offset: get_offset!(),
             }),
             Op::TryEnd => Some(TryEnd),
+            Op::Throw => Some(Throw {
+                register: get_register!(),
+            }),
             Op::Debug => Some(Debug {
-                register: get_byte!(),
+                register: get_register!(),
                 constant: get_u32!() as ConstantIndex,
             }),
             Op::CheckType => {
-                let register = get_byte!();
+                let register = get_register!();
                 match TypeId::from_byte(get_byte!()) {
                     Ok(type_id) => Some(CheckType { register, type_id }),
                     Err(byte) => Some(Error {
@@ -1229,7 +1408,7 @@ impl Iterator for InstructionReader {
                 }
             }
             Op::CheckSize => Some(CheckSize {
-                register: get_byte!(),
+                register: get_register!(),
                 size: get_byte!() as usize,
             }),
             _ => Some(Error {
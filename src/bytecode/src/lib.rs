@@ -3,15 +3,23 @@
 //! Contains Koto's compiler and its bytecode operations
 
 mod chunk;
+mod chunk_serialization;
 mod compiler;
+mod constant_folding;
 mod instruction_reader;
 mod loader;
+mod module_resolver;
 mod op;
 
 pub use {
-    chunk::{chunk_to_string, chunk_to_string_annotated, Chunk, DebugInfo},
+    chunk::{chunk_to_string, chunk_to_string_annotated, Chunk, DebugInfo, FrameDebugInfo},
     compiler::{Compiler, CompilerError, CompilerSettings},
+    constant_folding::fold_constants,
     instruction_reader::{FunctionFlags, Instruction, InstructionReader, TypeId},
     loader::{Loader, LoaderError},
+    module_resolver::{
+        FileSystemResolver, MemoryModuleResolver, ModuleResolver, ModuleSource, ResolvedModule,
+        KOTO_PATH,
+    },
     op::Op,
 };
@@ -1,15 +1,27 @@
 use {
-    crate::{Chunk, Compiler, CompilerError, CompilerSettings},
+    crate::{
+        fold_constants, Chunk, Compiler, CompilerError, CompilerSettings, FileSystemResolver,
+        ModuleResolver, ModuleSource, ResolvedModule,
+    },
     koto_parser::{format_error_with_excerpt, Parser, ParserError},
-    std::{collections::HashMap, error, fmt, path::PathBuf, sync::Arc},
+    std::{
+        collections::{hash_map::DefaultHasher, HashMap},
+        error, fmt, fs,
+        hash::{Hash, Hasher},
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
 };
 
 /// Errors that can be returned from [Loader] operations
 #[derive(Clone, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum LoaderErrorType {
-    ParserError(ParserError),
+    /// The errors that were found while parsing, ordered by position in the source
+    ParserErrors(Vec<ParserError>),
     CompilerError(CompilerError),
     IoError(String),
+    InvalidBytecode(String),
 }
 
 #[derive(Clone, Debug)]
@@ -24,9 +36,17 @@ impl LoaderError {
         error: ParserError,
         source: &str,
         source_path: Option<PathBuf>,
+    ) -> Self {
+        Self::from_parser_errors(vec![error], source, source_path)
+    }
+
+    pub fn from_parser_errors(
+        errors: Vec<ParserError>,
+        source: &str,
+        source_path: Option<PathBuf>,
     ) -> Self {
         Self {
-            error: LoaderErrorType::ParserError(error),
+            error: LoaderErrorType::ParserErrors(errors),
             source: source.into(),
             source_path,
         }
@@ -52,10 +72,27 @@ impl LoaderError {
         }
     }
 
-    pub fn is_indentation_error(&self) -> bool {
+    pub fn invalid_bytecode(error: String, source_path: Option<PathBuf>) -> Self {
+        Self {
+            error: LoaderErrorType::InvalidBytecode(error),
+            source: "".into(),
+            source_path,
+        }
+    }
+
+    /// The parser errors that caused loading to fail, empty for other error types
+    pub fn parser_errors(&self) -> &[ParserError] {
         match &self.error {
-            LoaderErrorType::ParserError(e) => e.is_indentation_error(),
-            _ => false,
+            LoaderErrorType::ParserErrors(errors) => errors,
+            _ => &[],
+        }
+    }
+
+    pub fn is_indentation_error(&self) -> bool {
+        // Only the first error is considered, later errors could be a consequence of it
+        match self.parser_errors().first() {
+            Some(e) => e.is_indentation_error(),
+            None => false,
         }
     }
 }
@@ -66,33 +103,54 @@ impl fmt::Display for LoaderError {
 
         if f.alternate() {
             match &self.error {
-                ParserError(koto_parser::ParserError { error, .. }) => {
-                    f.write_str(&error.to_string())
+                ParserErrors(errors) => {
+                    for (i, error) in errors.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str("\n")?;
+                        }
+                        write!(f, "{}", error)?;
+                    }
+                    Ok(())
                 }
                 CompilerError(crate::CompilerError { message, .. }) => f.write_str(message),
-                IoError(e) => f.write_str(&e),
+                IoError(e) | InvalidBytecode(e) => f.write_str(e),
             }
         } else {
             match &self.error {
-                ParserError(koto_parser::ParserError { error, span }) => {
-                    f.write_str(&format_error_with_excerpt(
-                        Some(&error.to_string()),
-                        &self.source_path,
-                        &self.source,
-                        span.start,
-                        span.end,
-                    ))
+                ParserErrors(errors) => {
+                    for (i, error) in errors.iter().enumerate() {
+                        if i > 0 {
+                            f.write_str("\n\n")?;
+                        }
+                        f.write_str(&format_error_with_excerpt(
+                            Some(&error.to_string()),
+                            &self.source_path,
+                            None,
+                            &self.source,
+                            error.span.start,
+                            error.span.end,
+                        ))?;
+                        if let Some(suggestion) = error.suggestion() {
+                            write!(f, "\n = help: {}", suggestion)?;
+                        }
+                    }
+                    Ok(())
                 }
                 CompilerError(crate::CompilerError { message, span }) => {
                     f.write_str(&format_error_with_excerpt(
-                        Some(&message),
+                        Some(message),
                         &self.source_path,
+                        None,
                         &self.source,
                         span.start,
                         span.end,
                     ))
                 }
-                IoError(e) => f.write_str(&e),
+                IoError(e) => f.write_str(e),
+                InvalidBytecode(e) => match &self.source_path {
+                    Some(path) => write!(f, "Failed to load '{}': {}", path.display(), e),
+                    None => write!(f, "Failed to load bytecode: {}", e),
+                },
             }
         }
     }
@@ -101,20 +159,101 @@ impl fmt::Display for LoaderError {
 impl error::Error for LoaderError {}
 
 /// Helper for loading, compiling, and caching Koto modules
-#[derive(Clone, Default)]
+///
+/// Modules are found by the loader's [ModuleResolver], by default a [FileSystemResolver] that
+/// loads modules from `.koto` source files, or from `.kotoc` files containing bytecode that
+/// was produced by [Chunk::to_bytes].
+#[derive(Clone)]
 pub struct Loader {
     chunks: HashMap<PathBuf, Arc<Chunk>>,
+    cache_dir: Option<PathBuf>,
+    resolver: Arc<dyn ModuleResolver>,
+    fold_constants: bool,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Self {
+            chunks: HashMap::new(),
+            cache_dir: None,
+            resolver: Arc::new(FileSystemResolver::default()),
+            fold_constants: false,
+        }
+    }
 }
 
 impl Loader {
+    /// Sets the resolver that's used to find imported modules
+    ///
+    /// Any previously loaded modules are cleared.
+    pub fn set_module_resolver(&mut self, resolver: Arc<dyn ModuleResolver>) {
+        self.resolver = resolver;
+        self.clear();
+    }
+
+    /// Sets a directory that compiled modules should be cached in
+    ///
+    /// When a module's source is loaded, the cache is checked for a previously compiled
+    /// version of the same source, allowing parsing and compilation to be skipped.
+    pub fn set_cache_dir(&mut self, cache_dir: Option<PathBuf>) {
+        self.cache_dir = cache_dir;
+    }
+
+    /// Enables constant folding for scripts and modules compiled by the loader
+    ///
+    /// See [CompilerSettings::fold_constants].
+    pub fn set_fold_constants(&mut self, enabled: bool) {
+        self.fold_constants = enabled;
+    }
+
+    /// Clears the loader's compiled modules
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Removes a compiled module from the loader, returning true if the module had been loaded
+    ///
+    /// The next time the module is loaded it will be compiled again.
+    pub fn invalidate_module(&mut self, id: &Path) -> bool {
+        self.chunks.remove(id).is_some()
+    }
+
+    /// Loads a chunk from bytecode that was produced by [Chunk::to_bytes]
+    pub fn load_bytecode(
+        &mut self,
+        bytes: &[u8],
+        source_path: Option<PathBuf>,
+    ) -> Result<Arc<Chunk>, LoaderError> {
+        match Chunk::from_bytes(bytes) {
+            Ok(mut chunk) => {
+                if source_path.is_some() {
+                    chunk.source_path = source_path;
+                }
+                Ok(Arc::new(chunk))
+            }
+            Err(e) => Err(LoaderError::invalid_bytecode(e, source_path)),
+        }
+    }
+
+    fn compiler_settings(&self, repl_mode: bool) -> CompilerSettings {
+        CompilerSettings {
+            repl_mode,
+            fold_constants: self.fold_constants,
+        }
+    }
+
     fn compile(
         &mut self,
         script: &str,
         script_path: Option<PathBuf>,
         compiler_settings: CompilerSettings,
     ) -> Result<Arc<Chunk>, LoaderError> {
-        match Parser::parse(&script) {
-            Ok((ast, constants)) => {
+        match Parser::parse_with_diagnostics(script) {
+            Ok((mut ast, mut constants)) => {
+                if compiler_settings.fold_constants {
+                    constants = fold_constants(&mut ast, constants);
+                }
+
                 let (bytes, mut debug_info) = match Compiler::compile(&ast, compiler_settings) {
                     Ok((bytes, debug_info)) => (bytes, debug_info),
                     Err(e) => return Err(LoaderError::from_compiler_error(e, script, script_path)),
@@ -129,12 +268,12 @@ impl Loader {
                     debug_info,
                 )))
             }
-            Err(e) => Err(LoaderError::from_parser_error(e, script, script_path)),
+            Err(errors) => Err(LoaderError::from_parser_errors(errors, script, script_path)),
         }
     }
 
     pub fn compile_repl(&mut self, script: &str) -> Result<Arc<Chunk>, LoaderError> {
-        self.compile(script, None, CompilerSettings { repl_mode: true })
+        self.compile(script, None, self.compiler_settings(true))
     }
 
     pub fn compile_script(
@@ -142,73 +281,108 @@ impl Loader {
         script: &str,
         script_path: &Option<PathBuf>,
     ) -> Result<Arc<Chunk>, LoaderError> {
-        self.compile(script, script_path.clone(), CompilerSettings::default())
+        self.compile(script, script_path.clone(), self.compiler_settings(false))
     }
 
+    /// Finds and loads the module with the given name using the loader's [ModuleResolver]
+    ///
+    /// `load_from_path` is the source path of the script that's importing the module.
+    ///
+    /// The loaded module is returned along with the module's id.
     pub fn compile_module(
         &mut self,
         name: &str,
         load_from_path: Option<PathBuf>,
     ) -> Result<(Arc<Chunk>, PathBuf), LoaderError> {
-        // Get either the directory of the provided path, or the current working directory
-        let path = match &load_from_path {
-            Some(path) => match path.canonicalize() {
-                Ok(canonicalized) if canonicalized.is_file() => match canonicalized.parent() {
-                    Some(parent_dir) => parent_dir.to_path_buf(),
-                    None => {
-                        return Err(LoaderError::io_error(
-                            "Failed to get parent of provided path".to_string(),
-                        ))
-                    }
-                },
-                Ok(canonicalized) => canonicalized,
-                Err(e) => return Err(LoaderError::io_error(e.to_string())),
-            },
-            None => match std::env::current_dir() {
-                Ok(path) => path,
-                Err(e) => return Err(LoaderError::io_error(e.to_string())),
-            },
-        };
+        let module = self.resolve_module(name, load_from_path.as_deref())?;
+        self.load_module(module)
+    }
 
-        let mut load_module_from_path = |module_path: PathBuf| match self.chunks.get(&module_path) {
-            Some(chunk) => Ok((chunk.clone(), module_path.clone())),
-            None => match std::fs::read_to_string(&module_path) {
-                Ok(script) => {
-                    let chunk = self.compile(
-                        &script,
-                        Some(module_path.clone()),
-                        CompilerSettings::default(),
-                    )?;
-
-                    self.chunks.insert(module_path.clone(), chunk.clone());
-                    Ok((chunk, module_path))
-                }
-                Err(_) => Err(LoaderError::io_error(format!(
-                    "File not found: {}",
-                    module_path.to_string_lossy()
-                ))),
-            },
+    /// Resolves a module name into a module's id without loading its source
+    ///
+    /// See [Loader::load_module].
+    pub fn resolve_module(
+        &self,
+        name: &str,
+        importing_path: Option<&Path>,
+    ) -> Result<ResolvedModule, LoaderError> {
+        self.resolver.resolve(name, importing_path)
+    }
+
+    /// Finds the path of the module with the given name on the filesystem
+    ///
+    /// Modules are searched for relative to `load_from_path` (or the current directory if no
+    /// path is provided), first as a neighbouring file with a matching name, and then as a
+    /// `main` file in a neighbouring directory with a matching name.
+    pub fn find_module(
+        name: &str,
+        load_from_path: Option<PathBuf>,
+    ) -> Result<PathBuf, LoaderError> {
+        FileSystemResolver::default().find_module(name, load_from_path.as_deref())
+    }
+
+    /// Loads a module that was found by [Loader::resolve_module]
+    ///
+    /// Modules are cached by id, so the module's source is only loaded and compiled the first
+    /// time it's loaded.
+    pub fn load_module(
+        &mut self,
+        module: ResolvedModule,
+    ) -> Result<(Arc<Chunk>, PathBuf), LoaderError> {
+        if let Some(chunk) = self.chunks.get(&module.id) {
+            return Ok((chunk.clone(), module.id));
+        }
+
+        let chunk = match self.resolver.load(&module)? {
+            ModuleSource::Script(script) => self.compile_module_source(&script, &module.id)?,
+            ModuleSource::Bytecode(bytes) => self.load_bytecode(&bytes, Some(module.id.clone()))?,
         };
 
-        let extension = "koto";
-        let named_path = path.join(name);
+        self.chunks.insert(module.id.clone(), chunk.clone());
+        Ok((chunk, module.id))
+    }
 
-        // first, check for a neighbouring file with a matching name
-        let module_path = named_path.with_extension(extension);
-        if module_path.exists() {
-            load_module_from_path(module_path)
-        } else {
-            // alternatively, check for a neighbouring directory with a matching name,
-            // containing a main file
-            let module_path = named_path.join("main").with_extension(extension);
-            if module_path.exists() {
-                load_module_from_path(module_path)
-            } else {
-                Err(LoaderError::io_error(format!(
-                    "Unable to find module '{}'",
-                    name
-                )))
+    // Compiles a module's source, making use of the cache directory if one has been set
+    fn compile_module_source(
+        &mut self,
+        script: &str,
+        module_path: &Path,
+    ) -> Result<Arc<Chunk>, LoaderError> {
+        let cache_path = self.cache_dir.as_ref().map(|cache_dir| {
+            let mut hasher = DefaultHasher::new();
+            module_path.hash(&mut hasher);
+            script.hash(&mut hasher);
+            self.fold_constants.hash(&mut hasher);
+            cache_dir.join(format!("{:016x}.kotoc", hasher.finish()))
+        });
+
+        if let Some(cache_path) = &cache_path {
+            if let Some(chunk) = fs::read(cache_path)
+                .ok()
+                .and_then(|bytes| Chunk::from_bytes(&bytes).ok())
+            {
+                // Guard against hash collisions
+                if chunk.debug_info.source == script
+                    && chunk.source_path.as_deref() == Some(module_path)
+                {
+                    return Ok(Arc::new(chunk));
+                }
             }
         }
+
+        let chunk = self.compile(
+            script,
+            Some(module_path.to_path_buf()),
+            self.compiler_settings(false),
+        )?;
+
+        if let (Some(cache_dir), Some(cache_path)) = (&self.cache_dir, &cache_path) {
+            // Failing to write to the cache isn't an error, the module will be recompiled
+            // next time it's loaded
+            let _ =
+                fs::create_dir_all(cache_dir).and_then(|_| fs::write(cache_path, chunk.to_bytes()));
+        }
+
+        Ok(chunk)
     }
 }
@@ -0,0 +1,238 @@
+use {
+    crate::LoaderError,
+    std::{
+        collections::HashMap,
+        env,
+        ffi::OsStr,
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+/// The environment variable that's used by [FileSystemResolver::from_env]
+pub const KOTO_PATH: &str = "KOTO_PATH";
+
+/// The contents of a module, as loaded by [ModuleResolver::load]
+#[derive(Clone, Debug)]
+pub enum ModuleSource {
+    /// The module's source code
+    Script(String),
+    /// The module's compiled bytecode, as produced by [Chunk::to_bytes](crate::Chunk::to_bytes)
+    Bytecode(Vec<u8>),
+}
+
+/// A module that was found by a [ModuleResolver]
+#[derive(Clone, Debug)]
+pub struct ResolvedModule {
+    /// A unique id for the module
+    ///
+    /// Modules with matching ids are only loaded once. The id is used as the module's source
+    /// path, which is included in error messages and passed back to the resolver when the module
+    /// imports other modules. The id doesn't need to refer to an existing file.
+    pub id: PathBuf,
+    /// The path of the file that the module will be loaded from, or None for virtual modules
+    pub file_path: Option<PathBuf>,
+}
+
+/// Resolves the names of imported modules, and loads their sources
+///
+/// Resolving and loading are separate steps so that a module's source is only loaded once it's
+/// known that the module is allowed to be imported, and that it isn't already cached.
+///
+/// The [Loader](crate::Loader) uses a [FileSystemResolver] by default, which can be replaced with
+/// [Loader::set_module_resolver](crate::Loader::set_module_resolver).
+pub trait ModuleResolver: Send + Sync {
+    /// Resolves a module name into the module's id
+    ///
+    /// `importing_path` is the source path of the script that's importing the module, if known.
+    fn resolve(
+        &self,
+        name: &str,
+        importing_path: Option<&Path>,
+    ) -> Result<ResolvedModule, LoaderError>;
+
+    /// Loads the source of a module that was returned by [ModuleResolver::resolve]
+    fn load(&self, module: &ResolvedModule) -> Result<ModuleSource, LoaderError>;
+}
+
+/// Resolves modules by loading them from the filesystem
+///
+/// Modules are searched for relative to the importing script (or the current directory if the
+/// importing script's path isn't known), and then in the resolver's search paths.
+///
+/// In each directory, a neighbouring `.koto` or `.kotoc` file with a matching name is looked for
+/// first, followed by a `main` file in a neighbouring directory with a matching name.
+#[derive(Clone, Debug, Default)]
+pub struct FileSystemResolver {
+    search_paths: Vec<PathBuf>,
+}
+
+impl FileSystemResolver {
+    /// Makes a resolver that falls back to looking for modules in the given directories
+    pub fn with_search_paths(search_paths: Vec<PathBuf>) -> Self {
+        Self { search_paths }
+    }
+
+    /// Makes a resolver that uses the search paths listed in the `KOTO_PATH` environment variable
+    ///
+    /// The paths are separated in the same way as the platform's `PATH` variable.
+    pub fn from_env() -> Self {
+        let search_paths = match env::var_os(KOTO_PATH) {
+            Some(paths) => env::split_paths(&paths)
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Self::with_search_paths(search_paths)
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    /// Finds the path of the module with the given name
+    pub fn find_module(
+        &self,
+        name: &str,
+        importing_path: Option<&Path>,
+    ) -> Result<PathBuf, LoaderError> {
+        let importing_dir = importing_dir(importing_path)?;
+
+        std::iter::once(&importing_dir)
+            .chain(self.search_paths.iter())
+            .find_map(|dir| find_module_in_dir(dir, name))
+            .ok_or_else(|| LoaderError::io_error(format!("Unable to find module '{}'", name)))
+    }
+}
+
+impl ModuleResolver for FileSystemResolver {
+    fn resolve(
+        &self,
+        name: &str,
+        importing_path: Option<&Path>,
+    ) -> Result<ResolvedModule, LoaderError> {
+        let path = self.find_module(name, importing_path)?;
+
+        Ok(ResolvedModule {
+            id: path.clone(),
+            file_path: Some(path),
+        })
+    }
+
+    fn load(&self, module: &ResolvedModule) -> Result<ModuleSource, LoaderError> {
+        let path = module.file_path.as_ref().unwrap_or(&module.id);
+
+        let file_not_found =
+            || LoaderError::io_error(format!("File not found: {}", path.to_string_lossy()));
+
+        let source = if path.extension() == Some(OsStr::new("kotoc")) {
+            ModuleSource::Bytecode(fs::read(path).map_err(|_| file_not_found())?)
+        } else {
+            ModuleSource::Script(fs::read_to_string(path).map_err(|_| file_not_found())?)
+        };
+
+        Ok(source)
+    }
+}
+
+/// Resolves modules from a map of module names to source code
+///
+/// This is useful for modules that are embedded in an application, or that are generated at
+/// runtime. Module names are matched exactly, regardless of the importing script's path.
+///
+/// # Example
+///
+/// ```
+/// use {
+///     koto_bytecode::{Loader, MemoryModuleResolver},
+///     std::sync::Arc,
+/// };
+///
+/// let mut resolver = MemoryModuleResolver::default();
+/// resolver.add_module("greetings", "export hello = \"Hello!\"");
+///
+/// let mut loader = Loader::default();
+/// loader.set_module_resolver(Arc::new(resolver));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryModuleResolver {
+    modules: HashMap<String, String>,
+}
+
+impl MemoryModuleResolver {
+    /// Adds a module with the given name, replacing any existing module with the same name
+    pub fn add_module(&mut self, name: &str, source: &str) {
+        self.modules.insert(name.to_string(), source.to_string());
+    }
+
+    pub fn remove_module(&mut self, name: &str) -> Option<String> {
+        self.modules.remove(name)
+    }
+}
+
+impl ModuleResolver for MemoryModuleResolver {
+    fn resolve(
+        &self,
+        name: &str,
+        _importing_path: Option<&Path>,
+    ) -> Result<ResolvedModule, LoaderError> {
+        if self.modules.contains_key(name) {
+            Ok(ResolvedModule {
+                id: PathBuf::from(name),
+                file_path: None,
+            })
+        } else {
+            Err(module_not_found(name))
+        }
+    }
+
+    fn load(&self, module: &ResolvedModule) -> Result<ModuleSource, LoaderError> {
+        let name = module.id.to_string_lossy();
+        match self.modules.get(name.as_ref()) {
+            Some(source) => Ok(ModuleSource::Script(source.clone())),
+            None => Err(module_not_found(&name)),
+        }
+    }
+}
+
+fn module_not_found(name: &str) -> LoaderError {
+    LoaderError::io_error(format!("Unable to find module '{}'", name))
+}
+
+// Returns either the directory of the provided path, or the current working directory
+fn importing_dir(importing_path: Option<&Path>) -> Result<PathBuf, LoaderError> {
+    match importing_path {
+        Some(path) => match path.canonicalize() {
+            Ok(canonicalized) if canonicalized.is_file() => match canonicalized.parent() {
+                Some(parent_dir) => Ok(parent_dir.to_path_buf()),
+                None => Err(LoaderError::io_error(
+                    "Failed to get parent of provided path".to_string(),
+                )),
+            },
+            Ok(canonicalized) => Ok(canonicalized),
+            Err(e) => Err(LoaderError::io_error(e.to_string())),
+        },
+        None => env::current_dir().map_err(|e| LoaderError::io_error(e.to_string())),
+    }
+}
+
+// Looks for a module with the given name in a directory
+//
+// First a neighbouring file with a matching name is looked for,
+// and then a neighbouring directory with a matching name containing a main file.
+fn find_module_in_dir(dir: &Path, name: &str) -> Option<PathBuf> {
+    let named_path = dir.join(name);
+
+    for module_path in &[named_path.clone(), named_path.join("main")] {
+        // Source files take priority over compiled bytecode
+        for extension in &["koto", "kotoc"] {
+            let path = module_path.with_extension(extension);
+            if path.exists() {
+                return Some(path);
+            }
+        }
+    }
+
+    None
+}
@@ -1,97 +1,102 @@
 /// The operation identifiers used in Koto bytecode
 ///
+/// Register operands are encoded as single bytes, and jump offsets as two bytes.
+/// Instructions that need more room are preceded by the [Op::Wide] prefix, which causes the
+/// instruction's registers to be encoded as two bytes, and its jump offsets as four bytes.
+///
 /// See [InstructionReader]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(u8)]
 pub enum Op {
-    Copy,             // target, source
-    SetEmpty,         // register
-    SetFalse,         // register
-    SetTrue,          // register
-    Set0,             // register
-    Set1,             // register
-    SetNumberU8,      // register, number
-    LoadFloat,        // register, constant
-    LoadFloatLong,    // register, constant[4]
-    LoadInt,          // register, constant
-    LoadIntLong,      // register, constant[4]
-    LoadString,       // register, constant
-    LoadStringLong,   // register, constant[4]
-    LoadGlobal,       // register, constant
-    LoadGlobalLong,   // register, constant[4]
-    SetGlobal,        // global, source
-    SetGlobalLong,    // global[4], source
-    Import,           // register, constant
-    ImportLong,       // register, constant[4]
-    MakeTuple,        // register, start register, count
-    MakeTempTuple,    // register, start register, count
-    MakeList,         // register, size hint
-    MakeListLong,     // register, size hint[4]
-    MakeMap,          // register, size hint
-    MakeMapLong,      // register, size hint[4]
-    MakeNum2,         // register, element count, first element
-    MakeNum4,         // register, element count, first element
-    MakeIterator,     // register, range
-    Function,         // register, arg count, capture count, flags, size[2]
-    Capture,          // function, target, source
-    Range,            // register, start, end
-    RangeInclusive,   // register, start, end
-    RangeTo,          // register, end
-    RangeToInclusive, // register, end
-    RangeFrom,        // register, start
-    RangeFull,        // register
-    Negate,           // register, source
-    Add,              // result, lhs, rhs
-    Subtract,         // result, lhs, rhs
-    Multiply,         // result, lhs, rhs
-    Divide,           // result, lhs, rhs
-    Modulo,           // result, lhs, rhs
-    Less,             // result, lhs, rhs
-    LessOrEqual,      // result, lhs, rhs
-    Greater,          // result, lhs, rhs
-    GreaterOrEqual,   // result, lhs, rhs
-    Equal,            // result, lhs, rhs
-    NotEqual,         // result, lhs, rhs
-    Jump,             // offset[2]
-    JumpTrue,         // condition, offset[2]
-    JumpFalse,        // condition, offset[2]
-    JumpBack,         // offset[2]
-    JumpBackFalse,    // offset[2]
-    Call,             // result, function, arg register, arg count
-    CallChild,        // result, function, arg register, arg count, parent
-    Return,           // register
-    Yield,            // register
-    IterNext,         // output, iterator, jump offset[2]
-    IterNextTemp,     // output, iterator, jump offset[2]
-    IterNextQuiet,    // iterator, jump offset[2]
-    ValueIndex,       // result, value register, signed index
-    SliceFrom,        // result, value register, signed index
-    SliceTo,          // result, value register, signed index
-    ListPushValue,    // list, value
-    ListPushValues,   // list, start register, count
-    ListUpdate,       // list, index, value
-    Index,            // result, list register, index register
-    MapInsert,        // map register, value register, key constant
-    MapInsertLong,    // map register, value register, key constant[4]
-    Access,           // register, value register, key
-    AccessLong,       // register, value register, key[4]
-    IsList,           // register, value
-    IsTuple,          // register, value
-    Size,             // register, value
-    TryStart,         // catch arg register, catch body offset[2]
-    TryEnd,           //
-    Debug,            // register, constant[4]
-    CheckType,        // register, type (see TypeId)
-    CheckSize,        // register, size
-    Unused79,
-    Unused80,
-    Unused81,
-    Unused82,
-    Unused83,
-    Unused84,
-    Unused85,
-    Unused86,
-    Unused87,
+    Copy,           // target, source
+    SetEmpty,       // register
+    SetFalse,       // register
+    SetTrue,        // register
+    Set0,           // register
+    Set1,           // register
+    SetNumberU8,    // register, number
+    LoadFloat,      // register, constant
+    LoadFloatLong,  // register, constant[4]
+    LoadInt,        // register, constant
+    LoadIntLong,    // register, constant[4]
+    LoadString,     // register, constant
+    LoadStringLong, // register, constant[4]
+    LoadGlobal,     // register, constant
+    LoadGlobalLong, // register, constant[4]
+    SetGlobal,      // source, global
+    SetGlobalLong,  // source, global[4]
+    Import,         // register, constant
+    ImportLong,     // register, constant[4]
+    MakeTuple,      // register, start register, count
+    MakeTempTuple,  // register, start register, count
+    MakeString,     // register, start register, count[2]
+    MakeList,       // register, size hint
+    MakeListLong,   // register, size hint[4]
+    MakeMap,        // register, size hint
+    MakeMapLong,    // register, size hint[4]
+    MakeNum2,       // register, first element, element count
+    MakeNum4,       // register, first element, element count
+    MakeIterator,   // register, range
+    Function,       // register, arg count, optional arg count, capture count, flags,
+    //                   arg name constants[4 * arg count], size[2]
+    Capture,           // function, source, target
+    Range,             // register, start, end
+    RangeInclusive,    // register, start, end
+    RangeTo,           // register, end
+    RangeToInclusive,  // register, end
+    RangeFrom,         // register, start
+    RangeFull,         // register
+    Negate,            // register, source
+    Add,               // result, lhs, rhs
+    Subtract,          // result, lhs, rhs
+    Multiply,          // result, lhs, rhs
+    Divide,            // result, lhs, rhs
+    Modulo,            // result, lhs, rhs
+    Less,              // result, lhs, rhs
+    LessOrEqual,       // result, lhs, rhs
+    Greater,           // result, lhs, rhs
+    GreaterOrEqual,    // result, lhs, rhs
+    Equal,             // result, lhs, rhs
+    NotEqual,          // result, lhs, rhs
+    Jump,              // offset[2]
+    JumpTrue,          // condition, offset[2]
+    JumpFalse,         // condition, offset[2]
+    JumpBack,          // offset[2]
+    JumpBackFalse,     // offset[2]
+    Call,              // result, function, arg register, arg count
+    CallChild,         // result, function, arg register, parent, arg count
+    Return,            // register
+    Yield,             // register
+    IterNext,          // output, iterator, jump offset[2]
+    IterNextTemp,      // output, iterator, jump offset[2]
+    IterNextQuiet,     // iterator, jump offset[2]
+    ValueIndex,        // result, value register, signed index
+    SliceFrom,         // result, value register, signed index
+    SliceTo,           // result, value register, signed index
+    ListPushValue,     // list, value
+    ListPushValues,    // list, start register, count
+    ListUpdate,        // list, index, value
+    Index,             // result, list register, index register
+    MapInsert,         // map register, value register, key constant
+    MapInsertLong,     // map register, value register, key constant[4]
+    MetaInsert,        // map register, value register, meta id (see MetaKeyId)
+    Access,            // register, value register, key
+    AccessLong,        // register, value register, key[4]
+    IsList,            // register, value
+    IsTuple,           // register, value
+    Size,              // register, value
+    TryStart,          // catch arg register, catch body offset[2]
+    TryEnd,            //
+    Debug,             // register, constant[4]
+    CheckType,         // register, type (see TypeId)
+    CheckSize,         // register, size
+    Wide,              // Prefix for an instruction with wide registers and offsets
+    TailCall,          // result, function, arg register, arg count
+    TailCallChild,     // result, function, arg register, parent, arg count
+    Throw,             // register
+    JumpIfArgProvided, // register, offset[2]
+    CallNamed,         // result, function, arg register, arg count (named args map follows args)
+    CallChildNamed,    // result, function, arg register, parent, arg count (as above)
     Unused88,
     Unused89,
     Unused90,
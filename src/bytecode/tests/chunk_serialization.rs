@@ -0,0 +1,168 @@
+mod bytecode {
+    use {
+        koto_bytecode::{Chunk, DebugInfo, Loader, Op},
+        koto_parser::ConstantPoolBuilder,
+    };
+
+    fn compile(script: &str) -> Chunk {
+        let mut loader = Loader::default();
+        let chunk = loader
+            .compile_script(script, &Some("test.koto".into()))
+            .expect("Failed to compile script");
+        (*chunk).clone()
+    }
+
+    fn check_round_trip(script: &str) {
+        let chunk = compile(script);
+        let bytes = chunk.to_bytes();
+        match Chunk::from_bytes(&bytes) {
+            Ok(loaded) => {
+                assert_eq!(loaded, chunk);
+                assert_eq!(loaded.constants.to_string(), chunk.constants.to_string());
+                assert_eq!(loaded.string_constants_arc, chunk.string_constants_arc);
+                assert_eq!(loaded.debug_info.source, script);
+                for ip in 0..chunk.bytes.len() {
+                    assert_eq!(
+                        loaded.debug_info.get_source_span(ip),
+                        chunk.debug_info.get_source_span(ip)
+                    );
+                }
+            }
+            Err(error) => panic!("Failed to load chunk: {}", error),
+        }
+    }
+
+    mod round_trip {
+        use super::*;
+
+        #[test]
+        fn empty_script() {
+            check_round_trip("");
+        }
+
+        #[test]
+        fn constants() {
+            check_round_trip(
+                r#"
+a = 1, -2, 3.5, 1e300, "hello", "🦀"
+b = {foo: 42, bar: "{a[4]}!"}
+"#,
+            );
+        }
+
+        #[test]
+        fn functions() {
+            check_round_trip(
+                "
+f = |x, y|
+  for i in 0..x
+    if i > y
+      return i
+  -1
+f 10, 5
+",
+            );
+        }
+
+        #[test]
+        fn wide_operands() {
+            // More than 255 locals causes registers to be encoded with wide operands
+            let mut script = String::from("f = |n|\n");
+            for i in 0..300 {
+                script.push_str(&format!("  x{} = n + {}\n", i, i));
+            }
+            script.push_str("  if n > 0\n    x299\n  else\n    x0\nf 1\n");
+            check_round_trip(&script);
+        }
+
+        #[test]
+        fn without_source_path() {
+            let mut loader = Loader::default();
+            let chunk = loader.compile_script("x = 42", &None).unwrap();
+            let loaded = Chunk::from_bytes(&chunk.to_bytes()).unwrap();
+            assert_eq!(loaded.source_path, None);
+            assert_eq!(loaded, *chunk);
+        }
+    }
+
+    mod invalid_bytecode {
+        use super::*;
+
+        #[test]
+        fn missing_header() {
+            assert!(Chunk::from_bytes(b"").is_err());
+            assert!(Chunk::from_bytes(b"not bytecode").is_err());
+        }
+
+        #[test]
+        fn incompatible_version() {
+            let mut bytes = compile("x = 1").to_bytes();
+            bytes[4] = bytes[4].wrapping_add(1); // format version
+            assert!(Chunk::from_bytes(&bytes).is_err());
+        }
+
+        #[test]
+        fn truncated() {
+            let bytes = compile("x = [1, 2, 3]").to_bytes();
+            for len in 0..bytes.len() {
+                assert!(Chunk::from_bytes(&bytes[..len]).is_err());
+            }
+        }
+
+        #[test]
+        fn trailing_data() {
+            let mut bytes = compile("x = 1").to_bytes();
+            bytes.push(0);
+            assert!(Chunk::from_bytes(&bytes).is_err());
+        }
+
+        fn check_invalid_instructions(bytes: &[u8]) {
+            let mut constants = ConstantPoolBuilder::new();
+            constants.add_string("x");
+            constants.add_f64(1.5);
+            let chunk = Chunk::new(
+                bytes.to_vec(),
+                constants.build(),
+                None,
+                DebugInfo::default(),
+            );
+            assert!(Chunk::from_bytes(&chunk.to_bytes()).is_err());
+        }
+
+        #[test]
+        fn valid_instructions() {
+            let mut constants = ConstantPoolBuilder::new();
+            constants.add_string("x");
+            constants.add_f64(1.5);
+            let chunk = Chunk::new(
+                vec![Op::LoadFloat as u8, 0, 1, Op::Jump as u8, 0, 0],
+                constants.build(),
+                None,
+                DebugInfo::default(),
+            );
+            assert!(Chunk::from_bytes(&chunk.to_bytes()).is_ok());
+        }
+
+        #[test]
+        fn missing_constant() {
+            check_invalid_instructions(&[Op::LoadString as u8, 0, 2]);
+        }
+
+        #[test]
+        fn constant_with_wrong_type() {
+            check_invalid_instructions(&[Op::LoadFloat as u8, 0, 0]);
+            check_invalid_instructions(&[Op::LoadGlobal as u8, 0, 1]);
+        }
+
+        #[test]
+        fn jump_out_of_bounds() {
+            check_invalid_instructions(&[Op::Jump as u8, 1, 0]);
+            check_invalid_instructions(&[Op::JumpBack as u8, 4, 0]);
+        }
+
+        #[test]
+        fn truncated_instruction() {
+            check_invalid_instructions(&[Op::Add as u8, 0, 1]);
+        }
+    }
+}
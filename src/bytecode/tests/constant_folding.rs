@@ -0,0 +1,308 @@
+mod constant_folding {
+    use {
+        koto_bytecode::{fold_constants, Loader},
+        koto_parser::{Ast, ConstantPool, Node, Parser},
+    };
+
+    fn fold(source: &str) -> (Ast, ConstantPool) {
+        match Parser::parse(source) {
+            Ok((mut ast, constants)) => {
+                let constants = fold_constants(&mut ast, constants);
+                (ast, constants)
+            }
+            Err(parser_error) => {
+                panic!("Failure while parsing:\n{}\n{}", source, parser_error);
+            }
+        }
+    }
+
+    // Returns the last expression in the script's main block
+    fn last_expression(ast: &Ast) -> &Node {
+        match &ast.entry_point().unwrap().node {
+            Node::MainBlock { body, .. } => &ast.node(*body.last().unwrap()).node,
+            other => panic!("Expected main block, found {}", other),
+        }
+    }
+
+    fn check_int(source: &str, expected: i64) {
+        let (ast, constants) = fold(source);
+        match last_expression(&ast) {
+            Node::Int(constant) => assert_eq!(constants.get_i64(*constant), expected),
+            other => panic!("Expected Int, found {}", other),
+        }
+    }
+
+    fn check_float(source: &str, expected: f64) {
+        let (ast, constants) = fold(source);
+        match last_expression(&ast) {
+            Node::Float(constant) => assert_eq!(constants.get_f64(*constant), expected),
+            other => panic!("Expected Float, found {}", other),
+        }
+    }
+
+    fn check_str(source: &str, expected: &str) {
+        let (ast, constants) = fold(source);
+        match last_expression(&ast) {
+            Node::Str(constant) => assert_eq!(constants.get_str(*constant), expected),
+            other => panic!("Expected Str, found {}", other),
+        }
+    }
+
+    fn check_node(source: &str, expected: Node) {
+        let (ast, _) = fold(source);
+        assert_eq!(last_expression(&ast), &expected);
+    }
+
+    fn check_unfolded(source: &str) {
+        let (ast, _) = fold(source);
+        match last_expression(&ast) {
+            Node::BinaryOp { .. } | Node::Negate(_) => {}
+            other => panic!(
+                "Expected the expression to be left unfolded, found {}",
+                other
+            ),
+        }
+    }
+
+    mod arithmetic {
+        use super::*;
+
+        #[test]
+        fn nested_ints() {
+            check_int("60 * 60 * 24", 86400);
+        }
+
+        #[test]
+        fn ints_and_floats() {
+            check_float("1 + 2.5", 3.5);
+            check_float("1 / 4", 0.25);
+            check_float("(2 - 0.5) * 2", 3.0);
+        }
+
+        #[test]
+        fn results_of_zero_and_one() {
+            check_node("2 - 2", Node::Number0);
+            check_node("3 % 2", Node::Number1);
+        }
+
+        #[test]
+        fn negation() {
+            check_int("-(2 * 3)", -6);
+            check_node("not (1 < 2)", Node::BoolFalse);
+        }
+
+        #[test]
+        fn string_concatenation() {
+            check_str("\"foo\" + \"bar\"", "foobar");
+        }
+
+        #[test]
+        fn operations_that_fail_at_runtime_are_left_unfolded() {
+            check_unfolded("9223372036854775807 + 1");
+            check_unfolded("1 % 0");
+            check_unfolded("1 / 0");
+            check_unfolded("1 + \"foo\"");
+            check_unfolded("\"foo\" * 2");
+            check_unfolded("-\"foo\"");
+        }
+
+        #[test]
+        fn non_constant_operands_are_left_unfolded() {
+            check_unfolded("x = 1\nx + 1");
+        }
+    }
+
+    mod comparisons {
+        use super::*;
+
+        #[test]
+        fn numbers() {
+            check_node("1 < 2.5", Node::BoolTrue);
+            check_node("2 == 2.0", Node::BoolTrue);
+            check_node("3 >= 4", Node::BoolFalse);
+        }
+
+        #[test]
+        fn strings() {
+            check_node("\"abc\" < \"abd\"", Node::BoolTrue);
+            check_node("\"abc\" != \"abc\"", Node::BoolFalse);
+        }
+
+        #[test]
+        fn chained_comparisons() {
+            check_node("1 < 2 <= 2 < 3", Node::BoolTrue);
+            check_node("1 < 3 < 2", Node::BoolFalse);
+            check_node("(3 > 2) == true", Node::BoolTrue);
+        }
+
+        #[test]
+        fn chains_with_non_constant_operands_are_left_unfolded() {
+            check_unfolded("x = 2\n1 < x < 3");
+        }
+
+        #[test]
+        fn mismatched_types_are_left_unfolded() {
+            check_unfolded("1 < \"foo\"");
+            check_unfolded("true < false");
+        }
+    }
+
+    mod logic {
+        use super::*;
+
+        #[test]
+        fn short_circuiting() {
+            check_node("false and x", Node::BoolFalse);
+            check_node("true or x", Node::BoolTrue);
+        }
+
+        #[test]
+        fn result_is_rhs() {
+            let (ast, _) = fold("true and x");
+            assert!(matches!(last_expression(&ast), Node::Id(_)));
+
+            check_node("false or 1 < 2", Node::BoolTrue);
+        }
+
+        #[test]
+        fn non_bool_lhs_is_left_unfolded() {
+            check_unfolded("1 and true");
+        }
+    }
+
+    mod branches {
+        use super::*;
+
+        #[test]
+        fn if_true() {
+            check_int("if 1 < 2 then 42 else 99", 42);
+        }
+
+        #[test]
+        fn if_false() {
+            check_int("if 1 > 2 then 42 else 99", 99);
+            check_node("if false then 42", Node::Empty);
+        }
+
+        #[test]
+        fn else_if() {
+            let source = "
+x = 1
+if x > 0
+  42
+else if 1 == 1
+  99
+else
+  -1
+";
+            let (ast, _) = fold(source);
+            match last_expression(&ast) {
+                Node::If(ast_if) => {
+                    assert!(ast_if.else_if_blocks.is_empty());
+                    let else_node = ast_if.else_node.expect("Missing else node");
+                    assert!(matches!(ast.node(else_node).node, Node::Int(_)));
+                }
+                other => panic!("Expected If, found {}", other),
+            }
+        }
+
+        #[test]
+        fn unreachable_else_if() {
+            let source = "
+if false
+  42
+else if 2 > 1
+  99
+else
+  -1
+";
+            check_int(source, 99);
+        }
+
+        #[test]
+        fn switch() {
+            let source = "
+switch
+  1 > 2 then 42
+  1 < 2 then 99
+  else -1
+";
+            check_int(source, 99);
+        }
+
+        #[test]
+        fn switch_with_non_constant_arms() {
+            let source = "
+x = 1
+switch
+  false then 42
+  x > 0 then 99
+  true then -1
+  else 0
+";
+            let (ast, _) = fold(source);
+            match last_expression(&ast) {
+                Node::Switch(arms) => {
+                    assert_eq!(arms.len(), 2);
+                    assert!(arms[0].condition.is_some());
+                    assert!(arms[1].condition.is_none());
+                }
+                other => panic!("Expected Switch, found {}", other),
+            }
+        }
+
+        #[test]
+        fn branches_that_assign_locals_are_kept() {
+            let (ast, _) = fold("if false then x = 1\nx");
+            let main_block = match &ast.entry_point().unwrap().node {
+                Node::MainBlock { body, .. } => body.clone(),
+                other => panic!("Expected main block, found {}", other),
+            };
+            assert!(matches!(ast.node(main_block[0]).node, Node::If(_)));
+
+            let (ast, _) = fold("switch\n  true then 42\n  else y = 99\ny");
+            let main_block = match &ast.entry_point().unwrap().node {
+                Node::MainBlock { body, .. } => body.clone(),
+                other => panic!("Expected main block, found {}", other),
+            };
+            assert!(matches!(ast.node(main_block[0]).node, Node::Switch(_)));
+        }
+
+        #[test]
+        fn non_bool_conditions_are_left_unfolded() {
+            let (ast, _) = fold("if 1 then 42");
+            assert!(matches!(last_expression(&ast), Node::If(_)));
+        }
+    }
+
+    #[test]
+    fn existing_constants_are_preserved() {
+        let source = "
+x = \"foo\"
+y = 60 * 60
+";
+        let (_, original) = Parser::parse(source).unwrap();
+        let (_, folded) = fold(source);
+
+        assert!(folded.len() > original.len());
+        for (a, b) in original.iter().zip(folded.iter()) {
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn loader_setting() {
+        let source = "
+if false
+  x = 60 * 60 * 24
+  debug x
+";
+        let mut loader = Loader::default();
+        let unfolded = loader.compile_script(source, &None).unwrap();
+
+        loader.set_fold_constants(true);
+        let folded = loader.compile_script(source, &None).unwrap();
+
+        assert!(folded.bytes.len() < unfolded.bytes.len());
+    }
+}
@@ -0,0 +1,28 @@
+mod loader {
+    use koto_bytecode::Loader;
+
+    #[test]
+    fn multiple_parser_errors_are_reported() {
+        let source = "
+x = [1, 2
+y = 42
+z = (1 + 2
+";
+        let error = Loader::default().compile_script(source, &None).unwrap_err();
+
+        let lines = error
+            .parser_errors()
+            .iter()
+            .map(|error| error.span.start.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, &[2, 4]);
+
+        let message = error.to_string();
+        assert!(message.contains(" --- 2:"), "{}", message);
+        assert!(message.contains(" --- 4:"), "{}", message);
+        assert!(message.contains("help: add a closing ']'"), "{}", message);
+        assert!(message.contains("help: add a closing ')'"), "{}", message);
+
+        assert_eq!(format!("{:#}", error).lines().count(), 2);
+    }
+}
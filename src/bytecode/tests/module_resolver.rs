@@ -0,0 +1,101 @@
+use {
+    koto_bytecode::{
+        FileSystemResolver, Loader, MemoryModuleResolver, ModuleResolver, ModuleSource,
+    },
+    std::{fs, path::PathBuf, sync::Arc},
+    tempfile::tempdir,
+};
+
+mod file_system_resolver {
+    use super::*;
+
+    #[test]
+    fn neighbouring_files_and_dirs() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().canonicalize().unwrap();
+        let main_path = dir.join("main.koto");
+        fs::write(&main_path, "").unwrap();
+        fs::write(dir.join("foo.koto"), "export x = 1").unwrap();
+        fs::create_dir(dir.join("bar")).unwrap();
+        fs::write(dir.join("bar").join("main.koto"), "export y = 2").unwrap();
+
+        let resolver = FileSystemResolver::default();
+
+        let foo = resolver.resolve("foo", Some(&main_path)).unwrap();
+        assert_eq!(foo.id, dir.join("foo.koto"));
+        assert_eq!(foo.file_path, Some(dir.join("foo.koto")));
+        assert!(matches!(
+            resolver.load(&foo).unwrap(),
+            ModuleSource::Script(s) if s == "export x = 1"
+        ));
+
+        let bar = resolver.resolve("bar", Some(&main_path)).unwrap();
+        assert_eq!(bar.id, dir.join("bar").join("main.koto"));
+
+        assert!(resolver.resolve("baz", Some(&main_path)).is_err());
+    }
+
+    #[test]
+    fn search_paths() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path().canonicalize().unwrap();
+        let scripts = dir.join("scripts");
+        let lib_a = dir.join("lib_a");
+        let lib_b = dir.join("lib_b");
+        for sub_dir in &[&scripts, &lib_a, &lib_b] {
+            fs::create_dir(sub_dir).unwrap();
+        }
+        let main_path = scripts.join("main.koto");
+        fs::write(&main_path, "").unwrap();
+        fs::write(scripts.join("local.koto"), "").unwrap();
+        fs::write(lib_a.join("local.koto"), "").unwrap();
+        fs::write(lib_a.join("shared.koto"), "").unwrap();
+        fs::write(lib_b.join("shared.koto"), "").unwrap();
+        fs::write(lib_b.join("other.koto"), "").unwrap();
+
+        let resolver = FileSystemResolver::with_search_paths(vec![lib_a.clone(), lib_b.clone()]);
+        let find = |name| resolver.find_module(name, Some(&main_path)).unwrap();
+
+        // Modules next to the importing script take priority over the search paths
+        assert_eq!(find("local"), scripts.join("local.koto"));
+        // Search paths are checked in order
+        assert_eq!(find("shared"), lib_a.join("shared.koto"));
+        assert_eq!(find("other"), lib_b.join("other.koto"));
+    }
+}
+
+mod memory_resolver {
+    use super::*;
+
+    #[test]
+    fn load_modules() {
+        let mut resolver = MemoryModuleResolver::default();
+        resolver.add_module("foo", "export x = 42");
+
+        let mut loader = Loader::default();
+        loader.set_module_resolver(Arc::new(resolver));
+
+        let (chunk, id) = loader.compile_module("foo", None).unwrap();
+        assert_eq!(id, PathBuf::from("foo"));
+        assert_eq!(chunk.source_path, Some(PathBuf::from("foo")));
+
+        // Modules are cached by id
+        let (cached_chunk, _) = loader.compile_module("foo", None).unwrap();
+        assert!(Arc::ptr_eq(&chunk, &cached_chunk));
+
+        let error = loader.compile_module("bar", None).unwrap_err();
+        assert_eq!(error.to_string(), "Unable to find module 'bar'");
+    }
+
+    #[test]
+    fn errors_include_module_id() {
+        let mut resolver = MemoryModuleResolver::default();
+        resolver.add_module("broken", "x = [1, 2");
+
+        let mut loader = Loader::default();
+        loader.set_module_resolver(Arc::new(resolver));
+
+        let error = loader.compile_module("broken", None).unwrap_err();
+        assert!(error.to_string().contains("broken"));
+    }
+}
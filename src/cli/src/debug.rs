@@ -0,0 +1,192 @@
+use {
+    koto::runtime::{
+        Breakpoint, Breakpoints, DebugCommand, DebugFrame, DebugHandler, DebugState, Debugger,
+        PauseReason,
+    },
+    std::{
+        io::{self, BufRead, Write},
+        path::PathBuf,
+    },
+};
+
+const PROMPT: &str = "(debug) ";
+
+const HELP: &str = "\
+COMMANDS:
+    c, continue           Run until the next breakpoint is reached
+    s, step               Step to the next line, entering called functions
+    n, next               Step to the next line, stepping over called functions
+    o, out                Run until the current function returns
+    b, break <location>   Add a breakpoint, e.g. 'b 12' or 'b script.koto:12'
+    d, delete <location>  Remove a breakpoint
+    bl, breakpoints       List the active breakpoints
+    l, locals             Show the current frame's local values
+    r, registers          Show the current frame's registers
+    bt, backtrace         Show the call stack
+    g, globals            Show the module's global values
+    q, quit               Stop the script
+    h, help               Show this help
+
+Entering an empty line repeats the last step command.";
+
+/// Returns a debugger that pauses at the start of the script, controlled from the terminal
+pub fn make_debugger() -> Debugger {
+    let mut debugger = Debugger::new(TerminalDebugger::default());
+    debugger.pause();
+    debugger
+}
+
+#[derive(Default)]
+struct TerminalDebugger {
+    last_command: Option<DebugCommand>,
+}
+
+impl DebugHandler for TerminalDebugger {
+    fn on_pause(&mut self, state: &DebugState, breakpoints: &mut Breakpoints) -> DebugCommand {
+        let frame = state.current_frame();
+
+        let reason = match state.reason {
+            PauseReason::Breakpoint => "Breakpoint",
+            PauseReason::Step => "Paused",
+        };
+        println!("{} at {}", reason, frame_location(frame));
+        if let (Some(line), Some(source_line)) = (frame.line(), frame.source_line()) {
+            println!("{:>5} | {}", line, source_line);
+        }
+
+        let stdin = io::stdin();
+        let mut input = String::new();
+
+        loop {
+            print!("{}", PROMPT);
+            io::stdout().flush().expect("Failed to flush stdout");
+
+            input.clear();
+            match stdin.lock().read_line(&mut input) {
+                // Stop the script when the end of the input has been reached
+                Ok(0) | Err(_) => return DebugCommand::Stop,
+                Ok(_) => {}
+            }
+
+            let mut words = input.split_whitespace();
+            let command = words.next();
+            let argument = words.next();
+
+            let step_command = match command {
+                None => match self.last_command {
+                    Some(command) => command,
+                    None => continue,
+                },
+                Some("c") | Some("continue") => DebugCommand::Continue,
+                Some("s") | Some("step") => DebugCommand::StepIn,
+                Some("n") | Some("next") => DebugCommand::StepOver,
+                Some("o") | Some("out") => DebugCommand::StepOut,
+                Some("q") | Some("quit") => DebugCommand::Stop,
+                Some("b") | Some("break") => {
+                    match parse_breakpoint(argument, frame) {
+                        Ok(breakpoint) => {
+                            if breakpoints.add(breakpoint.clone()) {
+                                println!("Added breakpoint at {}", breakpoint);
+                            } else {
+                                println!("A breakpoint already exists at {}", breakpoint);
+                            }
+                        }
+                        Err(error) => println!("{}", error),
+                    }
+                    continue;
+                }
+                Some("d") | Some("delete") => {
+                    match parse_breakpoint(argument, frame) {
+                        Ok(breakpoint) => {
+                            if breakpoints.remove(&breakpoint) {
+                                println!("Removed breakpoint at {}", breakpoint);
+                            } else {
+                                println!("No breakpoint found at {}", breakpoint);
+                            }
+                        }
+                        Err(error) => println!("{}", error),
+                    }
+                    continue;
+                }
+                Some("bl") | Some("breakpoints") => {
+                    for breakpoint in breakpoints.iter() {
+                        println!("  {}", breakpoint);
+                    }
+                    continue;
+                }
+                Some("l") | Some("locals") => {
+                    for (name, value) in frame.locals.iter() {
+                        println!("  {}: {:#}", name, value);
+                    }
+                    continue;
+                }
+                Some("r") | Some("registers") => {
+                    for (register, value) in frame.registers.iter().enumerate() {
+                        println!("  {}: {:#}", register, value);
+                    }
+                    continue;
+                }
+                Some("bt") | Some("backtrace") => {
+                    for (index, frame) in state.frames.iter().enumerate() {
+                        println!("  {}: {}", index, frame_location(frame));
+                    }
+                    continue;
+                }
+                Some("g") | Some("globals") => {
+                    for (key, value) in state.globals.cloned_iter() {
+                        println!("  {}: {:#}", key, value);
+                    }
+                    continue;
+                }
+                Some("h") | Some("help") => {
+                    println!("{}", HELP);
+                    continue;
+                }
+                Some(other) => {
+                    println!("Unknown command '{}', see 'help'", other);
+                    continue;
+                }
+            };
+
+            if step_command != DebugCommand::Stop {
+                self.last_command = Some(step_command);
+            }
+            return step_command;
+        }
+    }
+}
+
+fn frame_location(frame: &DebugFrame) -> String {
+    let path = match &frame.chunk.source_path {
+        Some(path) => path.display().to_string(),
+        None => "<script>".to_string(),
+    };
+    let location = match frame.line() {
+        Some(line) => format!("{}:{}", path, line),
+        None => path,
+    };
+    match &frame.function {
+        Some(function) => format!("'{}' ({})", function, location),
+        None => location,
+    }
+}
+
+// Parses a breakpoint location in the form '<line>' or '<path>:<line>'
+//
+// If no path is provided then the breakpoint is placed in the current frame's script.
+fn parse_breakpoint(location: Option<&str>, frame: &DebugFrame) -> Result<Breakpoint, String> {
+    let location = location.ok_or("Expected a breakpoint location, e.g. 'b 12'")?;
+
+    let (path, line) = match location.rfind(':') {
+        Some(separator) => (
+            Some(PathBuf::from(&location[..separator])),
+            &location[separator + 1..],
+        ),
+        None => (frame.chunk.source_path.clone(), location),
+    };
+
+    match line.parse::<u32>() {
+        Ok(line) if line > 0 => Ok(Breakpoint { path, line }),
+        _ => Err(format!("Invalid line number '{}'", line)),
+    }
+}
@@ -0,0 +1,99 @@
+use std::{ffi::OsString, fs};
+
+pub fn help_string() -> String {
+    format!(
+        "{version}
+
+Formats Koto scripts
+
+USAGE:
+    koto fmt [FLAGS] <files>...
+
+FLAGS:
+    -c, --check  Check that the files are formatted without changing them
+    -h, --help   Prints help information
+
+ARGS:
+    <files>...   The scripts to format, each script is rewritten in place
+",
+        version = crate::version_string()
+    )
+}
+
+struct FormatArgs {
+    help: bool,
+    check: bool,
+    files: Vec<String>,
+}
+
+fn parse_arguments(args: Vec<OsString>) -> Result<FormatArgs, String> {
+    let mut args = pico_args::Arguments::from_vec(args);
+
+    let help = args.contains(["-h", "--help"]);
+    let check = args.contains(["-c", "--check"]);
+
+    let files = args
+        .free()
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+
+    if files.is_empty() && !help {
+        return Err("At least one script to format is required".into());
+    }
+
+    Ok(FormatArgs { help, check, files })
+}
+
+// Runs the `fmt` subcommand with the arguments that follow it
+//
+// Returns true if all of the files were formatted successfully, or when checking, if all of the
+// files are already formatted.
+pub fn run(args: Vec<OsString>) -> bool {
+    let args = match parse_arguments(args) {
+        Ok(args) => args,
+        Err(error) => {
+            println!("{}\n\n{}", help_string(), error);
+            return false;
+        }
+    };
+
+    if args.help {
+        println!("{}", help_string());
+        return true;
+    }
+
+    let mut success = true;
+
+    for path in args.files.iter() {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Failed to read '{}': {}", path, e);
+                success = false;
+                continue;
+            }
+        };
+
+        let formatted = match koto_format::format(&source) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("Failed to format '{}': {}", path, e);
+                success = false;
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        }
+
+        if args.check {
+            println!("{}", path);
+            success = false;
+        } else if let Err(e) = fs::write(path, formatted) {
+            eprintln!("Failed to write '{}': {}", path, e);
+            success = false;
+        }
+    }
+
+    success
+}
@@ -1,15 +1,36 @@
+mod debug;
+mod format;
 mod repl;
+mod test;
 
 use {
-    koto::{Koto, KotoSettings},
+    koto::{
+        bytecode::FileSystemResolver,
+        runtime::{ExecutionLimit, ExecutionLimits, Profiler, RuntimeError},
+        Koto, KotoError, KotoSettings,
+    },
     repl::Repl,
-    std::fs,
+    std::{
+        ffi::OsString,
+        fs,
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::Duration,
+    },
 };
 
-#[cfg(all(jemalloc, not(target_env = "msvc")))]
+#[cfg(all(feature = "jemalloc", not(target_env = "msvc")))]
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
+fn add_libs_to_prelude(koto: &Koto) {
+    let mut prelude = koto.prelude();
+    prelude.add_map("json", koto_json::make_module());
+    prelude.add_map("random", koto_random::make_module());
+    prelude.add_map("tempfile", koto_tempfile::make_module());
+    prelude.add_map("toml", koto_toml::make_module());
+}
+
 fn version_string() -> String {
     format!("Koto {}", env!("CARGO_PKG_VERSION"))
 }
@@ -20,17 +41,37 @@ fn help_string() -> String {
 
 USAGE:
     koto [FLAGS] [script] [<args>...]
+    koto fmt [--check] <files>...
+    koto test [--filter <name>] [--format <format>] <scripts>...
 
 FLAGS:
     -i, --show_instructions  Show compiled instructions annotated with source lines
     -b, --show_bytecode      Show the script's compiled bytecode
+    -O, --fold_constants     Fold constant expressions and remove unreachable branches
     -t, --tests              Run the script's tests before running the script
+    -d, --debug              Run the script in the interactive debugger
+    -p, --profile            Print a profile of the script's function calls and lines after running
     -h, --help               Prints help information
     -v, --version            Prints version information
 
+OPTIONS:
+    --timeout <seconds>         Stop execution after the given number of seconds
+    --instruction_limit <count> Stop execution after the given number of instructions
+    --compile <output>          Compile the script to bytecode and save it to the output path
+    --module_cache <dir>        Cache compiled modules in the given directory
+    --profile_output <path>     Save the script's profile, as a Chrome trace if the path ends
+                                with '.json', otherwise as folded stacks for flame graphs
+
 ARGS:
-    <script>     The koto script to run
+    <script>     The koto script to run, either as source or as compiled '.kotoc' bytecode
     <args>...    Arguments to pass into the script
+
+SUBCOMMANDS:
+    fmt          Formats Koto scripts, see 'koto fmt --help'
+    test         Runs the tests exported by Koto scripts, see 'koto test --help'
+
+ENVIRONMENT:
+    KOTO_PATH    Directories to search for imported modules, separated like PATH
 ",
         version = version_string()
     )
@@ -41,8 +82,16 @@ struct KotoArgs {
     help: bool,
     version: bool,
     run_tests: bool,
+    debug: bool,
+    profile: bool,
     show_bytecode: bool,
     show_annotated: bool,
+    fold_constants: bool,
+    timeout: Option<f64>,
+    instruction_limit: Option<u64>,
+    compile_output: Option<String>,
+    module_cache: Option<String>,
+    profile_output: Option<String>,
     script: Option<String>,
     script_args: Vec<String>,
 }
@@ -53,13 +102,38 @@ fn parse_arguments() -> Result<KotoArgs, String> {
     let help = args.contains(["-h", "--help"]);
     let version = args.contains(["-v", "--version"]);
     let run_tests = args.contains(["-t", "--tests"]);
+    let debug = args.contains(["-d", "--debug"]);
+    let profile = args.contains(["-p", "--profile"]);
     let show_bytecode = args.contains(["-b", "--show_bytecode"]);
     let show_annotated = args.contains(["-i", "--show_instructions"]);
+    let fold_constants = args.contains(["-O", "--fold_constants"]);
+    let timeout: Option<f64> = args
+        .opt_value_from_str("--timeout")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+    if matches!(timeout, Some(timeout) if !timeout.is_finite() || timeout < 0.0) {
+        return Err("The timeout must be a positive number of seconds".into());
+    }
+    let instruction_limit = args
+        .opt_value_from_str("--instruction_limit")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+    let compile_output = args
+        .opt_value_from_str("--compile")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+    let module_cache = args
+        .opt_value_from_str("--module_cache")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+    let profile_output = args
+        .opt_value_from_str("--profile_output")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
 
     let script = args
         .subcommand()
         .map_err(|e| format!("Error while parsing arguments: {}", e))?;
 
+    if compile_output.is_some() && script.is_none() {
+        return Err("A script to compile is required when using --compile".into());
+    }
+
     let script_args = match args.free() {
         Ok(extra_args) => extra_args,
         Err(e) => {
@@ -76,14 +150,54 @@ fn parse_arguments() -> Result<KotoArgs, String> {
         help,
         version,
         run_tests,
+        debug,
+        profile,
         show_bytecode,
         show_annotated,
+        fold_constants,
+        timeout,
+        instruction_limit,
+        compile_output,
+        module_cache,
+        profile_output,
         script,
         script_args,
     })
 }
 
+fn save_profile(profiler: &Profiler, print_report: bool, output_path: Option<&str>) {
+    let report = profiler.report();
+
+    if print_report {
+        eprintln!("{}", report.to_text());
+    }
+
+    if let Some(output_path) = output_path {
+        let output = if output_path.ends_with(".json") {
+            report.to_chrome_trace()
+        } else {
+            report.to_folded_stacks()
+        };
+        if let Err(e) = fs::write(output_path, output) {
+            eprintln!("Failed to write '{}': {}", output_path, e);
+        }
+    }
+}
+
 fn main() {
+    let mut env_args = std::env::args_os().skip(1);
+    let subcommand: Option<fn(Vec<OsString>) -> bool> = match env_args.next() {
+        Some(arg) if arg == "fmt" => Some(format::run),
+        Some(arg) if arg == "test" => Some(test::run),
+        _ => None,
+    };
+    if let Some(subcommand) = subcommand {
+        if !subcommand(env_args.collect()) {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let args = match parse_arguments() {
         Ok(args) => args,
         Err(error) => {
@@ -106,25 +220,67 @@ fn main() {
         run_tests: args.run_tests,
         show_bytecode: args.show_bytecode,
         show_annotated: args.show_annotated,
+        fold_constants: args.fold_constants,
+        execution_limits: ExecutionLimits {
+            instruction_budget: args.instruction_limit,
+            timeout: args.timeout.map(Duration::from_secs_f64),
+        },
         ..Default::default()
     };
 
     if let Some(script_path) = args.script {
         let mut koto = Koto::with_settings(settings);
 
-        let mut prelude = koto.prelude();
-        prelude.add_map("json", koto_json::make_module());
-        prelude.add_map("random", koto_random::make_module());
-        prelude.add_map("tempfile", koto_tempfile::make_module());
-        prelude.add_map("toml", koto_toml::make_module());
-
-        let script = fs::read_to_string(&script_path).expect("Unable to load script");
-        koto.set_script_path(Some(script_path.into()));
-        match koto.compile(&script) {
-            Ok(_) => match koto.run_with_args(&args.script_args) {
-                Ok(_) => {}
-                Err(e) => eprintln!("{}", e),
-            },
+        add_libs_to_prelude(&koto);
+
+        koto.set_module_resolver(Arc::new(FileSystemResolver::from_env()));
+        koto.set_module_cache_dir(args.module_cache.map(PathBuf::from));
+
+        if args.debug {
+            koto.set_debugger(Some(debug::make_debugger()));
+        }
+
+        let profiler = if args.profile || args.profile_output.is_some() {
+            let profiler = Profiler::new();
+            koto.set_profiler(Some(profiler.clone()));
+            Some(profiler)
+        } else {
+            None
+        };
+
+        let is_bytecode = Path::new(&script_path).extension() == Some("kotoc".as_ref());
+
+        let load_result = if is_bytecode {
+            let bytes = fs::read(&script_path).expect("Unable to load script");
+            koto.set_script_path(Some(script_path.into()));
+            koto.load_bytecode(&bytes)
+        } else {
+            let script = fs::read_to_string(&script_path).expect("Unable to load script");
+            koto.set_script_path(Some(script_path.into()));
+            koto.compile(&script)
+        };
+
+        match load_result {
+            Ok(chunk) => {
+                if let Some(output_path) = args.compile_output {
+                    if let Err(e) = fs::write(&output_path, chunk.to_bytes()) {
+                        eprintln!("Failed to write '{}': {}", output_path, e);
+                    }
+                } else {
+                    match koto.run_with_args(&args.script_args) {
+                        Ok(_) => {}
+                        // Quitting the debugger isn't an error
+                        Err(KotoError::RuntimeError(RuntimeError::ExecutionLimitExceeded(
+                            ExecutionLimit::StoppedByDebugger,
+                        ))) => {}
+                        Err(e) => eprintln!("{}", e),
+                    }
+
+                    if let Some(profiler) = profiler {
+                        save_profile(&profiler, args.profile, args.profile_output.as_deref());
+                    }
+                }
+            }
             Err(e) => eprintln!("{}", e),
         }
     } else {
@@ -1,8 +1,13 @@
 use {
-    koto::{Koto, KotoSettings},
+    koto::{
+        runtime::{Value, ValueMap},
+        Koto, KotoSettings,
+    },
     std::{
-        fmt,
+        env, fmt,
+        fs::{self, OpenOptions},
         io::{self, Stdout, Write},
+        path::PathBuf,
     },
     termion::{
         clear, color, cursor, cursor::DetectCursorPos, event::Key, input::TermRead,
@@ -17,14 +22,38 @@ const CONTINUED: &str = "… ";
 
 const INDENT_SIZE: usize = 2;
 
+const HISTORY_FILE: &str = ".koto_history";
+const MAX_HISTORY_SIZE: usize = 1000;
+
+// An in-progress reverse search through the input history, started with Ctrl-R
+#[derive(Default)]
+struct HistorySearch {
+    query: String,
+    // The position in the input history of the current match
+    position: Option<usize>,
+}
+
+// A previous line in a continued block that's being edited
+struct EditedLine {
+    // The index in the block's continued lines of the line being edited
+    index: usize,
+    // The contents of the block's last line, restored when editing is finished
+    last_line: String,
+}
+
 #[derive(Default)]
 pub struct Repl {
     koto: Koto,
     input: String,
     continued_lines: Vec<String>,
+    edited_line: Option<EditedLine>,
     input_history: Vec<String>,
     history_position: Option<usize>,
+    history_path: Option<PathBuf>,
+    search: Option<HistorySearch>,
     cursor: Option<usize>,
+    // The number of lines above the input line that the terminal's cursor was last placed on
+    cursor_row_offset: usize,
 }
 
 impl Repl {
@@ -57,67 +86,136 @@ impl Repl {
             None
         };
 
+        // History is only persisted for interactive sessions
+        if tty.is_some() {
+            self.load_history();
+        }
+
         write!(stdout, "Welcome to Koto v{}\r\n{}", VERSION, PROMPT).unwrap();
         stdout.flush().unwrap();
 
-        for c in stdin.keys() {
-            self.on_keypress(c.unwrap(), &mut stdout, &mut tty);
+        for key in stdin.keys() {
+            // Unsupported key sequences are ignored
+            let key = match key {
+                Ok(key) => key,
+                Err(_) => continue,
+            };
 
-            if let Some(ref mut tty) = tty {
-                let (_, cursor_y) = stdout.cursor_pos().unwrap();
-
-                let prompt = if self.continued_lines.is_empty() {
-                    PROMPT
-                } else {
-                    CONTINUED
-                };
+            self.on_keypress(key, &mut stdout, &mut tty);
 
-                write!(
-                    tty,
-                    "{move_cursor}{clear}{prompt}{input}",
-                    move_cursor = cursor::Goto(1, cursor_y),
-                    clear = clear::CurrentLine,
-                    prompt = prompt,
-                    input = self.input
-                )
-                .unwrap();
-
-                if let Some(position) = self.cursor {
-                    if position < self.input.len() {
-                        let x_offset = (self.input.len() - position) as u16;
-                        let (cursor_x, cursor_y) = stdout.cursor_pos().unwrap();
-                        write!(tty, "{}", cursor::Goto(cursor_x - x_offset, cursor_y),).unwrap();
-                    }
-                }
+            if let Some(ref mut tty) = tty {
+                self.render(&mut stdout, tty);
             }
 
             stdout.flush().unwrap();
         }
     }
 
+    fn render<T>(&mut self, stdout: &mut Stdout, tty: &mut RawTerminal<T>)
+    where
+        T: Write,
+    {
+        let row_offset = match &self.edited_line {
+            Some(edited) => self.continued_lines.len() - edited.index,
+            None => 0,
+        };
+
+        if row_offset > self.cursor_row_offset {
+            write!(
+                tty,
+                "{}",
+                cursor::Up((row_offset - self.cursor_row_offset) as u16)
+            )
+            .unwrap();
+        } else if row_offset < self.cursor_row_offset {
+            write!(
+                tty,
+                "{}",
+                cursor::Down((self.cursor_row_offset - row_offset) as u16)
+            )
+            .unwrap();
+        }
+        tty.flush().unwrap();
+        self.cursor_row_offset = row_offset;
+
+        let (_, cursor_y) = stdout.cursor_pos().unwrap();
+
+        if let Some(search) = &self.search {
+            let found = search
+                .position
+                .map_or("", |position| self.input_history[position].as_str());
+
+            write!(
+                tty,
+                "{move_cursor}{clear}(search) '{query}': {found}",
+                move_cursor = cursor::Goto(1, cursor_y),
+                clear = clear::CurrentLine,
+                query = search.query,
+                found = found,
+            )
+            .unwrap();
+
+            return;
+        }
+
+        let line_index = match &self.edited_line {
+            Some(edited) => edited.index,
+            None => self.continued_lines.len(),
+        };
+
+        let prompt = if line_index == 0 { PROMPT } else { CONTINUED };
+
+        write!(
+            tty,
+            "{move_cursor}{clear}{prompt}{input}",
+            move_cursor = cursor::Goto(1, cursor_y),
+            clear = clear::CurrentLine,
+            prompt = prompt,
+            input = self.input
+        )
+        .unwrap();
+
+        if let Some(position) = self.cursor {
+            let x_offset = self.input[position..].chars().count() as u16;
+            if x_offset > 0 {
+                let (cursor_x, cursor_y) = stdout.cursor_pos().unwrap();
+                write!(tty, "{}", cursor::Goto(cursor_x - x_offset, cursor_y)).unwrap();
+            }
+        }
+    }
+
     fn on_keypress<T>(&mut self, key: Key, stdout: &mut Stdout, tty: &mut Option<RawTerminal<T>>)
     where
         T: Write,
     {
+        let key = if self.search.is_some() {
+            match self.on_search_keypress(key) {
+                Some(key) => key,
+                None => return,
+            }
+        } else {
+            key
+        };
+
         match key {
-            Key::Up => {
-                if !self.input_history.is_empty() {
-                    let new_position = match self.history_position {
-                        Some(position) => {
-                            if position > 0 {
-                                position - 1
-                            } else {
-                                0
-                            }
+            Key::Up if !self.continued_lines.is_empty() => self.edit_previous_line(),
+            Key::Down if self.edited_line.is_some() => self.edit_next_line(),
+            Key::Up if !self.input_history.is_empty() => {
+                let new_position = match self.history_position {
+                    Some(position) => {
+                        if position > 0 {
+                            position - 1
+                        } else {
+                            0
                         }
-                        None => self.input_history.len() - 1,
-                    };
-                    self.input = self.input_history[new_position].clone();
-                    self.cursor = None;
-                    self.history_position = Some(new_position);
-                }
+                    }
+                    None => self.input_history.len() - 1,
+                };
+                self.input = self.input_history[new_position].clone();
+                self.cursor = None;
+                self.history_position = Some(new_position);
             }
-            Key::Down => {
+            Key::Down if self.continued_lines.is_empty() => {
                 self.history_position = match self.history_position {
                     Some(position) => {
                         if position < self.input_history.len() - 1 {
@@ -135,56 +233,54 @@ impl Repl {
                 }
                 self.cursor = None;
             }
-            Key::Left => match self.cursor {
-                Some(position) => {
-                    if position > 0 {
-                        self.cursor = Some(position - 1);
-                    }
+            Key::Left => {
+                if let Some(position) = self.previous_char_position() {
+                    self.set_cursor(position);
                 }
-                None => {
-                    if !self.input.is_empty() {
-                        self.cursor = Some(self.input.len() - 1);
-                    }
-                }
-            },
+            }
             Key::Right => {
-                if let Some(position) = self.cursor {
-                    if position < self.input.len() - 1 {
-                        self.cursor = Some(position + 1);
-                    } else {
-                        self.cursor = None;
-                    }
+                if let Some(position) = self.next_char_position() {
+                    self.set_cursor(position);
                 }
             }
+            Key::Home | Key::Ctrl('a') => self.set_cursor(0),
+            Key::End | Key::Ctrl('e') => self.cursor = None,
+            Key::Alt('b') => {
+                let position = self.previous_word_position();
+                self.set_cursor(position);
+            }
+            Key::Alt('f') => {
+                let position = self.next_word_position();
+                self.set_cursor(position);
+            }
             Key::Backspace => {
-                let cursor = self.cursor;
-                match cursor {
-                    Some(position) => {
-                        let new_position = position - 1;
-                        self.input.remove(new_position);
-                        if self.input.is_empty() {
-                            self.cursor = None;
-                        } else {
-                            self.cursor = Some(new_position);
-                        }
-                    }
-                    None => {
-                        self.input.pop();
-                    }
+                if let Some(position) = self.previous_char_position() {
+                    self.delete_range(position, self.cursor_position());
+                }
+            }
+            Key::Delete => {
+                if let Some(position) = self.next_char_position() {
+                    self.delete_range(self.cursor_position(), position);
                 }
             }
+            Key::Ctrl('w') | Key::Alt('\x7f') => {
+                let position = self.previous_word_position();
+                self.delete_range(position, self.cursor_position());
+            }
+            Key::Alt('d') => {
+                let position = self.next_word_position();
+                self.delete_range(self.cursor_position(), position);
+            }
+            Key::Ctrl('u') => self.delete_range(0, self.cursor_position()),
+            Key::Ctrl('k') => self.delete_range(self.cursor_position(), self.input.len()),
+            Key::Ctrl('r') if self.edited_line.is_none() => {
+                self.search = Some(HistorySearch::default());
+            }
             Key::Char(c) => match c {
+                '\n' if self.edited_line.is_some() => self.finish_editing_line(),
                 '\n' => self.on_enter(stdout, tty),
-                _ => {
-                    let cursor = self.cursor;
-                    match cursor {
-                        Some(position) => {
-                            self.input.insert(position, c);
-                            self.cursor = Some(position + 1);
-                        }
-                        None => self.input.push(c),
-                    }
-                }
+                '\t' => self.on_tab(stdout, tty),
+                _ => self.insert_str(&c.to_string()),
             },
             Key::Ctrl(c) => match c {
                 'c' => {
@@ -208,12 +304,63 @@ impl Repl {
                     }
                     std::process::exit(0)
                 }
+                'd' => {
+                    if let Some(position) = self.next_char_position() {
+                        self.delete_range(self.cursor_position(), position);
+                    }
+                }
                 _ => {}
             },
             _ => {}
         }
     }
 
+    // Handles a keypress while searching through the history
+    //
+    // Keys that don't modify the search cause the search's result to be placed in the input,
+    // and the key is then returned so that it can be processed as a regular keypress.
+    fn on_search_keypress(&mut self, key: Key) -> Option<Key> {
+        let search = self.search.as_mut().unwrap();
+
+        let key = match key {
+            Key::Char('\n') => Some(key),
+            // Tab accepts the result without any further action
+            Key::Char('\t') => None,
+            Key::Char(c) => {
+                search.query.push(c);
+                search.position = find_in_history(&self.input_history, &search.query, None);
+                return None;
+            }
+            Key::Backspace => {
+                search.query.pop();
+                search.position = find_in_history(&self.input_history, &search.query, None);
+                return None;
+            }
+            Key::Ctrl('r') => {
+                if let Some(position) =
+                    find_in_history(&self.input_history, &search.query, search.position)
+                {
+                    search.position = Some(position);
+                }
+                return None;
+            }
+            Key::Esc | Key::Ctrl('c') | Key::Ctrl('g') => {
+                self.search = None;
+                return None;
+            }
+            _ => Some(key),
+        };
+
+        if let Some(position) = search.position {
+            self.input = self.input_history[position].clone();
+            self.history_position = Some(position);
+            self.cursor = None;
+        }
+
+        self.search = None;
+        key
+    }
+
     fn on_enter<T>(&mut self, stdout: &mut Stdout, tty: &mut Option<RawTerminal<T>>)
     where
         T: Write,
@@ -237,7 +384,11 @@ impl Repl {
 
             match self.koto.compile(&input) {
                 Ok(_) => {
-                    match self.koto.run() {
+                    match self
+                        .koto
+                        .run()
+                        .and_then(|result| self.koto.value_to_string(&result))
+                    {
                         Ok(result) => writeln!(stdout, "{}", result).unwrap(),
                         Err(error) => self.print_error(stdout, tty, &error),
                     }
@@ -274,6 +425,7 @@ impl Repl {
             && (self.input_history.is_empty() || self.input_history.last().unwrap() != &self.input)
         {
             self.input_history.push(self.input.clone());
+            self.save_history_entry(&self.input);
         }
 
         self.history_position = None;
@@ -298,6 +450,262 @@ impl Repl {
         self.input = " ".repeat(indent);
     }
 
+    fn on_tab<T>(&mut self, stdout: &mut Stdout, tty: &mut Option<RawTerminal<T>>)
+    where
+        T: Write,
+    {
+        let cursor = self.cursor_position();
+        let before_cursor = &self.input[..cursor];
+
+        // Tab inserts indentation when there's nothing to complete
+        if before_cursor.chars().all(char::is_whitespace) {
+            self.insert_str(&" ".repeat(INDENT_SIZE));
+            return;
+        }
+
+        let word_start = before_cursor
+            .rfind(|c: char| !is_completion_char(c))
+            .map_or(0, |i| i + 1);
+        let word = &before_cursor[word_start..];
+
+        let candidates = self.completion_candidates(word);
+        let partial = word.rsplit('.').next().unwrap_or(word);
+
+        match candidates.as_slice() {
+            [] => {}
+            [candidate] => {
+                let completion = candidate[partial.len()..].to_string();
+                self.insert_str(&completion);
+            }
+            _ => {
+                let common = common_prefix(&candidates);
+                if common.len() > partial.len() {
+                    let completion = common[partial.len()..].to_string();
+                    self.insert_str(&completion);
+                } else if tty.is_some() && self.edited_line.is_none() {
+                    write!(stdout, "\r\n{}\r\n", candidates.join("  ")).unwrap();
+
+                    // Redraw the block's previous lines below the candidates so that they
+                    // remain available for editing
+                    for (i, line) in self.continued_lines.iter().enumerate() {
+                        let prompt = if i == 0 { PROMPT } else { CONTINUED };
+                        write!(stdout, "{}{}\r\n", prompt, line).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    // Returns the sorted list of names that could complete the given word
+    //
+    // If the word contains dots then the preceding identifiers are used to find a map, e.g.
+    // 'string.sp' completes with the members of the string module that start with 'sp'.
+    fn completion_candidates(&self, word: &str) -> Vec<String> {
+        let globals = self.koto.globals();
+        let prelude = self.koto.prelude();
+
+        let mut names = word.split('.').collect::<Vec<_>>();
+        let partial = names.pop().unwrap_or_default();
+
+        let mut candidates = if names.is_empty() {
+            map_keys(&globals)
+                .into_iter()
+                .chain(map_keys(&prelude))
+                .filter(|key| key.starts_with(partial))
+                .collect::<Vec<_>>()
+        } else {
+            let first = globals.data().get_with_string(names[0]).cloned();
+            let mut value =
+                match first.or_else(|| prelude.data().get_with_string(names[0]).cloned()) {
+                    Some(value) => value,
+                    None => return vec![],
+                };
+
+            for name in names[1..].iter() {
+                value = match &value {
+                    Value::Map(map) => match map.data().get_with_string(name) {
+                        Some(value) => value.clone(),
+                        None => return vec![],
+                    },
+                    _ => return vec![],
+                };
+            }
+
+            let mut candidates = Vec::new();
+
+            if let Value::Map(map) = &value {
+                candidates.extend(map_keys(map));
+            }
+
+            // Values also have access to the functions in their type's core module
+            let core_module = match &value {
+                Value::List(_) => Some("list"),
+                Value::Map(_) => Some("map"),
+                Value::Number(_) => Some("number"),
+                Value::Range { .. } => Some("range"),
+                Value::Str(_) => Some("string"),
+                Value::Tuple(_) => Some("tuple"),
+                Value::Iterator(_) => Some("iterator"),
+                _ => None,
+            };
+
+            if let Some(Value::Map(module)) =
+                core_module.and_then(|name| prelude.data().get_with_string(name).cloned())
+            {
+                candidates.extend(map_keys(&module));
+            }
+
+            candidates.retain(|key| key.starts_with(partial));
+            candidates
+        };
+
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+
+    // Moves editing to the previous line in the continued block
+    fn edit_previous_line(&mut self) {
+        let index = match &mut self.edited_line {
+            Some(edited) => {
+                if edited.index == 0 {
+                    return;
+                }
+                self.continued_lines[edited.index] = self.input.clone();
+                edited.index -= 1;
+                edited.index
+            }
+            None => {
+                let index = self.continued_lines.len() - 1;
+                self.edited_line = Some(EditedLine {
+                    index,
+                    last_line: self.input.clone(),
+                });
+                index
+            }
+        };
+
+        self.input = self.continued_lines[index].clone();
+        self.cursor = None;
+    }
+
+    // Moves editing to the next line in the continued block
+    fn edit_next_line(&mut self) {
+        let edited = self.edited_line.as_mut().unwrap();
+        self.continued_lines[edited.index] = self.input.clone();
+
+        if edited.index + 1 < self.continued_lines.len() {
+            edited.index += 1;
+            self.input = self.continued_lines[edited.index].clone();
+            self.cursor = None;
+        } else {
+            self.finish_editing_line();
+        }
+    }
+
+    // Stores the edited line in the continued block, and returns to the block's last line
+    fn finish_editing_line(&mut self) {
+        if let Some(edited) = self.edited_line.take() {
+            self.continued_lines[edited.index] = self.input.clone();
+            self.input = edited.last_line;
+            self.cursor = None;
+        }
+    }
+
+    fn cursor_position(&self) -> usize {
+        self.cursor.unwrap_or(self.input.len())
+    }
+
+    fn set_cursor(&mut self, position: usize) {
+        self.cursor = if position < self.input.len() {
+            Some(position)
+        } else {
+            None
+        };
+    }
+
+    fn previous_char_position(&self) -> Option<usize> {
+        self.input[..self.cursor_position()]
+            .char_indices()
+            .last()
+            .map(|(i, _)| i)
+    }
+
+    fn next_char_position(&self) -> Option<usize> {
+        let position = self.cursor_position();
+        self.input[position..]
+            .chars()
+            .next()
+            .map(|c| position + c.len_utf8())
+    }
+
+    fn previous_word_position(&self) -> usize {
+        let before_cursor = &self.input[..self.cursor_position()];
+        let word_end = before_cursor.trim_end_matches(|c| !is_word_char(c));
+        word_end.trim_end_matches(is_word_char).len()
+    }
+
+    fn next_word_position(&self) -> usize {
+        let after_cursor = &self.input[self.cursor_position()..];
+        let word_start = after_cursor.trim_start_matches(|c| !is_word_char(c));
+        self.input.len() - word_start.trim_start_matches(is_word_char).len()
+    }
+
+    fn insert_str(&mut self, s: &str) {
+        match self.cursor {
+            Some(position) => {
+                self.input.insert_str(position, s);
+                self.cursor = Some(position + s.len());
+            }
+            None => self.input.push_str(s),
+        }
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        if start < end {
+            self.input.replace_range(start..end, "");
+            let cursor = self.cursor_position();
+            self.set_cursor(if cursor > end {
+                cursor - (end - start)
+            } else {
+                cursor.min(start)
+            });
+        }
+    }
+
+    fn load_history(&mut self) {
+        self.history_path = history_path();
+
+        let path = match &self.history_path {
+            Some(path) => path,
+            None => return,
+        };
+
+        if let Ok(history) = fs::read_to_string(path) {
+            self.input_history = history.lines().map(String::from).collect();
+
+            if self.input_history.len() > MAX_HISTORY_SIZE {
+                let excess = self.input_history.len() - MAX_HISTORY_SIZE;
+                self.input_history.drain(..excess);
+
+                let mut contents = self.input_history.join("\n");
+                contents.push('\n');
+                if fs::write(path, contents).is_err() {
+                    self.history_path = None;
+                }
+            }
+        }
+    }
+
+    fn save_history_entry(&self, entry: &str) {
+        if let Some(path) = &self.history_path {
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+                // Failing to save the history shouldn't interrupt the session
+                let _ = writeln!(file, "{}", entry);
+            }
+        }
+    }
+
     fn print_error<T, E>(&self, stdout: &mut Stdout, tty: &mut Option<RawTerminal<T>>, error: &E)
     where
         T: Write,
@@ -321,3 +729,56 @@ impl Repl {
         }
     }
 }
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+// Finds the most recent history entry containing the query, starting before the given position
+fn find_in_history(history: &[String], query: &str, before: Option<usize>) -> Option<usize> {
+    if query.is_empty() {
+        return None;
+    }
+
+    let end = before.unwrap_or(history.len());
+    history[..end]
+        .iter()
+        .rposition(|entry| entry.contains(query))
+}
+
+fn map_keys(map: &ValueMap) -> Vec<String> {
+    map.data()
+        .keys()
+        .filter_map(|key| match key {
+            Value::Str(s) => Some(s.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn common_prefix(strings: &[String]) -> &str {
+    let first = &strings[0];
+    let mut len = first.len();
+
+    for s in strings[1..].iter() {
+        len = first
+            .char_indices()
+            .zip(s.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+
+    &first[..len]
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_completion_char(c: char) -> bool {
+    is_word_char(c) || c == '.'
+}
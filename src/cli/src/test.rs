@@ -0,0 +1,140 @@
+use {
+    koto::{
+        bytecode::FileSystemResolver,
+        runtime::{format_test_reports, Coverage, TestReportFormat},
+        Koto,
+    },
+    std::{ffi::OsString, fs, sync::Arc},
+};
+
+pub fn help_string() -> String {
+    format!(
+        "{version}
+
+Runs the tests exported by Koto scripts
+
+USAGE:
+    koto test [OPTIONS] <scripts>...
+
+FLAGS:
+    -h, --help   Prints help information
+
+OPTIONS:
+    --filter <name>    Only run the tests with names that contain the filter
+    --format <format>  The format of the test report, 'text' (the default), 'tap', or 'junit'
+    --coverage <path>  Save the line coverage of the tested scripts and their imported modules
+                       to the given path in the lcov format
+
+ARGS:
+    <scripts>...   The scripts containing the tests to run
+",
+        version = crate::version_string()
+    )
+}
+
+struct TestArgs {
+    help: bool,
+    filter: Option<String>,
+    format: TestReportFormat,
+    coverage: Option<String>,
+    scripts: Vec<String>,
+}
+
+fn parse_arguments(args: Vec<OsString>) -> Result<TestArgs, String> {
+    let mut args = pico_args::Arguments::from_vec(args);
+
+    let help = args.contains(["-h", "--help"]);
+    let filter = args
+        .opt_value_from_str("--filter")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+    let format = args
+        .opt_value_from_str("--format")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?
+        .unwrap_or(TestReportFormat::Text);
+    let coverage = args
+        .opt_value_from_str("--coverage")
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+
+    let scripts = args
+        .free()
+        .map_err(|e| format!("Error while parsing arguments: {}", e))?;
+
+    if scripts.is_empty() && !help {
+        return Err("At least one script to test is required".into());
+    }
+
+    Ok(TestArgs {
+        help,
+        filter,
+        format,
+        coverage,
+        scripts,
+    })
+}
+
+// Runs the `test` subcommand with the arguments that follow it
+//
+// Returns true if all of the scripts were loaded and all of their tests passed.
+pub fn run(args: Vec<OsString>) -> bool {
+    let args = match parse_arguments(args) {
+        Ok(args) => args,
+        Err(error) => {
+            println!("{}\n\n{}", help_string(), error);
+            return false;
+        }
+    };
+
+    if args.help {
+        println!("{}", help_string());
+        return true;
+    }
+
+    let mut success = true;
+    let mut reports = Vec::with_capacity(args.scripts.len());
+    let coverage = args.coverage.as_ref().map(|_| Coverage::new());
+
+    for path in args.scripts.iter() {
+        let script = match fs::read_to_string(path) {
+            Ok(script) => script,
+            Err(e) => {
+                eprintln!("Failed to read '{}': {}", path, e);
+                success = false;
+                continue;
+            }
+        };
+
+        let mut koto = Koto::new();
+        crate::add_libs_to_prelude(&koto);
+        koto.set_module_resolver(Arc::new(FileSystemResolver::from_env()));
+        koto.set_script_path(Some(path.into()));
+        koto.set_coverage(coverage.clone());
+
+        if let Err(e) = koto.compile(&script) {
+            eprintln!("{}", e);
+            success = false;
+            continue;
+        }
+
+        match koto.run_tests(args.filter.as_deref()) {
+            Ok(report) => {
+                success &= report.success();
+                reports.push(report);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                success = false;
+            }
+        }
+    }
+
+    print!("{}", format_test_reports(&reports, args.format));
+
+    if let (Some(coverage), Some(output_path)) = (coverage, args.coverage) {
+        if let Err(e) = fs::write(&output_path, coverage.report().to_lcov()) {
+            eprintln!("Failed to write '{}': {}", output_path, e);
+            success = false;
+        }
+    }
+
+    success
+}
@@ -56,6 +56,11 @@ mod repl_tests {
         run_koto_repl_test(&[("x = 1, 2, 3", Some("(1, 2, 3)")), ("x", Some("(1, 2, 3)"))]);
     }
 
+    #[test]
+    fn display_override() {
+        run_koto_repl_test(&[("x = {@display: |self| \"Foo\"}", Some("Foo"))]);
+    }
+
     #[test]
     fn import_assert() {
         run_koto_repl_test(&[
@@ -63,4 +68,54 @@ mod repl_tests {
             ("assert true", Some("()")),
         ]);
     }
+
+    #[test]
+    fn tab_completion_of_global() {
+        run_koto_repl_test(&[("xyz_long = 42", Some("42")), ("xyz\t + 1", Some("43"))]);
+    }
+
+    #[test]
+    fn tab_completion_of_prelude_module() {
+        run_koto_repl_test(&[("import str\t.size", Some("||"))]);
+    }
+
+    #[test]
+    fn tab_completion_of_core_function() {
+        run_koto_repl_test(&[("s = \"abc\"", Some("abc")), ("s.to_upp\t()", Some("ABC"))]);
+    }
+
+    #[test]
+    fn tab_completion_of_map_member() {
+        run_koto_repl_test(&[
+            ("m = {foo_bar: 42}", Some("{foo_bar: 42}")),
+            ("m.foo\t + 1", Some("43")),
+        ]);
+    }
+
+    #[test]
+    fn word_movement() {
+        // Alt-b moves the cursor back to the start of '3'
+        run_koto_repl_test(&[("12 + 3\x1bb4", Some("55"))]);
+    }
+
+    #[test]
+    fn history_search() {
+        run_koto_repl_test(&[
+            ("x = 99", Some("99")),
+            ("x = 1", Some("1")),
+            // Ctrl-R followed by a query runs the most recent matching entry
+            ("\x12x = 9", Some("99")),
+        ]);
+    }
+
+    #[test]
+    fn edit_previous_line_in_block() {
+        run_koto_repl_test(&[
+            ("x = 0", Some("0")),
+            ("for i in 0..3", None),
+            ("  x += i", None),
+            // Up moves to the previous line, which is then edited to '  x += 10'
+            ("\x1b[A\x7f10\n", Some("30")),
+        ]);
+    }
 }
@@ -0,0 +1,257 @@
+//! Macros for exposing Rust types to the Koto programming language
+//!
+//! See `KotoObject` in `koto_runtime` for usage details.
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::TokenStream as TokenStream2,
+    quote::{format_ident, quote},
+    syn::{
+        parse_macro_input, AttributeArgs, DeriveInput, Error, FnArg, ImplItem, ItemImpl, Lit, Meta,
+        NestedMeta, Path,
+    },
+};
+
+/// Implements `KotoObject` for a struct, along with `ExternalValue`, `Display`, and `IntoValue`
+///
+/// Supported attributes:
+///   - `#[koto(type_name = "Name")]`: the type name that's shown in Koto.
+///   - `#[koto(display = false)]`: `Display` won't be implemented, allowing a custom
+///     implementation to be used.
+///   - `#[koto(runtime = "koto::runtime")]`: the path to the Koto runtime crate.
+#[proc_macro_derive(KotoObject, attributes(koto))]
+pub fn derive_koto_object(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match koto_object(input) {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Implements `KotoMethods` for a type, exposing each method with a `self` receiver to Koto
+///
+/// Supported attributes:
+///   - `#[koto_methods(runtime = "koto::runtime")]`: the path to the Koto runtime crate.
+///   - `#[koto(name = "name")]` on a method: the name of the method in Koto.
+///   - `#[koto(skip)]` on a method: the method won't be made available to Koto.
+#[proc_macro_attribute]
+pub fn koto_methods(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let input = parse_macro_input!(input as ItemImpl);
+
+    match methods(args, input) {
+        Ok(output) => output.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn koto_object(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let mut runtime = default_runtime_path();
+    let mut type_name = input.ident.to_string();
+    let mut display = true;
+
+    for meta in koto_attributes(&input.attrs)? {
+        match &meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident("runtime") => {
+                runtime = parse_path(&name_value.lit)?;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("type_name") => {
+                type_name = parse_string(&name_value.lit)?;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("display") => {
+                display = match &name_value.lit {
+                    Lit::Bool(b) => b.value,
+                    other => return Err(Error::new_spanned(other, "Expected a bool")),
+                }
+            }
+            other => return Err(Error::new_spanned(other, "Unknown koto attribute")),
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let display_impl = if display {
+        quote! {
+            impl #impl_generics ::std::fmt::Display for #ident #type_generics #where_clause {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.write_str(<Self as #runtime::KotoObject>::TYPE_NAME)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl #impl_generics #runtime::KotoObject for #ident #type_generics #where_clause {
+            const TYPE_NAME: &'static str = #type_name;
+        }
+
+        impl #impl_generics #runtime::ExternalValue for #ident #type_generics #where_clause {
+            fn value_type(&self) -> String {
+                <Self as #runtime::KotoObject>::TYPE_NAME.to_string()
+            }
+        }
+
+        impl #impl_generics #runtime::IntoValue for #ident #type_generics #where_clause {
+            fn into_value(self) -> #runtime::Value {
+                #runtime::Value::Map(#runtime::KotoObject::into_value_map(self))
+            }
+        }
+
+        #display_impl
+    })
+}
+
+fn methods(args: AttributeArgs, mut input: ItemImpl) -> Result<TokenStream2, Error> {
+    let mut runtime = default_runtime_path();
+
+    for arg in args.iter() {
+        match arg {
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("runtime") =>
+            {
+                runtime = parse_path(&name_value.lit)?;
+            }
+            other => return Err(Error::new_spanned(other, "Unknown koto_methods argument")),
+        }
+    }
+
+    let mut add_methods = Vec::new();
+
+    for item in input.items.iter_mut() {
+        let method = match item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+
+        let mut name = method.sig.ident.to_string();
+        let mut skip = false;
+
+        for meta in koto_attributes(&method.attrs)? {
+            match &meta {
+                Meta::Path(path) if path.is_ident("skip") => skip = true,
+                Meta::NameValue(name_value) if name_value.path.is_ident("name") => {
+                    name = parse_string(&name_value.lit)?;
+                }
+                other => return Err(Error::new_spanned(other, "Unknown koto attribute")),
+            }
+        }
+        method.attrs.retain(|attr| !attr.path.is_ident("koto"));
+
+        if skip {
+            continue;
+        }
+
+        match method.sig.receiver() {
+            Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() => {}
+            Some(receiver) => {
+                return Err(Error::new_spanned(
+                    receiver,
+                    "Koto methods need to take self by reference, consider adding #[koto(skip)]",
+                ))
+            }
+            // Associated functions without a receiver aren't exposed as methods
+            None => continue,
+        }
+
+        let arg_types = method
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                FnArg::Typed(arg) => Some(&arg.ty),
+                FnArg::Receiver(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let arg_names = (0..arg_types.len())
+            .map(|i| format_ident!("arg_{}", i))
+            .collect::<Vec<_>>();
+        let method_ident = &method.sig.ident;
+
+        add_methods.push(quote! {
+            map.add_instance_fn(#name, |vm, args| match vm.get_args(args) {
+                [#runtime::Value::Map(instance), args @ ..] => {
+                    let function_name = format!(
+                        "{}.{}",
+                        <Self as #runtime::KotoObject>::TYPE_NAME,
+                        #name
+                    );
+                    let mut method_args = Some(
+                        <(#(#arg_types,)*) as #runtime::FromArgs>::from_args(&function_name, args)?
+                    );
+                    #runtime::visit_external_value(instance, |object: &mut Self| {
+                        let (#(#arg_names,)*) = method_args
+                            .take()
+                            .expect("Method arguments should only be used once");
+                        #runtime::IntoRuntimeResult::into_runtime_result(
+                            object.#method_ident(#(#arg_names),*)
+                        )
+                    })
+                }
+                _ => #runtime::external_error!(
+                    "{0}.{1}: Expected {0} instance as first argument",
+                    <Self as #runtime::KotoObject>::TYPE_NAME,
+                    #name
+                ),
+            });
+        });
+    }
+
+    let self_type = &input.self_ty;
+    let (impl_generics, _, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        #input
+
+        impl #impl_generics #runtime::KotoMethods for #self_type #where_clause {
+            #[allow(unused_variables)]
+            fn add_methods(map: &mut #runtime::ValueMap) {
+                #(#add_methods)*
+            }
+        }
+    })
+}
+
+fn default_runtime_path() -> Path {
+    syn::parse_quote!(::koto_runtime)
+}
+
+// Returns the contents of any #[koto(...)] attributes
+fn koto_attributes(attrs: &[syn::Attribute]) -> Result<Vec<Meta>, Error> {
+    let mut result = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("koto")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested.into_iter() {
+                    match nested {
+                        NestedMeta::Meta(meta) => result.push(meta),
+                        NestedMeta::Lit(lit) => {
+                            return Err(Error::new_spanned(lit, "Unexpected literal"))
+                        }
+                    }
+                }
+            }
+            other => return Err(Error::new_spanned(other, "Expected #[koto(...)]")),
+        }
+    }
+
+    Ok(result)
+}
+
+fn parse_string(lit: &Lit) -> Result<String, Error> {
+    match lit {
+        Lit::Str(s) => Ok(s.value()),
+        other => Err(Error::new_spanned(other, "Expected a string")),
+    }
+}
+
+fn parse_path(lit: &Lit) -> Result<Path, Error> {
+    match lit {
+        Lit::Str(s) => s.parse(),
+        other => Err(Error::new_spanned(other, "Expected a path as a string")),
+    }
+}
@@ -0,0 +1,32 @@
+use {
+    koto_parser::ParserError,
+    std::{error, fmt},
+};
+
+/// Errors that can be returned from [format](crate::format)
+#[derive(Clone, Debug)]
+pub enum FormatError {
+    /// The source couldn't be parsed, only valid Koto code can be formatted
+    ParserError(ParserError),
+    /// The formatted output wasn't equivalent to the source
+    ///
+    /// This indicates a bug in the formatter, the source is left unformatted.
+    ChangedMeaning,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FormatError::*;
+
+        match self {
+            ParserError(error) => write!(
+                f,
+                "Failed to parse source ({}:{}): {}",
+                error.span.start.line, error.span.start.column, error
+            ),
+            ChangedMeaning => f.write_str("Formatting would change the meaning of the source"),
+        }
+    }
+}
+
+impl error::Error for FormatError {}
@@ -0,0 +1,366 @@
+use {
+    crate::FormatError,
+    koto_lexer::{Lexer, Token},
+    koto_parser::{Ast, ConstantPool, Function, Node, Parser},
+};
+
+// The number of spaces used for each level of indentation
+const INDENT_WIDTH: usize = 2;
+
+/// Formats Koto source code with a canonical layout
+///
+/// The source is lexed and then re-emitted with the following rules applied:
+///   - Indented blocks are indented with 2 spaces per level.
+///   - Binary operators are surrounded by single spaces.
+///   - Commas and colons are followed by a single space, with no space before them.
+///   - There's no space after opening brackets or before closing brackets,
+///     or inside the `|` delimiters of function arguments.
+///   - Other whitespace between tokens is reduced to a single space, note that whitespace is
+///     significant in Koto, e.g. `f (x)` and `f(x)` have different meanings.
+///   - Trailing whitespace is removed, consecutive blank lines are reduced to a single blank
+///     line, and the output ends with a single newline.
+///
+/// Comments are preserved, and multi-line strings and comments are left untouched.
+///
+/// The formatted output is parsed and checked against the source to ensure that formatting
+/// hasn't changed the meaning of the code.
+pub fn format(source: &str) -> Result<String, FormatError> {
+    let (source_ast, source_constants) = Parser::parse(source).map_err(FormatError::ParserError)?;
+
+    let output = Formatter::default().format(&lex_lines(source));
+
+    match Parser::parse(&output) {
+        Ok((ast, constants))
+            if asts_match(&source_ast, &ast) && constants_match(&source_constants, &constants) =>
+        {
+            Ok(output)
+        }
+        _ => Err(FormatError::ChangedMeaning),
+    }
+}
+
+struct LineToken<'a> {
+    token: Token,
+    slice: &'a str,
+    // True if the token was preceded by whitespace on the same line
+    space_before: bool,
+}
+
+struct Line<'a> {
+    // The width of the line's indentation in the source
+    indent: usize,
+    tokens: Vec<LineToken<'a>>,
+    // The line's source, without indentation
+    source: &'a str,
+}
+
+impl<'a> Line<'a> {
+    fn is_blank(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn is_comment(&self) -> bool {
+        self.tokens
+            .iter()
+            .all(|token| matches!(token.token, Token::CommentSingle | Token::CommentMulti))
+    }
+}
+
+// Splits the source into lines of tokens
+//
+// Tokens that span multiple lines (multi-line strings and comments) are included in the line on
+// which they start.
+fn lex_lines(source: &str) -> Vec<Line<'_>> {
+    let mut lexer = Lexer::new(source);
+    let mut lines = Vec::new();
+    let mut line = Line {
+        indent: 0,
+        tokens: Vec::new(),
+        source: "",
+    };
+    let mut line_start = 0;
+    let mut space_before = false;
+
+    while let Some(token) = lexer.next() {
+        match token {
+            Token::Whitespace => {
+                if line.tokens.is_empty() {
+                    line.indent = lexer.slice().len();
+                    line_start = lexer.source_position();
+                } else {
+                    space_before = true;
+                }
+            }
+            Token::NewLine | Token::NewLineIndented => {
+                let line_end = lexer.source_position() - lexer.slice().len();
+                line.source = &source[line_start..line_end];
+
+                let indent = lexer.slice().len() - 1; // -1 for the newline character
+                lines.push(std::mem::replace(
+                    &mut line,
+                    Line {
+                        indent,
+                        tokens: Vec::new(),
+                        source: "",
+                    },
+                ));
+
+                line_start = lexer.source_position();
+                space_before = false;
+            }
+            _ => {
+                line.tokens.push(LineToken {
+                    token,
+                    slice: lexer.slice(),
+                    space_before,
+                });
+                space_before = false;
+            }
+        }
+    }
+
+    line.source = &source[line_start..];
+    lines.push(line);
+
+    lines
+}
+
+#[derive(Default)]
+struct Formatter {
+    output: String,
+    // The source indentation widths of the currently open blocks
+    indent_stack: Vec<usize>,
+    // True when between the `|` delimiters of a function's arguments
+    in_function_args: bool,
+}
+
+impl Formatter {
+    fn format(mut self, lines: &[Line]) -> String {
+        self.indent_stack.push(0);
+        let mut blank_line_pending = false;
+
+        for line in lines.iter() {
+            if line.is_blank() {
+                // Blank lines at the start of the output are dropped
+                blank_line_pending = !self.output.is_empty();
+                continue;
+            }
+
+            // Comment lines don't affect the block structure
+            let level = if line.is_comment() {
+                self.level_for_indent(line.indent)
+            } else {
+                self.update_indent_stack(line.indent)
+            };
+
+            if blank_line_pending {
+                self.output.push('\n');
+                blank_line_pending = false;
+            }
+
+            self.output.push_str(&" ".repeat(level * INDENT_WIDTH));
+
+            let pipes = self.classify_pipes(&line.tokens);
+
+            // Debug expressions capture their source text, so lines containing them are kept
+            if line.tokens.iter().any(|token| token.token == Token::Debug) {
+                self.output.push_str(line.source.trim_end());
+            } else {
+                self.format_line_tokens(&line.tokens, &pipes);
+            }
+
+            self.output.push('\n');
+        }
+
+        self.output
+    }
+
+    // Determines which of the line's `|` tokens open a function's arguments
+    //
+    // Function arguments can span multiple lines, so the state is carried over between lines.
+    fn classify_pipes(&mut self, tokens: &[LineToken]) -> Vec<Pipe> {
+        tokens
+            .iter()
+            .map(|token| {
+                if token.token == Token::Function {
+                    self.in_function_args = !self.in_function_args;
+                    if self.in_function_args {
+                        Pipe::Open
+                    } else {
+                        Pipe::Close
+                    }
+                } else {
+                    Pipe::None
+                }
+            })
+            .collect()
+    }
+
+    fn format_line_tokens(&mut self, tokens: &[LineToken], pipes: &[Pipe]) {
+        for (i, token) in tokens.iter().enumerate() {
+            if i > 0 && space_between(tokens, pipes, i) {
+                self.output.push(' ');
+            }
+
+            match token.token {
+                // Trailing whitespace is removed from comments
+                Token::CommentSingle => self.output.push_str(token.slice.trim_end()),
+                _ => self.output.push_str(token.slice),
+            }
+        }
+    }
+
+    // Updates the stack of open blocks for a line with the given indentation,
+    // returning the line's indentation level
+    fn update_indent_stack(&mut self, indent: usize) -> usize {
+        while indent < *self.indent_stack.last().unwrap() {
+            self.indent_stack.pop();
+        }
+
+        if indent > *self.indent_stack.last().unwrap() {
+            self.indent_stack.push(indent);
+        }
+
+        self.indent_stack.len() - 1
+    }
+
+    // Returns the indentation level for a line without changing the stack of open blocks
+    fn level_for_indent(&self, indent: usize) -> usize {
+        match self
+            .indent_stack
+            .iter()
+            .rposition(|block_indent| *block_indent <= indent)
+        {
+            Some(level) if self.indent_stack[level] == indent => level,
+            Some(level) => level + 1,
+            None => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pipe {
+    None,
+    Open,
+    Close,
+}
+
+// Decides if a space should be added between the token at the given index and the previous
+fn space_between(tokens: &[LineToken], pipes: &[Pipe], index: usize) -> bool {
+    use Token::*;
+
+    let previous = &tokens[index - 1];
+    let token = &tokens[index];
+
+    match (previous.token, token.token) {
+        (_, CommentSingle) | (_, CommentMulti) => true,
+        (_, Comma) | (_, Colon) => false,
+        // Trailing commas, e.g. `("foo",)`
+        (Comma, ParenClose) | (Comma, ListEnd) | (Comma, MapEnd) => false,
+        (Comma, _) | (Colon, _) => true,
+        // Meta keys, e.g. `@+`
+        (At, _) => false,
+        (ParenOpen, _) | (ListStart, _) | (MapStart, _) => false,
+        (_, ParenClose) | (_, ListEnd) | (_, MapEnd) => false,
+        (Function, _) if pipes[index - 1] == Pipe::Open => false,
+        (_, Function) if pipes[index] == Pipe::Close => false,
+        (Subtract, _) => match subtract_kind(tokens, index - 1) {
+            SubtractKind::Binary => true,
+            SubtractKind::Unary => token.space_before,
+        },
+        (_, Subtract) => match subtract_kind(tokens, index) {
+            SubtractKind::Binary => true,
+            SubtractKind::Unary => token.space_before || is_binary_operator(previous.token),
+        },
+        (previous, _) if is_binary_operator(previous) => true,
+        (_, next) if is_binary_operator(next) => true,
+        _ => token.space_before,
+    }
+}
+
+enum SubtractKind {
+    Unary,
+    Binary,
+}
+
+// Determines whether a `-` is a binary subtraction or a unary negation
+//
+// A `-` that follows an operand is treated as a subtraction, unless it's separated from the
+// operand with whitespace and is directly followed by the next token, e.g. `f -1`.
+fn subtract_kind(tokens: &[LineToken], index: usize) -> SubtractKind {
+    use Token::*;
+
+    let follows_operand = index > 0
+        && matches!(
+            tokens[index - 1].token,
+            Id | Number | String | True | False | ParenClose | ListEnd | MapEnd | Wildcard
+        );
+
+    let space_after = tokens.get(index + 1).iter().all(|next| next.space_before);
+
+    if follows_operand && (space_after || !tokens[index].space_before) {
+        SubtractKind::Binary
+    } else {
+        SubtractKind::Unary
+    }
+}
+
+fn is_binary_operator(token: Token) -> bool {
+    use Token::*;
+
+    matches!(
+        token,
+        Add | Multiply
+            | Divide
+            | Modulo
+            | Assign
+            | AssignAdd
+            | AssignSubtract
+            | AssignMultiply
+            | AssignDivide
+            | AssignModulo
+            | Equal
+            | NotEqual
+            | Greater
+            | GreaterOrEqual
+            | Less
+            | LessOrEqual
+            | And
+            | Or
+    )
+}
+
+fn asts_match(a: &Ast, b: &Ast) -> bool {
+    a.nodes().len() == b.nodes().len()
+        && a.nodes()
+            .iter()
+            .zip(b.nodes().iter())
+            .all(|(a, b)| nodes_match(&a.node, &b.node))
+}
+
+fn nodes_match(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        // The order of a function's accessed non-locals isn't significant
+        (Node::Function(a), Node::Function(b)) => {
+            let sorted = |f: &Function| {
+                let mut non_locals = f.accessed_non_locals.clone();
+                non_locals.sort_unstable();
+                non_locals
+            };
+
+            a.args == b.args
+                && a.default_args == b.default_args
+                && a.local_count == b.local_count
+                && a.body == b.body
+                && a.is_instance_function == b.is_instance_function
+                && a.is_variadic == b.is_variadic
+                && a.is_generator == b.is_generator
+                && sorted(a) == sorted(b)
+        }
+        _ => a == b,
+    }
+}
+
+fn constants_match(a: &ConstantPool, b: &ConstantPool) -> bool {
+    a.iter().eq(b.iter())
+}
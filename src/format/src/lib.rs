@@ -0,0 +1,8 @@
+//! A source code formatter for the Koto programming language
+//!
+//! See [format] for details.
+
+mod error;
+mod formatter;
+
+pub use {error::FormatError, formatter::format};
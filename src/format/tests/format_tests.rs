@@ -0,0 +1,225 @@
+use {
+    koto_format::{format, FormatError},
+    std::{fs, path::PathBuf},
+};
+
+fn check_format(source: &str, expected: &str) {
+    match format(source) {
+        Ok(output) => {
+            assert_eq!(output, expected);
+            // Formatting should be idempotent
+            assert_eq!(format(&output).unwrap(), expected);
+        }
+        Err(error) => panic!("Failed to format source: {}", error),
+    }
+}
+
+mod layout {
+    use super::*;
+
+    #[test]
+    fn indentation() {
+        let source = "
+if x
+    y = 1
+    if y
+         z
+else
+ w
+";
+        let expected = "\
+if x
+  y = 1
+  if y
+    z
+else
+  w
+";
+        check_format(source, expected);
+    }
+
+    #[test]
+    fn blank_lines() {
+        let source = "\n\nx = 1\n\n\n\ny = 2   \n\n";
+        let expected = "x = 1\n\ny = 2\n";
+        check_format(source, expected);
+    }
+
+    #[test]
+    fn comments() {
+        let source = "
+#  A comment
+x = 1    # trailing comment
+f = ||
+    # indented comment
+    x
+#- multi-line
+   comment -#
+";
+        let expected = "\
+#  A comment
+x = 1 # trailing comment
+f = ||
+  # indented comment
+  x
+#- multi-line
+   comment -#
+";
+        check_format(source, expected);
+    }
+
+    #[test]
+    fn nested_blocks_with_chained_calls() {
+        let source = "
+f = ||
+   for x in y
+         z = x
+             .foo 1
+             .bar 2
+   z
+";
+        let expected = "\
+f = ||
+  for x in y
+    z = x
+      .foo 1
+      .bar 2
+  z
+";
+        check_format(source, expected);
+    }
+}
+
+mod spacing {
+    use super::*;
+
+    #[test]
+    fn binary_operators() {
+        check_format("x=1+2*3\n", "x = 1 + 2 * 3\n");
+        check_format("x   +=   y%2\n", "x += y % 2\n");
+        check_format("a==b and c<=d\n", "a == b and c <= d\n");
+    }
+
+    #[test]
+    fn subtraction_and_negation() {
+        check_format("x = a-b\n", "x = a - b\n");
+        check_format("x = -a\n", "x = -a\n");
+        check_format("x = 1--a\n", "x = 1 - -a\n");
+        // A negated argument in a call without parentheses
+        check_format("f -1\n", "f -1\n");
+    }
+
+    #[test]
+    fn commas_and_colons() {
+        check_format("x = [1 ,2,  3]\n", "x = [1, 2, 3]\n");
+        check_format("x = {a:1 , b:  2}\n", "x = {a: 1, b: 2}\n");
+    }
+
+    #[test]
+    fn trailing_commas() {
+        check_format("x = (\"foo\",)\n", "x = (\"foo\",)\n");
+        check_format("x = [1, 2,]\n", "x = [1, 2,]\n");
+    }
+
+    #[test]
+    fn brackets() {
+        check_format("x = f( 1, (2 + 3))\n", "x = f(1, (2 + 3))\n");
+        check_format("x = [ ]\n", "x = []\n");
+    }
+
+    #[test]
+    fn whitespace_before_parentheses_is_preserved() {
+        // `f (x)` is a call with a parenthesized argument, `f(x)` is a call
+        check_format("f (x)\n", "f (x)\n");
+        check_format("f(x)\n", "f(x)\n");
+    }
+
+    #[test]
+    fn function_args() {
+        check_format("f = | x , y |   x*y\n", "f = |x, y| x * y\n");
+        check_format("f = ||  42\n", "f = || 42\n");
+    }
+
+    #[test]
+    fn default_and_named_args() {
+        check_format("f = |x,y=x*2|  x+y\n", "f = |x, y = x * 2| x + y\n");
+        check_format("f(1,y:2)\n", "f(1, y: 2)\n");
+    }
+
+    #[test]
+    fn meta_keys() {
+        check_format(
+            "x = {@+: |self, other| other}\n",
+            "x = {@+: |self, other| other}\n",
+        );
+    }
+}
+
+mod preserved_text {
+    use super::*;
+
+    #[test]
+    fn strings() {
+        check_format("x =  \"a  b\"\n", "x = \"a  b\"\n");
+        check_format("x = \"$a   ${b+1}\"\n", "x = \"$a   ${b+1}\"\n");
+    }
+
+    #[test]
+    fn debug_expressions() {
+        check_format("debug  x+1\n", "debug  x+1\n");
+    }
+}
+
+mod errors {
+    use super::*;
+
+    #[test]
+    fn parser_error() {
+        assert!(matches!(
+            format("x = [1, 2\n"),
+            Err(FormatError::ParserError(_))
+        ));
+    }
+}
+
+mod repo_scripts {
+    use super::*;
+
+    // Formats all of the scripts in the repo, checking that formatting succeeds and is idempotent
+    #[test]
+    fn format_repo_scripts() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        for dir in &[
+            "../koto/tests",
+            "../koto/benches",
+            "../examples/poetry/scripts",
+        ] {
+            let mut script_count = 0;
+            for entry in fs::read_dir(root.join(dir)).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension() != Some("koto".as_ref()) {
+                    continue;
+                }
+
+                let source = fs::read_to_string(&path).unwrap();
+                let output = format(&source).unwrap_or_else(|error| {
+                    panic!("Failed to format '{}': {}", path.display(), error)
+                });
+                assert_eq!(
+                    format(&output).unwrap(),
+                    output,
+                    "Formatting '{}' isn't idempotent",
+                    path.display()
+                );
+
+                script_count += 1;
+            }
+
+            assert!(script_count > 0, "No scripts found in '{}'", dir);
+        }
+    }
+}
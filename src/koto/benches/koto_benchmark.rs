@@ -27,7 +27,7 @@ impl BenchmarkRunner {
             Ok(_) => {
                 runtime.settings.run_tests = true;
 
-                if let Err(error) = runtime.run_with_args(&args) {
+                if let Err(error) = runtime.run_with_args(args) {
                     panic!("{}", error);
                 }
 
@@ -41,7 +41,7 @@ impl BenchmarkRunner {
 
     fn run(&mut self) {
         if let Err(error) = self.runtime.run() {
-            panic!(error);
+            std::panic::panic_any(error);
         }
     }
 }
@@ -29,11 +29,19 @@
 pub use {koto_bytecode as bytecode, koto_parser as parser, koto_runtime as runtime};
 
 use {
-    koto_bytecode::{chunk_to_string, chunk_to_string_annotated, Chunk, LoaderError},
+    koto_bytecode::{
+        chunk_to_string, chunk_to_string_annotated, Chunk, LoaderError, ModuleResolver,
+    },
     koto_runtime::{
-        type_as_string, Loader, RuntimeError, Value, ValueList, ValueMap, ValueVec, Vm,
+        type_as_string, Coverage, Debugger, ExecutionLimits, InterruptHandle, Loader, Profiler,
+        RuntimeError, TestReport, Value, ValueList, ValueMap, ValueVec, Vm, VmSettings,
+    },
+    std::{
+        error::Error,
+        fmt,
+        path::{Path, PathBuf},
+        sync::Arc,
     },
-    std::{error::Error, fmt, path::PathBuf, sync::Arc},
 };
 
 #[derive(Debug)]
@@ -74,12 +82,22 @@ impl From<RuntimeError> for KotoError {
 pub type KotoResult = Result<Value, KotoError>;
 
 /// Settings used to control the behaviour of the [Koto] runtime
+///
+/// The settings can be changed after the [Koto] instance has been created. Settings that are
+/// needed when creating the runtime are provided via [VmSettings], see [Koto::with_vm_settings].
 #[derive(Copy, Clone, Debug, Default)]
 pub struct KotoSettings {
     pub run_tests: bool,
     pub show_annotated: bool,
     pub show_bytecode: bool,
     pub repl_mode: bool,
+    /// Folds constant expressions and removes unreachable branches when compiling
+    ///
+    /// This applies to imported modules as well as to the main script, see
+    /// [Loader::set_fold_constants].
+    pub fold_constants: bool,
+    /// Limits that are applied each time a script or function is run
+    pub execution_limits: ExecutionLimits,
 }
 
 /// The main interface for the Koto language.
@@ -100,12 +118,25 @@ impl Koto {
     }
 
     pub fn with_settings(settings: KotoSettings) -> Self {
-        let mut result = Self::new();
-        result.settings = settings;
-        result
+        Self::with_vm_settings(settings, VmSettings::default())
+    }
+
+    /// Creates a [Koto] instance with settings for its runtime
+    ///
+    /// The runtime settings, e.g. the output used for printing, can't be changed afterwards.
+    pub fn with_vm_settings(settings: KotoSettings, vm_settings: VmSettings) -> Self {
+        Self {
+            runtime: Vm::with_settings(vm_settings),
+            settings,
+            script_path: None,
+            loader: Loader::default(),
+            chunk: None,
+        }
     }
 
     pub fn compile(&mut self, script: &str) -> Result<Arc<Chunk>, LoaderError> {
+        self.loader.set_fold_constants(self.settings.fold_constants);
+
         let compile_result = if self.settings.repl_mode {
             self.loader.compile_repl(script)
         } else {
@@ -116,7 +147,7 @@ impl Koto {
             Ok(chunk) => {
                 self.chunk = Some(chunk.clone());
                 if self.settings.show_annotated {
-                    println!("Constants\n---------\n{}\n", chunk.constants.to_string());
+                    println!("Constants\n---------\n{}\n", chunk.constants);
 
                     let script_lines = script.lines().collect::<Vec<_>>();
                     println!(
@@ -132,6 +163,45 @@ impl Koto {
         }
     }
 
+    /// Loads a chunk from bytecode that was produced by [Chunk::to_bytes]
+    ///
+    /// The loaded chunk is then ready to be run with [Koto::run].
+    ///
+    /// If a script path has been set then it's used as the chunk's source path,
+    /// so that modules get imported relative to the bytecode's location.
+    pub fn load_bytecode(&mut self, bytes: &[u8]) -> Result<Arc<Chunk>, LoaderError> {
+        let chunk = self.loader.load_bytecode(bytes, self.script_path.clone())?;
+        self.chunk = Some(chunk.clone());
+        Ok(chunk)
+    }
+
+    /// Sets the resolver that's used to find imported modules
+    ///
+    /// By default modules are loaded from files relative to the importing script,
+    /// see [FileSystemResolver](koto_bytecode::FileSystemResolver).
+    pub fn set_module_resolver(&mut self, resolver: Arc<dyn ModuleResolver>) {
+        self.runtime.set_module_resolver(resolver);
+    }
+
+    /// Sets a directory that compiled modules should be cached in
+    pub fn set_module_cache_dir(&mut self, cache_dir: Option<PathBuf>) {
+        self.runtime.set_module_cache_dir(cache_dir);
+    }
+
+    /// Invalidates an imported module, along with any modules that import it
+    ///
+    /// See [Vm::invalidate_module].
+    pub fn invalidate_module(&mut self, id: &Path) -> Vec<PathBuf> {
+        self.runtime.invalidate_module(id)
+    }
+
+    /// Invalidates any imported modules with files that have changed since they were loaded
+    ///
+    /// Running the script again afterwards will recompile and run the changed modules.
+    pub fn invalidate_changed_modules(&mut self) -> Vec<PathBuf> {
+        self.runtime.invalidate_changed_modules()
+    }
+
     pub fn run_with_args(&mut self, args: &[String]) -> KotoResult {
         self.set_args(args);
         self.run()
@@ -146,13 +216,15 @@ impl Koto {
     }
 
     pub fn run_chunk(&mut self, chunk: Arc<Chunk>) -> KotoResult {
+        self.apply_runtime_settings();
+
         let result = self.runtime.run(chunk)?;
 
         if self.settings.repl_mode {
             Ok(result)
         } else {
             if self.settings.run_tests {
-                let _test_result = match self.runtime.get_global_value("tests") {
+                match self.runtime.get_global_value("tests") {
                     Some(Value::Map(tests)) => {
                         self.runtime.run_tests(tests)?;
                     }
@@ -169,10 +241,65 @@ impl Koto {
         }
     }
 
+    /// Runs the compiled script, and then runs the tests in its exported `tests` map
+    ///
+    /// Unlike running the script with [KotoSettings::run_tests], all of the tests are run even
+    /// if one of them fails, and a report of the results is returned.
+    /// The script's `main` function isn't called.
+    ///
+    /// If a filter is provided, then only the tests with names that contain the filter are run.
+    pub fn run_tests(&mut self, filter: Option<&str>) -> Result<TestReport, KotoError> {
+        let chunk = self.chunk.clone().ok_or(KotoError::NothingToRun)?;
+
+        self.apply_runtime_settings();
+        self.runtime.run(chunk)?;
+
+        let report_name = match &self.script_path {
+            Some(path) => path.display().to_string(),
+            None => "tests".to_string(),
+        };
+
+        match self.runtime.get_global_value("tests") {
+            Some(Value::Map(tests)) => {
+                Ok(self
+                    .runtime
+                    .run_tests_with_report(tests, filter, &report_name))
+            }
+            Some(other) => Err(KotoError::InvalidTestsType(type_as_string(&other))),
+            None => Ok(TestReport::new(&report_name)),
+        }
+    }
+
+    /// Attaches a debugger that's used when running scripts, see [Debugger]
+    pub fn set_debugger(&mut self, debugger: Option<Debugger>) {
+        self.runtime.set_debugger(debugger);
+    }
+
+    /// Attaches a profiler that's used when running scripts, see [Profiler]
+    pub fn set_profiler(&mut self, profiler: Option<Profiler>) {
+        self.runtime.set_profiler(profiler);
+    }
+
+    /// Attaches a coverage collector that's used when running scripts, see [Coverage]
+    pub fn set_coverage(&mut self, coverage: Option<Coverage>) {
+        self.runtime.set_coverage(coverage);
+    }
+
+    /// Returns a handle that can be used to interrupt execution from another thread
+    ///
+    /// An interrupt stops any subsequent runs until it's cleared with [InterruptHandle::reset].
+    pub fn interrupt_handle(&mut self) -> InterruptHandle {
+        self.runtime.interrupt_handle()
+    }
+
     pub fn prelude(&self) -> ValueMap {
         self.runtime.prelude()
     }
 
+    pub fn globals(&self) -> ValueMap {
+        self.runtime.globals()
+    }
+
     pub fn set_args(&mut self, args: &[String]) {
         use Value::{Map, Str};
 
@@ -186,12 +313,12 @@ impl Koto {
             .prelude()
             .data_mut()
             .get_with_string_mut("koto")
-            .unwrap()
         {
-            Map(map) => map
+            Some(Map(map)) => map
                 .data_mut()
                 .add_list("args", ValueList::with_data(koto_args)),
-            _ => unreachable!(),
+            Some(_) => unreachable!(),
+            None => {} // The koto module has been disabled
         }
     }
 
@@ -208,8 +335,7 @@ impl Koto {
                         let s = p.to_string_lossy() + "/";
                         Str(s.into_owned().into())
                     })
-                    .or(Some(Empty))
-                    .unwrap();
+                    .unwrap_or(Empty);
                 let script_path = Str(path.display().to_string().into());
 
                 (script_dir, script_path)
@@ -224,14 +350,14 @@ impl Koto {
             .prelude()
             .data_mut()
             .get_with_string_mut("koto")
-            .unwrap()
         {
-            Map(map) => {
+            Some(Map(map)) => {
                 let mut map = map.data_mut();
                 map.add_value("script_dir", script_dir);
                 map.add_value("script_path", script_path);
             }
-            _ => unreachable!(),
+            Some(_) => unreachable!(),
+            None => {} // The koto module has been disabled
         }
     }
 
@@ -243,8 +369,24 @@ impl Koto {
     }
 
     pub fn call_function(&mut self, function: Value, args: &[Value]) -> KotoResult {
+        self.apply_runtime_settings();
         self.runtime
             .run_function(function, args)
             .map_err(|e| e.into())
     }
+
+    /// Returns the value's string representation, respecting any `@display` overrides
+    ///
+    /// See [Vm::value_to_string].
+    pub fn value_to_string(&mut self, value: &Value) -> Result<String, KotoError> {
+        self.runtime.value_to_string(value).map_err(|e| e.into())
+    }
+
+    // Applies the settings that can be changed after creation to the runtime
+    fn apply_runtime_settings(&mut self) {
+        self.runtime
+            .set_execution_limits(self.settings.execution_limits);
+        self.runtime
+            .set_fold_constants(self.settings.fold_constants);
+    }
 }
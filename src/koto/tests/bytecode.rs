@@ -0,0 +1,97 @@
+use {
+    koto::{runtime::Value, Koto},
+    std::{fs, path::PathBuf},
+    tempfile::tempdir,
+};
+
+fn run_bytecode(bytes: &[u8], script_path: Option<PathBuf>) -> Value {
+    let mut koto = Koto::default();
+    koto.set_script_path(script_path);
+    koto.load_bytecode(bytes).expect("Failed to load bytecode");
+    koto.run().expect("Failed to run bytecode")
+}
+
+#[test]
+fn run_precompiled_script() {
+    let mut koto = Koto::default();
+    let chunk = koto
+        .compile(
+            "
+f = |n| n * 2
+x = (1..=4).each(|n| f n).fold(0, |a, b| a + b)
+\"x: {x}\"
+",
+        )
+        .unwrap();
+
+    let bytes = chunk.to_bytes();
+    assert_eq!(run_bytecode(&bytes, None), Value::Str("x: 20".into()));
+}
+
+#[test]
+fn import_precompiled_module() {
+    let temp_dir = tempdir().unwrap();
+    let dir = temp_dir.path();
+
+    let mut koto = Koto::default();
+    let module = koto.compile("export square = |x| x * x").unwrap();
+    fs::write(dir.join("square.kotoc"), module.to_bytes()).unwrap();
+
+    let main = koto
+        .compile("import square\nsquare.square 9")
+        .unwrap()
+        .to_bytes();
+    let main_path = dir.join("main.kotoc");
+    fs::write(&main_path, &main).unwrap();
+
+    assert_eq!(
+        run_bytecode(&main, Some(main_path)),
+        Value::Number(81.0.into())
+    );
+}
+
+#[test]
+fn module_cache() {
+    let temp_dir = tempdir().unwrap();
+    let dir = temp_dir.path();
+    let cache_dir = dir.join("cache");
+
+    fs::write(dir.join("double.koto"), "export double = |x| x * 2").unwrap();
+    let script_path = dir.join("main.koto");
+    fs::write(&script_path, "").unwrap();
+
+    let run = |script: &str| {
+        let mut koto = Koto::default();
+        koto.set_script_path(Some(script_path.clone()));
+        koto.set_module_cache_dir(Some(cache_dir.clone()));
+        koto.compile(script).unwrap();
+        koto.run().unwrap()
+    };
+
+    assert_eq!(
+        run("import double\ndouble.double 21"),
+        Value::Number(42.0.into())
+    );
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+    // The cached module is used on subsequent runs
+    assert_eq!(
+        run("import double\ndouble.double 5"),
+        Value::Number(10.0.into())
+    );
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+
+    // Changes to the module's source are picked up
+    fs::write(dir.join("double.koto"), "export double = |x| x * 2 + 1").unwrap();
+    assert_eq!(
+        run("import double\ndouble.double 5"),
+        Value::Number(11.0.into())
+    );
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 2);
+}
+
+#[test]
+fn invalid_bytecode() {
+    let mut koto = Koto::default();
+    assert!(koto.load_bytecode(b"KOTO").is_err());
+}
@@ -0,0 +1,145 @@
+use {
+    koto::{
+        runtime::{Capabilities, CoreModule, VmSettings},
+        Koto, KotoError,
+    },
+    std::{fs, path::PathBuf},
+    tempfile::tempdir,
+};
+
+fn run_script(
+    script: &str,
+    script_path: Option<PathBuf>,
+    capabilities: Capabilities,
+) -> Result<(), String> {
+    let mut koto = Koto::with_vm_settings(
+        Default::default(),
+        VmSettings {
+            capabilities,
+            ..Default::default()
+        },
+    );
+    koto.set_script_path(script_path);
+
+    koto.compile(script).map_err(|e| e.to_string())?;
+    koto.run().map(|_| ()).map_err(|e| match e {
+        KotoError::RuntimeError(e) => format!("{:#}", e),
+        other => other.to_string(),
+    })
+}
+
+mod core_modules {
+    use super::*;
+
+    #[test]
+    fn sandboxed_modules_are_disabled() {
+        for module in &["io", "os", "thread"] {
+            let error = run_script(
+                &format!("import {}", module),
+                None,
+                Capabilities::sandboxed(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                error,
+                format!(
+                    "Failed to import '{0}': the '{0}' module has been disabled",
+                    module
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn other_modules_are_available_when_sandboxed() {
+        let script = r#"
+import list, string, test.assert_eq
+assert_eq (list.size [1, 2, 3]), 3
+assert_eq (string.to_uppercase "abc"), "ABC"
+"#;
+        run_script(script, None, Capabilities::sandboxed()).unwrap();
+    }
+
+    #[test]
+    fn enable_disabled_module() {
+        let capabilities = Capabilities::sandboxed().enable_core_module(CoreModule::Os);
+        run_script("import os", None, capabilities).unwrap();
+    }
+
+    #[test]
+    fn disabled_module_functions_are_still_available_for_values() {
+        // Disabling the string module removes it from the prelude,
+        // but string functions are still available as methods.
+        let capabilities = Capabilities::default().disable_core_module(CoreModule::String);
+        run_script("x = \"abc\".to_uppercase()", None, capabilities.clone()).unwrap();
+        assert!(run_script("import string", None, capabilities).is_err());
+    }
+
+    #[test]
+    fn disabled_koto_module() {
+        let capabilities = Capabilities::default().disable_core_module(CoreModule::Koto);
+        let mut koto = Koto::with_vm_settings(
+            Default::default(),
+            VmSettings {
+                capabilities,
+                ..Default::default()
+            },
+        );
+        koto.set_args(&["foo".to_string()]);
+        koto.compile("x = 1").unwrap();
+        koto.run().unwrap();
+    }
+}
+
+mod module_files {
+    use super::*;
+
+    #[test]
+    fn disabled_module_files() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("foo.koto"), "export x = 42").unwrap();
+        fs::write(dir.join("main.koto"), "").unwrap();
+
+        let error = run_script(
+            "import foo",
+            Some(dir.join("main.koto")),
+            Capabilities::default().disable_module_files(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            "Failed to import 'foo': importing modules from files has been disabled"
+        );
+    }
+
+    #[test]
+    fn allowed_module_dirs() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        let allowed = dir.join("allowed");
+        fs::create_dir_all(allowed.join("nested")).unwrap();
+        fs::write(allowed.join("foo.koto"), "export x = 42").unwrap();
+        fs::write(allowed.join("nested").join("main.koto"), "export y = 99").unwrap();
+        fs::write(dir.join("bar.koto"), "export z = -1").unwrap();
+        fs::write(allowed.join("main.koto"), "").unwrap();
+        fs::write(dir.join("main.koto"), "").unwrap();
+
+        let script_path = Some(allowed.join("main.koto"));
+        let capabilities = Capabilities::sandboxed().allow_module_dir(&allowed);
+
+        let script = "
+import foo, nested, test.assert_eq
+assert_eq foo.x + nested.y, 141
+";
+        run_script(script, script_path.clone(), capabilities.clone()).unwrap();
+
+        let error =
+            run_script("import bar", Some(dir.join("main.koto")), capabilities).unwrap_err();
+        assert!(
+            error.contains("is outside of the allowed module directories"),
+            "{}",
+            error
+        );
+    }
+}
@@ -4,27 +4,31 @@ use {
 };
 
 fn run_script(script: &str, path: Option<PathBuf>, should_fail_at_runtime: bool) {
-    let mut koto = Koto::with_settings(KotoSettings {
-        run_tests: true,
-        ..Default::default()
-    });
-    koto.set_script_path(path);
+    // Scripts are checked with and without constant folding, which shouldn't change behaviour
+    for fold_constants in [false, true].iter() {
+        let mut koto = Koto::with_settings(KotoSettings {
+            run_tests: true,
+            fold_constants: *fold_constants,
+            ..Default::default()
+        });
+        koto.set_script_path(path.clone());
 
-    match koto.compile(&script) {
-        Ok(_) => match koto.run() {
-            Ok(_) => {
-                if should_fail_at_runtime {
-                    panic!("Expected failure");
+        match koto.compile(script) {
+            Ok(_) => match koto.run() {
+                Ok(_) => {
+                    if should_fail_at_runtime {
+                        panic!("Expected failure");
+                    }
                 }
-            }
-            Err(error) => {
-                if !should_fail_at_runtime {
-                    panic!("{}", error);
+                Err(error) => {
+                    if !should_fail_at_runtime {
+                        panic!("{}", error);
+                    }
                 }
+            },
+            Err(error) => {
+                panic!("{}", error);
             }
-        },
-        Err(error) => {
-            panic!("{}", error);
         }
     }
 }
@@ -35,7 +39,7 @@ fn load_and_run_script(script_path: &str) {
     path.push("../../koto/tests");
     path.push(script_path);
     if !path.exists() {
-        panic!(format!("Path doesn't exist: {:?}", path));
+        panic!("Path doesn't exist: {:?}", path);
     }
     let script =
         read_to_string(&path).unwrap_or_else(|_| panic!("Unable to load path '{:?}'", &path));
@@ -110,6 +114,7 @@ assert_near 1, 2, 0.1
     koto_test!(loops);
     koto_test!(map_ops);
     koto_test!(maps);
+    koto_test!(meta_maps);
     koto_test!(maps_and_lists);
     koto_test!(os);
     koto_test!(numbers);
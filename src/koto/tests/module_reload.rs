@@ -0,0 +1,135 @@
+use {
+    koto::{
+        runtime::{Value, VmSettings},
+        Koto,
+    },
+    std::{fs, path::Path, thread, time::Duration},
+    tempfile::{tempdir, TempDir},
+};
+
+// Returns a directory containing a main script that imports foo, which imports bar
+fn test_dir() -> TempDir {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.koto"), "").unwrap();
+    fs::write(
+        dir.path().join("foo.koto"),
+        "import bar\nexport x = bar.y + 1",
+    )
+    .unwrap();
+    write_module(&dir.path().join("bar.koto"), "export y = 41");
+    dir
+}
+
+// Writes a module file, making sure that its modification time has changed
+//
+// Some file systems only record modification times with coarse resolution,
+// so the file is rewritten until the new time is visible.
+fn write_module(path: &Path, source: &str) {
+    let modified = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    let previous = modified(path);
+
+    loop {
+        fs::write(path, source).unwrap();
+        if modified(path) != previous {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn make_koto(dir: &Path, vm_settings: VmSettings) -> Koto {
+    let mut koto = Koto::with_vm_settings(Default::default(), vm_settings);
+    koto.set_script_path(Some(dir.join("main.koto")));
+    koto.compile("import foo\nfoo.x").unwrap();
+    koto
+}
+
+fn run(koto: &mut Koto) -> Value {
+    koto.run().unwrap()
+}
+
+mod module_reload {
+    use super::*;
+
+    #[test]
+    fn modules_are_cached_until_invalidated() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let mut koto = make_koto(dir, VmSettings::default());
+        assert_eq!(run(&mut koto), Value::Number(42.into()));
+
+        let bar = dir.join("bar.koto");
+        write_module(&bar, "export y = 99");
+        assert_eq!(run(&mut koto), Value::Number(42.into()));
+
+        let invalidated = koto.invalidate_module(&bar);
+        let bar = bar.canonicalize().unwrap();
+        let foo = dir.join("foo.koto").canonicalize().unwrap();
+        assert!(invalidated.contains(&bar));
+        assert!(invalidated.contains(&foo));
+
+        assert_eq!(run(&mut koto), Value::Number(100.into()));
+    }
+
+    #[test]
+    fn invalidate_changed_modules() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let mut koto = make_koto(dir, VmSettings::default());
+        assert_eq!(run(&mut koto), Value::Number(42.into()));
+
+        assert!(koto.invalidate_changed_modules().is_empty());
+
+        write_module(&dir.join("bar.koto"), "export y = -2");
+
+        let invalidated = koto.invalidate_changed_modules();
+        assert!(invalidated.contains(&dir.join("bar.koto").canonicalize().unwrap()));
+        assert!(invalidated.contains(&dir.join("foo.koto").canonicalize().unwrap()));
+
+        assert_eq!(run(&mut koto), Value::Number((-1).into()));
+    }
+
+    #[test]
+    fn reload_changed_modules_setting() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let mut koto = make_koto(
+            dir,
+            VmSettings {
+                reload_changed_modules: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(run(&mut koto), Value::Number(42.into()));
+
+        write_module(&dir.join("bar.koto"), "export y = 9");
+        assert_eq!(run(&mut koto), Value::Number(10.into()));
+
+        write_module(&dir.join("foo.koto"), "import bar\nexport x = bar.y * 2");
+        assert_eq!(run(&mut koto), Value::Number(18.into()));
+    }
+
+    #[test]
+    fn failed_reloads_are_retried() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let mut koto = make_koto(
+            dir,
+            VmSettings {
+                reload_changed_modules: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(run(&mut koto), Value::Number(42.into()));
+
+        write_module(&dir.join("bar.koto"), "export y = 1 +");
+        assert!(koto.run().is_err());
+
+        write_module(&dir.join("bar.koto"), "export y = 1");
+        assert_eq!(run(&mut koto), Value::Number(2.into()));
+    }
+}
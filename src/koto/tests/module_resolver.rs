@@ -0,0 +1,172 @@
+use {
+    koto::{
+        bytecode::{
+            LoaderError, MemoryModuleResolver, ModuleResolver, ModuleSource, ResolvedModule,
+        },
+        runtime::{Capabilities, Value, VmSettings},
+        Koto,
+    },
+    std::{
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+};
+
+fn run_with_resolver(
+    script: &str,
+    resolver: Arc<dyn ModuleResolver>,
+    capabilities: Capabilities,
+) -> Result<Value, String> {
+    let mut koto = Koto::with_vm_settings(
+        Default::default(),
+        VmSettings {
+            capabilities,
+            ..Default::default()
+        },
+    );
+    koto.set_module_resolver(resolver);
+    koto.compile(script).map_err(|e| e.to_string())?;
+    koto.run().map_err(|e| e.to_string())
+}
+
+mod module_resolver {
+    use super::*;
+
+    #[test]
+    fn memory_modules() {
+        let mut resolver = MemoryModuleResolver::default();
+        resolver.add_module("foo", "import bar\nexport x = bar.y + 1");
+        resolver.add_module("bar", "export y = 41");
+
+        let result = run_with_resolver(
+            "import foo\nfoo.x",
+            Arc::new(resolver),
+            Capabilities::default(),
+        )
+        .unwrap();
+        assert_eq!(result, Value::Number(42.into()));
+    }
+
+    #[test]
+    fn memory_modules_are_available_when_module_files_are_disabled() {
+        let mut resolver = MemoryModuleResolver::default();
+        resolver.add_module("foo", "export x = 99");
+
+        let result = run_with_resolver(
+            "import foo\nfoo.x",
+            Arc::new(resolver),
+            Capabilities::sandboxed(),
+        )
+        .unwrap();
+        assert_eq!(result, Value::Number(99.into()));
+    }
+
+    #[test]
+    fn custom_resolver() {
+        // A resolver that generates modules, checking that the importing path is provided
+        struct GeneratedModules;
+
+        impl ModuleResolver for GeneratedModules {
+            fn resolve(
+                &self,
+                name: &str,
+                importing_path: Option<&Path>,
+            ) -> Result<ResolvedModule, LoaderError> {
+                let expected_importer = match name {
+                    "outer" => None,
+                    _ => Some(Path::new("generated/outer")),
+                };
+                if importing_path != expected_importer {
+                    return Err(LoaderError::io_error(format!(
+                        "Unexpected importing path for '{}': {:?}",
+                        name, importing_path
+                    )));
+                }
+
+                Ok(ResolvedModule {
+                    id: PathBuf::from(format!("generated/{}", name)),
+                    file_path: None,
+                })
+            }
+
+            fn load(&self, module: &ResolvedModule) -> Result<ModuleSource, LoaderError> {
+                let source = if module.id == Path::new("generated/outer") {
+                    "import inner\nexport value = inner.value".to_string()
+                } else {
+                    format!("export value = \"loaded from {}\"", module.id.display())
+                };
+                Ok(ModuleSource::Script(source))
+            }
+        }
+
+        let result = run_with_resolver(
+            "import outer\nouter.value",
+            Arc::new(GeneratedModules),
+            Capabilities::default(),
+        )
+        .unwrap();
+        assert_eq!(result.to_string(), "loaded from generated/inner");
+    }
+
+    // A resolver that counts the number of times that module sources are loaded
+    #[derive(Default)]
+    struct CountingResolver {
+        load_count: AtomicUsize,
+    }
+
+    impl ModuleResolver for CountingResolver {
+        fn resolve(
+            &self,
+            name: &str,
+            _importing_path: Option<&Path>,
+        ) -> Result<ResolvedModule, LoaderError> {
+            Ok(ResolvedModule {
+                id: PathBuf::from(name),
+                file_path: Some(PathBuf::from(format!("{}.koto", name))),
+            })
+        }
+
+        fn load(&self, _module: &ResolvedModule) -> Result<ModuleSource, LoaderError> {
+            self.load_count.fetch_add(1, Ordering::SeqCst);
+            Ok(ModuleSource::Script("export x = 42".to_string()))
+        }
+    }
+
+    #[test]
+    fn cached_modules_arent_loaded_again() {
+        let resolver = Arc::new(CountingResolver::default());
+
+        let script = "
+import foo
+import foo
+foo.x
+";
+        let result = run_with_resolver(script, resolver.clone(), Capabilities::default()).unwrap();
+        assert_eq!(result, Value::Number(42.into()));
+        assert_eq!(resolver.load_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn disallowed_modules_arent_loaded() {
+        let resolver = Arc::new(CountingResolver::default());
+
+        let error = run_with_resolver("import foo", resolver.clone(), Capabilities::sandboxed())
+            .unwrap_err();
+        assert!(error.contains("has been disabled"), "{}", error);
+        assert_eq!(resolver.load_count.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn missing_module() {
+        let error = run_with_resolver(
+            "import foo",
+            Arc::new(MemoryModuleResolver::default()),
+            Capabilities::default(),
+        )
+        .unwrap_err();
+        assert!(error.contains("Unable to find module 'foo'"), "{}", error);
+    }
+}
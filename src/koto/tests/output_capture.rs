@@ -0,0 +1,139 @@
+use {
+    koto::{
+        runtime::{external_error, KotoFile, OutputBuffer, Value, VmSettings},
+        Koto,
+    },
+    std::sync::Arc,
+};
+
+fn run_script_with_output(script: &str) -> String {
+    let stdout = OutputBuffer::default();
+
+    let mut koto = Koto::with_vm_settings(
+        Default::default(),
+        VmSettings {
+            stdout: Arc::new(stdout.clone()),
+            ..Default::default()
+        },
+    );
+
+    koto.compile(script).expect("Failed to compile script");
+    if let Err(error) = koto.run() {
+        panic!("Error while running script: {}", error);
+    }
+
+    stdout.contents()
+}
+
+mod output_capture {
+    use super::*;
+
+    #[test]
+    fn string_print() {
+        let script = r#"
+"Hello".print()
+"{} + {} = {}".print 1, 2, 1 + 2
+"#;
+        let stdout = run_script_with_output(script);
+        assert_eq!(stdout, "Hello\n1 + 2 = 3\n");
+    }
+
+    #[test]
+    fn debug() {
+        let script = "
+x = 42
+debug x + 1
+";
+        let stdout = run_script_with_output(script);
+        assert_eq!(stdout, "[3] x + 1: 43\n");
+    }
+
+    #[test]
+    fn output_from_threads() {
+        let script = r#"
+import thread
+t = thread.create || "hello from a thread".print()
+t.join()
+"#;
+        let stdout = run_script_with_output(script);
+        assert_eq!(stdout, "hello from a thread\n");
+    }
+
+    #[test]
+    fn stderr() {
+        let stdout = OutputBuffer::default();
+        let stderr = OutputBuffer::default();
+        let mut koto = Koto::with_vm_settings(
+            Default::default(),
+            VmSettings {
+                stdout: Arc::new(stdout.clone()),
+                stderr: Arc::new(stderr.clone()),
+                ..Default::default()
+            },
+        );
+
+        koto.prelude()
+            .add_fn("warn", |vm, args| match vm.get_args(args) {
+                [Value::Str(message)] => {
+                    vm.stderr().write_line(message.as_str())?;
+                    Ok(Value::Empty)
+                }
+                _ => external_error!("warn: Expected a String as argument"),
+            });
+
+        koto.compile("import warn\nwarn \"oh no\"").unwrap();
+        koto.run().unwrap();
+        assert_eq!(stderr.contents(), "oh no\n");
+        assert_eq!(stdout.contents(), "");
+    }
+
+    #[test]
+    fn take_buffer_contents() {
+        let stdout = OutputBuffer::default();
+        let mut koto = Koto::with_vm_settings(
+            Default::default(),
+            VmSettings {
+                stdout: Arc::new(stdout.clone()),
+                ..Default::default()
+            },
+        );
+
+        koto.compile("\"first\".print()").unwrap();
+        koto.run().unwrap();
+        assert_eq!(stdout.take_contents(), "first\n");
+        assert_eq!(stdout.contents(), "");
+
+        koto.compile("\"second\".print()").unwrap();
+        koto.run().unwrap();
+        assert_eq!(stdout.contents(), "second\n");
+    }
+
+    #[test]
+    fn custom_output() {
+        #[derive(Debug, Default)]
+        struct LineCounter(std::sync::atomic::AtomicUsize);
+
+        impl KotoFile for LineCounter {
+            fn write(&self, bytes: &[u8]) -> Result<(), koto::runtime::RuntimeError> {
+                let newlines = bytes.iter().filter(|b| **b == b'\n').count();
+                self.0
+                    .fetch_add(newlines, std::sync::atomic::Ordering::Relaxed);
+                Ok(())
+            }
+        }
+
+        let counter = Arc::new(LineCounter::default());
+        let mut koto = Koto::with_vm_settings(
+            Default::default(),
+            VmSettings {
+                stdout: counter.clone(),
+                ..Default::default()
+            },
+        );
+
+        koto.compile("for i in 0..5\n  \"{}\".print i").unwrap();
+        koto.run().unwrap();
+
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::Relaxed), 5);
+    }
+}
@@ -0,0 +1,205 @@
+use koto::{
+    runtime::{format_test_reports, ExecutionLimits, TestReport, TestReportFormat},
+    Koto, KotoError, KotoSettings,
+};
+
+const SCRIPT: &str = "
+import test.assert_eq
+export tests =
+  pre_test: |self|
+    self.x = 1
+  test_pass: |self|
+    assert_eq self.x, 1
+  test_fail: ||
+    assert_eq 1 + 1, 3
+  test_pass_after_failure: ||
+    assert_eq 2, 2
+";
+
+fn run_tests(script: &str, filter: Option<&str>) -> Result<TestReport, KotoError> {
+    let mut koto = Koto::default();
+    koto.compile(script).expect("Failed to compile script");
+    koto.run_tests(filter)
+}
+
+fn test_outcomes(report: &TestReport) -> Vec<(&str, bool)> {
+    report
+        .results
+        .iter()
+        .map(|result| (result.name.as_str(), result.passed()))
+        .collect()
+}
+
+mod test_runner {
+    use super::*;
+
+    #[test]
+    fn all_tests_are_run() {
+        let report = run_tests(SCRIPT, None).unwrap();
+
+        assert_eq!(
+            test_outcomes(&report),
+            &[
+                ("pass", true),
+                ("fail", false),
+                ("pass_after_failure", true)
+            ]
+        );
+        assert_eq!(report.passed_count(), 2);
+        assert_eq!(report.failed_count(), 1);
+        assert!(!report.success());
+
+        let error = report.results[1].error.as_ref().unwrap();
+        assert!(error.to_string().contains("assert_eq 1 + 1, 3"));
+    }
+
+    #[test]
+    fn filtered_tests() {
+        let report = run_tests(SCRIPT, Some("pass")).unwrap();
+
+        assert_eq!(
+            test_outcomes(&report),
+            &[("pass", true), ("pass_after_failure", true)]
+        );
+        assert!(report.success());
+    }
+
+    #[test]
+    fn failing_pre_test() {
+        let script = "
+export tests =
+  pre_test: ||
+    x = 1 + ()
+  test_a: || true
+";
+        let report = run_tests(script, None).unwrap();
+
+        assert_eq!(test_outcomes(&report), &[("a", false)]);
+        let error = format!("{:#}", report.results[0].error.as_ref().unwrap());
+        assert!(error.starts_with("Error while preparing to run test 'a': "));
+        assert_eq!(error.matches("test 'a'").count(), 1);
+    }
+
+    #[test]
+    fn failing_pre_test_without_report() {
+        let script = "
+export tests =
+  pre_test: ||
+    x = 1 + ()
+  test_a: || true
+";
+        let mut koto = Koto::with_settings(KotoSettings {
+            run_tests: true,
+            ..Default::default()
+        });
+        koto.compile(script).expect("Failed to compile script");
+        let error = koto.run().unwrap_err().to_string();
+        assert!(error.contains("Error while preparing to run test 'a': "));
+        assert_eq!(error.matches("test 'a'").count(), 1);
+    }
+
+    #[test]
+    fn exceeded_execution_limits_stop_the_tests() {
+        let script = "
+export tests =
+  test_a: ||
+    loop
+      x = 1
+  test_b: || true
+";
+        let mut koto = Koto::with_settings(KotoSettings {
+            execution_limits: ExecutionLimits {
+                instruction_budget: Some(10_000),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        koto.compile(script).expect("Failed to compile script");
+        let report = koto.run_tests(None).unwrap();
+
+        assert_eq!(test_outcomes(&report), &[("a", false)]);
+    }
+
+    #[test]
+    fn no_tests() {
+        let report = run_tests("x = 1", None).unwrap();
+        assert!(report.results.is_empty());
+        assert!(report.success());
+    }
+
+    #[test]
+    fn invalid_tests_type() {
+        assert!(matches!(
+            run_tests("export tests = 42", None),
+            Err(KotoError::InvalidTestsType(_))
+        ));
+    }
+
+    #[test]
+    fn main_is_not_called() {
+        let script = "
+export main = ||
+  assert false
+export tests =
+  test_a: || true
+";
+        let report = run_tests(script, None).unwrap();
+        assert!(report.success());
+    }
+}
+
+mod report_formats {
+    use super::*;
+
+    fn make_report() -> TestReport {
+        let mut report = run_tests(SCRIPT, None).unwrap();
+        report.name = "script<1>".into();
+        report
+    }
+
+    #[test]
+    fn text() {
+        let output = format_test_reports(&[make_report()], TestReportFormat::Text);
+
+        assert!(output.starts_with("script<1>\n  pass ... ok"));
+        assert!(output.contains("\nFailures:\n\nscript<1> - fail\n"));
+        assert!(output.ends_with("Test result: FAILED. 2 passed, 1 failed\n"));
+    }
+
+    #[test]
+    fn tap() {
+        let output = format_test_reports(&[make_report()], TestReportFormat::Tap);
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[0], "TAP version 13");
+        assert_eq!(lines[1], "1..3");
+        assert_eq!(lines[2], "ok 1 - script<1>: pass");
+        assert_eq!(lines[3], "not ok 2 - script<1>: fail");
+        assert!(lines[4].starts_with("# Assertion failed"));
+        assert_eq!(
+            lines.last().unwrap(),
+            &"ok 3 - script<1>: pass_after_failure"
+        );
+    }
+
+    #[test]
+    fn junit() {
+        let output = format_test_reports(&[make_report()], TestReportFormat::JUnit);
+
+        assert!(output.contains(r#"<testsuites tests="3" failures="1">"#));
+        assert!(output.contains(r#"<testsuite name="script&lt;1&gt;" tests="3" failures="1""#));
+        assert!(output.contains(r#"<testcase name="pass" classname="script&lt;1&gt;""#));
+        assert!(output.contains(
+            r#"<failure message="Assertion failed, &apos;2&apos; is not equal to &apos;3&apos;">"#
+        ));
+        assert!(output.ends_with("</testsuites>\n"));
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!("junit".parse(), Ok(TestReportFormat::JUnit));
+        assert_eq!("tap".parse(), Ok(TestReportFormat::Tap));
+        assert_eq!("text".parse(), Ok(TestReportFormat::Text));
+        assert!("xml".parse::<TestReportFormat>().is_err());
+    }
+}
@@ -18,6 +18,7 @@ pub enum Token {
     Id,
 
     // Symbols
+    At,
     Colon,
     Comma,
     Dot,
@@ -79,6 +80,7 @@ pub enum Token {
     Return,
     Switch,
     Then,
+    Throw,
     True,
     Try,
     Until,
@@ -197,23 +199,19 @@ impl<'a> TokenLexer<'a> {
                 char_bytes += c.len_utf8();
                 position.column += c.width().unwrap_or(0) as u32;
                 match c {
-                    '#' => {
-                        if chars.peek() == Some(&'-') {
-                            chars.next();
-                            char_bytes += 1;
-                            position.column += 1;
-                            nest_count += 1;
-                        }
+                    '#' if chars.peek() == Some(&'-') => {
+                        chars.next();
+                        char_bytes += 1;
+                        position.column += 1;
+                        nest_count += 1;
                     }
-                    '-' => {
-                        if chars.peek() == Some(&'#') {
-                            chars.next();
-                            char_bytes += 1;
-                            position.column += 1;
-                            nest_count -= 1;
-                            if nest_count == 0 {
-                                break;
-                            }
+                    '-' if chars.peek() == Some(&'#') => {
+                        chars.next();
+                        char_bytes += 1;
+                        position.column += 1;
+                        nest_count -= 1;
+                        if nest_count == 0 {
+                            break;
                         }
                     }
                     '\n' => {
@@ -235,42 +233,20 @@ impl<'a> TokenLexer<'a> {
         }
     }
 
-    fn consume_string(&mut self, mut chars: Peekable<Chars>) -> Option<Token> {
+    fn consume_string(&mut self, remaining: &str) -> Option<Token> {
         use Token::*;
 
-        // The '"' character has already been matched
-        chars.next();
-
-        let mut string_bytes = 1; // 1 for '"'
+        // The '"' character has already been matched, it's skipped here and added to the column
         let mut position = self.position;
+        position.column += 1;
 
-        while let Some(c) = chars.next() {
-            string_bytes += c.len_utf8();
-            position.column += c.width().unwrap_or(0) as u32;
-
-            match c {
-                '\\' => {
-                    if chars.peek() == Some(&'"') {
-                        chars.next();
-                        string_bytes += 1;
-                        position.column += 1;
-                    }
-                }
-                '\n' => {
-                    position.line += 1;
-                    position.column = 1;
-                }
-                '"' => {
-                    // +1 to get the column 1 past the end of the string
-                    position.column += 1;
-                    self.advance_to_position(string_bytes, position);
-                    return Some(String);
-                }
-                _ => {}
+        match consume_string_contents(&remaining[1..], &mut position) {
+            Some(string_bytes) => {
+                self.advance_to_position(string_bytes + 1, position); // +1 for the opening '"'
+                Some(String)
             }
+            None => Some(Error),
         }
-
-        Some(Error)
     }
 
     fn consume_number(&mut self, mut chars: Peekable<Chars>) -> Option<Token> {
@@ -375,6 +351,7 @@ impl<'a> TokenLexer<'a> {
         check_keyword!("return", Return);
         check_keyword!("switch", Switch);
         check_keyword!("then", Then);
+        check_keyword!("throw", Throw);
         check_keyword!("true", True);
         check_keyword!("try", Try);
         check_keyword!("until", Until);
@@ -423,6 +400,7 @@ impl<'a> TokenLexer<'a> {
         check_symbol!("/", Divide);
         check_symbol!("%", Modulo);
 
+        check_symbol!("@", At);
         check_symbol!(":", Colon);
         check_symbol!(",", Comma);
         check_symbol!(".", Dot);
@@ -457,7 +435,7 @@ impl<'a> Iterator for TokenLexer<'a> {
                     }
                     Some('\n') => self.consume_newline(chars),
                     Some('#') => self.consume_comment(chars),
-                    Some('"') => self.consume_string(chars),
+                    Some('"') => self.consume_string(remaining),
                     Some('0'..='9') => self.consume_number(chars),
                     Some(c) if is_id_start(*c) => self.consume_id_or_keyword(chars),
                     Some(_) => {
@@ -475,8 +453,117 @@ impl<'a> Iterator for TokenLexer<'a> {
     }
 }
 
+// Consumes the contents of a string literal, up to and including the closing '"'
+//
+// Expressions that are interpolated in the string are skipped over, with nested strings and
+// braces being taken into account.
+//
+// Returns the number of consumed bytes, or None if the end of the string wasn't found.
+fn consume_string_contents(s: &str, position: &mut Position) -> Option<usize> {
+    let mut index = 0;
+
+    while let Some(c) = s[index..].chars().next() {
+        index += c.len_utf8();
+        position.column += c.width().unwrap_or(0) as u32;
+
+        match c {
+            '\\' => {
+                if matches!(
+                    s[index..].chars().next(),
+                    Some('"') | Some('\\') | Some('{') | Some('}')
+                ) {
+                    index += 1;
+                    position.column += 1;
+                }
+            }
+            '\n' => {
+                position.line += 1;
+                position.column = 1;
+            }
+            '{' => {
+                if s[index..].starts_with('{') {
+                    // `{{` is passed through to the string, see is_format_placeholder
+                    index += 1;
+                    position.column += 1;
+                } else if let Some(expression_len) = interpolated_expression_len(&s[index - 1..]) {
+                    // The opening '{' has already been consumed
+                    let expression = &s[index..index - 1 + expression_len];
+                    for c in expression.chars() {
+                        if c == '\n' {
+                            position.line += 1;
+                            position.column = 1;
+                        } else {
+                            position.column += c.width().unwrap_or(0) as u32;
+                        }
+                    }
+                    index += expression.len();
+                }
+            }
+            '"' => return Some(index),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the length of the interpolated expression at the start of the input
+///
+/// An interpolated expression starts with '{', and is followed by tokens on the same line
+/// with balanced brackets, up until the closing '}'. The returned length includes the braces.
+///
+/// `{}` and `{{` aren't interpolated expressions, and are left untouched in string literals so
+/// that they remain available for use with `string.format`.
+///
+/// None is also returned if the input starts with an interpolated expression that isn't
+/// terminated, in which case the parser reports an error for the string.
+pub fn interpolated_expression_len(s: &str) -> Option<usize> {
+    use Token::*;
+
+    let contents = s.strip_prefix('{')?;
+    if is_format_placeholder(contents) {
+        return None;
+    }
+
+    let mut lexer = TokenLexer::new(contents);
+    let mut brackets = Vec::new();
+
+    while let Some(token) = lexer.next() {
+        match token {
+            ParenOpen | ListStart | MapStart => brackets.push(token),
+            ParenClose => match brackets.pop() {
+                Some(ParenOpen) => {}
+                _ => return None,
+            },
+            ListEnd => match brackets.pop() {
+                Some(ListStart) => {}
+                _ => return None,
+            },
+            MapEnd => match brackets.pop() {
+                Some(MapStart) => {}
+                Some(_) => return None,
+                None => return Some(lexer.current + 1), // +1 for the opening '{'
+            },
+            // Nested strings need to be on the same line as the rest of the expression
+            String if lexer.slice().contains('\n') => return None,
+            Error | NewLine | NewLineIndented | CommentSingle | CommentMulti => return None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns true if the string contents following a '{' make it a `string.format` placeholder
+///
+/// `{}` and `{{` are passed through to the string unchanged, any other '{' starts an
+/// interpolated expression.
+pub fn is_format_placeholder(contents_after_brace: &str) -> bool {
+    matches!(contents_after_brace.chars().next(), Some('{') | Some('}'))
+}
+
 fn is_digit(c: char) -> bool {
-    matches!(c, '0'..='9')
+    c.is_ascii_digit()
 }
 
 fn is_whitespace(c: char) -> bool {
@@ -549,6 +636,40 @@ impl<'a> KotoLexer<'a> {
         }
     }
 
+    /// Returns a new lexer that starts at the given byte offset within the current token
+    ///
+    /// This is used by the parser to lex the expressions that are interpolated in strings.
+    pub fn lexer_at_token_offset(&self, offset: usize) -> Self {
+        let slice = self.slice();
+        let start = self.source_position() - slice.len() + offset;
+
+        let mut position = self.span().start;
+        for c in slice[..offset].chars() {
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += c.width().unwrap_or(0) as u32;
+            }
+        }
+
+        Self {
+            lexer: TokenLexer {
+                source: self.lexer.source,
+                previous: start,
+                current: start,
+                indent: self.current_indent(),
+                position,
+                span: Span {
+                    start: position,
+                    end: position,
+                },
+            },
+            peeked_tokens: Vec::new(),
+            current_peek_index: 0,
+        }
+    }
+
     pub fn peek(&mut self) -> Option<Token> {
         if self.peeked_tokens.is_empty() {
             self.peek_n(0)
@@ -681,7 +802,7 @@ mod tests {
                             assert_eq!(&lex.slice(), slice, "Slice mismatch at position {}", i);
                         }
                         assert_eq!(
-                            lex.line_number() as u32,
+                            lex.line_number(),
                             *line_number,
                             "Line number mismatch at position {}",
                             i
@@ -708,7 +829,7 @@ mod tests {
                             assert_eq!(&lex.slice(), slice, "Mismatch at token {}", i);
                         }
                         assert_eq!(
-                            lex.line_number() as u32,
+                            lex.line_number(),
                             *line_number,
                             "Line number - expected: {}, actual: {} - (token {} - {:?})",
                             *line_number,
@@ -817,6 +938,77 @@ true"#;
         );
     }
 
+    #[test]
+    fn interpolated_strings() {
+        let input = r#"
+"hello, {name}!"
+"{x + "}"} {{}} {} {0}"
+"{ {foo: "{bar}"}.foo }"
+"\{not_interpolated}"
+true"#;
+        check_lexer_output(
+            input,
+            &[
+                (NewLine, None, 2),
+                (String, Some(r#""hello, {name}!""#), 2),
+                (NewLine, None, 3),
+                (String, Some(r#""{x + "}"} {{}} {} {0}""#), 3),
+                (NewLine, None, 4),
+                (String, Some(r#""{ {foo: "{bar}"}.foo }""#), 4),
+                (NewLine, None, 5),
+                (String, Some(r#""\{not_interpolated}""#), 5),
+                (NewLine, None, 6),
+                (True, None, 6),
+            ],
+        );
+    }
+
+    #[test]
+    fn strings_with_unterminated_interpolation() {
+        // The parser reports errors for these strings, the lexer only needs to find their ends
+        let input = r#"
+"a { b"
+"{x"
+"{x" + "}"
+"{foo: 1"
+true"#;
+        check_lexer_output(
+            input,
+            &[
+                (NewLine, None, 2),
+                (String, Some(r#""a { b""#), 2),
+                (NewLine, None, 3),
+                (String, Some(r#""{x""#), 3),
+                (NewLine, None, 4),
+                (String, Some(r#""{x" + "}""#), 4),
+                (NewLine, None, 5),
+                (String, Some(r#""{foo: 1""#), 5),
+                (NewLine, None, 6),
+                (True, None, 6),
+            ],
+        );
+    }
+
+    #[test]
+    fn interpolated_expression_lengths() {
+        assert_eq!(interpolated_expression_len("{x}"), Some(3));
+        assert_eq!(interpolated_expression_len("{ x } y"), Some(5));
+        assert_eq!(interpolated_expression_len(r#"{(x, "y")}"#), Some(10));
+        assert_eq!(interpolated_expression_len(r#"{" + x + "}"#), Some(11));
+        assert_eq!(interpolated_expression_len("{0 + x}"), Some(7));
+        assert_eq!(interpolated_expression_len("{ {a: [1, 2]}.a }"), Some(17));
+        assert_eq!(interpolated_expression_len(r#"{x + "}"}"#), Some(9));
+        assert_eq!(interpolated_expression_len("{}"), None);
+        assert_eq!(interpolated_expression_len("{ }"), Some(3));
+        assert_eq!(interpolated_expression_len("{{x}}"), None);
+        assert_eq!(interpolated_expression_len("{42}"), Some(4));
+        assert_eq!(interpolated_expression_len("{x"), None);
+        assert_eq!(interpolated_expression_len("{(x}"), None);
+        assert_eq!(interpolated_expression_len("{x\n}"), None);
+        assert_eq!(interpolated_expression_len("{x + \"\n\"}"), None);
+        assert_eq!(interpolated_expression_len("x"), None);
+    }
+
     #[test]
     fn numbers() {
         let input = "\
@@ -1041,4 +1233,26 @@ else
             ],
         );
     }
+
+    #[test]
+    fn map_with_meta_keys() {
+        let input = "{@+: f, @display: g}";
+
+        check_lexer_output(
+            input,
+            &[
+                (MapStart, None, 1),
+                (At, None, 1),
+                (Add, None, 1),
+                (Colon, None, 1),
+                (Id, Some("f"), 1),
+                (Comma, None, 1),
+                (At, None, 1),
+                (Id, Some("display"), 1),
+                (Colon, None, 1),
+                (Id, Some("g"), 1),
+                (MapEnd, None, 1),
+            ],
+        );
+    }
 }
@@ -3,5 +3,8 @@
 mod lexer;
 mod span;
 
-pub use lexer::{is_id_continue, is_id_start, KotoLexer as Lexer, Token};
+pub use lexer::{
+    interpolated_expression_len, is_format_placeholder, is_id_continue, is_id_start,
+    KotoLexer as Lexer, Token,
+};
 pub use span::{Position, Span};
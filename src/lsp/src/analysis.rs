@@ -0,0 +1,712 @@
+use {
+    crate::position::utf16_offset,
+    koto_bytecode::{Compiler, CompilerSettings},
+    koto_lexer::{Lexer, Position, Span, Token},
+    koto_parser::{
+        Ast, AstIndex, ConstantIndex, ConstantPool, LookupNode, Node, Parser, Scope, StringNode,
+    },
+};
+
+/// A token produced by the lexer, along with its span and source text
+pub struct LexedToken<'a> {
+    pub token: Token,
+    pub span: Span,
+    pub slice: &'a str,
+}
+
+/// An error that was found while parsing or compiling a document
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SymbolKind {
+    Function,
+    Variable,
+}
+
+/// A top-level assignment or an export
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    // The span of the full assignment
+    pub span: Span,
+    // The span of the assigned ID
+    pub name_span: Span,
+    pub exported: bool,
+}
+
+/// A reference to a module, or to an item in a module
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModuleReference {
+    pub module: String,
+    pub item: Option<String>,
+}
+
+pub enum DefinitionKind {
+    Local,
+    Import(ModuleReference),
+}
+
+/// A location in the document where an ID is assigned a value
+pub struct Definition {
+    pub name: String,
+    pub span: Span,
+    pub kind: DefinitionKind,
+    // The span of the function that contains the definition, None for the main block
+    scope: Option<Span>,
+    // The number of functions that contain the definition
+    depth: usize,
+}
+
+struct ImportExpression {
+    span: Span,
+    from: Vec<String>,
+    items: Vec<Vec<String>>,
+}
+
+/// The result of analysing a document's source
+pub struct Analysis<'a> {
+    pub tokens: Vec<LexedToken<'a>>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub definitions: Vec<Definition>,
+    pub symbols: Vec<Symbol>,
+    imports: Vec<ImportExpression>,
+}
+
+impl<'a> Analysis<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut result = Self {
+            tokens: lex(source),
+            diagnostics: Vec::new(),
+            definitions: Vec::new(),
+            symbols: Vec::new(),
+            imports: Vec::new(),
+        };
+
+        match Parser::parse_with_diagnostics(source) {
+            Ok((ast, constants)) => {
+                if let Err(error) = Compiler::compile(&ast, CompilerSettings::default()) {
+                    result.diagnostics.push(Diagnostic {
+                        span: error.span,
+                        message: error.message,
+                    });
+                }
+
+                if let Some(entry_point) = ast.entry_point() {
+                    if let Node::MainBlock { body, .. } = &entry_point.node {
+                        let mut collector = Collector {
+                            ast: &ast,
+                            constants: &constants,
+                            analysis: &mut result,
+                            scopes: Vec::new(),
+                            statement: None,
+                        };
+                        collector.visit_main_block(body);
+                    }
+                }
+            }
+            Err(errors) => {
+                result.diagnostics = errors
+                    .into_iter()
+                    .map(|error| {
+                        let message = match error.suggestion() {
+                            Some(suggestion) => format!("{}\nhelp: {}", error, suggestion),
+                            None => error.to_string(),
+                        };
+                        Diagnostic {
+                            span: error.span,
+                            message,
+                        }
+                    })
+                    .collect()
+            }
+        }
+
+        result
+    }
+
+    /// Returns the index of the token at the given position
+    ///
+    /// A position at the end of a token is considered to be part of the token, which allows
+    /// IDs to be found when the cursor is placed directly after them.
+    pub fn token_at(&self, position: Position) -> Option<usize> {
+        let mut result = None;
+
+        for (i, token) in self.tokens.iter().enumerate() {
+            if token.span.start > position {
+                break;
+            }
+            if position <= token.span.end {
+                result = Some(i);
+                if token.token == Token::Id {
+                    break;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Finds the module that's referred to by an ID token in an import expression
+    pub fn module_reference_at(&self, token_index: usize) -> Option<ModuleReference> {
+        let token = self.tokens.get(token_index)?;
+        if token.token != Token::Id {
+            return None;
+        }
+
+        let import = self
+            .imports
+            .iter()
+            .find(|import| span_contains(&import.span, token.span.start))?;
+
+        match import.from.first() {
+            Some(module) if module == token.slice => Some(ModuleReference {
+                module: module.clone(),
+                item: None,
+            }),
+            Some(module) => import
+                .items
+                .iter()
+                .find(|item| item.first().map(String::as_str) == Some(token.slice))
+                .map(|_| ModuleReference {
+                    module: module.clone(),
+                    item: Some(token.slice.to_string()),
+                }),
+            None => import
+                .items
+                .iter()
+                .find(|item| item.first().map(String::as_str) == Some(token.slice))
+                .map(|_| ModuleReference {
+                    module: token.slice.to_string(),
+                    item: None,
+                }),
+        }
+    }
+
+    /// Finds the definition of the ID token at the given index
+    ///
+    /// The innermost definition that's visible from the token's position is returned, with
+    /// preference given to the latest definition that precedes the token.
+    pub fn definition_of(&self, token_index: usize) -> Option<&Definition> {
+        let token = self.tokens.get(token_index)?;
+        if token.token != Token::Id {
+            return None;
+        }
+
+        // IDs that follow a '.' are map accesses rather than references to a definition
+        if token_index > 0 && self.tokens[token_index - 1].token == Token::Dot {
+            return None;
+        }
+
+        let position = token.span.start;
+        let candidates = self
+            .definitions
+            .iter()
+            .filter(|definition| {
+                definition.name == token.slice
+                    && definition
+                        .scope
+                        .iter()
+                        .all(|scope| span_contains(scope, position))
+            })
+            .collect::<Vec<_>>();
+
+        let depth = candidates.iter().map(|definition| definition.depth).max()?;
+        let innermost = candidates
+            .into_iter()
+            .filter(|definition| definition.depth == depth)
+            .collect::<Vec<_>>();
+
+        innermost
+            .iter()
+            .rev()
+            .find(|definition| definition.span.start <= position)
+            .or_else(|| innermost.first())
+            .copied()
+    }
+
+    /// Returns the semantic tokens for the document, encoded in the LSP's relative format
+    ///
+    /// See [SEMANTIC_TOKEN_TYPES] for the token types that are used.
+    pub fn semantic_tokens(&self, source: &str) -> Vec<u32> {
+        let lines = source.lines().collect::<Vec<_>>();
+        let mut result = Vec::new();
+        let mut previous_line = 0;
+        let mut previous_start = 0;
+
+        for (i, token) in self.tokens.iter().enumerate() {
+            let token_type = match semantic_token_type(token.token) {
+                Some(token_type) => token_type,
+                None => continue,
+            };
+            let token_type =
+                if token.token == Token::Id && i > 0 && self.tokens[i - 1].token == Token::Dot {
+                    SemanticTokenType::Property
+                } else {
+                    token_type
+                };
+
+            // Tokens that span multiple lines are split into a token per line
+            //
+            // Token positions are converted into UTF-16 offsets, which are used by LSP clients.
+            for line in token.span.start.line..=token.span.end.line {
+                let text = lines.get(line as usize - 1).copied().unwrap_or_default();
+                let start = if line == token.span.start.line {
+                    utf16_offset(text, token.span.start.column)
+                } else {
+                    0
+                };
+                let end = if line == token.span.end.line {
+                    utf16_offset(text, token.span.end.column)
+                } else {
+                    text.encode_utf16().count() as u32
+                };
+
+                if end <= start {
+                    continue;
+                }
+
+                let line = line - 1;
+                let delta_line = line - previous_line;
+                let delta_start = if delta_line == 0 {
+                    start - previous_start
+                } else {
+                    start
+                };
+
+                result.extend_from_slice(&[
+                    delta_line,
+                    delta_start,
+                    end - start,
+                    token_type as u32,
+                    0,
+                ]);
+
+                previous_line = line;
+                previous_start = start;
+            }
+        }
+
+        result
+    }
+}
+
+/// The semantic token types that are provided by the server, in legend order
+pub const SEMANTIC_TOKEN_TYPES: &[&str] = &[
+    "keyword", "variable", "property", "string", "number", "comment", "operator",
+];
+
+#[derive(Clone, Copy)]
+enum SemanticTokenType {
+    Keyword,
+    Variable,
+    Property,
+    String,
+    Number,
+    Comment,
+    Operator,
+}
+
+fn semantic_token_type(token: Token) -> Option<SemanticTokenType> {
+    use {SemanticTokenType as Type, Token::*};
+
+    let result = match token {
+        Error | Whitespace | NewLine | NewLineIndented => return None,
+        CommentSingle | CommentMulti => Type::Comment,
+        Number => Type::Number,
+        String => Type::String,
+        Id => Type::Variable,
+        At | Colon | Comma | Dot | Ellipsis | ParenOpen | ParenClose | Function | ListStart
+        | ListEnd | MapStart | MapEnd | Wildcard => return None,
+        Range | RangeInclusive | Add | Subtract | Multiply | Divide | Modulo | Assign
+        | AssignAdd | AssignSubtract | AssignMultiply | AssignDivide | AssignModulo | Equal
+        | NotEqual | Greater | GreaterOrEqual | Less | LessOrEqual => Type::Operator,
+        And | Break | Catch | Continue | Debug | Else | ElseIf | Export | False | Finally | For
+        | From | If | Import | In | Loop | Match | Not | Num2 | Num4 | Or | Return | Switch
+        | Then | Throw | True | Try | Until | While | Yield => Type::Keyword,
+    };
+
+    Some(result)
+}
+
+// Lexes the source, skipping whitespace and newlines
+//
+// Lexing stops if an error is encountered.
+fn lex(source: &str) -> Vec<LexedToken<'_>> {
+    let mut lexer = Lexer::new(source);
+    let mut result = Vec::new();
+
+    while let Some(token) = lexer.next() {
+        match token {
+            Token::Error => break,
+            Token::Whitespace | Token::NewLine | Token::NewLineIndented => {}
+            _ => result.push(LexedToken {
+                token,
+                span: lexer.span(),
+                slice: lexer.slice(),
+            }),
+        }
+    }
+
+    result
+}
+
+fn span_contains(span: &Span, position: Position) -> bool {
+    span.start <= position && position <= span.end
+}
+
+// Walks the AST, collecting definitions, symbols, and imports
+struct Collector<'a, 'b> {
+    ast: &'a Ast,
+    constants: &'a ConstantPool,
+    analysis: &'a mut Analysis<'b>,
+    // The spans of the functions containing the current node
+    scopes: Vec<Span>,
+    // The index of the main block expression that's currently being visited
+    statement: Option<AstIndex>,
+}
+
+impl<'a, 'b> Collector<'a, 'b> {
+    fn visit_main_block(&mut self, body: &[AstIndex]) {
+        for &statement in body.iter() {
+            self.statement = Some(statement);
+
+            // A standalone ID preceded by 'export' is an export of an existing value
+            if let Node::Id(id) = &self.ast.node(statement).node {
+                let span = self.span(statement);
+                if self.token_before(span.start) == Some(Token::Export) {
+                    self.analysis.symbols.push(Symbol {
+                        name: self.string(*id),
+                        kind: SymbolKind::Variable,
+                        span,
+                        name_span: span,
+                        exported: true,
+                    });
+                }
+            }
+
+            self.visit(statement);
+        }
+
+        self.statement = None;
+    }
+
+    fn visit(&mut self, index: AstIndex) {
+        use Node::*;
+
+        let node = &self.ast.node(index).node;
+
+        match node {
+            Empty | Id(_) | BoolTrue | BoolFalse | Number0 | Number1 | Int(_) | Float(_)
+            | Str(_) | RangeFull | Wildcard | Ellipsis(_) | Break | Continue | Return => {}
+            Lookup((lookup, next)) => {
+                match lookup {
+                    LookupNode::Root(node) | LookupNode::Index(node) => self.visit(*node),
+                    LookupNode::Call(args) => self.visit_all(args),
+                    LookupNode::Id(_) => {}
+                }
+                if let Some(next) = next {
+                    self.visit(*next);
+                }
+            }
+            InterpolatedString(nodes) => {
+                for node in nodes.iter() {
+                    if let StringNode::Expression(expression) = node {
+                        self.visit(*expression);
+                    }
+                }
+            }
+            Num2(nodes) | Num4(nodes) | List(nodes) | Tuple(nodes) | TempTuple(nodes)
+            | Block(nodes) => self.visit_all(nodes),
+            Range { start, end, .. } => {
+                self.visit(*start);
+                self.visit(*end);
+            }
+            RangeFrom { start } => self.visit(*start),
+            RangeTo { end, .. } => self.visit(*end),
+            Map(entries) => {
+                for (_, value) in entries.iter() {
+                    if let Some(value) = value {
+                        self.visit(*value);
+                    }
+                }
+            }
+            MainBlock { body, .. } => self.visit_all(body),
+            Function(function) => {
+                self.scopes.push(self.span(index));
+                for arg in function.args.iter() {
+                    self.add_pattern_definitions(*arg);
+                }
+                self.visit_all(&function.default_args);
+                self.visit(function.body);
+                self.scopes.pop();
+            }
+            Call { function, args } => {
+                self.visit(*function);
+                self.visit_all(args);
+            }
+            NamedArg { value, .. } => self.visit(*value),
+            Import { from, items } => self.add_import(index, from, items),
+            Assign {
+                target, expression, ..
+            } => {
+                if let Id(id) = &self.ast.node(target.target_index).node {
+                    self.add_definition(*id, self.span(target.target_index));
+
+                    let exported = target.scope == Scope::Global;
+                    if exported || self.statement == Some(index) {
+                        self.add_symbol(*id, target.target_index, *expression, exported);
+                    }
+                } else {
+                    self.visit(target.target_index);
+                }
+                self.visit(*expression);
+            }
+            MultiAssign {
+                targets,
+                expression,
+            } => {
+                for target in targets.iter() {
+                    if let Id(id) = &self.ast.node(target.target_index).node {
+                        self.add_definition(*id, self.span(target.target_index));
+
+                        let exported = target.scope == Scope::Global;
+                        if exported || self.statement == Some(index) {
+                            let name_span = self.span(target.target_index);
+                            self.analysis.symbols.push(Symbol {
+                                name: self.string(*id),
+                                kind: SymbolKind::Variable,
+                                span: name_span,
+                                name_span,
+                                exported,
+                            });
+                        }
+                    } else {
+                        self.visit(target.target_index);
+                    }
+                }
+                self.visit(*expression);
+            }
+            BinaryOp { lhs, rhs, .. } => {
+                self.visit(*lhs);
+                self.visit(*rhs);
+            }
+            If(ast_if) => {
+                self.visit(ast_if.condition);
+                self.visit(ast_if.then_node);
+                for (condition, block) in ast_if.else_if_blocks.iter() {
+                    self.visit(*condition);
+                    self.visit(*block);
+                }
+                if let Some(else_node) = ast_if.else_node {
+                    self.visit(else_node);
+                }
+            }
+            Match { expression, arms } => {
+                self.visit(*expression);
+                for arm in arms.iter() {
+                    for pattern in arm.patterns.iter() {
+                        self.add_pattern_definitions(*pattern);
+                    }
+                    if let Some(condition) = arm.condition {
+                        self.visit(condition);
+                    }
+                    self.visit(arm.expression);
+                }
+            }
+            Switch(arms) => {
+                for arm in arms.iter() {
+                    if let Some(condition) = arm.condition {
+                        self.visit(condition);
+                    }
+                    self.visit(arm.expression);
+                }
+            }
+            For(ast_for) => {
+                let start = self.span(index).start;
+                let end = self.span(ast_for.range).start;
+                for arg in ast_for.args.iter().flatten() {
+                    if let Some(span) = self.find_id(*arg, start, end) {
+                        self.add_definition(*arg, span);
+                    }
+                }
+                self.visit(ast_for.range);
+                self.visit(ast_for.body);
+            }
+            Loop { body } => self.visit(*body),
+            While { condition, body } | Until { condition, body } => {
+                self.visit(*condition);
+                self.visit(*body);
+            }
+            ReturnExpression(expression)
+            | Negate(expression)
+            | Yield(expression)
+            | Throw(expression) => self.visit(*expression),
+            Try(ast_try) => {
+                self.visit(ast_try.try_block);
+                if let Some(catch_arg) = ast_try.catch_arg {
+                    let start = self.span(index).start;
+                    let end = self.span(ast_try.catch_block).start;
+                    if let Some(span) = self.find_id(catch_arg, start, end) {
+                        self.add_definition(catch_arg, span);
+                    }
+                }
+                self.visit(ast_try.catch_block);
+                if let Some(finally_block) = ast_try.finally_block {
+                    self.visit(finally_block);
+                }
+            }
+            Debug { expression, .. } => self.visit(*expression),
+        }
+    }
+
+    fn visit_all(&mut self, nodes: &[AstIndex]) {
+        for node in nodes.iter() {
+            self.visit(*node);
+        }
+    }
+
+    // Adds definitions for the IDs in function args or match patterns
+    fn add_pattern_definitions(&mut self, index: AstIndex) {
+        match &self.ast.node(index).node {
+            Node::Id(id) => self.add_definition(*id, self.span(index)),
+            Node::List(nested) | Node::Tuple(nested) | Node::TempTuple(nested) => {
+                for node in nested.iter() {
+                    self.add_pattern_definitions(*node);
+                }
+            }
+            _ => self.visit(index),
+        }
+    }
+
+    fn add_definition(&mut self, id: ConstantIndex, span: Span) {
+        self.add_definition_with_kind(id, span, DefinitionKind::Local);
+    }
+
+    fn add_definition_with_kind(&mut self, id: ConstantIndex, span: Span, kind: DefinitionKind) {
+        self.analysis.definitions.push(Definition {
+            name: self.string(id),
+            span,
+            kind,
+            scope: self.scopes.last().copied(),
+            depth: self.scopes.len(),
+        });
+    }
+
+    fn add_symbol(
+        &mut self,
+        id: ConstantIndex,
+        target: AstIndex,
+        expression: AstIndex,
+        exported: bool,
+    ) {
+        let name_span = self.span(target);
+        let expression_span = self.span(expression);
+
+        let kind = match self.ast.node(expression).node {
+            Node::Function(_) => SymbolKind::Function,
+            _ => SymbolKind::Variable,
+        };
+
+        let span = Span {
+            start: name_span.start,
+            end: if expression_span.end > name_span.end {
+                expression_span.end
+            } else {
+                name_span.end
+            },
+        };
+
+        self.analysis.symbols.push(Symbol {
+            name: self.string(id),
+            kind,
+            span,
+            name_span,
+            exported,
+        });
+    }
+
+    fn add_import(
+        &mut self,
+        index: AstIndex,
+        from: &[ConstantIndex],
+        items: &[Vec<ConstantIndex>],
+    ) {
+        let span = self.span(index);
+
+        // The imported items are listed after the 'import' keyword
+        let items_start = self
+            .analysis
+            .tokens
+            .iter()
+            .find(|token| token.token == Token::Import && span_contains(&span, token.span.start))
+            .map_or(span.start, |token| token.span.end);
+
+        for item in items.iter() {
+            let id = match item.last() {
+                Some(id) => *id,
+                None => continue,
+            };
+
+            let reference = match from.first() {
+                Some(module) => ModuleReference {
+                    module: self.string(*module),
+                    item: from
+                        .get(1)
+                        .or_else(|| item.first())
+                        .map(|id| self.string(*id)),
+                },
+                None => ModuleReference {
+                    module: self.string(item[0]),
+                    item: item.get(1).map(|id| self.string(*id)),
+                },
+            };
+
+            let id_span = self.find_id(id, items_start, span.end).unwrap_or(span);
+            self.add_definition_with_kind(id, id_span, DefinitionKind::Import(reference));
+        }
+
+        let strings = |ids: &[ConstantIndex]| -> Vec<String> {
+            ids.iter().map(|id| self.string(*id)).collect()
+        };
+        let import = ImportExpression {
+            span,
+            from: strings(from),
+            items: items.iter().map(|item| strings(item)).collect(),
+        };
+        self.analysis.imports.push(import);
+    }
+
+    // Finds the span of the first ID token with a matching name in the given range
+    fn find_id(&self, id: ConstantIndex, start: Position, end: Position) -> Option<Span> {
+        let name = self.constants.get_str(id);
+        self.analysis
+            .tokens
+            .iter()
+            .find(|token| {
+                token.token == Token::Id
+                    && token.slice == name
+                    && token.span.start >= start
+                    && token.span.start < end
+            })
+            .map(|token| token.span)
+    }
+
+    fn token_before(&self, position: Position) -> Option<Token> {
+        let tokens = &self.analysis.tokens;
+        let index = tokens
+            .iter()
+            .position(|token| token.span.start == position)?;
+        index.checked_sub(1).map(|previous| tokens[previous].token)
+    }
+
+    fn span(&self, index: AstIndex) -> Span {
+        *self.ast.span(self.ast.node(index).span)
+    }
+
+    fn string(&self, id: ConstantIndex) -> String {
+        self.constants.get_str(id).to_string()
+    }
+}
@@ -0,0 +1,66 @@
+use {
+    koto_runtime::{core::CoreLib, Value, ValueMap},
+    std::collections::BTreeMap,
+};
+
+/// The names of the modules and functions that are available in Koto's core library
+pub struct CoreLibInfo {
+    modules: BTreeMap<&'static str, Vec<String>>,
+}
+
+impl CoreLibInfo {
+    /// Returns the names of the functions in the given module, sorted alphabetically
+    pub fn module(&self, name: &str) -> Option<&[String]> {
+        self.modules.get(name).map(|functions| functions.as_slice())
+    }
+
+    /// Returns the names of the modules that contain a function with the given name
+    pub fn modules_containing(&self, function: &str) -> Vec<&'static str> {
+        self.modules
+            .iter()
+            .filter(|(_, functions)| functions.iter().any(|f| f == function))
+            .map(|(module, _)| *module)
+            .collect()
+    }
+}
+
+impl Default for CoreLibInfo {
+    fn default() -> Self {
+        let core_lib = CoreLib::default();
+
+        let modules = vec![
+            ("io", &core_lib.io),
+            ("iterator", &core_lib.iterator),
+            ("koto", &core_lib.koto),
+            ("list", &core_lib.list),
+            ("map", &core_lib.map),
+            ("num2", &core_lib.num2),
+            ("num4", &core_lib.num4),
+            ("number", &core_lib.number),
+            ("os", &core_lib.os),
+            ("range", &core_lib.range),
+            ("string", &core_lib.string),
+            ("test", &core_lib.test),
+            ("thread", &core_lib.thread),
+            ("tuple", &core_lib.tuple),
+        ]
+        .into_iter()
+        .map(|(name, module)| (name, function_names(module)))
+        .collect();
+
+        Self { modules }
+    }
+}
+
+fn function_names(module: &ValueMap) -> Vec<String> {
+    let mut result = module
+        .data()
+        .keys()
+        .filter_map(|key| match key {
+            Value::Str(s) => Some(s.to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    result.sort();
+    result
+}
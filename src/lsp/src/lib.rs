@@ -0,0 +1,17 @@
+//! A language server for the Koto programming language
+//!
+//! The server communicates with clients via JSON-RPC messages, following the
+//! [Language Server Protocol](https://microsoft.github.io/language-server-protocol/).
+//! See [Server] for the list of supported features.
+
+mod analysis;
+mod core_lib;
+mod position;
+mod server;
+mod transport;
+mod uri;
+
+pub use {
+    server::Server,
+    transport::{read_message, write_message},
+};
@@ -0,0 +1,39 @@
+use {
+    koto_lsp::{read_message, write_message, Server},
+    serde_json::json,
+    std::io::{self, ErrorKind},
+};
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+
+    let mut server = Server::default();
+
+    loop {
+        match read_message(&mut input) {
+            Ok(Some(message)) => {
+                for response in server.handle_message(message) {
+                    write_message(&mut output, &response)?;
+                }
+
+                if let Some(exit_code) = server.exit_code() {
+                    std::process::exit(exit_code);
+                }
+            }
+            // The client closed the connection without sending 'exit'
+            Ok(None) => std::process::exit(1),
+            Err(error) if error.kind() == ErrorKind::InvalidData => {
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": {"code": -32700, "message": error.to_string()},
+                });
+                write_message(&mut output, &response)?;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use unicode_width::UnicodeWidthChar;
+
+// Koto columns are measured by the display width of each character, while LSP positions
+// are measured in UTF-16 code units.
+//
+// Control characters don't have a display width, and are counted as taking a single column
+// to match the lexer's handling of whitespace.
+fn char_width(c: char) -> u32 {
+    c.width().unwrap_or(1) as u32
+}
+
+/// Converts a zero-based UTF-16 offset in a line into a Koto column, which starts at 1
+pub fn koto_column(line: &str, utf16_offset: u32) -> u32 {
+    let mut offset = 0;
+    let mut column = 1;
+
+    for c in line.chars() {
+        if offset >= utf16_offset {
+            break;
+        }
+        offset += c.len_utf16() as u32;
+        column += char_width(c);
+    }
+
+    column
+}
+
+/// Converts a Koto column in a line into a zero-based UTF-16 offset
+pub fn utf16_offset(line: &str, column: u32) -> u32 {
+    let mut offset = 0;
+    let mut current_column = 1;
+
+    for c in line.chars() {
+        let width = char_width(c);
+        // Zero-width characters, e.g. combining marks, belong with the preceding character
+        if current_column >= column && width > 0 {
+            break;
+        }
+        offset += c.len_utf16() as u32;
+        current_column += width;
+    }
+
+    offset
+}
@@ -0,0 +1,480 @@
+use {
+    crate::{
+        analysis::{Analysis, DefinitionKind, ModuleReference, SymbolKind, SEMANTIC_TOKEN_TYPES},
+        core_lib::CoreLibInfo,
+        position::{koto_column, utf16_offset},
+        uri::{path_to_uri, uri_to_path},
+    },
+    koto_bytecode::FileSystemResolver,
+    koto_lexer::{Position, Span, Token},
+    serde_json::{json, Value},
+    std::{collections::HashMap, fs, path::Path},
+};
+
+// JSON-RPC error codes
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+// LSP symbol kinds
+const SYMBOL_KIND_FUNCTION: u32 = 12;
+const SYMBOL_KIND_VARIABLE: u32 = 13;
+
+struct Document {
+    text: String,
+}
+
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+impl ResponseError {
+    fn invalid_params(message: &str) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+}
+
+type RequestResult = Result<Value, ResponseError>;
+
+/// A Language Server Protocol server for Koto
+///
+/// Messages are passed to the server with [Server::handle_message], which returns the messages
+/// that should be sent back to the client. The server doesn't perform any IO itself, see
+/// [read_message](crate::read_message) and [write_message](crate::write_message).
+///
+/// The following features are supported:
+///   - Diagnostics for parser and compiler errors.
+///   - Document symbols for top-level assignments and exports.
+///   - Go-to-definition for local values and imported modules.
+///   - Hover information for core library modules and functions.
+///   - Semantic tokens.
+///
+/// Documents are synchronized in full on each change.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    core_lib: CoreLibInfo,
+    initialized: bool,
+    shutdown_requested: bool,
+    exit_code: Option<i32>,
+}
+
+impl Server {
+    /// Handles a JSON-RPC message from the client, returning the messages to send in response
+    pub fn handle_message(&mut self, message: Value) -> Vec<Value> {
+        let method = message.get("method").and_then(Value::as_str);
+        let params = message.get("params").unwrap_or(&Value::Null);
+
+        match (method, message.get("id")) {
+            (Some(method), Some(id)) => {
+                let response = match self.handle_request(method, params) {
+                    Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                    Err(error) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": error.code, "message": error.message},
+                    }),
+                };
+                vec![response]
+            }
+            (Some(method), None) => self.handle_notification(method, params),
+            // Responses from the client aren't expected, they're ignored
+            (None, Some(_)) => vec![],
+            (None, None) => vec![json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {"code": INVALID_REQUEST, "message": "Invalid request"},
+            })],
+        }
+    }
+
+    /// The code that the server should exit with, available once an `exit` notification is received
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value) -> RequestResult {
+        if method == "initialize" {
+            self.initialized = true;
+            return Ok(initialize_result());
+        }
+
+        if !self.initialized {
+            return Err(ResponseError {
+                code: SERVER_NOT_INITIALIZED,
+                message: "The server hasn't been initialized".into(),
+            });
+        }
+
+        if self.shutdown_requested {
+            return Err(ResponseError {
+                code: INVALID_REQUEST,
+                message: "The server has been shut down".into(),
+            });
+        }
+
+        match method {
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Ok(Value::Null)
+            }
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/semanticTokens/full" => self.semantic_tokens(params),
+            _ => Err(ResponseError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unsupported method '{}'", method),
+            }),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        match method {
+            "exit" => {
+                self.exit_code = Some(if self.shutdown_requested { 0 } else { 1 });
+                vec![]
+            }
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                match (document["uri"].as_str(), document["text"].as_str()) {
+                    (Some(uri), Some(text)) => {
+                        self.update_document(uri, text.to_string(), document["version"].as_i64())
+                    }
+                    _ => vec![],
+                }
+            }
+            "textDocument/didChange" => {
+                let uri = match params["textDocument"]["uri"].as_str() {
+                    Some(uri) => uri,
+                    None => return vec![],
+                };
+                // Documents are synchronized in full, so only the last change is needed
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                match text {
+                    Some(text) => self.update_document(
+                        uri,
+                        text.to_string(),
+                        params["textDocument"]["version"].as_i64(),
+                    ),
+                    None => vec![],
+                }
+            }
+            "textDocument/didClose" => match params["textDocument"]["uri"].as_str() {
+                Some(uri) => {
+                    self.documents.remove(uri);
+                    vec![publish_diagnostics(uri, None, vec![])]
+                }
+                None => vec![],
+            },
+            // Other notifications (e.g. 'initialized') don't require any action
+            _ => vec![],
+        }
+    }
+
+    fn update_document(&mut self, uri: &str, text: String, version: Option<i64>) -> Vec<Value> {
+        let lines = text.lines().collect::<Vec<_>>();
+        let diagnostics = Analysis::new(&text)
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                json!({
+                    "range": lsp_range(diagnostic.span, &lines),
+                    "severity": 1,
+                    "source": "koto",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect();
+
+        self.documents.insert(uri.to_string(), Document { text });
+
+        vec![publish_diagnostics(uri, version, diagnostics)]
+    }
+
+    fn document<'a>(&'a self, params: &'a Value) -> Result<(&'a str, &'a Document), ResponseError> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or_else(|| ResponseError::invalid_params("Missing document URI"))?;
+
+        match self.documents.get(uri) {
+            Some(document) => Ok((uri, document)),
+            None => Err(ResponseError::invalid_params(&format!(
+                "Unknown document '{}'",
+                uri
+            ))),
+        }
+    }
+
+    fn document_symbols(&self, params: &Value) -> RequestResult {
+        let (_, document) = self.document(params)?;
+        let analysis = Analysis::new(&document.text);
+        let lines = document.text.lines().collect::<Vec<_>>();
+
+        let symbols = analysis
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let kind = match symbol.kind {
+                    SymbolKind::Function => SYMBOL_KIND_FUNCTION,
+                    SymbolKind::Variable => SYMBOL_KIND_VARIABLE,
+                };
+                let mut result = json!({
+                    "name": symbol.name,
+                    "kind": kind,
+                    "range": lsp_range(symbol.span, &lines),
+                    "selectionRange": lsp_range(symbol.name_span, &lines),
+                });
+                if symbol.exported {
+                    result["detail"] = json!("export");
+                }
+                result
+            })
+            .collect();
+
+        Ok(Value::Array(symbols))
+    }
+
+    fn definition(&self, params: &Value) -> RequestResult {
+        let (uri, document) = self.document(params)?;
+        let lines = document.text.lines().collect::<Vec<_>>();
+        let position = koto_position(&params["position"], &lines)?;
+        let analysis = Analysis::new(&document.text);
+
+        let token_index = match analysis.token_at(position) {
+            Some(index) => index,
+            None => return Ok(Value::Null),
+        };
+
+        if let Some(reference) = analysis.module_reference_at(token_index) {
+            return Ok(module_location(uri, &reference).unwrap_or(Value::Null));
+        }
+
+        let result = match analysis.definition_of(token_index) {
+            Some(definition) => match &definition.kind {
+                DefinitionKind::Local => {
+                    json!({"uri": uri, "range": lsp_range(definition.span, &lines)})
+                }
+                DefinitionKind::Import(reference) => {
+                    module_location(uri, reference).unwrap_or(Value::Null)
+                }
+            },
+            None => Value::Null,
+        };
+
+        Ok(result)
+    }
+
+    fn hover(&self, params: &Value) -> RequestResult {
+        let (_, document) = self.document(params)?;
+        let lines = document.text.lines().collect::<Vec<_>>();
+        let position = koto_position(&params["position"], &lines)?;
+        let analysis = Analysis::new(&document.text);
+
+        let token_index = match analysis.token_at(position) {
+            Some(index) => index,
+            None => return Ok(Value::Null),
+        };
+        let token = &analysis.tokens[token_index];
+        if token.token != Token::Id {
+            return Ok(Value::Null);
+        }
+        let name = token.slice;
+
+        // Check for access of a module's function, e.g. `string.to_lowercase`
+        let module_name = match token_index.checked_sub(2) {
+            Some(i)
+                if analysis.tokens[i].token == Token::Id
+                    && analysis.tokens[i + 1].token == Token::Dot =>
+            {
+                Some(analysis.tokens[i].slice)
+            }
+            _ => None,
+        };
+
+        let definition = analysis.definition_of(token_index);
+
+        let contents = match (module_name, definition.map(|d| &d.kind)) {
+            (Some(module), _) if self.core_lib.module(module).is_some() => {
+                self.core_function_hover(module, name)
+            }
+            // Functions called on a value could come from any of the core modules
+            (Some(_), _) => self.core_method_hover(name),
+            (None, Some(DefinitionKind::Import(ModuleReference { module, item }))) => match item {
+                Some(item) => self.core_function_hover(module, item),
+                None => self.core_module_hover(module),
+            },
+            (None, Some(DefinitionKind::Local)) => None,
+            (None, None) => self.core_module_hover(name),
+        };
+
+        let result = match contents {
+            Some(contents) => json!({
+                "contents": {"kind": "markdown", "value": contents},
+                "range": lsp_range(token.span, &lines),
+            }),
+            None => Value::Null,
+        };
+
+        Ok(result)
+    }
+
+    fn core_function_hover(&self, module: &str, function: &str) -> Option<String> {
+        let functions = self.core_lib.module(module)?;
+        if functions.iter().any(|f| f == function) {
+            Some(format!(
+                "```koto\n{}.{}\n```\nCore library function in the `{}` module",
+                module, function, module
+            ))
+        } else {
+            None
+        }
+    }
+
+    fn core_method_hover(&self, function: &str) -> Option<String> {
+        let modules = self.core_lib.modules_containing(function);
+        if modules.is_empty() {
+            return None;
+        }
+
+        let modules = modules
+            .iter()
+            .map(|module| format!("`{}`", module))
+            .collect::<Vec<_>>();
+        Some(format!(
+            "```koto\n{}\n```\nCore library function, available in {}",
+            function,
+            modules.join(", ")
+        ))
+    }
+
+    fn core_module_hover(&self, module: &str) -> Option<String> {
+        let functions = self.core_lib.module(module)?;
+        Some(format!(
+            "```koto\n{}\n```\nCore library module\n\nFunctions: {}",
+            module,
+            functions.join(", ")
+        ))
+    }
+
+    fn semantic_tokens(&self, params: &Value) -> RequestResult {
+        let (_, document) = self.document(params)?;
+        let analysis = Analysis::new(&document.text);
+        Ok(json!({"data": analysis.semantic_tokens(&document.text)}))
+    }
+}
+
+fn initialize_result() -> Value {
+    json!({
+        "capabilities": {
+            // Full document sync
+            "textDocumentSync": 1,
+            "documentSymbolProvider": true,
+            "definitionProvider": true,
+            "hoverProvider": true,
+            "semanticTokensProvider": {
+                "legend": {
+                    "tokenTypes": SEMANTIC_TOKEN_TYPES,
+                    "tokenModifiers": [],
+                },
+                "full": true,
+            },
+        },
+        "serverInfo": {
+            "name": "koto-lsp",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+fn publish_diagnostics(uri: &str, version: Option<i64>, diagnostics: Vec<Value>) -> Value {
+    let mut params = json!({"uri": uri, "diagnostics": diagnostics});
+    if let Some(version) = version {
+        params["version"] = json!(version);
+    }
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": params,
+    })
+}
+
+// Finds the location of a referenced module, resolved relative to the document's path,
+// or in the search paths listed in KOTO_PATH
+//
+// If an item in the module is referenced, then the location of its export is returned when
+// available, otherwise the start of the module is used.
+fn module_location(document_uri: &str, reference: &ModuleReference) -> Option<Value> {
+    let document_path = uri_to_path(document_uri)?;
+    let module_path = FileSystemResolver::from_env()
+        .find_module(&reference.module, Some(&document_path))
+        .ok()?;
+
+    let range = match &reference.item {
+        Some(item) => find_export(&module_path, item),
+        None => None,
+    }
+    .unwrap_or_else(|| lsp_range(Span::default(), &[]));
+
+    Some(json!({
+        "uri": path_to_uri(&module_path),
+        "range": range,
+    }))
+}
+
+// Finds the range of an exported item's name in a module
+fn find_export(module_path: &Path, name: &str) -> Option<Value> {
+    let source = fs::read_to_string(module_path).ok()?;
+    let analysis = Analysis::new(&source);
+    let lines = source.lines().collect::<Vec<_>>();
+    analysis
+        .symbols
+        .iter()
+        .find(|symbol| symbol.exported && symbol.name == name)
+        .map(|symbol| lsp_range(symbol.name_span, &lines))
+}
+
+// Converts an LSP position into a Koto position
+//
+// LSP positions are zero-based, while Koto positions start at 1.
+// LSP characters are UTF-16 offsets, which are converted into Koto columns using the line's text.
+fn koto_position(position: &Value, lines: &[&str]) -> Result<Position, ResponseError> {
+    match (position["line"].as_u64(), position["character"].as_u64()) {
+        (Some(line), Some(character)) => {
+            let column = match lines.get(line as usize) {
+                Some(text) => koto_column(text, character as u32),
+                None => character as u32 + 1,
+            };
+            Ok(Position {
+                line: line as u32 + 1,
+                column,
+            })
+        }
+        _ => Err(ResponseError::invalid_params("Invalid position")),
+    }
+}
+
+// Converts a Koto position into an LSP position, see koto_position
+fn lsp_position(position: Position, lines: &[&str]) -> Value {
+    let line = position.line.saturating_sub(1);
+    let character = match lines.get(line as usize) {
+        Some(text) => utf16_offset(text, position.column),
+        None => position.column.saturating_sub(1),
+    };
+    json!({"line": line, "character": character})
+}
+
+fn lsp_range(span: Span, lines: &[&str]) -> Value {
+    json!({
+        "start": lsp_position(span.start, lines),
+        "end": lsp_position(span.end, lines),
+    })
+}
@@ -0,0 +1,59 @@
+use {
+    serde_json::Value,
+    std::io::{self, BufRead, Write},
+};
+
+/// Reads a JSON-RPC message with a `Content-Length` header from the input
+///
+/// None is returned when the end of the input is reached. Invalid JSON is reported as an error
+/// with the kind [io::ErrorKind::InvalidData].
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                let length = value.trim().parse::<usize>().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid Content-Length: '{}'", value.trim()),
+                    )
+                })?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let content_length = content_length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length header")
+    })?;
+
+    let mut content = vec![0; content_length];
+    input.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes a JSON-RPC message to the output, preceded by a `Content-Length` header
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
@@ -0,0 +1,55 @@
+use std::path::{Path, PathBuf};
+
+/// Converts a `file://` URI into a path
+///
+/// None is returned for URIs with other schemes.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let path = percent_decode(path)?;
+
+    // Windows paths are provided in the form file:///C:/...
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => &path[1..],
+        _ => &path,
+    };
+
+    Some(PathBuf::from(path))
+}
+
+/// Converts a path into a `file://` URI
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut result = String::from("file://");
+
+    if !path.starts_with('/') {
+        result.push('/');
+    }
+
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                result.push(byte as char)
+            }
+            _ => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    result
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut input = s.bytes();
+
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next()?, input.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,406 @@
+use {
+    koto_lsp::{read_message, write_message, Server},
+    serde_json::{json, Value},
+    std::{fs, io::Cursor},
+    tempfile::tempdir,
+};
+
+const DOCUMENT_URI: &str = "file:///test/main.koto";
+
+fn make_server() -> Server {
+    let mut server = Server::default();
+    let response = request(&mut server, "initialize", json!({"capabilities": {}}));
+    assert!(response["result"]["capabilities"].is_object());
+    server.handle_message(json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}));
+    server
+}
+
+fn request(server: &mut Server, method: &str, params: Value) -> Value {
+    let mut responses = server.handle_message(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    }));
+    assert_eq!(responses.len(), 1);
+    responses.remove(0)
+}
+
+fn open_document(server: &mut Server, uri: &str, text: &str) -> Value {
+    let mut messages = server.handle_message(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": {"uri": uri, "languageId": "koto", "version": 1, "text": text}
+        },
+    }));
+    assert_eq!(messages.len(), 1);
+    messages.remove(0)
+}
+
+fn position_request(
+    server: &mut Server,
+    method: &str,
+    uri: &str,
+    line: u32,
+    character: u32,
+) -> Value {
+    let response = request(
+        server,
+        method,
+        json!({
+            "textDocument": {"uri": uri},
+            "position": {"line": line, "character": character},
+        }),
+    );
+    response["result"].clone()
+}
+
+fn range(start_line: u32, start_character: u32, end_line: u32, end_character: u32) -> Value {
+    json!({
+        "start": {"line": start_line, "character": start_character},
+        "end": {"line": end_line, "character": end_character},
+    })
+}
+
+mod lifecycle {
+    use super::*;
+
+    #[test]
+    fn request_before_initialize() {
+        let mut server = Server::default();
+        let response = request(&mut server, "shutdown", Value::Null);
+        assert_eq!(response["error"]["code"], -32002);
+    }
+
+    #[test]
+    fn shutdown_and_exit() {
+        let mut server = make_server();
+        assert_eq!(
+            request(&mut server, "shutdown", Value::Null)["result"],
+            Value::Null
+        );
+        assert_eq!(server.exit_code(), None);
+
+        server.handle_message(json!({"jsonrpc": "2.0", "method": "exit"}));
+        assert_eq!(server.exit_code(), Some(0));
+    }
+
+    #[test]
+    fn exit_without_shutdown() {
+        let mut server = make_server();
+        server.handle_message(json!({"jsonrpc": "2.0", "method": "exit"}));
+        assert_eq!(server.exit_code(), Some(1));
+    }
+
+    #[test]
+    fn unknown_method() {
+        let mut server = make_server();
+        let response = request(&mut server, "textDocument/unknown", Value::Null);
+        assert_eq!(response["error"]["code"], -32601);
+    }
+}
+
+mod transport {
+    use super::*;
+
+    #[test]
+    fn write_and_read_messages() {
+        let first = json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"});
+        let second = json!({"jsonrpc": "2.0", "method": "exit"});
+
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &first).unwrap();
+        write_message(&mut buffer, &second).unwrap();
+
+        let mut input = Cursor::new(buffer);
+        assert_eq!(read_message(&mut input).unwrap(), Some(first));
+        assert_eq!(read_message(&mut input).unwrap(), Some(second));
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_json() {
+        let mut input = Cursor::new(b"Content-Length: 5\r\n\r\n{oops".to_vec());
+        let error = read_message(&mut input).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+mod diagnostics {
+    use super::*;
+
+    #[test]
+    fn parser_errors() {
+        let mut server = make_server();
+        let notification = open_document(&mut server, DOCUMENT_URI, "x = [1, 2\ny = 42\nz = (1\n");
+
+        assert_eq!(notification["method"], "textDocument/publishDiagnostics");
+        assert_eq!(notification["params"]["uri"], DOCUMENT_URI);
+
+        let diagnostics = notification["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 0);
+        assert_eq!(diagnostics[1]["range"]["start"]["line"], 2);
+        assert!(diagnostics[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("help: add a closing ']'"));
+    }
+
+    #[test]
+    fn compiler_error() {
+        let mut server = make_server();
+        let notification = open_document(&mut server, DOCUMENT_URI, "x = 1 + _\n");
+
+        let diagnostics = notification["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0]["severity"], 1);
+    }
+
+    #[test]
+    fn errors_are_cleared_after_fix() {
+        let mut server = make_server();
+        open_document(&mut server, DOCUMENT_URI, "x = [1, 2\n");
+
+        let messages = server.handle_message(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": {"uri": DOCUMENT_URI, "version": 2},
+                "contentChanges": [{"text": "x = [1, 2]\n"}],
+            },
+        }));
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["params"]["version"], 2);
+        assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+    }
+}
+
+mod document_symbols {
+    use super::*;
+
+    #[test]
+    fn top_level_assignments_and_exports() {
+        let mut server = make_server();
+        let source = "\
+x = 42
+f = |n|
+  y = n * 2
+  y
+export z = f x
+";
+        open_document(&mut server, DOCUMENT_URI, source);
+
+        let response = request(
+            &mut server,
+            "textDocument/documentSymbol",
+            json!({"textDocument": {"uri": DOCUMENT_URI}}),
+        );
+        let symbols = response["result"].as_array().unwrap();
+
+        let names = symbols
+            .iter()
+            .map(|symbol| symbol["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, &["x", "f", "z"]);
+
+        assert_eq!(symbols[0]["kind"], 13); // Variable
+        assert_eq!(symbols[1]["kind"], 12); // Function
+        assert_eq!(symbols[1]["selectionRange"], range(1, 0, 1, 1));
+        assert_eq!(symbols[1]["range"], range(1, 0, 3, 3));
+        assert_eq!(symbols[2]["detail"], "export");
+    }
+}
+
+mod definition {
+    use super::*;
+
+    #[test]
+    fn local_values() {
+        let mut server = make_server();
+        let source = "\
+x = 1
+f = |x| x + 1
+y = f x
+";
+        open_document(&mut server, DOCUMENT_URI, source);
+
+        // The x in the function body refers to the function's argument
+        let result = position_request(&mut server, "textDocument/definition", DOCUMENT_URI, 1, 8);
+        assert_eq!(result["uri"], DOCUMENT_URI);
+        assert_eq!(result["range"], range(1, 5, 1, 6));
+
+        // The x in the last line refers to the first assignment
+        let result = position_request(&mut server, "textDocument/definition", DOCUMENT_URI, 2, 6);
+        assert_eq!(result["range"], range(0, 0, 0, 1));
+
+        let result = position_request(&mut server, "textDocument/definition", DOCUMENT_URI, 2, 4);
+        assert_eq!(result["range"], range(1, 0, 1, 1));
+    }
+
+    #[test]
+    fn wide_characters() {
+        let mut server = make_server();
+        let source = "\
+a = 1
+b = [\"é日😀\", a]
+c = [\"é日😀\", |z| z]
+";
+        open_document(&mut server, DOCUMENT_URI, source);
+
+        // LSP positions are UTF-16 offsets, while Koto columns are based on display width
+        let result = position_request(&mut server, "textDocument/definition", DOCUMENT_URI, 1, 14);
+        assert_eq!(result["range"], range(0, 0, 0, 1));
+
+        let result = position_request(&mut server, "textDocument/definition", DOCUMENT_URI, 2, 17);
+        assert_eq!(result["range"], range(2, 14, 2, 15));
+    }
+
+    #[test]
+    fn loop_and_match_values() {
+        let mut server = make_server();
+        let source = "\
+for i in 0..10
+  match i
+    (a, b) then a + b
+    n then n + i
+";
+        open_document(&mut server, DOCUMENT_URI, source);
+
+        let result = position_request(&mut server, "textDocument/definition", DOCUMENT_URI, 3, 15);
+        assert_eq!(result["range"], range(0, 4, 0, 5));
+
+        let result = position_request(&mut server, "textDocument/definition", DOCUMENT_URI, 2, 16);
+        assert_eq!(result["range"], range(2, 5, 2, 6));
+    }
+
+    #[test]
+    fn imported_modules() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        let main_path = dir.join("main.koto");
+        let source = "\
+import foo
+from foo import bar
+foo.baz bar
+";
+        fs::write(&main_path, source).unwrap();
+        fs::write(
+            dir.join("foo.koto"),
+            "export baz = 1\n\nexport bar = |x| x\n",
+        )
+        .unwrap();
+
+        let main_uri = format!("file://{}", main_path.to_string_lossy());
+        let foo_path = dir.join("foo.koto").canonicalize().unwrap();
+
+        let mut server = make_server();
+        open_document(&mut server, &main_uri, source);
+
+        // The module name in an import expression
+        let result = position_request(&mut server, "textDocument/definition", &main_uri, 0, 8);
+        assert!(result["uri"]
+            .as_str()
+            .unwrap()
+            .ends_with(&*foo_path.file_name().unwrap().to_string_lossy()));
+        assert_eq!(result["range"], range(0, 0, 0, 0));
+
+        // An item imported from a module
+        let result = position_request(&mut server, "textDocument/definition", &main_uri, 2, 9);
+        assert_eq!(result["range"], range(2, 7, 2, 10));
+
+        // An imported module used after the import
+        let result = position_request(&mut server, "textDocument/definition", &main_uri, 2, 1);
+        assert_eq!(result["range"], range(0, 0, 0, 0));
+    }
+}
+
+mod hover {
+    use super::*;
+
+    fn hover_text(server: &mut Server, line: u32, character: u32) -> Option<String> {
+        let result = position_request(server, "textDocument/hover", DOCUMENT_URI, line, character);
+        result["contents"]["value"].as_str().map(String::from)
+    }
+
+    #[test]
+    fn core_lib() {
+        let mut server = make_server();
+        let source = "\
+import string
+x = string.to_uppercase \"hello\"
+y = x.to_lowercase()
+";
+        open_document(&mut server, DOCUMENT_URI, source);
+
+        let module = hover_text(&mut server, 1, 6).unwrap();
+        assert!(module.contains("Core library module"));
+        assert!(module.contains("to_uppercase"));
+
+        let function = hover_text(&mut server, 1, 14).unwrap();
+        assert!(function.contains("string.to_uppercase"));
+
+        let method = hover_text(&mut server, 2, 9).unwrap();
+        assert!(method.contains("`string`"));
+
+        // Local values don't have hover information
+        assert_eq!(hover_text(&mut server, 2, 0), None);
+    }
+}
+
+mod semantic_tokens {
+    use super::*;
+
+    #[test]
+    fn token_types() {
+        let mut server = make_server();
+        open_document(
+            &mut server,
+            DOCUMENT_URI,
+            "# hi\nif x.y then \"z\" else 1\n",
+        );
+
+        let response = request(
+            &mut server,
+            "textDocument/semanticTokens/full",
+            json!({"textDocument": {"uri": DOCUMENT_URI}}),
+        );
+
+        #[rustfmt::skip]
+        let expected = json!([
+            0, 0, 4, 5, 0,  // comment
+            1, 0, 2, 0, 0,  // if
+            0, 3, 1, 1, 0,  // x
+            0, 2, 1, 2, 0,  // y
+            0, 2, 4, 0, 0,  // then
+            0, 5, 3, 3, 0,  // "z"
+            0, 4, 4, 0, 0,  // else
+            0, 5, 1, 4, 0,  // 1
+        ]);
+        assert_eq!(response["result"]["data"], expected);
+    }
+
+    #[test]
+    fn wide_characters() {
+        let mut server = make_server();
+        open_document(&mut server, DOCUMENT_URI, "x = \"😀\" + y\n");
+
+        let response = request(
+            &mut server,
+            "textDocument/semanticTokens/full",
+            json!({"textDocument": {"uri": DOCUMENT_URI}}),
+        );
+
+        #[rustfmt::skip]
+        let expected = json!([
+            0, 0, 1, 1, 0,  // x
+            0, 2, 1, 6, 0,  // =
+            0, 2, 4, 3, 0,  // "😀"
+            0, 5, 1, 6, 0,  // +
+            0, 2, 1, 1, 0,  // y
+        ]);
+        assert_eq!(response["result"]["data"], expected);
+    }
+}
@@ -73,6 +73,11 @@ impl Ast {
         &self.nodes[index as usize]
     }
 
+    /// Replaces the node at the given index, keeping its span
+    pub fn set_node(&mut self, index: AstIndex, node: Node) {
+        self.nodes[index as usize].node = node;
+    }
+
     pub fn span(&self, index: AstIndex) -> &Span {
         &self.spans[index as usize]
     }
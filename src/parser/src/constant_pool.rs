@@ -22,7 +22,7 @@ pub enum Constant<'a> {
     Str(&'a str),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct ConstantPool {
     index: Vec<ConstantInfo>,
     // Constant strings concatanated into one
@@ -32,18 +32,6 @@ pub struct ConstantPool {
     hash: u64,
 }
 
-impl Default for ConstantPool {
-    fn default() -> Self {
-        Self {
-            index: vec![],
-            strings: String::default(),
-            floats: vec![],
-            ints: vec![],
-            hash: 0,
-        }
-    }
-}
-
 impl ConstantPool {
     pub fn len(&self) -> usize {
         self.index.len()
@@ -53,7 +41,7 @@ impl ConstantPool {
         self.len() == 0
     }
 
-    pub fn get(&self, index: ConstantIndex) -> Option<Constant> {
+    pub fn get(&self, index: ConstantIndex) -> Option<Constant<'_>> {
         match self.index.get(index as usize) {
             Some(constant_info) => match constant_info {
                 ConstantInfo::F64(index) => Some(Constant::F64(self.floats[*index])),
@@ -71,7 +59,7 @@ impl ConstantPool {
     #[inline]
     pub fn get_str(&self, index: ConstantIndex) -> &str {
         // Safety: The bounds have already been checked while the pool is being prepared
-        unsafe { &self.strings.get_unchecked(self.get_str_bounds(index)) }
+        unsafe { self.strings.get_unchecked(self.get_str_bounds(index)) }
     }
 
     pub fn get_str_bounds(&self, index: ConstantIndex) -> Range<usize> {
@@ -95,7 +83,7 @@ impl ConstantPool {
         }
     }
 
-    pub fn iter(&self) -> ConstantPoolIterator {
+    pub fn iter(&self) -> ConstantPoolIterator<'_> {
         ConstantPoolIterator::new(self)
     }
 }
@@ -162,6 +150,23 @@ impl ConstantPoolBuilder {
         Self::default()
     }
 
+    /// Makes a builder that continues adding constants to a previously built pool
+    ///
+    /// The indices of the pool's existing constants are preserved.
+    pub fn from_pool(pool: &ConstantPool) -> Self {
+        let mut result = Self::new();
+
+        for constant in pool.iter() {
+            match constant {
+                Constant::F64(n) => result.add_f64(n),
+                Constant::I64(n) => result.add_i64(n),
+                Constant::Str(s) => result.add_string(s),
+            };
+        }
+
+        result
+    }
+
     pub fn add_string(&mut self, s: &str) -> ConstantIndex {
         match self.string_map.get(s) {
             Some(index) => *index,
@@ -307,6 +312,27 @@ mod tests {
         assert_eq!(4, pool.len());
     }
 
+    #[test]
+    fn test_from_pool() {
+        let mut builder = ConstantPoolBuilder::new();
+        builder.add_i64(42);
+        builder.add_string("foo");
+        builder.add_f64(1.5);
+        let pool = builder.build();
+
+        let mut builder = ConstantPoolBuilder::from_pool(&pool);
+        assert_eq!(1, builder.add_string("foo"));
+        assert_eq!(3, builder.add_string("bar"));
+
+        let extended = builder.build();
+        assert_eq!(4, extended.len());
+        assert_eq!("foo", extended.get_str(1));
+        assert_eq!("bar", extended.get_str(3));
+
+        // A rebuilt pool matches the original
+        assert_eq!(pool, ConstantPoolBuilder::from_pool(&pool).build());
+    }
+
     #[test]
     fn test_iter() {
         let mut builder = ConstantPoolBuilder::new();
@@ -26,6 +26,7 @@ pub enum InternalError {
 /// Having these errors separated out is useful for the interactive input,
 /// where an indented continuation can be started in response to an indentation error.
 #[derive(Clone, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum ExpectedIndentation {
     ExpectedCatchBody,
     ExpectedElseBlock,
@@ -50,6 +51,7 @@ pub enum SyntaxError {
     ExpectedCatchArgument,
     ExpectedCatch,
     ExpectedCloseParen,
+    ExpectedDefaultArgValue,
     ExpectedElseExpression,
     ExpectedElseIfCondition,
     ExpectedEndOfLine,
@@ -77,7 +79,11 @@ pub enum SyntaxError {
     ExpectedMatchCondition,
     ExpectedMatchExpression,
     ExpectedMatchPattern,
+    ExpectedMetaKey,
+    ExpectedMetaKeyValue,
+    ExpectedNamedArgValue,
     ExpectedNegatableExpression,
+    ExpectedStringInterpolationEnd,
     ExpectedSwitchArmExpression,
     ExpectedSwitchArmExpressionAfterThen,
     ExpectedThenExpression,
@@ -88,6 +94,8 @@ pub enum SyntaxError {
     LexerError,
     MatchEllipsisOutsideOfNestedPatterns,
     MatchElseNotInLastArm,
+    MissingDefaultArgValue,
+    PositionalArgAfterNamedArg,
     SelfArgNotInFirstPosition,
     SwitchElseNotInLastArm,
     TooManyNum2Terms,
@@ -97,6 +105,7 @@ pub enum SyntaxError {
     UnexpectedEscapeInString,
     UnexpectedMatchElse,
     UnexpectedMatchIf,
+    UnexpectedStringInterpolation,
     UnexpectedSwitchElse,
     UnexpectedToken,
     UnexpectedTokenAfterExportId,
@@ -154,6 +163,49 @@ impl ParserError {
     pub fn is_indentation_error(&self) -> bool {
         matches!(self.error, ErrorType::ExpectedIndentation(_))
     }
+
+    /// Returns a hint describing how the error could be fixed, if one is available
+    pub fn suggestion(&self) -> Option<&'static str> {
+        use {ExpectedIndentation::*, SyntaxError::*};
+
+        let result = match &self.error {
+            ErrorType::InternalError(_) => return None,
+            ErrorType::ExpectedIndentation(error) => match error {
+                ExpectedThenKeywordOrBlock => {
+                    "use 'then' for an inline if expression, or start an indented block"
+                }
+                ExpectedRhsExpression => "add an expression after the operator",
+                _ => "start an indented block on the next line",
+            },
+            ErrorType::SyntaxError(error) => match error {
+                ExpectedArgsEnd | ExpectedCloseParen => "add a closing ')'",
+                ExpectedCatch => "add a 'catch' block after the 'try' block",
+                ExpectedFunctionArgsEnd => "add a closing '|' after the function's arguments",
+                ExpectedForInKeyword => "add 'in' after the loop's arguments, e.g. 'for x in y'",
+                ExpectedImportKeywordAfterFrom => "use the form 'from module import item'",
+                ExpectedIndexEnd | ExpectedListEnd => "add a closing ']'",
+                ExpectedMapEnd => "add a closing '}'",
+                ExpectedMetaKey => {
+                    "use an operator (e.g. '@+' or '@=='), '@negate', '@index', or '@display'"
+                }
+                ExpectedStringInterpolationEnd => {
+                    "add a closing '}', or escape the opening brace with '\\{'"
+                }
+                MatchElseNotInLastArm | SwitchElseNotInLastArm => "move the 'else' arm to the end",
+                MissingDefaultArgValue => {
+                    "add a default value, or move the argument before the arguments with defaults"
+                }
+                PositionalArgAfterNamedArg => "move the named arguments to the end of the call",
+                SelfArgNotInFirstPosition => "move 'self' to the start of the argument list",
+                UnexpectedElseIndentation | UnexpectedElseIfIndentation => {
+                    "align the 'else' with its 'if'"
+                }
+                _ => return None,
+            },
+        };
+
+        Some(result)
+    }
 }
 
 impl fmt::Display for ParserError {
@@ -226,6 +278,7 @@ impl fmt::Display for SyntaxError {
             ExpectedCatchArgument => f.write_str("Expected argument for catch expression"),
             ExpectedCatch => f.write_str("Expected catch expression after try"),
             ExpectedCloseParen => f.write_str("Expected closing parenthesis"),
+            ExpectedDefaultArgValue => f.write_str("Expected default value after '=' in argument"),
             ExpectedElseExpression => f.write_str("Expected 'else' expression."),
             ExpectedElseIfCondition => f.write_str("Expected condition for 'else if'."),
             ExpectedEndOfLine => f.write_str("Expected end of line"),
@@ -257,7 +310,13 @@ impl fmt::Display for SyntaxError {
             ExpectedMatchCondition => f.write_str("Expected condition after if in match arm"),
             ExpectedMatchExpression => f.write_str("Expected expression after match"),
             ExpectedMatchPattern => f.write_str("Expected pattern for match arm"),
+            ExpectedMetaKey => f.write_str("Expected operator or meta key name after '@'"),
+            ExpectedMetaKeyValue => f.write_str("Expected value for meta key"),
+            ExpectedNamedArgValue => f.write_str("Expected value after ':' in named argument"),
             ExpectedNegatableExpression => f.write_str("Expected negatable expression"),
+            ExpectedStringInterpolationEnd => {
+                f.write_str("Unexpected token in string interpolation, expected '}'")
+            }
             ExpectedSwitchArmExpression => f.write_str("Expected expression in switch arm"),
             ExpectedSwitchArmExpressionAfterThen => {
                 f.write_str("Expected expression after then in switch arm")
@@ -281,6 +340,12 @@ impl fmt::Display for SyntaxError {
             SwitchElseNotInLastArm => {
                 f.write_str("else can only be used in the last arm in a switch expression")
             }
+            MissingDefaultArgValue => {
+                f.write_str("Arguments that follow an argument with a default value need defaults")
+            }
+            PositionalArgAfterNamedArg => {
+                f.write_str("Positional arguments can't follow named arguments")
+            }
             SelfArgNotInFirstPosition => f.write_str("self is only allowed as the first argument"),
             TooManyNum2Terms => f.write_str("num2 only supports up to 2 terms"),
             TooManyNum4Terms => f.write_str("num4 only supports up to 4 terms"),
@@ -289,6 +354,9 @@ impl fmt::Display for SyntaxError {
             UnexpectedEscapeInString => f.write_str("Unexpected escape pattern in string"),
             UnexpectedMatchElse => f.write_str("Unexpected else in match arm"),
             UnexpectedMatchIf => f.write_str("Unexpected if condition in match arm"),
+            UnexpectedStringInterpolation => {
+                f.write_str("String interpolation isn't allowed in this context")
+            }
             UnexpectedSwitchElse => f.write_str("Unexpected else in switch arm"),
             UnexpectedToken => f.write_str("Unexpected token"),
             UnexpectedTokenAfterExportId => f.write_str("Unexpected token after export ID"),
@@ -299,9 +367,14 @@ impl fmt::Display for SyntaxError {
     }
 }
 
+/// Formats an error message along with an excerpt of the source where the error occurred
+///
+/// If the error occurred inside a named function then the function's name can be provided,
+/// and it will be included along with the error's position.
 pub fn format_error_with_excerpt(
     message: Option<&str>,
     source_path: &Option<PathBuf>,
+    function_name: Option<&str>,
     source: &str,
     start_pos: Position,
     end_pos: Position,
@@ -330,13 +403,10 @@ pub fn format_error_with_excerpt(
             );
 
             excerpt += &format!(
-                "{}|{}",
+                "{}|{}{}",
                 padding,
-                format!(
-                    "{}{}",
-                    " ".repeat(start_pos.column as usize),
-                    "^".repeat((end_pos.column - start_pos.column) as usize)
-                ),
+                " ".repeat(start_pos.column as usize),
+                "^".repeat((end_pos.column - start_pos.column) as usize)
             );
 
             (excerpt, padding)
@@ -356,7 +426,7 @@ pub fn format_error_with_excerpt(
         }
     };
 
-    let position_info = if let Some(path) = source_path {
+    let mut position_info = if let Some(path) = source_path {
         let display_path = if let Ok(current_dir) = std::env::current_dir() {
             if let Ok(stripped) = path.strip_prefix(current_dir) {
                 stripped.display()
@@ -372,6 +442,10 @@ pub fn format_error_with_excerpt(
         format!("{}:{}", start_pos.line, start_pos.column)
     };
 
+    if let Some(function_name) = function_name {
+        position_info.push_str(&format!(" in '{}'", function_name));
+    }
+
     format!(
         "{message}\n --- {position_info}\n{padding}|\n{excerpt}",
         message = message.unwrap_or(""),
@@ -8,7 +8,7 @@ mod parser;
 
 pub use {
     ast::*,
-    constant_pool::{Constant, ConstantPool},
+    constant_pool::{Constant, ConstantPool, ConstantPoolBuilder},
     error::{format_error_with_excerpt, ParserError},
     koto_lexer::{Position, Span},
     node::*,
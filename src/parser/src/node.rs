@@ -3,8 +3,9 @@ use std::fmt;
 
 pub type ConstantIndex = u32;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Default)]
 pub enum Node {
+    #[default]
     Empty,
     Id(ConstantIndex),
     Lookup((LookupNode, Option<AstIndex>)), // lookup node, next node
@@ -15,6 +16,7 @@ pub enum Node {
     Int(ConstantIndex),
     Float(ConstantIndex),
     Str(ConstantIndex),
+    InterpolatedString(Vec<StringNode>),
     Num2(Vec<AstIndex>),
     Num4(Vec<AstIndex>),
     List(Vec<AstIndex>),
@@ -33,7 +35,7 @@ pub enum Node {
         inclusive: bool,
     },
     RangeFull,
-    Map(Vec<(ConstantIndex, Option<AstIndex>)>),
+    Map(Vec<(MapKey, Option<AstIndex>)>),
     MainBlock {
         body: Vec<AstIndex>,
         local_count: usize,
@@ -44,6 +46,10 @@ pub enum Node {
         function: AstIndex,
         args: Vec<AstIndex>,
     },
+    NamedArg {
+        name: ConstantIndex,
+        value: AstIndex,
+    },
     Import {
         from: Vec<ConstantIndex>,
         items: Vec<Vec<ConstantIndex>>,
@@ -88,6 +94,7 @@ pub enum Node {
     ReturnExpression(AstIndex),
     Negate(AstIndex),
     Try(AstTry),
+    Throw(AstIndex),
     Yield(AstIndex),
     Debug {
         expression_string: ConstantIndex,
@@ -95,12 +102,6 @@ pub enum Node {
     },
 }
 
-impl Default for Node {
-    fn default() -> Self {
-        Node::Empty
-    }
-}
-
 impl fmt::Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Node::*;
@@ -115,6 +116,7 @@ impl fmt::Display for Node {
             Number0 => write!(f, "Number0"),
             Number1 => write!(f, "Number1"),
             Str(_) => write!(f, "Str"),
+            InterpolatedString(_) => write!(f, "InterpolatedString"),
             Num2(_) => write!(f, "Num2"),
             Num4(_) => write!(f, "Num4"),
             List(_) => write!(f, "List"),
@@ -130,6 +132,7 @@ impl fmt::Display for Node {
             Negate(_) => write!(f, "Negate"),
             Function(_) => write!(f, "Function"),
             Call { .. } => write!(f, "Call"),
+            NamedArg { .. } => write!(f, "NamedArg"),
             Import { .. } => write!(f, "Import"),
             Assign { .. } => write!(f, "Assign"),
             MultiAssign { .. } => write!(f, "MultiAssign"),
@@ -148,6 +151,7 @@ impl fmt::Display for Node {
             Return => write!(f, "Return"),
             ReturnExpression(_) => write!(f, "ReturnExpression"),
             Try { .. } => write!(f, "Try"),
+            Throw(_) => write!(f, "Throw"),
             Yield { .. } => write!(f, "Yield"),
             Debug { .. } => write!(f, "Debug"),
         }
@@ -157,6 +161,9 @@ impl fmt::Display for Node {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Function {
     pub args: Vec<AstIndex>,
+    // Default values for the function's trailing args, not including a variadic arg
+    // e.g. `|a, b = 1, c = 2|` has defaults for `b` and `c`.
+    pub default_args: Vec<AstIndex>,
     pub local_count: usize,
     // Any ID or lookup root that's accessed in a function and which wasn't previously assigned
     // locally, is either a global or needs to be captured. The compiler takes care of determining
@@ -232,6 +239,93 @@ pub enum LookupNode {
     Call(Vec<AstIndex>),
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum StringNode {
+    Literal(ConstantIndex),
+    Expression(AstIndex),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapKey {
+    // A key defined with an id or a string
+    Str(ConstantIndex),
+    // A meta key, e.g. @+
+    Meta(MetaKeyId),
+}
+
+/// The ids of the meta keys that can be defined in a map
+///
+/// Meta keys are defined with an '@' prefix, e.g. `@+`, `@display`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum MetaKeyId {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    Negate,
+    Index,
+    Display,
+    // Must be last, see from_byte()
+    Invalid,
+}
+
+impl MetaKeyId {
+    pub fn from_byte(byte: u8) -> Result<Self, u8> {
+        if byte < Self::Invalid as u8 {
+            // Safety: Any value less than Invalid is a valid id
+            Ok(unsafe { std::mem::transmute::<u8, Self>(byte) })
+        } else {
+            Err(byte)
+        }
+    }
+
+    pub fn from_named_key(name: &str) -> Option<Self> {
+        use MetaKeyId::*;
+        let result = match name {
+            "negate" => Negate,
+            "index" => Index,
+            "display" => Display,
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        use MetaKeyId::*;
+        match self {
+            Add => "@+",
+            Subtract => "@-",
+            Multiply => "@*",
+            Divide => "@/",
+            Modulo => "@%",
+            Less => "@<",
+            LessOrEqual => "@<=",
+            Greater => "@>",
+            GreaterOrEqual => "@>=",
+            Equal => "@==",
+            NotEqual => "@!=",
+            Negate => "@negate",
+            Index => "@index",
+            Display => "@display",
+            Invalid => "@invalid",
+        }
+    }
+}
+
+impl fmt::Display for MetaKeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AssignTarget {
     pub target_index: AstIndex,
@@ -2,7 +2,7 @@
 
 use {
     crate::{constant_pool::ConstantPoolBuilder, error::*, *},
-    koto_lexer::{Lexer, Span, Token},
+    koto_lexer::{interpolated_expression_len, is_format_placeholder, Lexer, Span, Token},
     std::{collections::HashSet, iter::FromIterator, str::FromStr},
 };
 
@@ -130,12 +130,6 @@ struct ExpressionContext {
     //   ...
     // Here, `f y` can't be broken over lines as the while expression expects an indented block.
     allow_linebreaks: bool,
-    // x =
-    //   foo, bar
-    //
-    // `x` is at the start of a line, so it doesn't make sense to allow indentation.
-    // `foo, bar` is to the right of an assignment so indentation is allowed.
-    allow_initial_indentation: bool,
     // When None, then some indentation on following lines is expected.
     // When Some, then indentation should match the expected indentation.
     expected_indentation: Option<usize>,
@@ -146,7 +140,6 @@ impl ExpressionContext {
         Self {
             allow_space_separated_call: true,
             allow_linebreaks: true,
-            allow_initial_indentation: false,
             expected_indentation: None,
         }
     }
@@ -155,7 +148,6 @@ impl ExpressionContext {
         Self {
             allow_space_separated_call: true,
             allow_linebreaks: true,
-            allow_initial_indentation: true,
             expected_indentation: None,
         }
     }
@@ -164,7 +156,6 @@ impl ExpressionContext {
         Self {
             allow_space_separated_call: false,
             allow_linebreaks: false,
-            allow_initial_indentation: false,
             expected_indentation: None,
         }
     }
@@ -173,7 +164,6 @@ impl ExpressionContext {
         Self {
             allow_space_separated_call: true,
             allow_linebreaks: false,
-            allow_initial_indentation: false,
             expected_indentation: None,
         }
     }
@@ -181,7 +171,6 @@ impl ExpressionContext {
     fn start_new_expression(&self) -> Self {
         Self {
             allow_space_separated_call: true,
-            allow_initial_indentation: true,
             expected_indentation: None,
             ..*self
         }
@@ -193,22 +182,47 @@ pub struct Parser<'source> {
     constants: ConstantPoolBuilder,
     lexer: Lexer<'source>,
     frame_stack: Vec<Frame>,
+    // Errors that have been recovered from while parsing
+    errors: Vec<ParserError>,
 }
 
 impl<'source> Parser<'source> {
+    /// Parses the source, returning the first error that's encountered
+    ///
+    /// See [Parser::parse_with_diagnostics] for a version that reports all errors.
     pub fn parse(source: &'source str) -> Result<(Ast, ConstantPool), ParserError> {
+        Self::parse_with_diagnostics(source).map_err(|mut errors| errors.swap_remove(0))
+    }
+
+    /// Parses the source, returning all of the errors that are encountered
+    ///
+    /// When an error is encountered the parser skips ahead to the next line in the enclosing
+    /// block, allowing parsing to continue so that any further errors can be reported.
+    ///
+    /// The returned list of errors is never empty, and is ordered by position in the source.
+    pub fn parse_with_diagnostics(
+        source: &'source str,
+    ) -> Result<(Ast, ConstantPool), Vec<ParserError>> {
         let capacity_guess = source.len() / 4;
         let mut parser = Parser {
             ast: Ast::with_capacity(capacity_guess),
             constants: ConstantPoolBuilder::new(),
             lexer: Lexer::new(source),
             frame_stack: Vec::new(),
+            errors: Vec::new(),
         };
 
-        let main_block = parser.parse_main_block()?;
-        parser.ast.set_entry_point(main_block);
-
-        Ok((parser.ast, parser.constants.build()))
+        match parser.parse_main_block() {
+            Ok(main_block) if parser.errors.is_empty() => {
+                parser.ast.set_entry_point(main_block);
+                Ok((parser.ast, parser.constants.build()))
+            }
+            Ok(_) => Err(parser.errors),
+            Err(error) => {
+                parser.errors.push(error);
+                Err(parser.errors)
+            }
+        }
     }
 
     fn frame(&self) -> Result<&Frame, ParserError> {
@@ -240,34 +254,39 @@ impl<'source> Parser<'source> {
         context.expected_indentation = Some(0);
 
         let mut body = Vec::new();
-        while self.peek_next_token(&context).is_some() {
-            self.consume_until_next_token(&mut context);
-
-            if let Some(expression) = self.parse_line()? {
-                body.push(expression);
-
-                match self.peek_next_token_on_same_line() {
-                    Some(Token::NewLine) | Some(Token::NewLineIndented) => continue,
-                    None => break,
-                    _ => {
-                        self.consume_next_token_on_same_line();
-                        return syntax_error!(UnexpectedToken, self);
+        loop {
+            if self.peek_next_token(&context).is_some() {
+                self.consume_until_next_token(&mut context);
+
+                let line_result = match self.parse_line() {
+                    Ok(Some(expression)) => {
+                        body.push(expression);
+                        self.check_for_end_of_line()
                     }
+                    Ok(None) => {
+                        self.lexer.next();
+                        syntax_error!(ExpectedExpressionInMainBlock, self)
+                    }
+                    Err(error) => Err(error),
+                };
+
+                if let Err(error) = line_result {
+                    self.recover_from_error(error, 1, 0)?;
+                }
+            } else if self
+                .peek_next_token(&ExpressionContext::permissive())
+                .is_some()
+            {
+                // All tokens need to be consumed, so unexpected indentation is an error
+                let result: Result<(), ParserError> = syntax_error!(UnexpectedToken, self);
+                if let Err(error) = result {
+                    self.recover_from_error(error, 1, 0)?;
                 }
             } else {
-                self.lexer.next();
-                return syntax_error!(ExpectedExpressionInMainBlock, self);
+                break;
             }
         }
 
-        // Check that all tokens were consumed
-        if self
-            .peek_next_token(&ExpressionContext::permissive())
-            .is_some()
-        {
-            return syntax_error!(UnexpectedToken, self);
-        }
-
         let result = self.push_node_with_start_span(
             Node::MainBlock {
                 body,
@@ -348,9 +367,14 @@ impl<'source> Parser<'source> {
 
         let span_start = self.lexer.span().start;
 
+        // The function's frame is pushed before parsing the args,
+        // so that default arg values are parsed in the function's scope.
+        self.frame_stack.push(Frame::default());
+
         // Parse function's args
         let mut arg_nodes = Vec::new();
         let mut arg_ids = Vec::new();
+        let mut default_args = Vec::new();
         let mut is_instance_function = false;
         let mut is_variadic = false;
 
@@ -374,10 +398,41 @@ impl<'source> Parser<'source> {
                         is_variadic = true;
                         break;
                     }
+
+                    if self.peek_next_token_on_same_line() == Some(Token::Assign) {
+                        self.consume_next_token_on_same_line();
+
+                        // Previous args are available to the default value's expression
+                        self.frame_mut()?
+                            .ids_assigned_in_scope
+                            .extend(arg_ids.iter());
+
+                        if let Some(default_value) =
+                            self.parse_expression(&mut ExpressionContext::inline())?
+                        {
+                            self.frame_mut()?.finish_expression();
+                            default_args.push(default_value);
+                        } else {
+                            return syntax_error!(ExpectedDefaultArgValue, self);
+                        }
+                    } else if !default_args.is_empty() {
+                        return syntax_error!(MissingDefaultArgValue, self);
+                    }
                 }
                 Some(ConstantIndexOrWildcard::Wildcard) => {
+                    if !default_args.is_empty() {
+                        return syntax_error!(MissingDefaultArgValue, self);
+                    }
                     arg_nodes.push(self.push_node(Node::Wildcard)?)
                 }
+                None if !default_args.is_empty()
+                    && matches!(
+                        self.peek_token(),
+                        Some(Token::ListStart) | Some(Token::ParenOpen)
+                    ) =>
+                {
+                    return syntax_error!(MissingDefaultArgValue, self);
+                }
                 None => match self.peek_token() {
                     Some(Token::ListStart) => {
                         self.consume_token();
@@ -418,9 +473,9 @@ impl<'source> Parser<'source> {
         }
 
         // body
-        let mut function_frame = Frame::default();
-        function_frame.ids_assigned_in_scope.extend(arg_ids.iter());
-        self.frame_stack.push(function_frame);
+        self.frame_mut()?
+            .ids_assigned_in_scope
+            .extend(arg_ids.iter());
 
         let body = if let Some(block) = self.parse_indented_map_or_block()? {
             // If the body is a Map block, then finish_expressions is needed here to finalise the
@@ -452,6 +507,7 @@ impl<'source> Parser<'source> {
         let result = self.ast.push(
             Node::Function(Function {
                 args: arg_nodes,
+                default_args,
                 local_count,
                 accessed_non_locals: Vec::from_iter(function_frame.accessed_non_locals),
                 body,
@@ -484,12 +540,8 @@ impl<'source> Parser<'source> {
             Some(export_id)
         } else if let Some(debug_expression) = self.parse_debug_expression()? {
             Some(debug_expression)
-        } else if let Some(result) =
-            self.parse_expressions(&mut ExpressionContext::line_start(), false)?
-        {
-            Some(result)
         } else {
-            None
+            self.parse_expressions(&mut ExpressionContext::line_start(), false)?
         };
 
         self.frame_mut()?.finish_expression();
@@ -760,14 +812,45 @@ impl<'source> Parser<'source> {
             }
             Some(Token::String) => {
                 self.consume_next_token_on_same_line();
-                let s = self.parse_string(self.lexer.slice())?;
-                Some(self.constants.add_string(&s) as ConstantIndex)
+                match self.parse_string()?.as_slice() {
+                    [StringNode::Literal(constant_index)] => Some(*constant_index),
+                    _ => return syntax_error!(UnexpectedStringInterpolation, self),
+                }
             }
             _ => None,
         };
         Ok(result)
     }
 
+    fn parse_map_key(&mut self) -> Result<Option<MapKey>, ParserError> {
+        if self.peek_next_token_on_same_line() != Some(Token::At) {
+            return Ok(self.parse_id_or_string()?.map(MapKey::Str));
+        }
+
+        self.consume_next_token_on_same_line();
+
+        let meta_key = match self.consume_token() {
+            Some(Token::Add) => MetaKeyId::Add,
+            Some(Token::Subtract) => MetaKeyId::Subtract,
+            Some(Token::Multiply) => MetaKeyId::Multiply,
+            Some(Token::Divide) => MetaKeyId::Divide,
+            Some(Token::Modulo) => MetaKeyId::Modulo,
+            Some(Token::Less) => MetaKeyId::Less,
+            Some(Token::LessOrEqual) => MetaKeyId::LessOrEqual,
+            Some(Token::Greater) => MetaKeyId::Greater,
+            Some(Token::GreaterOrEqual) => MetaKeyId::GreaterOrEqual,
+            Some(Token::Equal) => MetaKeyId::Equal,
+            Some(Token::NotEqual) => MetaKeyId::NotEqual,
+            Some(Token::Id) => match MetaKeyId::from_named_key(self.lexer.slice()) {
+                Some(meta_key) => meta_key,
+                None => return syntax_error!(ExpectedMetaKey, self),
+            },
+            _ => return syntax_error!(ExpectedMetaKey, self),
+        };
+
+        Ok(Some(MapKey::Meta(meta_key)))
+    }
+
     fn parse_call_args(
         &mut self,
         context: &mut ExpressionContext,
@@ -780,7 +863,7 @@ impl<'source> Parser<'source> {
             ..*context
         };
 
-        while let Some((_, peek_count)) = self.peek_next_token(&context) {
+        while let Some((_, peek_count)) = self.peek_next_token(context) {
             let peeked_line = self.lexer.peek_line_number(peek_count);
             let new_line = peeked_line > last_arg_line;
             last_arg_line = peeked_line;
@@ -792,7 +875,10 @@ impl<'source> Parser<'source> {
                 break;
             }
 
-            if let Some(expression) = self.parse_expression(&mut arg_context)? {
+            if let Some(named_arg) = self.parse_named_arg(&mut arg_context)? {
+                args.push(named_arg);
+            } else if let Some(expression) = self.parse_expression(&mut arg_context)? {
+                self.check_positional_arg(&args)?;
                 args.push(expression);
             } else {
                 break;
@@ -808,6 +894,39 @@ impl<'source> Parser<'source> {
         Ok(args)
     }
 
+    // Parses a named argument in a function call, e.g. `scale: 2`
+    fn parse_named_arg(
+        &mut self,
+        context: &mut ExpressionContext,
+    ) -> Result<Option<AstIndex>, ParserError> {
+        if self.peek_token() != Some(Token::Id) || self.peek_token_n(1) != Some(Token::Colon) {
+            return Ok(None);
+        }
+
+        self.consume_token();
+        let start_span = self.lexer.span();
+        let name = self.constants.add_string(self.lexer.slice()) as ConstantIndex;
+        self.consume_token(); // :
+
+        if let Some(value) = self.parse_expression(context)? {
+            let result =
+                self.push_node_with_start_span(Node::NamedArg { name, value }, start_span)?;
+            Ok(Some(result))
+        } else {
+            syntax_error!(ExpectedNamedArgValue, self)
+        }
+    }
+
+    // Named arguments need to come after any positional arguments in a call
+    fn check_positional_arg(&self, args: &[AstIndex]) -> Result<(), ParserError> {
+        match args.last() {
+            Some(last_arg) if matches!(self.ast.node(*last_arg).node, Node::NamedArg { .. }) => {
+                syntax_error!(PositionalArgAfterNamedArg, self)
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn parse_id_expression(
         &mut self,
         context: &mut ExpressionContext,
@@ -1049,7 +1168,12 @@ impl<'source> Parser<'source> {
         while self.peek_next_token(&args_context).is_some() {
             self.consume_until_next_token(&mut args_context);
 
-            if let Some(expression) = self.parse_expression(&mut ExpressionContext::inline())? {
+            if let Some(named_arg) = self.parse_named_arg(&mut ExpressionContext::inline())? {
+                args.push(named_arg);
+            } else if let Some(expression) =
+                self.parse_expression(&mut ExpressionContext::inline())?
+            {
+                self.check_positional_arg(&args)?;
                 args.push(expression);
             } else {
                 break;
@@ -1134,7 +1258,7 @@ impl<'source> Parser<'source> {
 
                             Ok(Some(self.push_node(node)?))
                         } else {
-                            return indentation_error!(ExpectedRhsExpression, self);
+                            indentation_error!(ExpectedRhsExpression, self)
                         }
                     }
                     Some(Token::NewLine) | Some(Token::NewLineIndented) => Ok(Some(export_id)),
@@ -1171,8 +1295,7 @@ impl<'source> Parser<'source> {
 
         let expression_string = self
             .constants
-            .add_string(&self.lexer.source()[expression_source_start..expression_source_end])
-            as u32;
+            .add_string(&self.lexer.source()[expression_source_start..expression_source_end]);
 
         let result = self.ast.push(
             Node::Debug {
@@ -1208,9 +1331,13 @@ impl<'source> Parser<'source> {
                 Token::Number => self.parse_number(false, context)?,
                 Token::String => {
                     self.consume_next_token(context);
-                    let s = self.parse_string(self.lexer.slice())?;
-                    let constant_index = self.constants.add_string(&s) as u32;
-                    let string_node = self.push_node(Str(constant_index))?;
+                    let nodes = self.parse_string()?;
+                    let string_node = match nodes.as_slice() {
+                        [StringNode::Literal(constant_index)] => {
+                            self.push_node(Str(*constant_index))?
+                        }
+                        _ => self.push_node(InterpolatedString(nodes))?,
+                    };
                     if self.next_token_is_lookup_start(context) {
                         Some(self.parse_lookup(string_node, context)?)
                     } else {
@@ -1323,6 +1450,18 @@ impl<'source> Parser<'source> {
                     };
                     Some(result)
                 }
+                Token::Throw => {
+                    self.consume_next_token(context);
+                    // The throw node's span starts at the keyword so that errors point to it
+                    let start_span = self.lexer.span();
+                    if let Some(expression) =
+                        self.parse_expressions(&mut context.start_new_expression(), false)?
+                    {
+                        Some(self.push_node_with_start_span(Node::Throw(expression), start_span)?)
+                    } else {
+                        return syntax_error!(ExpectedExpression, self);
+                    }
+                }
                 Token::From | Token::Import => self.parse_import_expression(context)?,
                 Token::Try => self.parse_try_expression(context)?,
                 // Token::NewLineIndented => self.parse_map_block(current_indent, None)?,
@@ -1354,14 +1493,14 @@ impl<'source> Parser<'source> {
                     self.push_node(Number1)?
                 } else {
                     let n = if negate { -n } else { n };
-                    let constant_index = self.constants.add_i64(n) as u32;
+                    let constant_index = self.constants.add_i64(n);
                     self.push_node(Int(constant_index))?
                 }
             }
             Err(_) => match f64::from_str(slice) {
                 Ok(n) => {
                     let n = if negate { -n } else { n };
-                    let constant_index = self.constants.add_f64(n) as u32;
+                    let constant_index = self.constants.add_f64(n);
                     self.push_node(Float(constant_index))?
                 }
                 Err(_) => {
@@ -1443,10 +1582,8 @@ impl<'source> Parser<'source> {
             if self.lexer.peek_indent(peek_count) > start_indent {
                 let result = if let Some(map_block) = self.parse_map_block(&mut context)? {
                     Some(map_block)
-                } else if let Some(block) = self.parse_indented_block(&mut context)? {
-                    Some(block)
                 } else {
-                    None
+                    self.parse_indented_block(&mut context)?
                 };
 
                 return Ok(result);
@@ -1460,9 +1597,11 @@ impl<'source> Parser<'source> {
         &mut self,
         context: &mut ExpressionContext,
     ) -> Result<Option<AstIndex>, ParserError> {
-        if let Some((_, peek_count)) = self.peek_next_token(context) {
-            // The first entry in a map block should have a defined value
-            if self.peek_token_n(peek_count + 1) != Some(Token::Colon) {
+        if let Some((token, peek_count)) = self.peek_next_token(context) {
+            // The first entry in a map block should have a defined value,
+            // meta keys are made up of two tokens, e.g. @ +
+            let colon_offset = if token == Token::At { 2 } else { 1 };
+            if self.peek_token_n(peek_count + colon_offset) != Some(Token::Colon) {
                 return Ok(None);
             }
         } else {
@@ -1474,7 +1613,7 @@ impl<'source> Parser<'source> {
 
         let mut entries = Vec::new();
 
-        while let Some(key) = self.parse_id_or_string()? {
+        while let Some(key) = self.parse_map_key()? {
             if self.peek_next_token_on_same_line() == Some(Token::Colon) {
                 self.consume_next_token_on_same_line();
 
@@ -1491,6 +1630,8 @@ impl<'source> Parser<'source> {
                         return syntax_error!(ExpectedMapValue, self);
                     }
                 }
+            } else if let MapKey::Meta(_) = key {
+                return syntax_error!(ExpectedMetaKeyValue, self);
             } else {
                 entries.push((key, None));
             }
@@ -1529,7 +1670,7 @@ impl<'source> Parser<'source> {
         while self.peek_next_token(context).is_some() {
             self.consume_until_next_token(context);
 
-            if let Some(key) = self.parse_id_or_string()? {
+            if let Some(key) = self.parse_map_key()? {
                 if self.peek_token() == Some(Token::Colon) {
                     self.consume_token();
 
@@ -1544,6 +1685,8 @@ impl<'source> Parser<'source> {
                     } else {
                         return syntax_error!(ExpectedMapValue, self);
                     }
+                } else if let MapKey::Meta(_) = key {
+                    return syntax_error!(ExpectedMetaKeyValue, self);
                 } else {
                     entries.push((key, None));
                 }
@@ -1645,7 +1788,7 @@ impl<'source> Parser<'source> {
             let result = self.push_node(Node::Loop { body })?;
             Ok(Some(result))
         } else {
-            return indentation_error!(ExpectedLoopBody, self);
+            indentation_error!(ExpectedLoopBody, self)
         }
     }
 
@@ -2306,17 +2449,24 @@ impl<'source> Parser<'source> {
         let mut body = Vec::new();
 
         let start_span = self.lexer.span();
+        let block_indent = self.lexer.peek_indent(0);
+        let frame_depth = self.frame_stack.len();
 
-        while let Some(expression) = self.parse_line()? {
-            body.push(expression);
+        loop {
+            match self.parse_line() {
+                Ok(Some(expression)) => {
+                    body.push(expression);
 
-            match self.peek_next_token_on_same_line() {
-                None => break,
-                Some(Token::NewLine) | Some(Token::NewLineIndented) => {}
-                _ => {
-                    self.consume_next_token_on_same_line();
-                    return syntax_error!(UnexpectedToken, self);
+                    if let Err(error) = self.check_for_end_of_line() {
+                        self.recover_from_error(error, frame_depth, block_indent)?;
+                    }
                 }
+                Ok(None) => break,
+                Err(error) => self.recover_from_error(error, frame_depth, block_indent)?,
+            }
+
+            if self.peek_next_token_on_same_line().is_none() {
+                break;
             }
 
             // Peek ahead to see if the indented block continues after this line
@@ -2338,6 +2488,65 @@ impl<'source> Parser<'source> {
         }
     }
 
+    // Checks that the end of the line has been reached after parsing an expression
+    fn check_for_end_of_line(&mut self) -> Result<(), ParserError> {
+        match self.peek_next_token_on_same_line() {
+            None | Some(Token::NewLine) | Some(Token::NewLineIndented) => Ok(()),
+            _ => {
+                self.consume_next_token_on_same_line();
+                syntax_error!(UnexpectedToken, self)
+            }
+        }
+    }
+
+    // Records an error and then skips ahead to the next line with the given indentation
+    //
+    // Frames that were pushed while parsing the failed expression are discarded.
+    //
+    // Internal errors aren't recoverable and are returned as-is, as are errors that leave the
+    // lexer unable to make progress.
+    fn recover_from_error(
+        &mut self,
+        error: ParserError,
+        frame_depth: usize,
+        indent: usize,
+    ) -> Result<(), ParserError> {
+        if matches!(error.error, ErrorType::InternalError(_)) {
+            return Err(error);
+        }
+
+        self.frame_stack.truncate(frame_depth);
+        if let Some(frame) = self.frame_stack.last_mut() {
+            frame.pending_accesses.clear();
+            frame.pending_assignments.clear();
+        }
+
+        'skip: while let Some(token) = self.peek_token() {
+            match token {
+                Token::Error => return Err(error),
+                Token::NewLine | Token::NewLineIndented => {
+                    let mut peek_count = 1;
+                    while let Some(peeked) = self.peek_token_n(peek_count) {
+                        if peeked.is_whitespace() || peeked.is_newline() {
+                            peek_count += 1;
+                        } else {
+                            if self.lexer.peek_indent(peek_count) <= indent {
+                                break 'skip;
+                            }
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            self.consume_token();
+        }
+
+        self.errors.push(error);
+        Ok(())
+    }
+
     fn parse_nested_expressions(
         &mut self,
         context: &mut ExpressionContext,
@@ -2384,37 +2593,118 @@ impl<'source> Parser<'source> {
         }
     }
 
-    fn parse_string(&self, s: &str) -> Result<String, ParserError> {
-        let without_quotes = &s[1..s.len() - 1];
+    // Parses the string literal that has just been consumed
+    //
+    // Literal sections of the string are added to the constant pool, and interpolated expressions
+    // are parsed and added to the AST.
+    fn parse_string(&mut self) -> Result<Vec<StringNode>, ParserError> {
+        let s = self.lexer.slice();
+        // The string's opening and closing quotes are skipped
+        let end = s.len() - 1;
+        let mut index = 1;
 
-        let mut result = String::with_capacity(without_quotes.len());
-        let mut chars = without_quotes.chars().peekable();
+        let mut nodes = Vec::new();
+        let mut literal = String::with_capacity(end - index);
+
+        while let Some(c) = s[index..end].chars().next() {
+            index += c.len_utf8();
 
-        while let Some(c) = chars.next() {
             match c {
-                '\\' => match chars.next() {
-                    Some('\\') => result.push('\\'),
-                    Some('\'') => result.push('\''),
-                    Some('"') => result.push('"'),
-                    Some('n') => result.push('\n'),
-                    Some('r') => result.push('\r'),
-                    Some('t') => result.push('\t'),
-                    Some('\n') | Some('\r') => {
-                        while let Some(c) = chars.peek() {
-                            if c.is_whitespace() {
-                                chars.next();
-                            } else {
-                                break;
+                '\\' => {
+                    let escaped = s[index..end].chars().next();
+                    index += escaped.map_or(0, |c| c.len_utf8());
+
+                    match escaped {
+                        Some('\\') => literal.push('\\'),
+                        Some('\'') => literal.push('\''),
+                        Some('"') => literal.push('"'),
+                        Some('{') => literal.push('{'),
+                        Some('}') => literal.push('}'),
+                        Some('n') => literal.push('\n'),
+                        Some('r') => literal.push('\r'),
+                        Some('t') => literal.push('\t'),
+                        Some('\n') | Some('\r') => {
+                            while let Some(c) = s[index..end].chars().next() {
+                                if c.is_whitespace() {
+                                    index += c.len_utf8();
+                                } else {
+                                    break;
+                                }
                             }
                         }
+                        _ => return syntax_error!(UnexpectedEscapeInString, self),
                     }
-                    _ => return syntax_error!(UnexpectedEscapeInString, self),
-                },
-                _ => result.push(c),
+                }
+                '{' if is_format_placeholder(&s[index..end]) => {
+                    literal.push('{');
+                    // `{{` is passed through to the string, the second brace is skipped so that
+                    // it doesn't start an interpolated expression.
+                    if s[index..end].starts_with('{') {
+                        literal.push('{');
+                        index += 1;
+                    }
+                }
+                '{' => {
+                    // Unterminated expressions are reported as errors rather than being
+                    // treated as part of the string, `\{` can be used to include a brace.
+                    if interpolated_expression_len(&s[index - 1..end]).is_none() {
+                        return syntax_error!(ExpectedStringInterpolationEnd, self);
+                    }
+                    let (expression, expression_end) = self.parse_interpolated_expression(index)?;
+
+                    if !literal.is_empty() {
+                        nodes.push(StringNode::Literal(self.constants.add_string(&literal)));
+                        literal.clear();
+                    }
+                    nodes.push(StringNode::Expression(expression));
+                    index = expression_end;
+                }
+                _ => literal.push(c),
             }
         }
 
-        Ok(result)
+        if !literal.is_empty() || nodes.is_empty() {
+            nodes.push(StringNode::Literal(self.constants.add_string(&literal)));
+        }
+
+        Ok(nodes)
+    }
+
+    // Parses an expression that's interpolated in the current string token
+    //
+    // The expression starts at the provided offset in the string, and the offset following the
+    // expression's closing '}' is returned along with the expression.
+    fn parse_interpolated_expression(
+        &mut self,
+        offset: usize,
+    ) -> Result<(AstIndex, usize), ParserError> {
+        let expression_lexer = self.lexer.lexer_at_token_offset(offset);
+        let string_lexer = std::mem::replace(&mut self.lexer, expression_lexer);
+
+        let result = self.parse_interpolated_expression_contents();
+        let expression_end = self.lexer.source_position();
+
+        // The string's lexer is restored whether or not the expression was successfully parsed,
+        // allowing parsing to continue after errors.
+        self.lexer = string_lexer;
+
+        let string_start = self.lexer.source_position() - self.lexer.slice().len();
+        Ok((result?, expression_end - string_start))
+    }
+
+    fn parse_interpolated_expression_contents(&mut self) -> Result<AstIndex, ParserError> {
+        self.consume_until_next_token_on_same_line();
+
+        let expression = match self.parse_expression(&mut ExpressionContext::inline())? {
+            Some(expression) => expression,
+            None => return syntax_error!(ExpectedExpression, self),
+        };
+
+        if self.consume_next_token_on_same_line() != Some(Token::MapEnd) {
+            return syntax_error!(ExpectedStringInterpolationEnd, self);
+        }
+
+        Ok(expression)
     }
 
     fn push_ast_op(
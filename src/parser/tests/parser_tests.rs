@@ -97,6 +97,146 @@ a
             )
         }
 
+        #[test]
+        fn interpolated_string() {
+            let source = r#"
+name = "World"
+"hello, {name}!"
+"#;
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Str(1),
+                    Assign {
+                        target: AssignTarget {
+                            target_index: 0,
+                            scope: Scope::Local,
+                        },
+                        op: AssignOp::Equal,
+                        expression: 1,
+                    },
+                    Id(0),
+                    InterpolatedString(vec![
+                        StringNode::Literal(2),
+                        StringNode::Expression(3),
+                        StringNode::Literal(3),
+                    ]),
+                    MainBlock {
+                        body: vec![2, 4],
+                        local_count: 1,
+                    },
+                ],
+                Some(&[
+                    Constant::Str("name"),
+                    Constant::Str("World"),
+                    Constant::Str("hello, "),
+                    Constant::Str("!"),
+                ]),
+            )
+        }
+
+        #[test]
+        fn interpolated_id_that_isnt_assigned_locally() {
+            let source = r#"
+"hello, {name}!"
+"#;
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    InterpolatedString(vec![
+                        StringNode::Literal(1),
+                        StringNode::Expression(0),
+                        StringNode::Literal(2),
+                    ]),
+                    MainBlock {
+                        body: vec![1],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[
+                    Constant::Str("name"),
+                    Constant::Str("hello, "),
+                    Constant::Str("!"),
+                ]),
+            )
+        }
+
+        #[test]
+        fn string_with_escaped_braces() {
+            let source = r#"
+"a \{ } b \{a: 1} \{x"
+"#;
+            check_ast(
+                source,
+                &[
+                    Str(0),
+                    MainBlock {
+                        body: vec![0],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("a { } b {a: 1} {x")]),
+            )
+        }
+
+        #[test]
+        fn interpolated_string_with_format_placeholders() {
+            let source = r#"
+"{x + 1} {{}} {} \{0} \{y}"
+"#;
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Number1,
+                    BinaryOp {
+                        op: AstOp::Add,
+                        lhs: 0,
+                        rhs: 1,
+                    },
+                    InterpolatedString(vec![StringNode::Expression(2), StringNode::Literal(1)]),
+                    MainBlock {
+                        body: vec![3],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("x"), Constant::Str(" {{}} {} {0} {y}")]),
+            )
+        }
+
+        #[test]
+        fn interpolated_string_containing_a_string() {
+            let source = r#"
+"{f "{x + 1}"}"
+"#;
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Id(1),
+                    Number1,
+                    BinaryOp {
+                        op: AstOp::Add,
+                        lhs: 1,
+                        rhs: 2,
+                    },
+                    InterpolatedString(vec![StringNode::Expression(3)]),
+                    Call {
+                        function: 0,
+                        args: vec![4],
+                    },
+                    InterpolatedString(vec![StringNode::Expression(5)]),
+                    MainBlock {
+                        body: vec![6],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("f"), Constant::Str("x")]),
+            )
+        }
+
         #[test]
         fn negatives() {
             let source = "\
@@ -227,7 +367,11 @@ x = [
                     Int(1),
                     Str(4),
                     // map entries are constant/ast index pairs
-                    Map(vec![(0, Some(1)), (2, None), (3, Some(2))]),
+                    Map(vec![
+                        (MapKey::Str(0), Some(1)),
+                        (MapKey::Str(2), None),
+                        (MapKey::Str(3), Some(2)),
+                    ]),
                     MainBlock {
                         body: vec![0, 3],
                         local_count: 0,
@@ -258,7 +402,11 @@ x = [
                     Int(1),
                     Str(4),
                     // map entries are constant/ast index pairs
-                    Map(vec![(0, Some(0)), (2, None), (3, Some(1))]),
+                    Map(vec![
+                        (MapKey::Str(0), Some(0)),
+                        (MapKey::Str(2), None),
+                        (MapKey::Str(3), Some(1)),
+                    ]),
                     MainBlock {
                         body: vec![2],
                         local_count: 0,
@@ -290,8 +438,12 @@ x"#;
                     Int(2), // 42
                     Number0,
                     // map entries are constant/ast pairs
-                    Map(vec![(1, Some(2))]), // baz, nested map
-                    Map(vec![(1, Some(1)), (3, None), (4, Some(3))]),
+                    Map(vec![(MapKey::Str(1), Some(2))]), // baz, nested map
+                    Map(vec![
+                        (MapKey::Str(1), Some(1)),
+                        (MapKey::Str(3), None),
+                        (MapKey::Str(4), Some(3)),
+                    ]),
                     Assign {
                         target: AssignTarget {
                             target_index: 0,
@@ -316,6 +468,61 @@ x"#;
             )
         }
 
+        #[test]
+        fn map_inline_with_meta_keys() {
+            let source = "{@+: f, @display: g}";
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Id(1),
+                    Map(vec![
+                        (MapKey::Meta(MetaKeyId::Add), Some(0)),
+                        (MapKey::Meta(MetaKeyId::Display), Some(1)),
+                    ]),
+                    MainBlock {
+                        body: vec![2],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("f"), Constant::Str("g")]),
+            )
+        }
+
+        #[test]
+        fn map_block_with_meta_keys() {
+            let source = r#"
+x =
+  @==: y
+  @index: z
+"#;
+            check_ast(
+                source,
+                &[
+                    Id(0), // x
+                    Id(1),
+                    Id(2),
+                    Map(vec![
+                        (MapKey::Meta(MetaKeyId::Equal), Some(1)),
+                        (MapKey::Meta(MetaKeyId::Index), Some(2)),
+                    ]),
+                    Assign {
+                        target: AssignTarget {
+                            target_index: 0,
+                            scope: Scope::Local,
+                        },
+                        op: AssignOp::Equal,
+                        expression: 3,
+                    },
+                    MainBlock {
+                        body: vec![4],
+                        local_count: 1,
+                    }, // 5
+                ],
+                Some(&[Constant::Str("x"), Constant::Str("y"), Constant::Str("z")]),
+            )
+        }
+
         #[test]
         fn ranges_from_literals() {
             let source = "
@@ -1404,6 +1611,7 @@ a";
                     Block(vec![2, 3]),
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![0],
                         body: 4,
@@ -1599,6 +1807,7 @@ a()";
                     Int(1),
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![],
                         body: 1,
@@ -1643,6 +1852,7 @@ a()";
                     },
                     Function(koto_parser::Function {
                         args: vec![0, 1],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 4,
@@ -1659,6 +1869,41 @@ a()";
             )
         }
 
+        #[test]
+        fn inline_default_args() {
+            let source = "|x, y = x| x + y";
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Id(1),
+                    Id(0),
+                    Id(0),
+                    Id(1),
+                    BinaryOp {
+                        op: AstOp::Add,
+                        lhs: 3,
+                        rhs: 4,
+                    }, // 5
+                    Function(koto_parser::Function {
+                        args: vec![0, 1],
+                        default_args: vec![2],
+                        local_count: 2,
+                        accessed_non_locals: vec![],
+                        body: 5,
+                        is_instance_function: false,
+                        is_variadic: false,
+                        is_generator: false,
+                    }),
+                    MainBlock {
+                        body: vec![6],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("x"), Constant::Str("y")]),
+            )
+        }
+
         #[test]
         fn inline_var_args() {
             let source = "|x, y...| x + y.size()";
@@ -1679,6 +1924,7 @@ a()";
                     },
                     Function(koto_parser::Function {
                         args: vec![0, 1],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 7,
@@ -1725,6 +1971,7 @@ f 42";
                     Block(vec![4, 5]),
                     Function(koto_parser::Function {
                         args: vec![1],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 6,
@@ -1778,6 +2025,7 @@ f 42";
                     Id(3), // z
                     Function(koto_parser::Function {
                         args: vec![3],
+                        default_args: vec![],
                         local_count: 1,
                         accessed_non_locals: vec![],
                         body: 4,
@@ -1802,6 +2050,7 @@ f 42";
                     Block(vec![6, 9]), // 10
                     Function(koto_parser::Function {
                         args: vec![1],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 10,
@@ -1909,6 +2158,49 @@ f 42";
             )
         }
 
+        #[test]
+        fn call_with_named_args() {
+            let source = "f x, y: 1";
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Id(1),
+                    Number1,
+                    NamedArg { name: 2, value: 2 },
+                    Call {
+                        function: 0,
+                        args: vec![1, 3],
+                    },
+                    MainBlock {
+                        body: vec![4],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("f"), Constant::Str("x"), Constant::Str("y")]),
+            )
+        }
+
+        #[test]
+        fn call_with_parentheses_and_named_args() {
+            let source = "f(x: 1)";
+            check_ast(
+                source,
+                &[
+                    Id(0),
+                    Number1,
+                    NamedArg { name: 1, value: 1 },
+                    Lookup((LookupNode::Call(vec![2]), None)),
+                    Lookup((LookupNode::Root(0), Some(3))),
+                    MainBlock {
+                        body: vec![4],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[Constant::Str("f"), Constant::Str("x")]),
+            )
+        }
+
         #[test]
         fn call_over_lines() {
             let source = "
@@ -1980,6 +2272,7 @@ f x";
                     },
                     Function(koto_parser::Function {
                         args: vec![1],
+                        default_args: vec![],
                         local_count: 1,
                         accessed_non_locals: vec![0],
                         body: 4,
@@ -2021,6 +2314,7 @@ f x";
                     }, // 5
                     Function(koto_parser::Function {
                         args: vec![2],
+                        default_args: vec![],
                         local_count: 1,
                         accessed_non_locals: vec![0],
                         body: 5,
@@ -2037,6 +2331,7 @@ f x";
                     }, // 10
                     Function(koto_parser::Function {
                         args: vec![7],
+                        default_args: vec![],
                         local_count: 1,
                         accessed_non_locals: vec![1],
                         body: 10,
@@ -2090,6 +2385,7 @@ f x";
                     },
                     Function(koto_parser::Function {
                         args: vec![1, 2],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 7,
@@ -2098,7 +2394,7 @@ f x";
                         is_generator: false,
                     }),
                     // Map entries are constant/ast index pairs
-                    Map(vec![(0, Some(0)), (2, Some(8))]),
+                    Map(vec![(MapKey::Str(0), Some(0)), (MapKey::Str(2), Some(8))]),
                     MainBlock {
                         body: vec![9],
                         local_count: 0,
@@ -2128,9 +2424,10 @@ f = ||
                     Id(2),
                     Number0,
                     // Map entries are constant/ast index pairs
-                    Map(vec![(1, Some(1)), (3, Some(2))]),
+                    Map(vec![(MapKey::Str(1), Some(1)), (MapKey::Str(3), Some(2))]),
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![2],
                         body: 3,
@@ -2188,6 +2485,7 @@ f()";
                     },
                     Function(koto_parser::Function {
                         args: vec![2, 3],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 8,
@@ -2196,9 +2494,10 @@ f()";
                         is_generator: false,
                     }),
                     // Map entries are constant/ast index pairs
-                    Map(vec![(1, Some(1)), (3, Some(9))]), // 10
+                    Map(vec![(MapKey::Str(1), Some(1)), (MapKey::Str(3), Some(9))]), // 10
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![],
                         body: 10,
@@ -2279,6 +2578,7 @@ f = |n|
                     }),
                     Function(koto_parser::Function {
                         args: vec![3],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 13,
@@ -2298,6 +2598,7 @@ f = |n|
                     Block(vec![15, 16]),
                     Function(koto_parser::Function {
                         args: vec![1],
+                        default_args: vec![],
                         local_count: 2,
                         accessed_non_locals: vec![],
                         body: 17,
@@ -2357,6 +2658,7 @@ f = |n|
                     Block(vec![4, 5]),
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 1,
                         accessed_non_locals: vec![0], // initial read of x via capture
                         body: 6,
@@ -2393,6 +2695,7 @@ f = |n|
                     },
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![0], // initial read of x via capture
                         body: 2,
@@ -2437,6 +2740,7 @@ y z";
                     },
                     Function(koto_parser::Function {
                         args: vec![6],
+                        default_args: vec![],
                         local_count: 1,
                         accessed_non_locals: vec![],
                         body: 9,
@@ -2486,6 +2790,7 @@ y z";
                     Yield(0),
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![],
                         body: 1,
@@ -2514,6 +2819,7 @@ y z";
                     Yield(2),
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![],
                         body: 3,
@@ -2541,10 +2847,11 @@ y z";
                 source,
                 &[
                     Int(1),
-                    Map(vec![(0, Some(0))]),
+                    Map(vec![(MapKey::Str(0), Some(0))]),
                     Yield(1),
                     Function(koto_parser::Function {
                         args: vec![],
+                        default_args: vec![],
                         local_count: 0,
                         accessed_non_locals: vec![],
                         body: 2,
@@ -2580,6 +2887,7 @@ y z";
                     Id(0),
                     Function(koto_parser::Function {
                         args: vec![0, 5, 6],
+                        default_args: vec![],
                         local_count: 4,
                         accessed_non_locals: vec![],
                         body: 7,
@@ -2620,6 +2928,7 @@ y z";
                     Id(0),
                     Function(koto_parser::Function {
                         args: vec![0, 5, 6],
+                        default_args: vec![],
                         local_count: 4,
                         accessed_non_locals: vec![],
                         body: 7,
@@ -3027,7 +3336,7 @@ x.bar()."baz" = 1
             check_ast(
                 source,
                 &[
-                    Map(vec![(0, None)]),
+                    Map(vec![(MapKey::Str(0), None)]),
                     Lookup((LookupNode::Call(vec![]), None)),
                     Lookup((LookupNode::Id(1), Some(1))),
                     Lookup((LookupNode::Root(0), Some(2))),
@@ -3125,6 +3434,33 @@ return 1";
             )
         }
 
+        #[test]
+        fn throw() {
+            let source = r#"
+throw "error!"
+throw {message: x}
+"#;
+            check_ast(
+                source,
+                &[
+                    Str(0),
+                    Throw(0),
+                    Id(2),
+                    Map(vec![(MapKey::Str(1), Some(2))]),
+                    Throw(3),
+                    MainBlock {
+                        body: vec![1, 4],
+                        local_count: 0,
+                    },
+                ],
+                Some(&[
+                    Constant::Str("error!"),
+                    Constant::Str("message"),
+                    Constant::Str("x"),
+                ]),
+            )
+        }
+
         #[test]
         fn expressions() {
             let source = r#"
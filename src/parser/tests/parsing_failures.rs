@@ -35,6 +35,16 @@ mod parser {
             check_parsing_fails("{_}");
         }
 
+        #[test]
+        fn unknown_meta_key() {
+            check_parsing_fails("{@foo: 42}");
+        }
+
+        #[test]
+        fn meta_key_without_value() {
+            check_parsing_fails("{@+}");
+        }
+
         #[test]
         fn missing_term_in_arithmetic() {
             check_parsing_fails("1 + * 2");
@@ -45,6 +55,20 @@ mod parser {
             check_parsing_fails("import foo bar");
         }
 
+        #[test]
+        fn string_interpolation_in_map_key() {
+            check_parsing_fails(r#"{"{x + 1}": 42}"#);
+        }
+
+        #[test]
+        fn malformed_string_interpolation() {
+            check_parsing_fails(r#""{x""#);
+            check_parsing_fails(r#""a { } b""#);
+            check_parsing_fails(r#""{a: 1}""#);
+            check_parsing_fails(r#""{x + }""#);
+            check_parsing_fails(r#""{x" + "}""#);
+        }
+
         mod indentation {
             use super::*;
 
@@ -181,6 +205,27 @@ f = ||
             fn missing_commas_in_lookup_call() {
                 check_parsing_fails("f.bar 1 2 3");
             }
+
+            #[test]
+            fn missing_default_arg_value() {
+                check_parsing_fails("f = |x, y = | x");
+            }
+
+            #[test]
+            fn arg_without_default_following_default_arg() {
+                check_parsing_fails("f = |x = 1, y| x");
+            }
+
+            #[test]
+            fn missing_named_arg_value() {
+                check_parsing_fails("f x:");
+            }
+
+            #[test]
+            fn positional_arg_following_named_arg() {
+                check_parsing_fails("f x: 1, 2");
+                check_parsing_fails("f(x: 1, 2)");
+            }
         }
 
         mod lookups {
@@ -248,4 +293,70 @@ match
             }
         }
     }
+
+    #[cfg(not(feature = "panic_on_parser_error"))]
+    mod error_recovery {
+        use super::*;
+
+        fn check_error_lines(source: &str, expected_lines: &[u32]) {
+            match Parser::parse_with_diagnostics(source) {
+                Ok(_) => panic!("Unexpected success while parsing:\n{}", source),
+                Err(errors) => {
+                    let lines = errors
+                        .iter()
+                        .map(|error| error.span.start.line)
+                        .collect::<Vec<_>>();
+                    assert_eq!(lines, expected_lines, "errors: {:#?}", errors);
+                }
+            }
+        }
+
+        #[test]
+        fn errors_on_separate_lines() {
+            let source = "
+x = [1, 2
+y = 42
+z = (1 + 2
+z = y
+";
+            check_error_lines(source, &[2, 4]);
+        }
+
+        #[test]
+        fn error_in_nested_block() {
+            let source = "
+f = |x|
+  y = x + 1
+  z = {foo: 42
+  y
+a = 1 +
+b = f a
+";
+            check_error_lines(source, &[4, 6]);
+        }
+
+        #[test]
+        fn errors_in_separate_nested_blocks() {
+            let source = "
+if x
+  for i 0..10
+    i
+else
+  y = [1, 2
+z = 1
+";
+            check_error_lines(source, &[3, 6]);
+        }
+
+        #[test]
+        fn parse_returns_the_first_error() {
+            let source = "
+x = [1, 2
+y = (1 + 2
+";
+            let error = Parser::parse(source).unwrap_err();
+            assert_eq!(error.span.start.line, 2);
+            assert!(error.suggestion().is_some());
+        }
+    }
 }
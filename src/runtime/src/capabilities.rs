@@ -0,0 +1,197 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// The core library modules that can be made available to scripts via the prelude
+///
+/// See [Capabilities].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CoreModule {
+    Io,
+    Iterator,
+    Koto,
+    List,
+    Map,
+    Number,
+    Os,
+    Range,
+    String,
+    Test,
+    Thread,
+    Tuple,
+}
+
+impl CoreModule {
+    /// All of the core modules
+    pub const ALL: [CoreModule; 12] = [
+        CoreModule::Io,
+        CoreModule::Iterator,
+        CoreModule::Koto,
+        CoreModule::List,
+        CoreModule::Map,
+        CoreModule::Number,
+        CoreModule::Os,
+        CoreModule::Range,
+        CoreModule::String,
+        CoreModule::Test,
+        CoreModule::Thread,
+        CoreModule::Tuple,
+    ];
+
+    /// The name of the module in the prelude
+    pub fn name(&self) -> &'static str {
+        use CoreModule::*;
+
+        match self {
+            Io => "io",
+            Iterator => "iterator",
+            Koto => "koto",
+            List => "list",
+            Map => "map",
+            Number => "number",
+            Os => "os",
+            Range => "range",
+            String => "string",
+            Test => "test",
+            Thread => "thread",
+            Tuple => "tuple",
+        }
+    }
+
+    /// Returns the core module with the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|module| module.name() == name)
+            .copied()
+    }
+}
+
+impl fmt::Display for CoreModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Controls whether or not scripts are able to import modules from files
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModuleFileAccess {
+    /// Modules can be imported from any path
+    Unrestricted,
+    /// Modules can't be imported from files
+    Disabled,
+    /// Modules can only be imported from files that are inside the listed directories
+    AllowedDirectories(Vec<PathBuf>),
+}
+
+impl ModuleFileAccess {
+    /// Returns true if the module at the given path is allowed to be imported
+    ///
+    /// Paths are canonicalized before being compared, so links can't be used to escape from the
+    /// allowed directories.
+    pub fn is_path_allowed(&self, path: &Path) -> bool {
+        match self {
+            Self::Unrestricted => true,
+            Self::Disabled => false,
+            Self::AllowedDirectories(dirs) => match path.canonicalize() {
+                Ok(path) => dirs.iter().any(|dir| match dir.canonicalize() {
+                    Ok(dir) => path.starts_with(dir),
+                    Err(_) => false,
+                }),
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+/// The capabilities that are made available to scripts by a [Vm](crate::Vm)
+///
+/// By default all core modules are available, and modules can be imported from any path.
+///
+/// Capabilities can be restricted to run untrusted scripts, e.g.
+///
+/// ```
+/// use koto_runtime::{Capabilities, CoreModule, Vm, VmSettings};
+///
+/// let vm = Vm::with_settings(VmSettings {
+///     capabilities: Capabilities::sandboxed()
+///         .enable_core_module(CoreModule::Thread)
+///         .allow_module_dir("scripts/modules"),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    core_modules: Vec<CoreModule>,
+    module_file_access: ModuleFileAccess,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            core_modules: CoreModule::ALL.to_vec(),
+            module_file_access: ModuleFileAccess::Unrestricted,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Capabilities for untrusted scripts
+    ///
+    /// The `io`, `os`, and `thread` modules are disabled, along with importing modules from files.
+    pub fn sandboxed() -> Self {
+        Self::default()
+            .disable_core_module(CoreModule::Io)
+            .disable_core_module(CoreModule::Os)
+            .disable_core_module(CoreModule::Thread)
+            .disable_module_files()
+    }
+
+    /// Makes the given core module available to scripts
+    pub fn enable_core_module(mut self, module: CoreModule) -> Self {
+        if !self.core_modules.contains(&module) {
+            self.core_modules.push(module);
+        }
+        self
+    }
+
+    /// Removes the given core module from the prelude
+    ///
+    /// Attempts to import the module will result in an error.
+    pub fn disable_core_module(mut self, module: CoreModule) -> Self {
+        self.core_modules.retain(|enabled| *enabled != module);
+        self
+    }
+
+    /// Prevents scripts from importing modules from files
+    pub fn disable_module_files(mut self) -> Self {
+        self.module_file_access = ModuleFileAccess::Disabled;
+        self
+    }
+
+    /// Allows modules to be imported from files inside the given directory
+    ///
+    /// Once a directory has been allowed, modules can only be imported from allowed directories.
+    pub fn allow_module_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        match &mut self.module_file_access {
+            ModuleFileAccess::AllowedDirectories(dirs) => dirs.push(dir.into()),
+            _ => self.module_file_access = ModuleFileAccess::AllowedDirectories(vec![dir.into()]),
+        }
+        self
+    }
+
+    /// Returns true if the given core module is available to scripts
+    pub fn is_core_module_enabled(&self, module: CoreModule) -> bool {
+        self.core_modules.contains(&module)
+    }
+
+    /// The core modules that are available to scripts
+    pub fn core_modules(&self) -> &[CoreModule] {
+        &self.core_modules
+    }
+
+    pub fn module_file_access(&self) -> &ModuleFileAccess {
+        &self.module_file_access
+    }
+}
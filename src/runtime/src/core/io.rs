@@ -35,7 +35,7 @@ pub fn make_file_map() -> ValueMap {
                 [_, value] => {
                     let data = format!("{}", value);
 
-                    match file_handle.file.write(data.as_bytes()) {
+                    match file_handle.file.write_all(data.as_bytes()) {
                         Ok(_) => Ok(Value::Empty),
                         Err(e) => external_error!("File.write: Error while writing to file: {}", e),
                     }
@@ -54,7 +54,7 @@ pub fn make_file_map() -> ValueMap {
                     return external_error!("File.write_line: Expected single value as argument");
                 }
             };
-            match file_handle.file.write(line.as_bytes()) {
+            match file_handle.file.write_all(line.as_bytes()) {
                 Ok(_) => Ok(Value::Empty),
                 Err(e) => external_error!("File.write_line: Error while writing to file: {}", e),
             }
@@ -96,7 +96,7 @@ pub fn make_file_map() -> ValueMap {
                 }
                 [_, unexpected] => external_error!(
                     "File.seek: Expected Number for seek position, found '{}'",
-                    type_as_string(&unexpected),
+                    type_as_string(unexpected),
                 ),
                 _ => external_error!("File.seek: Expected seek position as second argument"),
             }
@@ -128,7 +128,7 @@ pub fn make_module() -> ValueMap {
         move |vm, args| match vm.get_args(args) {
             [Str(path)] => {
                 let path = Path::new(path.as_str());
-                match fs::File::open(&path) {
+                match fs::File::open(path) {
                     Ok(file) => {
                         let file_map = make_file_map();
 
@@ -143,14 +143,12 @@ pub fn make_module() -> ValueMap {
 
                         Ok(Map(file_map))
                     }
-                    Err(e) => {
-                        return external_error!("io.open: Error while opening path: {}", e);
-                    }
+                    Err(e) => external_error!("io.open: Error while opening path: {}", e),
                 }
             }
             [unexpected] => external_error!(
                 "io.open: Expected a String as argument, found '{}'",
-                type_as_string(&unexpected),
+                type_as_string(unexpected),
             ),
             _ => external_error!("io.open: Expected a String as argument"),
         }
@@ -160,7 +158,7 @@ pub fn make_module() -> ValueMap {
         move |vm, args| match vm.get_args(args) {
             [Str(path)] => {
                 let path = Path::new(path.as_str());
-                match fs::File::create(&path) {
+                match fs::File::create(path) {
                     Ok(file) => {
                         let mut file_map = make_file_map();
 
@@ -175,14 +173,12 @@ pub fn make_module() -> ValueMap {
 
                         Ok(Map(file_map))
                     }
-                    Err(e) => {
-                        return external_error!("io.create: Error while creating file: {}", e);
-                    }
+                    Err(e) => external_error!("io.create: Error while creating file: {}", e),
                 }
             }
             [unexpected] => external_error!(
                 "io.create: Expected a String as argument, found '{}'",
-                type_as_string(&unexpected),
+                type_as_string(unexpected),
             ),
             _ => external_error!("io.create: Expected a String as argument"),
         }
@@ -196,7 +192,7 @@ pub fn make_module() -> ValueMap {
         |vm, args| match vm.get_args(args) {
             [Str(path)] => {
                 let path = Path::new(path.as_str());
-                match fs::remove_file(&path) {
+                match fs::remove_file(path) {
                     Ok(_) => Ok(Value::Empty),
                     Err(e) => external_error!(
                         "io.remove_file: Error while removing file '{}': {}",
@@ -207,7 +203,7 @@ pub fn make_module() -> ValueMap {
             }
             [unexpected] => external_error!(
                 "io.remove_file: Expected a String as argument, found '{}'",
-                type_as_string(&unexpected),
+                type_as_string(unexpected),
             ),
             _ => external_error!("io.remove_file: Expected a String as argument"),
         }
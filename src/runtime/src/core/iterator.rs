@@ -97,9 +97,7 @@ pub fn make_module() -> ValueMap {
         [iterable] if value_is_iterable(iterable) => {
             let iter = make_iterator(iterable).unwrap();
             for output in iter {
-                if let Err(error) = output {
-                    return Err(error);
-                }
+                output?;
             }
             Ok(Empty)
         }
@@ -111,9 +109,7 @@ pub fn make_module() -> ValueMap {
             let iter = make_iterator(iterable).unwrap();
             let mut result = 0;
             for output in iter {
-                if let Err(error) = output {
-                    return Err(error);
-                }
+                output?;
                 result += 1;
             }
             Ok(Number(result.into()))
@@ -208,7 +204,7 @@ pub fn make_module() -> ValueMap {
                 for output in &mut iter {
                     match output {
                         Ok(Output::Value(value)) => {
-                            match vm.run_function(f.clone(), &[value.clone()]) {
+                            match vm.run_function(f.clone(), std::slice::from_ref(&value)) {
                                 Ok(Bool(result)) => {
                                     if result {
                                         return Some(Ok(Output::Value(value)));
@@ -326,7 +322,7 @@ pub fn make_module() -> ValueMap {
             for (i, output) in iter.enumerate() {
                 match output {
                     Ok(Output::Value(value)) => {
-                        match vm.run_function(f.clone(), &[value.clone()]) {
+                        match vm.run_function(f.clone(), std::slice::from_ref(&value)) {
                             Ok(Bool(result)) => {
                                 if result {
                                     return Ok(Number(i.into()));
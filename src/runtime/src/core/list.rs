@@ -157,7 +157,7 @@ pub fn make_module() -> ValueMap {
                 let mut write_index = 0;
                 for read_index in 0..l.len() {
                     let value = l.data()[read_index].clone();
-                    match vm.run_function(f.clone(), &[value.clone()]) {
+                    match vm.run_function(f.clone(), std::slice::from_ref(&value)) {
                         Ok(Bool(result)) => {
                             if result {
                                 l.data_mut()[write_index] = value;
@@ -214,7 +214,7 @@ pub fn make_module() -> ValueMap {
             let mut error = None;
 
             l.data_mut().sort_by_cached_key(|value| {
-                match vm.run_function(f.clone(), &[value.clone()]) {
+                match vm.run_function(f.clone(), std::slice::from_ref(value)) {
                     Ok(result) => result,
                     Err(e) => {
                         error.get_or_insert(Err(e.with_prefix("list.sort")));
@@ -262,7 +262,7 @@ pub fn make_module() -> ValueMap {
             let mut vm = vm.spawn_shared_vm();
 
             for value in l.data_mut().iter_mut() {
-                *value = match vm.run_function(f.clone(), &[value.clone()]) {
+                *value = match vm.run_function(f.clone(), std::slice::from_ref(value)) {
                     Ok(result) => result,
                     Err(error) => return Err(error.with_prefix("list.transform")),
                 }
@@ -15,7 +15,7 @@ pub mod test;
 pub mod thread;
 pub mod tuple;
 
-use crate::ValueMap;
+use crate::{CoreModule, ValueMap};
 
 #[derive(Clone)]
 pub struct CoreLib {
@@ -55,3 +55,23 @@ impl Default for CoreLib {
         }
     }
 }
+
+impl CoreLib {
+    /// Returns the map that contains the given module's functions
+    pub fn module(&self, module: CoreModule) -> &ValueMap {
+        match module {
+            CoreModule::Io => &self.io,
+            CoreModule::Iterator => &self.iterator,
+            CoreModule::Koto => &self.koto,
+            CoreModule::List => &self.list,
+            CoreModule::Map => &self.map,
+            CoreModule::Number => &self.number,
+            CoreModule::Os => &self.os,
+            CoreModule::Range => &self.range,
+            CoreModule::String => &self.string,
+            CoreModule::Test => &self.test,
+            CoreModule::Thread => &self.thread,
+            CoreModule::Tuple => &self.tuple,
+        }
+    }
+}
@@ -60,7 +60,7 @@ pub fn make_module() -> ValueMap {
     number_f64_fn!(exp2);
     number_fn!(floor);
 
-    result.add_value("infinity", Number(std::f64::INFINITY.into()));
+    result.add_value("infinity", Number(f64::INFINITY.into()));
 
     result.add_fn("is_nan", |vm, args| match vm.get_args(args) {
         [Number(n)] => Ok(Bool(n.is_nan())),
@@ -81,8 +81,8 @@ pub fn make_module() -> ValueMap {
         _ => external_error!("number.min: Expected two numbers as arguments"),
     });
 
-    result.add_value("nan", Number(std::f64::NAN.into()));
-    result.add_value("negative_infinity", Number(std::f64::NEG_INFINITY.into()));
+    result.add_value("nan", Number(f64::NAN.into()));
+    result.add_value("negative_infinity", Number(f64::NEG_INFINITY.into()));
     result.add_value("pi", Number(std::f64::consts::PI.into()));
 
     result.add_fn("pow", |vm, args| match vm.get_args(args) {
@@ -44,10 +44,14 @@ pub fn make_module() -> ValueMap {
 
     result.add_fn("format", |vm, args| match vm.get_args(args) {
         [result @ Str(_)] => Ok(result.clone()),
-        [Str(format), format_args @ ..] => match format::format_string(format, format_args) {
-            Ok(result) => Ok(Str(result.into())),
-            Err(error) => external_error!("string.format: {}", error),
-        },
+        [Str(format), format_args @ ..] => {
+            let format = format.clone();
+            let format_args = format_args.to_vec();
+            match format::format_string(vm, &format, &format_args) {
+                Ok(result) => Ok(Str(result.into())),
+                Err(error) => external_error!("string.format: {}", error),
+            }
+        }
         _ => external_error!("string.format: Expected a string as first argument"),
     });
 
@@ -85,11 +89,15 @@ pub fn make_module() -> ValueMap {
 
     result.add_fn("print", |vm, args| {
         match vm.get_args(args) {
-            [Str(s)] => println!("{}", s.as_str()),
-            [Str(format), format_args @ ..] => match format::format_string(format, format_args) {
-                Ok(result) => println!("{}", result.as_str()),
-                Err(error) => return external_error!("string.print: {}", error),
-            },
+            [Str(s)] => vm.stdout().write_line(s.as_str())?,
+            [Str(format), format_args @ ..] => {
+                let format = format.clone();
+                let format_args = format_args.to_vec();
+                match format::format_string(vm, &format, &format_args) {
+                    Ok(result) => vm.stdout().write_line(result.as_str())?,
+                    Err(error) => return external_error!("string.print: {}", error),
+                }
+            }
             _ => return external_error!("string.print: Expected a string as first argument"),
         }
         Ok(Empty)
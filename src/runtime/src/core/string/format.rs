@@ -1,5 +1,5 @@
 use {
-    crate::Value,
+    crate::{Value, Vm},
     koto_lexer::{is_id_continue, is_id_start},
 };
 
@@ -55,7 +55,7 @@ impl<'a> Iterator for FormatLexer<'a> {
                                 self.position += 1;
                                 let mut n = n.to_digit(10).unwrap();
 
-                                while let Some(c) = chars.next() {
+                                for c in chars.by_ref() {
                                     match c {
                                         n2 @ '0'..='9' => {
                                             self.position += 1;
@@ -77,7 +77,7 @@ impl<'a> Iterator for FormatLexer<'a> {
                                 let mut end = start + 1;
                                 self.position += 1;
 
-                                while let Some(c) = chars.next() {
+                                for c in chars.by_ref() {
                                     match c {
                                         _ if is_id_continue(c) => {
                                             end += 1;
@@ -147,26 +147,31 @@ impl<'a> Iterator for FormatLexer<'a> {
     }
 }
 
-pub fn format_string(format_string: &str, format_args: &[Value]) -> Result<String, String> {
+pub fn format_string(
+    vm: &mut Vm,
+    format_string: &str,
+    format_args: &[Value],
+) -> Result<String, String> {
     let mut arg_iter = format_args.iter();
     let mut result = String::with_capacity(format_string.len());
 
-    for token in FormatLexer::new(&format_string) {
+    for token in FormatLexer::new(format_string) {
         match token {
             FormatToken::String(s) => result.push_str(s),
             FormatToken::Placeholder => match arg_iter.next() {
-                Some(arg) => result.push_str(&arg.to_string()),
+                Some(arg) => result.push_str(&value_to_string(vm, arg)?),
                 None => return Err("Not enough arguments for format string".to_string()),
             },
             FormatToken::Positional(n) => match format_args.get(n as usize) {
-                Some(arg) => result.push_str(&arg.to_string()),
+                Some(arg) => result.push_str(&value_to_string(vm, arg)?),
                 None => return Err(format!("Missing argument for index {}", n)),
             },
             FormatToken::Identifier(id) => match format_args.first() {
                 Some(Value::Map(map)) => {
                     // TODO pass in runtime's string cache
-                    match map.data().get_with_string(id) {
-                        Some(value) => result.push_str(&value.to_string()),
+                    let value = map.data().get_with_string(id).cloned();
+                    match value {
+                        Some(value) => result.push_str(&value_to_string(vm, &value)?),
                         None => return Err(format!("Key '{}' not found in map", id)),
                     }
                 }
@@ -182,11 +187,15 @@ pub fn format_string(format_string: &str, format_args: &[Value]) -> Result<Strin
     Ok(result)
 }
 
+fn value_to_string(vm: &mut Vm, value: &Value) -> Result<String, String> {
+    vm.value_to_string(value).map_err(|e| e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use {
         super::*,
-        crate::{ValueHashMap, ValueMap},
+        crate::{ValueHashMap, ValueMap, Vm},
     };
 
     mod lexer {
@@ -294,9 +303,9 @@ mod tests {
         use super::*;
 
         fn check_format_output(format: &str, args: &[Value], expected: &str) {
-            match format_string(format, args) {
+            match format_string(&mut Vm::default(), format, args) {
                 Ok(result) => assert_eq!(result, expected),
-                Err(error) => panic!(error),
+                Err(error) => panic!("{}", error),
             }
         }
 
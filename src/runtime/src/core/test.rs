@@ -91,9 +91,9 @@ pub fn make_module() -> ValueMap {
         }
         [a, b, c] => external_error!(
             "assert_near expects Numbers as arguments, found '{}', '{}', and '{}'",
-            type_as_string(&a),
-            type_as_string(&b),
-            type_as_string(&c),
+            type_as_string(a),
+            type_as_string(b),
+            type_as_string(c),
         ),
         _ => external_error!("assert_eq expects three arguments"),
     });
@@ -0,0 +1,148 @@
+use {
+    koto_bytecode::Chunk,
+    parking_lot::Mutex,
+    std::{
+        collections::{BTreeMap, HashMap},
+        fmt::Write,
+        path::PathBuf,
+        sync::Arc,
+    },
+};
+
+/// Collects line coverage while running Koto scripts
+///
+/// Coverage is collected by attaching the collector to a VM with
+/// [Vm::set_coverage](crate::Vm::set_coverage), and it's shared with any VMs that are spawned by
+/// the VM, including the VMs used for imported modules.
+///
+/// The collector is a handle to shared data, so it can be attached to multiple VMs to aggregate
+/// coverage across several scripts, with a clone kept by the host to retrieve a
+/// [CoverageReport] after the scripts have been run.
+#[derive(Clone, Default)]
+pub struct Coverage(Arc<Mutex<CoverageData>>);
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clears any coverage that has been collected so far
+    pub fn clear(&self) {
+        self.0.lock().chunks.clear();
+    }
+
+    /// Returns a report of the coverage that has been collected so far
+    pub fn report(&self) -> CoverageReport {
+        self.0.lock().report()
+    }
+
+    pub(crate) fn record_instruction(&self, chunk: &Arc<Chunk>, ip: usize) {
+        self.0.lock().record_instruction(chunk, ip)
+    }
+}
+
+/// The line coverage for a script, see [CoverageReport]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileCoverage {
+    /// The script's path, or `None` for scripts that were compiled without a path
+    pub path: Option<PathBuf>,
+    /// The script's executable lines, along with the number of times each line was executed
+    ///
+    /// The lines are sorted by line number, starting from 1.
+    pub lines: Vec<(u32, u64)>,
+}
+
+impl FileCoverage {
+    /// The number of executable lines that were executed at least once
+    pub fn covered_line_count(&self) -> usize {
+        self.lines.iter().filter(|(_, count)| *count > 0).count()
+    }
+}
+
+/// A report of the coverage that was collected by a [Coverage] collector
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CoverageReport {
+    /// The coverage for each script, sorted by path
+    pub files: Vec<FileCoverage>,
+}
+
+impl CoverageReport {
+    /// Returns the report in the lcov tracefile format
+    ///
+    /// Scripts without a path are left out of the output.
+    pub fn to_lcov(&self) -> String {
+        let mut result = String::new();
+
+        for file in self.files.iter() {
+            let path = match &file.path {
+                Some(path) => path,
+                None => continue,
+            };
+
+            result.push_str("TN:\n");
+            let _ = writeln!(result, "SF:{}", path.display());
+            for (line, count) in file.lines.iter() {
+                let _ = writeln!(result, "DA:{},{}", line, count);
+            }
+            let _ = writeln!(result, "LF:{}", file.lines.len());
+            let _ = writeln!(result, "LH:{}", file.covered_line_count());
+            result.push_str("end_of_record\n");
+        }
+
+        result
+    }
+}
+
+#[derive(Default)]
+struct CoverageData {
+    // The number of times each instruction has been executed for each chunk,
+    // keyed by the chunk's address. The chunk is kept alive so that its address isn't reused.
+    chunks: HashMap<usize, (Arc<Chunk>, Vec<u64>)>,
+}
+
+impl CoverageData {
+    fn record_instruction(&mut self, chunk: &Arc<Chunk>, ip: usize) {
+        let (_, counts) = self
+            .chunks
+            .entry(Arc::as_ptr(chunk) as usize)
+            .or_insert_with(|| (chunk.clone(), vec![0; chunk.bytes.len()]));
+
+        if let Some(count) = counts.get_mut(ip) {
+            *count += 1;
+        }
+    }
+
+    fn report(&self) -> CoverageReport {
+        // Chunks with matching paths are merged, e.g. when a module is imported by multiple scripts
+        let mut files: BTreeMap<Option<PathBuf>, BTreeMap<u32, u64>> = BTreeMap::new();
+
+        for (chunk, counts) in self.chunks.values() {
+            let lines = files.entry(chunk.source_path.clone()).or_default();
+
+            // Each line with instructions is executable, and a line's count is taken from the
+            // instructions where its spans begin. Instructions that follow the start of a span
+            // aren't counted, as they might have been emitted without a span of their own,
+            // e.g. a function's implicit return.
+            let mut chunk_lines = BTreeMap::new();
+            for (ip, span) in chunk.debug_info.source_map() {
+                let count = counts.get(*ip).copied().unwrap_or(0);
+                let line_count = chunk_lines.entry(span.start.line).or_insert(0);
+                *line_count = count.max(*line_count);
+            }
+
+            for (line, count) in chunk_lines {
+                *lines.entry(line).or_default() += count;
+            }
+        }
+
+        CoverageReport {
+            files: files
+                .into_iter()
+                .map(|(path, lines)| FileCoverage {
+                    path,
+                    lines: lines.into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use {
+    crate::{Value, ValueMap},
+    koto_bytecode::Chunk,
+    koto_parser::Span,
+    parking_lot::Mutex,
+    std::{
+        fmt,
+        path::{Path, PathBuf},
+        sync::{atomic::AtomicU64, Arc},
+    },
+};
+
+/// The reason that execution was paused by a [Debugger]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PauseReason {
+    /// A breakpoint was reached
+    Breakpoint,
+    /// A step was completed, or a pause was requested with [Debugger::pause]
+    Step,
+}
+
+/// The command returned by a [DebugHandler] that decides how execution should continue
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugCommand {
+    /// Run until the next breakpoint is reached
+    Continue,
+    /// Pause at the next line, including lines in called functions
+    StepIn,
+    /// Pause at the next line in the current function or one of its callers
+    StepOver,
+    /// Pause when execution has returned from the current function
+    StepOut,
+    /// Stop execution
+    ///
+    /// The VM returns a [StoppedByDebugger](crate::ExecutionLimit::StoppedByDebugger) error.
+    Stop,
+}
+
+/// A breakpoint that pauses execution when a line in a script is reached
+#[derive(Clone, Debug, PartialEq)]
+pub struct Breakpoint {
+    /// The path of the script containing the breakpoint
+    ///
+    /// A chunk's path matches the breakpoint if it ends with the breakpoint's path,
+    /// e.g. `foo.koto` will match `/path/to/foo.koto`.
+    /// If no path is set then the breakpoint matches any script.
+    pub path: Option<PathBuf>,
+    /// The breakpoint's line number, starting from 1
+    pub line: u32,
+}
+
+impl Breakpoint {
+    fn matches(&self, source_path: Option<&Path>, line: u32) -> bool {
+        if self.line != line {
+            return false;
+        }
+
+        match (&self.path, source_path) {
+            (None, _) => true,
+            (Some(path), Some(source_path)) => source_path.ends_with(path),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}:{}", path.display(), self.line),
+            None => write!(f, "{}", self.line),
+        }
+    }
+}
+
+/// The breakpoints that are checked by a [Debugger]
+#[derive(Clone, Debug, Default)]
+pub struct Breakpoints(Vec<Breakpoint>);
+
+impl Breakpoints {
+    /// Adds a breakpoint, returning false if a matching breakpoint already exists
+    pub fn add(&mut self, breakpoint: Breakpoint) -> bool {
+        if self.0.contains(&breakpoint) {
+            false
+        } else {
+            self.0.push(breakpoint);
+            true
+        }
+    }
+
+    /// Removes a breakpoint, returning false if the breakpoint wasn't found
+    pub fn remove(&mut self, breakpoint: &Breakpoint) -> bool {
+        let len_before = self.0.len();
+        self.0.retain(|existing| existing != breakpoint);
+        self.0.len() != len_before
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Breakpoint> {
+        self.0.iter()
+    }
+
+    fn find(&self, source_path: Option<&Path>, line: u32) -> bool {
+        self.0
+            .iter()
+            .any(|breakpoint| breakpoint.matches(source_path, line))
+    }
+}
+
+/// A frame in the call stack of a paused VM
+#[derive(Clone, Debug)]
+pub struct DebugFrame {
+    /// The chunk that's being executed in the frame
+    pub chunk: Arc<Chunk>,
+    /// The position of the frame's current instruction
+    pub ip: usize,
+    /// The source span of the frame's current instruction
+    pub span: Option<Span>,
+    /// The name of the frame's function, if it has one
+    pub function: Option<String>,
+    /// The frame's named local values
+    pub locals: Vec<(String, Value)>,
+    /// The frame's registers, including temporary values
+    pub registers: Vec<Value>,
+}
+
+impl DebugFrame {
+    /// The line of the frame's current instruction, starting from 1
+    pub fn line(&self) -> Option<u32> {
+        self.span.map(|span| span.start.line)
+    }
+
+    /// Returns the source text of the frame's current line
+    pub fn source_line(&self) -> Option<&str> {
+        let line = self.line()?;
+        self.chunk
+            .debug_info
+            .source
+            .lines()
+            .nth(line.checked_sub(1)? as usize)
+    }
+}
+
+/// The state of a VM that has been paused by a [Debugger]
+#[derive(Clone, Debug)]
+pub struct DebugState {
+    pub reason: PauseReason,
+    /// The frames of the paused VM's call stack, starting with the innermost frame
+    ///
+    /// Only the frames of the VM that's currently executing are included, e.g. when a function
+    /// is called by an external function then the external function's caller won't be included.
+    pub frames: Vec<DebugFrame>,
+    /// The global values of the module that's being executed
+    pub globals: ValueMap,
+}
+
+impl DebugState {
+    /// The frame that execution is paused in
+    pub fn current_frame(&self) -> &DebugFrame {
+        self.frames
+            .first()
+            .expect("A paused VM should have at least one frame")
+    }
+}
+
+/// Decides what should happen whenever a [Debugger] pauses execution
+///
+/// The handler can inspect the VM's state, and modify the breakpoints before execution continues.
+///
+/// The trait is implemented for closures, e.g.
+///
+/// ```
+/// use koto_runtime::{Breakpoints, DebugCommand, DebugState, Debugger};
+///
+/// let debugger = Debugger::new(|state: &DebugState, _: &mut Breakpoints| {
+///     println!("Paused at line {:?}", state.current_frame().line());
+///     DebugCommand::StepOver
+/// });
+/// ```
+pub trait DebugHandler: Send {
+    fn on_pause(&mut self, state: &DebugState, breakpoints: &mut Breakpoints) -> DebugCommand;
+}
+
+impl<F> DebugHandler for F
+where
+    F: FnMut(&DebugState, &mut Breakpoints) -> DebugCommand + Send,
+{
+    fn on_pause(&mut self, state: &DebugState, breakpoints: &mut Breakpoints) -> DebugCommand {
+        self(state, breakpoints)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StepMode {
+    Run,
+    StepIn,
+    StepOver { depth: usize },
+    StepOut { depth: usize },
+}
+
+/// A debugger that can be attached to a VM with [Vm::set_debugger](crate::Vm::set_debugger)
+///
+/// The debugger is checked each time the VM reaches a new line in a script, and pauses execution
+/// when a breakpoint is reached or when a step has been completed. While paused, the debugger's
+/// [DebugHandler] is called with the state of the VM, and returns a [DebugCommand] that decides
+/// how execution should continue.
+pub struct Debugger {
+    handler: Box<dyn DebugHandler>,
+    breakpoints: Breakpoints,
+    mode: StepMode,
+    // The most recently executed line at each depth of the call stack,
+    // as (chunk address, line) pairs.
+    lines: Vec<Option<(usize, u32)>>,
+}
+
+impl Debugger {
+    pub fn new(handler: impl DebugHandler + 'static) -> Self {
+        Self {
+            handler: Box::new(handler),
+            breakpoints: Breakpoints::default(),
+            mode: StepMode::Run,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Requests that execution should be paused when the next line is reached
+    ///
+    /// Calling this before running a script will pause at the script's first line.
+    pub fn pause(&mut self) {
+        self.mode = StepMode::StepIn;
+    }
+
+    pub fn breakpoints(&self) -> &Breakpoints {
+        &self.breakpoints
+    }
+
+    pub fn breakpoints_mut(&mut self) -> &mut Breakpoints {
+        &mut self.breakpoints
+    }
+
+    // Called by the VM before each instruction
+    //
+    // Returns the reason for pausing if execution should be paused before the instruction.
+    pub(crate) fn check_pause(
+        &mut self,
+        depth: usize,
+        chunk: &Arc<Chunk>,
+        line: u32,
+    ) -> Option<PauseReason> {
+        if !self.is_new_line(depth, Arc::as_ptr(chunk) as usize, line) {
+            return None;
+        }
+
+        if self.breakpoints.find(chunk.source_path.as_deref(), line) {
+            return Some(PauseReason::Breakpoint);
+        }
+
+        let step_complete = match self.mode {
+            StepMode::Run => false,
+            StepMode::StepIn => true,
+            StepMode::StepOver { depth: step_depth } => depth <= step_depth,
+            StepMode::StepOut { depth: step_depth } => depth < step_depth,
+        };
+
+        if step_complete {
+            Some(PauseReason::Step)
+        } else {
+            None
+        }
+    }
+
+    // Calls the handler with the paused VM's state
+    //
+    // Returns false if execution should be stopped.
+    pub(crate) fn on_pause(&mut self, depth: usize, state: &DebugState) -> bool {
+        self.mode = match self.handler.on_pause(state, &mut self.breakpoints) {
+            DebugCommand::Continue => StepMode::Run,
+            DebugCommand::StepIn => StepMode::StepIn,
+            DebugCommand::StepOver => StepMode::StepOver { depth },
+            DebugCommand::StepOut => StepMode::StepOut { depth },
+            DebugCommand::Stop => {
+                self.mode = StepMode::Run;
+                return false;
+            }
+        };
+        true
+    }
+
+    // Updates the current line at the given depth, returning true if the line has changed
+    //
+    // Returning from a function call to the calling line doesn't count as a new line.
+    fn is_new_line(&mut self, depth: usize, chunk: usize, line: u32) -> bool {
+        let location = Some((chunk, line));
+
+        if depth < self.lines.len() {
+            self.lines.truncate(depth + 1);
+            if self.lines[depth] == location {
+                return false;
+            }
+        } else {
+            self.lines.resize(depth + 1, None);
+        }
+
+        self.lines[depth] = location;
+        true
+    }
+}
+
+// A debugger that's shared between a VM and the VMs that it spawns
+pub(crate) struct SharedDebugger {
+    pub debugger: Mutex<Debugger>,
+    // Incremented each time a VM checks the debugger, which allows a VM to detect that another
+    // VM has checked the debugger since its own most recent check.
+    pub check_count: AtomicU64,
+}
+
+impl SharedDebugger {
+    pub fn new(debugger: Debugger) -> Self {
+        Self {
+            debugger: Mutex::new(debugger),
+            check_count: AtomicU64::new(0),
+        }
+    }
+}
+
+impl fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("breakpoints", &self.breakpoints)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
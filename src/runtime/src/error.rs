@@ -1,8 +1,9 @@
 use {
-    crate::Value,
+    crate::{ExecutionLimit, Value},
     koto_bytecode::Chunk,
     koto_parser::format_error_with_excerpt,
     std::{
+        path::PathBuf,
         sync::Arc,
         {error, fmt},
     },
@@ -14,6 +15,31 @@ pub struct ErrorFrame {
     instruction: usize,
 }
 
+impl ErrorFrame {
+    // Returns the name of the function that contains the frame's instruction
+    fn function_name(&self) -> Option<&str> {
+        let frame_info = self.chunk.debug_info.get_frame_info(self.instruction)?;
+        frame_info
+            .function_name
+            .map(|name| self.chunk.constants.get_str(name))
+    }
+}
+
+/// A frame in a [RuntimeError]'s backtrace, see [RuntimeError::backtrace]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceFrame {
+    /// The name of the function that was being executed
+    ///
+    /// Anonymous functions and the top level of a script have no name.
+    pub function: Option<String>,
+    /// The path of the script that contains the function, if known
+    pub path: Option<PathBuf>,
+    /// The line in the script where the error occurred, starting from 1
+    pub line: u32,
+    /// The column in the script where the error occurred, starting from 1
+    pub column: u32,
+}
+
 #[derive(Clone, Debug)]
 pub enum RuntimeError {
     VmError {
@@ -23,9 +49,21 @@ pub enum RuntimeError {
     ExternalError {
         message: String,
     },
+    /// A value that was thrown in a script with a `throw` expression
+    ///
+    /// The value is passed unchanged to `catch` blocks, or is available here for hosts that want
+    /// to inspect errors that weren't caught.
+    ThrownValue {
+        value: Value,
+        trace: Vec<ErrorFrame>,
+    },
     FunctionNotFound {
         name: String,
     },
+    /// Execution was stopped by one of the VM's execution limits
+    ///
+    /// Errors caused by execution limits can't be caught in scripts with try/catch.
+    ExecutionLimitExceeded(ExecutionLimit),
 }
 
 impl RuntimeError {
@@ -41,12 +79,43 @@ impl RuntimeError {
                 message: format!("{}: {}", prefix, message),
             },
             FunctionNotFound { .. } => unimplemented!(),
+            // The thrown value is left untouched so that it can be caught unchanged
+            ThrownValue { .. } | ExecutionLimitExceeded(_) => self,
+        }
+    }
+
+    pub fn is_catchable(&self) -> bool {
+        !matches!(self, Self::ExecutionLimitExceeded(_))
+    }
+
+    /// Returns the error's backtrace
+    ///
+    /// The first frame is where the error occurred, followed by each of its callers.
+    /// Errors that weren't produced while running a script return an empty backtrace.
+    pub fn backtrace(&self) -> Vec<TraceFrame> {
+        match self {
+            Self::VmError { trace, .. } | Self::ThrownValue { trace, .. } => trace
+                .iter()
+                .filter_map(|frame| {
+                    let span = frame.chunk.debug_info.get_source_span(frame.instruction)?;
+                    Some(TraceFrame {
+                        function: frame.function_name().map(String::from),
+                        path: frame.chunk.source_path.clone(),
+                        line: span.start.line,
+                        column: span.start.column,
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
         }
     }
 
     pub fn extend_trace(&mut self, chunk: Arc<Chunk>, instruction: usize) {
-        if let Self::VmError { trace, .. } = self {
-            trace.push(ErrorFrame { chunk, instruction });
+        match self {
+            Self::VmError { trace, .. } | Self::ThrownValue { trace, .. } => {
+                trace.push(ErrorFrame { chunk, instruction })
+            }
+            _ => {}
         }
     }
 }
@@ -57,43 +126,57 @@ impl fmt::Display for RuntimeError {
 
         match &self {
             VmError { message, .. } if f.alternate() => f.write_str(message),
-            VmError { message, trace } => {
-                let mut first_frame = true;
-                for frame in trace.iter() {
-                    let frame_message = if first_frame {
-                        first_frame = false;
-                        Some(message.as_str())
-                    } else {
-                        None
-                    };
-
-                    match frame.chunk.debug_info.get_source_span(frame.instruction) {
-                        Some(span) => f.write_str(&format_error_with_excerpt(
-                            frame_message,
-                            &frame.chunk.source_path,
-                            &frame.chunk.debug_info.source,
-                            span.start,
-                            span.end,
-                        ))?,
-                        None => write!(
-                            f,
-                            "Runtime error at instruction {}: {}",
-                            frame.instruction, message
-                        )?,
-                    };
-                }
-                Ok(())
-            }
+            VmError { message, trace } => fmt_trace(f, message, trace),
+            ThrownValue { value, .. } if f.alternate() => f.write_str(&value.to_string()),
+            ThrownValue { value, trace } => fmt_trace(f, &value.to_string(), trace),
             ExternalError { message } => f.write_str(message),
             FunctionNotFound { name } => write!(f, "Function '{}' not found", name),
+            ExecutionLimitExceeded(limit) => limit.fmt(f),
         }
     }
 }
 
 impl error::Error for RuntimeError {}
 
+fn fmt_trace(f: &mut fmt::Formatter<'_>, message: &str, trace: &[ErrorFrame]) -> fmt::Result {
+    let mut first_frame = true;
+    for frame in trace.iter() {
+        let frame_message = if first_frame {
+            first_frame = false;
+            Some(message)
+        } else {
+            None
+        };
+
+        match frame.chunk.debug_info.get_source_span(frame.instruction) {
+            Some(span) => f.write_str(&format_error_with_excerpt(
+                frame_message,
+                &frame.chunk.source_path,
+                frame.function_name(),
+                &frame.chunk.debug_info.source,
+                span.start,
+                span.end,
+            ))?,
+            None => write!(
+                f,
+                "Runtime error at instruction {}: {}",
+                frame.instruction, message
+            )?,
+        };
+    }
+    Ok(())
+}
+
 pub type RuntimeResult = Result<Value, RuntimeError>;
 
+// Called by the error macros, panics when the panic_on_runtime_error feature is enabled
+#[doc(hidden)]
+#[inline]
+pub fn check_panic_on_runtime_error() {
+    #[cfg(feature = "panic_on_runtime_error")]
+    panic!();
+}
+
 #[macro_export]
 macro_rules! make_vm_error {
     ($message:expr) => {{
@@ -101,10 +184,7 @@ macro_rules! make_vm_error {
             message: $message,
             trace: Vec::new(),
         };
-        #[cfg(panic_on_runtime_error)]
-        {
-            panic!();
-        }
+        $crate::check_panic_on_runtime_error();
         error
     }};
 }
@@ -123,10 +203,7 @@ macro_rules! vm_error {
 macro_rules! make_external_error {
     ($message:expr) => {{
         let error = $crate::RuntimeError::ExternalError { message: $message };
-        #[cfg(panic_on_runtime_error)]
-        {
-            panic!();
-        }
+        $crate::check_panic_on_runtime_error();
         error
     }};
 }
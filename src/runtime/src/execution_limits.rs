@@ -0,0 +1,133 @@
+use {
+    crate::RuntimeError,
+    std::{
+        fmt,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    },
+};
+
+/// Limits that can be placed on a [Vm](crate::Vm)'s execution
+///
+/// See [Vm::set_execution_limits](crate::Vm::set_execution_limits).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ExecutionLimits {
+    /// The maximum number of instructions that can be executed
+    pub instruction_budget: Option<u64>,
+    /// The maximum amount of time that execution can take
+    pub timeout: Option<Duration>,
+}
+
+impl ExecutionLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.instruction_budget.is_none() && self.timeout.is_none()
+    }
+}
+
+/// The limit that caused execution to be stopped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExecutionLimit {
+    InstructionBudget,
+    Timeout,
+    Interrupted,
+    /// Execution was stopped by a [Debugger](crate::Debugger)
+    StoppedByDebugger,
+}
+
+impl fmt::Display for ExecutionLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ExecutionLimit::*;
+
+        match self {
+            InstructionBudget => f.write_str("The instruction budget has been exceeded"),
+            Timeout => f.write_str("The execution time limit has been exceeded"),
+            Interrupted => f.write_str("Execution was interrupted"),
+            StoppedByDebugger => f.write_str("Execution was stopped by the debugger"),
+        }
+    }
+}
+
+/// A handle that can be used to interrupt a [Vm](crate::Vm) from another thread
+///
+/// See [Vm::interrupt_handle](crate::Vm::interrupt_handle).
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle(Arc<AtomicBool>);
+
+impl InterruptHandle {
+    /// Stops execution of the VM, and any VMs that were spawned by it
+    pub fn interrupt(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears a pending interrupt, allowing the VM to be run again
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+// Checking the clock is relatively expensive, so the deadline is only checked periodically
+//
+// The interval needs to be a power of two, see ExecutionLimiter::check.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+// Tracks a VM's execution against its limits
+//
+// The limiter is shared between a VM and any VMs that it spawns.
+pub(crate) struct ExecutionLimiter {
+    instruction_budget: Option<u64>,
+    instruction_count: AtomicU64,
+    deadline: Option<Instant>,
+    interrupt: InterruptHandle,
+}
+
+impl ExecutionLimiter {
+    pub fn new(limits: ExecutionLimits, interrupt: InterruptHandle) -> Self {
+        Self {
+            instruction_budget: limits.instruction_budget,
+            instruction_count: AtomicU64::new(0),
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+            interrupt,
+        }
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    // Called before each instruction is executed
+    #[inline]
+    pub fn check(&self) -> Result<(), RuntimeError> {
+        let count = self.instruction_count.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if self.interrupt.is_interrupted() {
+            return Err(RuntimeError::ExecutionLimitExceeded(
+                ExecutionLimit::Interrupted,
+            ));
+        }
+
+        if let Some(budget) = self.instruction_budget {
+            if count > budget {
+                return Err(RuntimeError::ExecutionLimitExceeded(
+                    ExecutionLimit::InstructionBudget,
+                ));
+            }
+        }
+
+        if let Some(deadline) = self.deadline {
+            if count & (DEADLINE_CHECK_INTERVAL - 1) == 0 && Instant::now() >= deadline {
+                return Err(RuntimeError::ExecutionLimitExceeded(
+                    ExecutionLimit::Timeout,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 use {
-    crate::{external_error, RuntimeResult, Value, ValueMap, Vm},
+    crate::{external_error, RuntimeResult, Value, ValueMap, ValueMapKey, Vm},
     downcast_rs::impl_downcast,
     std::{
+        cell::RefCell,
         fmt,
         hash::{Hash, Hasher},
         sync::Arc,
@@ -17,8 +18,51 @@ pub trait ExternalValue: fmt::Debug + fmt::Display + Send + Sync + Downcast {
 impl_downcast!(ExternalValue);
 
 pub struct Args {
-    pub register: u8,
+    pub register: u16,
     pub count: u8,
+    // The named arguments that were provided with the call, e.g. `f 1, 2, foo: 42`
+    named_args: Option<ValueMap>,
+    // Tracks which of the named arguments have been read by the function
+    read_named_args: RefCell<Vec<bool>>,
+}
+
+impl Args {
+    pub(crate) fn new(register: u16, count: u8, named_args: Option<ValueMap>) -> Self {
+        let named_arg_count = named_args.as_ref().map_or(0, |named_args| named_args.len());
+        Self {
+            register,
+            count,
+            named_args,
+            read_named_args: RefCell::new(vec![false; named_arg_count]),
+        }
+    }
+
+    /// Returns the value of the named argument with the given name, if it was provided
+    pub fn named_arg(&self, name: &str) -> Option<Value> {
+        let named_args = self.named_args.as_ref()?;
+        let data = named_args.data();
+        let (index, _, value) = data.get_full(&name as &dyn ValueMapKey)?;
+        self.read_named_args.borrow_mut()[index] = true;
+        Some(value.clone())
+    }
+
+    /// Returns all of the named arguments that were provided with the call
+    pub fn named_args(&self) -> Option<&ValueMap> {
+        let named_args = self.named_args.as_ref()?;
+        self.read_named_args.borrow_mut().fill(true);
+        Some(named_args)
+    }
+
+    // Returns the name of the first named argument that wasn't read by the function
+    pub(crate) fn unread_named_arg(&self) -> Option<Value> {
+        let named_args = self.named_args.as_ref()?;
+        let read_named_args = self.read_named_args.borrow();
+        let data = named_args.data();
+        data.keys()
+            .zip(read_named_args.iter())
+            .find(|(_, read)| !**read)
+            .map(|(name, _)| name.clone())
+    }
 }
 
 // Once Trait aliases are stabilized this can be simplified a bit,
@@ -8,12 +8,17 @@ pub(crate) struct Frame {
     // or the first local register if there are no arguments.
     pub register_base: usize,
     // When returning to this frame, the register for the return value and the ip to resume from.
-    pub return_register_and_ip: Option<(u8, usize)>,
+    pub return_register_and_ip: Option<(u16, usize)>,
     // A stack of catch points for handling errors
-    pub catch_stack: Vec<(u8, usize)>, // catch error register, catch ip
+    pub catch_stack: Vec<(u16, usize)>, // catch error register, catch ip
     // True if the frame should prevent errors from being caught further down the stack,
     // e.g. when an external function is calling back into the VM with a functor
     pub catch_barrier: bool,
+    // The token used by the profiler to identify the frame's call
+    pub profile_token: Option<u64>,
+    // A mask of the arg registers that weren't provided by the caller,
+    // used to decide if an arg's default value should be used.
+    pub omitted_args: u64,
 }
 
 impl Frame {
@@ -24,6 +29,8 @@ impl Frame {
             return_register_and_ip: None,
             catch_stack: vec![],
             catch_barrier: false,
+            profile_token: None,
+            omitted_args: 0,
         }
     }
 }
@@ -0,0 +1,114 @@
+use {
+    crate::ValueMap,
+    koto_bytecode::Loader,
+    std::{
+        collections::{HashMap, HashSet},
+        fs,
+        path::{Path, PathBuf},
+        time::SystemTime,
+    },
+};
+
+/// The modules that have been imported by a VM and the VMs that it spawned
+#[derive(Default)]
+pub(crate) struct ImportCache {
+    pub loader: Loader,
+    // The exported values of imported modules, None while a module is being imported
+    modules: HashMap<PathBuf, Option<ValueMap>>,
+    // The ids of the modules and scripts that import each module
+    dependents: HashMap<PathBuf, HashSet<PathBuf>>,
+    // The files that modules were loaded from, along with their modification times when loaded
+    files: HashMap<PathBuf, (PathBuf, Option<SystemTime>)>,
+}
+
+impl ImportCache {
+    pub fn get(&self, id: &Path) -> Option<Option<ValueMap>> {
+        self.modules.get(id).cloned()
+    }
+
+    // Inserts a placeholder for a module that's being imported, preventing recursive imports
+    pub fn begin_import(&mut self, id: &Path, file_path: Option<PathBuf>) {
+        self.modules.insert(id.to_path_buf(), None);
+
+        if let Some(file_path) = file_path {
+            let modified = modified_time(&file_path);
+            self.files.insert(id.to_path_buf(), (file_path, modified));
+        }
+    }
+
+    pub fn finish_import(&mut self, id: &Path, module: ValueMap) {
+        self.modules.insert(id.to_path_buf(), Some(module));
+    }
+
+    pub fn cancel_import(&mut self, id: &Path) {
+        self.modules.remove(id);
+        self.files.remove(id);
+    }
+
+    pub fn add_dependent(&mut self, id: &Path, dependent: &Path) {
+        self.dependents
+            .entry(id.to_path_buf())
+            .or_default()
+            .insert(dependent.to_path_buf());
+    }
+
+    pub fn dependents(&self, id: &Path) -> Vec<PathBuf> {
+        self.dependents
+            .get(id)
+            .map(|dependents| dependents.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    // Removes a module and the modules that depend on it from the cache
+    //
+    // The ids of the invalidated modules are returned, along with the ids of any dependent
+    // scripts.
+    pub fn invalidate(&mut self, id: &Path) -> Vec<PathBuf> {
+        let mut invalidated = Vec::new();
+        let mut pending = vec![id.to_path_buf()];
+
+        while let Some(id) = pending.pop() {
+            if invalidated.contains(&id) {
+                continue;
+            }
+
+            self.modules.remove(&id);
+            self.files.remove(&id);
+            self.loader.invalidate_module(&id);
+
+            if let Some(dependents) = self.dependents.get(&id) {
+                pending.extend(dependents.iter().cloned());
+            }
+
+            invalidated.push(id);
+        }
+
+        invalidated
+    }
+
+    // Invalidates any modules with files that have changed since they were loaded
+    pub fn invalidate_changed(&mut self) -> Vec<PathBuf> {
+        let changed = self
+            .files
+            .iter()
+            .filter(|(_, (path, modified))| modified_time(path) != *modified)
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+
+        let mut invalidated = Vec::new();
+        for id in changed {
+            for id in self.invalidate(&id) {
+                if !invalidated.contains(&id) {
+                    invalidated.push(id);
+                }
+            }
+        }
+        invalidated
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
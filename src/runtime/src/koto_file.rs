@@ -0,0 +1,138 @@
+use {
+    crate::{make_external_error, RuntimeError},
+    parking_lot::Mutex,
+    std::{
+        fmt,
+        io::{self, Write},
+        sync::Arc,
+    },
+};
+
+/// An output stream that's used by the runtime for printing
+///
+/// The VM's stdout and stderr can be replaced with custom implementations via [VmSettings],
+/// e.g. to redirect script output in an embedding application.
+///
+/// [VmSettings]: crate::VmSettings
+pub trait KotoFile: fmt::Debug + Send + Sync {
+    /// Writes bytes to the output
+    fn write(&self, bytes: &[u8]) -> Result<(), RuntimeError>;
+
+    /// Writes text to the output, followed by a newline
+    fn write_line(&self, text: &str) -> Result<(), RuntimeError> {
+        self.write(text.as_bytes())?;
+        self.write(b"\n")
+    }
+
+    /// Flushes any buffered output
+    fn flush(&self) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+}
+
+/// The default stdout used by the runtime, writing to the process's stdout
+#[derive(Debug, Default)]
+pub struct DefaultStdout;
+
+impl KotoFile for DefaultStdout {
+    fn write(&self, bytes: &[u8]) -> Result<(), RuntimeError> {
+        write_to_stream(io::stdout(), bytes, "stdout")
+    }
+
+    fn write_line(&self, text: &str) -> Result<(), RuntimeError> {
+        // Lock stdout for the whole line so that output from other threads isn't interleaved
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        write_to_stream(&mut stdout, text.as_bytes(), "stdout")?;
+        write_to_stream(&mut stdout, b"\n", "stdout")
+    }
+
+    fn flush(&self) -> Result<(), RuntimeError> {
+        io::stdout()
+            .flush()
+            .map_err(|e| make_external_error!(format!("Error while flushing stdout: {}", e)))
+    }
+}
+
+/// The default stderr used by the runtime, writing to the process's stderr
+#[derive(Debug, Default)]
+pub struct DefaultStderr;
+
+impl KotoFile for DefaultStderr {
+    fn write(&self, bytes: &[u8]) -> Result<(), RuntimeError> {
+        write_to_stream(io::stderr(), bytes, "stderr")
+    }
+
+    fn flush(&self) -> Result<(), RuntimeError> {
+        io::stderr()
+            .flush()
+            .map_err(|e| make_external_error!(format!("Error while flushing stderr: {}", e)))
+    }
+}
+
+fn write_to_stream(
+    mut stream: impl Write,
+    bytes: &[u8],
+    stream_name: &str,
+) -> Result<(), RuntimeError> {
+    stream
+        .write_all(bytes)
+        .map_err(|e| make_external_error!(format!("Error while writing to {}: {}", stream_name, e)))
+}
+
+/// An in-memory output buffer
+///
+/// Clones of the buffer share the same data, so a clone can be given to the runtime while the
+/// original is used to retrieve the output.
+///
+/// # Example
+///
+/// ```
+/// use {
+///     koto_runtime::{OutputBuffer, Vm, VmSettings},
+///     std::sync::Arc,
+/// };
+///
+/// let output = OutputBuffer::default();
+/// let vm = Vm::with_settings(VmSettings {
+///     stdout: Arc::new(output.clone()),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct OutputBuffer {
+    data: Arc<Mutex<Vec<u8>>>,
+}
+
+impl OutputBuffer {
+    /// Returns the buffer's contents as a String
+    ///
+    /// Any invalid UTF-8 sequences are replaced with `U+FFFD`.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.data.lock()).into_owned()
+    }
+
+    /// Returns the buffer's contents, leaving the buffer empty
+    pub fn take_contents(&self) -> String {
+        let data = std::mem::take(&mut *self.data.lock());
+        String::from_utf8_lossy(&data).into_owned()
+    }
+
+    pub fn clear(&self) {
+        self.data.lock().clear();
+    }
+}
+
+impl KotoFile for OutputBuffer {
+    fn write(&self, bytes: &[u8]) -> Result<(), RuntimeError> {
+        self.data.lock().extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn write_line(&self, text: &str) -> Result<(), RuntimeError> {
+        let mut data = self.data.lock();
+        data.extend_from_slice(text.as_bytes());
+        data.push(b'\n');
+        Ok(())
+    }
+}
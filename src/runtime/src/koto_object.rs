@@ -0,0 +1,59 @@
+use crate::{make_external_value, ExternalValue, Value, ValueMap};
+
+/// A Rust type that can be exposed to Koto as an external value with methods
+///
+/// This is usually implemented with `#[derive(KotoObject)]`, along with `#[koto_methods]` on an
+/// `impl` block for the type, e.g.
+///
+/// ```
+/// use koto_runtime::{koto_methods, KotoObject, ValueMap};
+///
+/// #[derive(Debug, KotoObject)]
+/// struct Counter {
+///     count: i64,
+/// }
+///
+/// #[koto_methods]
+/// impl Counter {
+///     fn increment(&mut self, amount: Option<i64>) -> i64 {
+///         self.count += amount.unwrap_or(1);
+///         self.count
+///     }
+/// }
+///
+/// let mut module = ValueMap::new();
+/// module.add_typed_fn("make_counter", || Counter { count: 0 });
+/// ```
+///
+/// The derive macro implements [ExternalValue], [Display](std::fmt::Display),
+/// and [IntoValue](crate::IntoValue) for the type. The type name that's shown in Koto defaults
+/// to the name of the struct, and can be set with `#[koto(type_name = "...")]`. A custom
+/// `Display` implementation can be used by adding `#[koto(display = false)]`.
+///
+/// `#[koto_methods]` implements [KotoMethods], adding each method with a `self` receiver to the
+/// object's map. Arguments and return values are converted with [FromValue](crate::FromValue)
+/// and [IntoRuntimeResult](crate::IntoRuntimeResult). Methods can be renamed with
+/// `#[koto(name = "...")]` or left out with `#[koto(skip)]`.
+///
+/// The generated code refers to the runtime as `koto_runtime`, which can be overridden with
+/// `#[koto(runtime = "koto::runtime")]` and `#[koto_methods(runtime = "koto::runtime")]`.
+pub trait KotoObject: ExternalValue + KotoMethods + Sized {
+    /// The name of the object's type, as shown in Koto
+    const TYPE_NAME: &'static str;
+
+    /// Wraps the object in a map containing the object's methods
+    fn into_value_map(self) -> ValueMap {
+        let mut result = ValueMap::new();
+        Self::add_methods(&mut result);
+        result.insert(Value::ExternalDataId, make_external_value(self));
+        result
+    }
+}
+
+/// The methods that are made available to Koto for a [KotoObject]
+///
+/// This is usually implemented with `#[koto_methods]`, see [KotoObject].
+pub trait KotoMethods {
+    /// Adds the type's methods to the given map as instance functions
+    fn add_methods(map: &mut ValueMap);
+}
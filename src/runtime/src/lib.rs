@@ -1,12 +1,22 @@
 //! Contains the runtime and core library for the Koto language
 
+mod capabilities;
 pub mod core;
+mod coverage;
+mod debugger;
 mod error;
+mod execution_limits;
 mod external;
 mod frame;
+mod import_cache;
+mod koto_file;
+mod koto_object;
 pub mod num2;
 pub mod num4;
+mod profiler;
+mod test_report;
 pub mod value;
+mod value_conversion;
 mod value_iterator;
 mod value_list;
 mod value_map;
@@ -16,20 +26,33 @@ mod value_tuple;
 mod vm;
 
 pub use {
+    capabilities::{Capabilities, CoreModule, ModuleFileAccess},
+    coverage::{Coverage, CoverageReport, FileCoverage},
+    debugger::{
+        Breakpoint, Breakpoints, DebugCommand, DebugFrame, DebugHandler, DebugState, Debugger,
+        PauseReason,
+    },
     error::*,
+    execution_limits::{ExecutionLimit, ExecutionLimits, InterruptHandle},
     external::{is_external_instance, visit_external_value, ExternalFunction, ExternalValue},
     koto_bytecode::{CompilerError, Loader, LoaderError},
-    koto_parser::ParserError,
+    koto_derive::{koto_methods, KotoObject},
+    koto_file::{DefaultStderr, DefaultStdout, KotoFile, OutputBuffer},
+    koto_object::{KotoMethods, KotoObject},
+    koto_parser::{MetaKeyId, ParserError},
     num2::Num2,
     num4::Num4,
+    profiler::{FunctionProfile, LineProfile, ProfileReport, Profiler, TraceEvent},
+    test_report::{format_test_reports, TestReport, TestReportFormat, TestResult},
     value::{
         make_external_value, type_as_string, value_is_immutable, RuntimeFunction, Value, ValueRef,
     },
+    value_conversion::{FromArgs, FromValue, IntoRuntimeResult, IntoValue, TypedFunction},
     value_iterator::{IntRange, ValueIterator, ValueIteratorOutput},
     value_list::{ValueList, ValueVec},
-    value_map::{ValueHashMap, ValueMap, ValueMapKey},
+    value_map::{MetaMap, ValueHashMap, ValueMap, ValueMapKey},
     value_number::ValueNumber,
     value_string::ValueString,
     value_tuple::ValueTuple,
-    vm::Vm,
+    vm::{Vm, VmSettings},
 };
@@ -0,0 +1,661 @@
+use {
+    koto_bytecode::Chunk,
+    parking_lot::Mutex,
+    std::{
+        collections::HashMap,
+        fmt::Write,
+        path::PathBuf,
+        sync::Arc,
+        thread::{self, ThreadId},
+        time::{Duration, Instant},
+    },
+};
+
+/// A profiler that records where time is spent while running Koto scripts
+///
+/// The profiler is attached to a VM with [Vm::set_profiler](crate::Vm::set_profiler), and is
+/// shared with any VMs that are spawned by the VM.
+///
+/// Each call to a Koto function is timed, along with the number of instructions that are executed
+/// for each line of the script. Calls to external functions aren't timed individually, and are
+/// included in the timing of the Koto function that called them.
+///
+/// Tail calls replace the calling function's frame, but the profiler still treats the called
+/// function as being called by the calling function, so that the calling function's time includes
+/// the time spent in the tail call. Repeated tail calls to the same function, e.g. in a
+/// recursive loop, are merged into a single call.
+///
+/// The profiler is a handle to shared data, so a clone of the profiler can be kept by the host
+/// to retrieve a [ProfileReport] after the script has been run.
+#[derive(Clone, Default)]
+pub struct Profiler(Arc<Mutex<ProfileData>>);
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clears any data that has been recorded so far
+    pub fn clear(&self) {
+        let mut data = self.0.lock();
+        // Tokens for calls that are in progress remain valid
+        *data = ProfileData {
+            next_token: data.next_token,
+            ..Default::default()
+        };
+    }
+
+    /// Returns a report of the data that has been recorded so far
+    pub fn report(&self) -> ProfileReport {
+        self.0.lock().report()
+    }
+
+    // Called by the VM when a frame is pushed for a function call or module,
+    // returns a token that's passed to exit when the frame is popped.
+    pub(crate) fn enter(&self, chunk: &Arc<Chunk>, ip: usize) -> u64 {
+        self.0.lock().enter(chunk, ip)
+    }
+
+    pub(crate) fn exit(&self, token: u64) {
+        self.0.lock().exit(token)
+    }
+
+    // Called when the frame that was just pushed for a call replaces its caller's frame,
+    // returns the token that should be used for the frame.
+    pub(crate) fn tail_call(&self, token: u64, caller_token: u64) -> u64 {
+        self.0.lock().tail_call(token, caller_token)
+    }
+
+    // Called when a VM's frames are suspended, e.g. when a generator yields
+    pub(crate) fn suspend(&self, tokens: &[u64]) {
+        self.0.lock().suspend(tokens)
+    }
+
+    // Called when a VM's suspended frames are resumed
+    pub(crate) fn resume(&self, tokens: &[u64]) {
+        self.0.lock().resume(tokens)
+    }
+
+    pub(crate) fn record_instruction(&self, chunk: &Arc<Chunk>, ip: usize) {
+        self.0.lock().record_instruction(chunk, ip)
+    }
+}
+
+/// The time spent in a Koto function, see [ProfileReport]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionProfile {
+    /// The function's name, made up of its script's path and the line where it's defined
+    ///
+    /// Named functions are prefixed with their name, e.g. `foo (script.koto:12)`.
+    /// Module bodies are named with the module's path.
+    pub name: String,
+    /// The number of times that the function was called
+    pub call_count: u64,
+    /// The time spent in the function, including time spent in functions that it called
+    ///
+    /// Time spent in recursive calls is only counted once, by the outermost call.
+    pub inclusive_time: Duration,
+    /// The time spent in the function, excluding time spent in functions that it called
+    pub exclusive_time: Duration,
+}
+
+/// The number of instructions that were executed for a line in a script, see [ProfileReport]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineProfile {
+    /// The path of the line's script
+    pub path: Option<PathBuf>,
+    /// The line number, starting from 1
+    pub line: u32,
+    pub instruction_count: u64,
+}
+
+/// A completed function call, used to produce a trace of the profiled script
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEvent {
+    /// The index of the called function in [ProfileReport::functions]
+    pub function: usize,
+    /// An index identifying the thread that the call was made on
+    pub thread: usize,
+    /// The time when the call started, relative to when profiling started
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+/// A report of the data that was recorded by a [Profiler]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProfileReport {
+    /// The functions that were called, sorted by exclusive time
+    pub functions: Vec<FunctionProfile>,
+    /// The lines that were executed, sorted by instruction count
+    pub lines: Vec<LineProfile>,
+    /// The exclusive time spent in each call stack
+    ///
+    /// The call stacks are made up of indices in [ProfileReport::functions], starting with the
+    /// outermost function.
+    pub stacks: Vec<(Vec<usize>, Duration)>,
+    /// The completed function calls, in the order that they finished
+    pub events: Vec<TraceEvent>,
+}
+
+impl ProfileReport {
+    /// Returns a human-readable summary of the report
+    pub fn to_text(&self) -> String {
+        let mut result = String::new();
+
+        result.push_str("Functions:\n");
+        let _ = writeln!(
+            result,
+            "  {:>10}  {:>14}  {:>14}  function",
+            "calls", "inclusive (ms)", "exclusive (ms)"
+        );
+        for function in self.functions.iter() {
+            let _ = writeln!(
+                result,
+                "  {:>10}  {:>14.3}  {:>14.3}  {}",
+                function.call_count,
+                as_millis(function.inclusive_time),
+                as_millis(function.exclusive_time),
+                function.name
+            );
+        }
+
+        result.push_str("\nLines:\n");
+        let _ = writeln!(result, "  {:>12}  line", "instructions");
+        for line in self.lines.iter() {
+            let _ = writeln!(
+                result,
+                "  {:>12}  {}",
+                line.instruction_count,
+                location_name(&line.path, Some(line.line))
+            );
+        }
+
+        result
+    }
+
+    /// Returns the report's call stacks in the 'folded stacks' format used by flame graph tools
+    ///
+    /// Each line contains a call stack with function names separated by `;`, followed by the
+    /// exclusive time spent in the call stack in microseconds.
+    pub fn to_folded_stacks(&self) -> String {
+        let mut result = String::new();
+
+        for (stack, time) in self.stacks.iter() {
+            let names = stack
+                .iter()
+                .map(|function| self.functions[*function].name.replace(';', ":"))
+                .collect::<Vec<_>>();
+            let _ = writeln!(result, "{} {}", names.join(";"), time.as_micros());
+        }
+
+        result
+    }
+
+    /// Returns the report's function calls in the Chrome trace event format
+    ///
+    /// The output is JSON that can be loaded by `chrome://tracing` and similar tools.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut result = String::from("{\"traceEvents\":[");
+
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                result.push(',');
+            }
+            let _ = write!(
+                result,
+                "\n{{\"name\":\"{}\",\"cat\":\"koto\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\
+                 \"pid\":1,\"tid\":{}}}",
+                escape_json(&self.functions[event.function].name),
+                as_micros(event.start),
+                as_micros(event.duration),
+                event.thread + 1
+            );
+        }
+
+        result.push_str("\n]}\n");
+        result
+    }
+}
+
+// A call that's in progress
+struct ActiveCall {
+    token: u64,
+    function: usize,
+    start: Instant,
+    // The time spent in calls made by this call
+    child_time: Duration,
+    // The number of calls that have been merged into this call, see ProfileData::tail_call
+    call_count: u64,
+    // True when the call was made as a tail call, in which case its parent is the calling
+    // function, which exits along with this call.
+    tail_call: bool,
+}
+
+struct ChunkProfile {
+    // The chunk is kept alive so that its address can be used as an id
+    chunk: Arc<Chunk>,
+    // The number of times each instruction has been executed, indexed by ip
+    instruction_counts: Vec<u64>,
+}
+
+struct ProfileData {
+    start: Instant,
+    next_token: u64,
+    functions: Vec<FunctionProfile>,
+    // Function indices, keyed by (chunk address, function ip)
+    function_ids: HashMap<(usize, usize), usize>,
+    chunks: HashMap<usize, ChunkProfile>,
+    // The calls that are in progress on each thread
+    call_stacks: HashMap<ThreadId, Vec<ActiveCall>>,
+    // The number of calls that are in progress for each function on each thread,
+    // used to find the outermost call of recursive functions.
+    recursion_depths: HashMap<(ThreadId, usize), usize>,
+    // Calls that have been suspended, along with the time that they were suspended
+    suspended: HashMap<u64, (ActiveCall, Instant)>,
+    threads: Vec<ThreadId>,
+    stacks: HashMap<Vec<usize>, Duration>,
+    events: Vec<TraceEvent>,
+}
+
+impl Default for ProfileData {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            next_token: 0,
+            functions: Vec::new(),
+            function_ids: HashMap::new(),
+            chunks: HashMap::new(),
+            call_stacks: HashMap::new(),
+            recursion_depths: HashMap::new(),
+            suspended: HashMap::new(),
+            threads: Vec::new(),
+            stacks: HashMap::new(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl ProfileData {
+    fn enter(&mut self, chunk: &Arc<Chunk>, ip: usize) -> u64 {
+        let function = self.function_id(chunk, ip);
+        let token = self.next_token;
+        self.next_token += 1;
+
+        let thread_id = thread::current().id();
+        *self
+            .recursion_depths
+            .entry((thread_id, function))
+            .or_default() += 1;
+        self.call_stacks
+            .entry(thread_id)
+            .or_default()
+            .push(ActiveCall {
+                token,
+                function,
+                start: Instant::now(),
+                child_time: Duration::default(),
+                call_count: 1,
+                tail_call: false,
+            });
+
+        token
+    }
+
+    fn exit(&mut self, token: u64) {
+        let now = Instant::now();
+        let thread_id = thread::current().id();
+
+        let call_stack = match self.call_stacks.get_mut(&thread_id) {
+            Some(call_stack) => call_stack,
+            None => return,
+        };
+        let position = match call_stack.iter().rposition(|call| call.token == token) {
+            Some(position) => position,
+            None => return,
+        };
+        // Any calls above the exiting call have been abandoned, e.g. by a VM that was stopped
+        for abandoned in call_stack.drain(position + 1..) {
+            decrement_depth(&mut self.recursion_depths, thread_id, abandoned.function);
+        }
+
+        let mut call = call_stack.pop().unwrap();
+        loop {
+            let tail_call = call.tail_call;
+            self.finish_call(thread_id, call, now);
+
+            // Functions that made tail calls exit along with the functions that they called
+            if !tail_call {
+                break;
+            }
+            match self.pop_call(thread_id) {
+                Some(caller) => call = caller,
+                None => break,
+            }
+        }
+    }
+
+    fn tail_call(&mut self, token: u64, caller_token: u64) -> u64 {
+        let now = Instant::now();
+        let thread_id = thread::current().id();
+
+        let call_stack = match self.call_stacks.get_mut(&thread_id) {
+            Some(call_stack) => call_stack,
+            None => return token,
+        };
+        let position = match call_stack.iter().rposition(|call| call.token == token) {
+            Some(position) if position > 0 && call_stack[position - 1].token == caller_token => {
+                position
+            }
+            _ => return token,
+        };
+        for abandoned in call_stack.drain(position + 1..) {
+            decrement_depth(&mut self.recursion_depths, thread_id, abandoned.function);
+        }
+
+        // Look for a call to the same function in the chain of tail calls that led to this call,
+        // so that repeated tail calls don't cause the call stack to grow.
+        let function = call_stack[position].function;
+        let mut caller = position - 1;
+        let repeated_call = loop {
+            if call_stack[caller].function == function {
+                break Some(caller);
+            } else if call_stack[caller].tail_call && caller > 0 {
+                caller -= 1;
+            } else {
+                break None;
+            }
+        };
+
+        match repeated_call {
+            Some(repeated_call) => {
+                // The new call is merged into the earlier call, and the calls that were made
+                // in between have finished.
+                let call = call_stack.pop().unwrap();
+                decrement_depth(&mut self.recursion_depths, thread_id, call.function);
+                for _ in repeated_call + 1..position {
+                    if let Some(finished) = self.pop_call(thread_id) {
+                        self.finish_call(thread_id, finished, now);
+                    }
+                }
+
+                let call = &mut self.call_stacks.get_mut(&thread_id).unwrap()[repeated_call];
+                call.call_count += 1;
+                call.token
+            }
+            None => {
+                call_stack[position].tail_call = true;
+                token
+            }
+        }
+    }
+
+    fn pop_call(&mut self, thread_id: ThreadId) -> Option<ActiveCall> {
+        self.call_stacks
+            .get_mut(&thread_id)
+            .and_then(|call_stack| call_stack.pop())
+    }
+
+    // Records the timing of a call that has been removed from the thread's call stack
+    fn finish_call(&mut self, thread_id: ThreadId, call: ActiveCall, now: Instant) {
+        let call_stack = self.call_stacks.entry(thread_id).or_default();
+
+        let elapsed = now - call.start;
+        let exclusive_time = elapsed.checked_sub(call.child_time).unwrap_or_default();
+        if let Some(parent) = call_stack.last_mut() {
+            parent.child_time += elapsed;
+        }
+
+        let stack = call_stack
+            .iter()
+            .map(|call| call.function)
+            .chain(std::iter::once(call.function))
+            .collect();
+        *self.stacks.entry(stack).or_default() += exclusive_time;
+
+        let outermost_call =
+            decrement_depth(&mut self.recursion_depths, thread_id, call.function) == 0;
+        let function = &mut self.functions[call.function];
+        function.call_count += call.call_count;
+        // The time spent in nested recursive calls is already included in the outermost call
+        if outermost_call {
+            function.inclusive_time += elapsed;
+        }
+        function.exclusive_time += exclusive_time;
+
+        let thread = match self.threads.iter().position(|id| *id == thread_id) {
+            Some(index) => index,
+            None => {
+                self.threads.push(thread_id);
+                self.threads.len() - 1
+            }
+        };
+        self.events.push(TraceEvent {
+            function: call.function,
+            thread,
+            start: call.start - self.start,
+            duration: elapsed,
+        });
+    }
+
+    fn suspend(&mut self, tokens: &[u64]) {
+        let now = Instant::now();
+        let thread_id = thread::current().id();
+
+        if let Some(call_stack) = self.call_stacks.get_mut(&thread_id) {
+            let mut i = 0;
+            while i < call_stack.len() {
+                if tokens.contains(&call_stack[i].token) {
+                    let call = call_stack.remove(i);
+                    decrement_depth(&mut self.recursion_depths, thread_id, call.function);
+                    self.suspended.insert(call.token, (call, now));
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    fn resume(&mut self, tokens: &[u64]) {
+        let now = Instant::now();
+        let thread_id = thread::current().id();
+        let call_stack = self.call_stacks.entry(thread_id).or_default();
+
+        for token in tokens {
+            if let Some((mut call, suspended_at)) = self.suspended.remove(token) {
+                // The time spent while suspended isn't included in the call's timing
+                call.start += now - suspended_at;
+                *self
+                    .recursion_depths
+                    .entry((thread_id, call.function))
+                    .or_default() += 1;
+                call_stack.push(call);
+            }
+        }
+    }
+
+    fn record_instruction(&mut self, chunk: &Arc<Chunk>, ip: usize) {
+        let chunk_profile = self
+            .chunks
+            .entry(Arc::as_ptr(chunk) as usize)
+            .or_insert_with(|| ChunkProfile {
+                chunk: chunk.clone(),
+                instruction_counts: vec![0; chunk.bytes.len()],
+            });
+
+        if let Some(count) = chunk_profile.instruction_counts.get_mut(ip) {
+            *count += 1;
+        }
+    }
+
+    fn function_id(&mut self, chunk: &Arc<Chunk>, ip: usize) -> usize {
+        let key = (Arc::as_ptr(chunk) as usize, ip);
+
+        if let Some(id) = self.function_ids.get(&key) {
+            return *id;
+        }
+
+        // Make sure that the chunk stays alive so that its address isn't reused
+        self.chunks.entry(key.0).or_insert_with(|| ChunkProfile {
+            chunk: chunk.clone(),
+            instruction_counts: vec![0; chunk.bytes.len()],
+        });
+
+        let name = if ip == 0 {
+            location_name(&chunk.source_path, None)
+        } else {
+            let line = chunk
+                .debug_info
+                .get_source_span(ip)
+                .map(|span| span.start.line);
+            let location = location_name(&chunk.source_path, line);
+
+            // Named functions are shown with their name, followed by their location
+            match chunk
+                .debug_info
+                .get_frame_info(ip)
+                .and_then(|frame_info| frame_info.function_name)
+            {
+                Some(name) => format!("{} ({})", chunk.constants.get_str(name), location),
+                None => location,
+            }
+        };
+
+        let id = self.functions.len();
+        self.functions.push(FunctionProfile {
+            name,
+            call_count: 0,
+            inclusive_time: Duration::default(),
+            exclusive_time: Duration::default(),
+        });
+        self.function_ids.insert(key, id);
+        id
+    }
+
+    fn report(&self) -> ProfileReport {
+        // Sort the functions by exclusive time, and then remap the function indices
+        let mut order = (0..self.functions.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| {
+            self.functions[*b]
+                .exclusive_time
+                .cmp(&self.functions[*a].exclusive_time)
+        });
+        let mut remapped = vec![0; order.len()];
+        for (new_index, old_index) in order.iter().enumerate() {
+            remapped[*old_index] = new_index;
+        }
+
+        let functions = order
+            .iter()
+            .map(|index| self.functions[*index].clone())
+            .collect();
+
+        let mut stacks = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| {
+                (
+                    stack.iter().map(|function| remapped[*function]).collect(),
+                    *time,
+                )
+            })
+            .collect::<Vec<(Vec<usize>, Duration)>>();
+        stacks.sort();
+
+        let events = self
+            .events
+            .iter()
+            .map(|event| TraceEvent {
+                function: remapped[event.function],
+                ..event.clone()
+            })
+            .collect();
+
+        let mut line_counts: HashMap<(Option<PathBuf>, u32), u64> = HashMap::new();
+        for chunk_profile in self.chunks.values() {
+            let chunk = &chunk_profile.chunk;
+            for (ip, count) in chunk_profile.instruction_counts.iter().enumerate() {
+                if *count == 0 {
+                    continue;
+                }
+                if let Some(span) = chunk.debug_info.get_source_span(ip) {
+                    *line_counts
+                        .entry((chunk.source_path.clone(), span.start.line))
+                        .or_default() += count;
+                }
+            }
+        }
+        let mut lines = line_counts
+            .into_iter()
+            .map(|((path, line), instruction_count)| LineProfile {
+                path,
+                line,
+                instruction_count,
+            })
+            .collect::<Vec<_>>();
+        lines.sort_by(|a, b| {
+            b.instruction_count
+                .cmp(&a.instruction_count)
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.line.cmp(&b.line))
+        });
+
+        ProfileReport {
+            functions,
+            lines,
+            stacks,
+            events,
+        }
+    }
+}
+
+// Decrements the number of calls in progress for a function, returning the remaining count
+fn decrement_depth(
+    depths: &mut HashMap<(ThreadId, usize), usize>,
+    thread_id: ThreadId,
+    function: usize,
+) -> usize {
+    match depths.get_mut(&(thread_id, function)) {
+        Some(depth) if *depth > 1 => {
+            *depth -= 1;
+            *depth
+        }
+        _ => {
+            depths.remove(&(thread_id, function));
+            0
+        }
+    }
+}
+
+fn location_name(path: &Option<PathBuf>, line: Option<u32>) -> String {
+    let path = match path {
+        Some(path) => path.display().to_string(),
+        None => "<script>".to_string(),
+    };
+    match line {
+        Some(line) => format!("{}:{}", path, line),
+        None => path,
+    }
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000.0
+}
+
+fn as_micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
+
+fn escape_json(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(result, "\\u{:04x}", c as u32);
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
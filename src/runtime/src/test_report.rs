@@ -0,0 +1,234 @@
+use {
+    crate::RuntimeError,
+    std::{fmt::Write, str::FromStr, time::Duration},
+};
+
+/// The result of running a single test
+///
+/// See [Vm::run_tests_with_report](crate::Vm::run_tests_with_report).
+#[derive(Debug)]
+pub struct TestResult {
+    /// The test's name, without the `test_` prefix
+    pub name: String,
+    /// The time taken to run the test, including its `pre_test` and `post_test` functions
+    pub duration: Duration,
+    /// The error that caused the test to fail, or None if the test passed
+    pub error: Option<RuntimeError>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The results of running a group of tests
+#[derive(Debug, Default)]
+pub struct TestReport {
+    /// The name of the group of tests, e.g. the script that contained them
+    pub name: String,
+    pub results: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            results: Vec::new(),
+        }
+    }
+
+    pub fn passed_count(&self) -> usize {
+        self.results.iter().filter(|result| result.passed()).count()
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.results.len() - self.passed_count()
+    }
+
+    /// Returns true if all of the tests passed
+    pub fn success(&self) -> bool {
+        self.results.iter().all(|result| result.passed())
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.results.iter().map(|result| result.duration).sum()
+    }
+}
+
+/// The output formats supported by [format_test_reports]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestReportFormat {
+    /// Human readable text
+    Text,
+    /// The [Test Anything Protocol](https://testanything.org)
+    Tap,
+    /// JUnit XML, as supported by most CI systems
+    JUnit,
+}
+
+impl FromStr for TestReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "tap" => Ok(Self::Tap),
+            "junit" => Ok(Self::JUnit),
+            _ => Err(format!(
+                "Unknown test report format '{}', expected 'text', 'tap', or 'junit'",
+                s
+            )),
+        }
+    }
+}
+
+/// Renders a group of test reports in the given format
+pub fn format_test_reports(reports: &[TestReport], format: TestReportFormat) -> String {
+    let mut output = String::new();
+
+    // Writing to a String can't fail, so the results are ignored
+    let _ = match format {
+        TestReportFormat::Text => write_text(&mut output, reports),
+        TestReportFormat::Tap => write_tap(&mut output, reports),
+        TestReportFormat::JUnit => write_junit(&mut output, reports),
+    };
+
+    output
+}
+
+fn write_text(output: &mut String, reports: &[TestReport]) -> std::fmt::Result {
+    for report in reports.iter() {
+        writeln!(output, "{}", report.name)?;
+        for result in report.results.iter() {
+            let outcome = if result.passed() { "ok" } else { "FAILED" };
+            writeln!(
+                output,
+                "  {} ... {} ({:.2}ms)",
+                result.name,
+                outcome,
+                result.duration.as_secs_f64() * 1000.0
+            )?;
+        }
+    }
+
+    let failures = reports.iter().flat_map(|report| {
+        report
+            .results
+            .iter()
+            .filter_map(move |result| result.error.as_ref().map(|error| (report, result, error)))
+    });
+    for (i, (report, result, error)) in failures.enumerate() {
+        if i == 0 {
+            writeln!(output, "\nFailures:")?;
+        }
+        writeln!(output, "\n{} - {}\n{}", report.name, result.name, error)?;
+    }
+
+    let passed: usize = reports.iter().map(TestReport::passed_count).sum();
+    let failed: usize = reports.iter().map(TestReport::failed_count).sum();
+    writeln!(
+        output,
+        "\nTest result: {}. {} passed, {} failed",
+        if failed == 0 { "ok" } else { "FAILED" },
+        passed,
+        failed
+    )
+}
+
+fn write_tap(output: &mut String, reports: &[TestReport]) -> std::fmt::Result {
+    let test_count: usize = reports.iter().map(|report| report.results.len()).sum();
+
+    writeln!(output, "TAP version 13")?;
+    writeln!(output, "1..{}", test_count)?;
+
+    let results = reports
+        .iter()
+        .flat_map(|report| report.results.iter().map(move |result| (report, result)));
+    for (i, (report, result)) in results.enumerate() {
+        let outcome = if result.passed() { "ok" } else { "not ok" };
+        writeln!(
+            output,
+            "{} {} - {}: {}",
+            outcome,
+            i + 1,
+            report.name,
+            result.name
+        )?;
+
+        if let Some(error) = &result.error {
+            for line in error.to_string().lines() {
+                writeln!(output, "# {}", line)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_junit(output: &mut String, reports: &[TestReport]) -> std::fmt::Result {
+    let test_count: usize = reports.iter().map(|report| report.results.len()).sum();
+    let failed: usize = reports.iter().map(TestReport::failed_count).sum();
+
+    writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        output,
+        r#"<testsuites tests="{}" failures="{}">"#,
+        test_count, failed
+    )?;
+
+    for report in reports.iter() {
+        writeln!(
+            output,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" time="{:.6}">"#,
+            escape_xml(&report.name),
+            report.results.len(),
+            report.failed_count(),
+            report.duration().as_secs_f64()
+        )?;
+
+        for result in report.results.iter() {
+            let attributes = format!(
+                r#"name="{}" classname="{}" time="{:.6}""#,
+                escape_xml(&result.name),
+                escape_xml(&report.name),
+                result.duration.as_secs_f64()
+            );
+
+            match &result.error {
+                Some(error) => {
+                    writeln!(output, "    <testcase {}>", attributes)?;
+                    writeln!(
+                        output,
+                        r#"      <failure message="{}">{}</failure>"#,
+                        escape_xml(&format!("{:#}", error)),
+                        escape_xml(&error.to_string())
+                    )?;
+                    writeln!(output, "    </testcase>")?;
+                }
+                None => writeln!(output, "    <testcase {}/>", attributes)?,
+            }
+        }
+
+        writeln!(output, "  </testsuite>")?;
+    }
+
+    writeln!(output, "</testsuites>")
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&apos;"),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
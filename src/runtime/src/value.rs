@@ -7,14 +7,16 @@ use {
     parking_lot::RwLock,
     std::{
         cmp::Ordering,
+        convert::TryInto,
         fmt,
         hash::{Hash, Hasher},
         sync::Arc,
     },
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub enum Value {
+    #[default]
     Empty,
     Bool(bool),
     Number(ValueNumber),
@@ -60,14 +62,14 @@ pub enum ValueRef<'a> {
 
 impl Value {
     #[inline]
-    pub fn as_ref(&self) -> ValueRef {
+    pub fn as_ref(&self) -> ValueRef<'_> {
         match self {
             Value::Empty => ValueRef::Empty,
             Value::Bool(b) => ValueRef::Bool(b),
             Value::Number(n) => ValueRef::Number(n),
             Value::Num2(n) => ValueRef::Num2(n),
             Value::Num4(n) => ValueRef::Num4(n),
-            Value::Str(s) => ValueRef::Str(&s),
+            Value::Str(s) => ValueRef::Str(s),
             Value::List(l) => ValueRef::List(l),
             Value::Map(m) => ValueRef::Map(m),
             Value::Tuple(m) => ValueRef::Tuple(m),
@@ -84,12 +86,6 @@ impl Value {
     }
 }
 
-impl Default for Value {
-    fn default() -> Self {
-        Value::Empty
-    }
-}
-
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Value::*;
@@ -117,7 +113,7 @@ impl fmt::Display for Value {
             ExternalValue(ref value) => f.write_str(&value.read().to_string()),
             IndexRange(self::IndexRange { .. }) => f.write_str("IndexRange"),
             TemporaryTuple(RegisterSlice { start, count }) => {
-                write!(f, "TemporaryTuple [{}..{}]", start, start + count)
+                write!(f, "TemporaryTuple [{}..{}]", start, start + *count as u16)
             }
             ExternalDataId => write!(f, "External Data"),
         }
@@ -172,6 +168,7 @@ impl<'a> PartialEq for ValueRef<'a> {
 
 impl Eq for Value {}
 
+#[allow(clippy::non_canonical_partial_ord_impl)]
 impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         use Value::*;
@@ -184,7 +181,7 @@ impl PartialOrd for Value {
             (Num2(a), Num2(b)) => a.partial_cmp(b),
             (Num4(a), Num4(b)) => a.partial_cmp(b),
             (Str(a), Str(b)) => a.partial_cmp(b),
-            (a, b) => panic!(format!("partial_cmp unsupported for {} and {}", a, b)),
+            (a, b) => panic!("partial_cmp unsupported for {} and {}", a, b),
         }
     }
 }
@@ -199,7 +196,7 @@ impl Ord for Value {
             (_, Empty) => Ordering::Greater,
             (Number(a), Number(b)) => a.cmp(b),
             (Str(a), Str(b)) => a.cmp(b),
-            (a, b) => panic!(format!("cmp unsupported for {} and {}", a, b)),
+            (a, b) => panic!("cmp unsupported for {} and {}", a, b),
         }
     }
 }
@@ -261,9 +258,32 @@ pub struct RuntimeFunction {
     pub chunk: Arc<Chunk>,
     pub ip: usize,
     pub arg_count: u8,
+    pub optional_arg_count: u8,
     pub instance_function: bool,
     pub variadic: bool,
     pub captures: Option<ValueList>,
+    /// The position in the chunk of the function's arg names, see [Op::Function]
+    ///
+    /// [Op::Function]: koto_bytecode::Op::Function
+    pub arg_names_ip: usize,
+}
+
+impl RuntimeFunction {
+    /// Returns the name of the arg in the given register of the function's frame
+    ///
+    /// None is returned for args without a name, e.g. wildcards or unpacked containers.
+    pub fn arg_name(&self, arg_register: u16) -> Option<&str> {
+        if arg_register >= self.arg_count as u16 {
+            return None;
+        }
+
+        let name_ip = self.arg_names_ip + arg_register as usize * 4;
+        let name_bytes = self.chunk.bytes.get(name_ip..name_ip + 4)?;
+        match u32::from_le_bytes(name_bytes.try_into().ok()?) {
+            u32::MAX => None,
+            constant => Some(self.chunk.constants.get_str(constant)),
+        }
+    }
 }
 
 impl PartialEq for RuntimeFunction {
@@ -294,7 +314,7 @@ pub struct IndexRange {
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct RegisterSlice {
-    pub start: u8,
+    pub start: u16,
     pub count: u8,
 }
 
@@ -303,19 +323,11 @@ pub fn deep_copy_value(value: &Value) -> Value {
 
     match value {
         List(l) => {
-            let result = l
-                .data()
-                .iter()
-                .map(|v| deep_copy_value(v))
-                .collect::<ValueVec>();
+            let result = l.data().iter().map(deep_copy_value).collect::<ValueVec>();
             List(ValueList::with_data(result))
         }
         Tuple(t) => {
-            let result = t
-                .data()
-                .iter()
-                .map(|v| deep_copy_value(v))
-                .collect::<Vec<_>>();
+            let result = t.data().iter().map(deep_copy_value).collect::<Vec<_>>();
             Tuple(result.into())
         }
         Map(m) => {
@@ -0,0 +1,532 @@
+use {
+    crate::{
+        make_external_error, type_as_string, Num2, Num4, RuntimeError, RuntimeResult, Value,
+        ValueHashMap, ValueList, ValueMap, ValueNumber, ValueString, ValueTuple, ValueVec,
+    },
+    std::{collections::HashMap, convert::TryFrom, fmt},
+};
+
+/// Conversion from a Koto [Value] into a Rust type
+///
+/// Implementations are provided for the core value types, along with Rust primitives,
+/// `String`, `Vec<T>`, `HashMap<String, T>`, `Option<T>`, and tuples of up to four elements.
+///
+/// See [ValueMap::add_typed_fn].
+pub trait FromValue: Sized {
+    /// The name of the expected value type, used in error messages
+    fn type_name() -> String;
+
+    /// Converts the value, returning None if the value has an incompatible type
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+/// Conversion from a Rust type into a Koto [Value]
+///
+/// See [FromValue].
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// Conversion of a typed function's return value into a [RuntimeResult]
+///
+/// This is implemented for all types that implement [IntoValue], and for `Result<T, E>` where
+/// `T` implements [IntoValue], with errors being converted into runtime errors.
+pub trait IntoRuntimeResult {
+    fn into_runtime_result(self) -> RuntimeResult;
+}
+
+impl<T: IntoValue> IntoRuntimeResult for T {
+    fn into_runtime_result(self) -> RuntimeResult {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue, E: fmt::Display> IntoRuntimeResult for Result<T, E> {
+    fn into_runtime_result(self) -> RuntimeResult {
+        match self {
+            Ok(value) => Ok(value.into_value()),
+            Err(error) => Err(make_external_error!(error.to_string())),
+        }
+    }
+}
+
+impl FromValue for Value {
+    fn type_name() -> String {
+        "Value".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Empty
+    }
+}
+
+impl FromValue for bool {
+    fn type_name() -> String {
+        "Bool".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Bool(self)
+    }
+}
+
+impl FromValue for ValueNumber {
+    fn type_name() -> String {
+        "Number".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueNumber {
+    fn into_value(self) -> Value {
+        Value::Number(self)
+    }
+}
+
+macro_rules! float_conversions {
+    ($($type:ident),+) => {
+        $(
+            impl FromValue for $type {
+                fn type_name() -> String {
+                    "Number".to_string()
+                }
+
+                fn from_value(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::Number(n) => Some(n.into()),
+                        _ => None,
+                    }
+                }
+            }
+
+            impl IntoValue for $type {
+                fn into_value(self) -> Value {
+                    Value::Number(self.into())
+                }
+            }
+        )+
+    };
+}
+
+float_conversions!(f32, f64);
+
+// Integers are only converted from values that are integral and in the type's range,
+// with out of range integers being converted into floats.
+macro_rules! int_conversions {
+    ($($type:ident),+) => {
+        $(
+            impl FromValue for $type {
+                fn type_name() -> String {
+                    format!("Number ({})", stringify!($type))
+                }
+
+                fn from_value(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::Number(ValueNumber::I64(n)) => $type::try_from(*n).ok(),
+                        Value::Number(ValueNumber::F64(n)) => {
+                            // i64::MAX as f64 rounds up to 2^63, which is out of range
+                            if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 {
+                                $type::try_from(*n as i64).ok()
+                            } else {
+                                None
+                            }
+                        }
+                        _ => None,
+                    }
+                }
+            }
+
+            impl IntoValue for $type {
+                fn into_value(self) -> Value {
+                    match i64::try_from(self) {
+                        Ok(n) => Value::Number(n.into()),
+                        Err(_) => Value::Number((self as f64).into()),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+int_conversions!(i32, u32, i64, u64, isize, usize);
+
+impl FromValue for Num2 {
+    fn type_name() -> String {
+        "Num2".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Num2(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for Num2 {
+    fn into_value(self) -> Value {
+        Value::Num2(self)
+    }
+}
+
+impl FromValue for Num4 {
+    fn type_name() -> String {
+        "Num4".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Num4(n) => Some(*n),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for Num4 {
+    fn into_value(self) -> Value {
+        Value::Num4(self)
+    }
+}
+
+impl FromValue for ValueString {
+    fn type_name() -> String {
+        "String".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueString {
+    fn into_value(self) -> Value {
+        Value::Str(self)
+    }
+}
+
+impl FromValue for String {
+    fn type_name() -> String {
+        "String".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Str(s) => Some(s.as_str().to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::Str(self.into())
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::Str(self.into())
+    }
+}
+
+impl FromValue for ValueList {
+    fn type_name() -> String {
+        "List".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(l) => Some(l.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueList {
+    fn into_value(self) -> Value {
+        Value::List(self)
+    }
+}
+
+impl FromValue for ValueTuple {
+    fn type_name() -> String {
+        "Tuple".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Tuple(t) => Some(t.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueTuple {
+    fn into_value(self) -> Value {
+        Value::Tuple(self)
+    }
+}
+
+impl FromValue for ValueMap {
+    fn type_name() -> String {
+        "Map".to_string()
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Map(m) => Some(m.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoValue for ValueMap {
+    fn into_value(self) -> Value {
+        Value::Map(self)
+    }
+}
+
+// Lists and tuples can both be converted into Vecs
+impl<T: FromValue> FromValue for Vec<T> {
+    fn type_name() -> String {
+        format!("List of {}", T::type_name())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(l) => l.data().iter().map(T::from_value).collect(),
+            Value::Tuple(t) => t.data().iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::List(ValueList::with_data(
+            self.into_iter()
+                .map(IntoValue::into_value)
+                .collect::<ValueVec>(),
+        ))
+    }
+}
+
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn type_name() -> String {
+        format!("Map of {}", T::type_name())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Map(m) => m
+                .data()
+                .iter()
+                .map(|(key, value)| match key {
+                    Value::Str(key) => Some((key.as_str().to_string(), T::from_value(value)?)),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+    fn into_value(self) -> Value {
+        Value::Map(ValueMap::with_data(
+            self.into_iter()
+                .map(|(key, value)| (key.into_value(), value.into_value()))
+                .collect::<ValueHashMap>(),
+        ))
+    }
+}
+
+// Empty values are converted into None
+impl<T: FromValue> FromValue for Option<T> {
+    fn type_name() -> String {
+        format!("{} or Empty", T::type_name())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Empty => Some(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(value) => value.into_value(),
+            None => Value::Empty,
+        }
+    }
+}
+
+// Tuples can be converted from Koto tuples or lists with a matching number of elements
+macro_rules! tuple_conversions {
+    ($count:expr; $($type:ident $index:tt),+) => {
+        impl<$($type: FromValue),+> FromValue for ($($type,)+) {
+            fn type_name() -> String {
+                let types: &[String] = &[$($type::type_name()),+];
+                format!("Tuple of ({})", types.join(", "))
+            }
+
+            fn from_value(value: &Value) -> Option<Self> {
+                let convert = |values: &[Value]| {
+                    if values.len() == $count {
+                        Some(($($type::from_value(&values[$index])?,)+))
+                    } else {
+                        None
+                    }
+                };
+
+                match value {
+                    Value::Tuple(t) => convert(t.data()),
+                    Value::List(l) => convert(&l.data()),
+                    _ => None,
+                }
+            }
+        }
+
+        impl<$($type: IntoValue),+> IntoValue for ($($type,)+) {
+            fn into_value(self) -> Value {
+                Value::Tuple(vec![$(self.$index.into_value()),+].into())
+            }
+        }
+    };
+}
+
+tuple_conversions!(1; A 0);
+tuple_conversions!(2; A 0, B 1);
+tuple_conversions!(3; A 0, B 1, C 2);
+tuple_conversions!(4; A 0, B 1, C 2, D 3);
+
+/// Conversion of a function's arguments into a tuple of Rust values
+///
+/// This is implemented for tuples of up to eight types that implement [FromValue].
+/// Missing arguments are treated as Empty, so that trailing `Option` arguments can be omitted.
+pub trait FromArgs: Sized {
+    /// Converts the arguments
+    ///
+    /// `function_name` is used in the error messages that are produced when the arguments
+    /// don't match the expected types.
+    fn from_args(function_name: &str, args: &[Value]) -> Result<Self, RuntimeError>;
+}
+
+/// A Rust function with arguments and a return value that can be converted to and from Koto values
+///
+/// This is implemented for functions and closures with up to eight arguments that implement
+/// [FromValue], returning a type that implements [IntoRuntimeResult].
+///
+/// See [ValueMap::add_typed_fn].
+pub trait TypedFunction<Args>: Send + Sync + 'static {
+    /// Calls the function with the given arguments
+    ///
+    /// `function_name` is used in the error messages that are produced when the arguments
+    /// don't match the function's signature.
+    fn call_typed(&self, function_name: &str, args: &[Value]) -> RuntimeResult;
+}
+
+macro_rules! typed_function {
+    ($count:expr; $($type:ident $index:tt),*) => {
+        impl<$($type: FromValue),*> FromArgs for ($($type,)*) {
+            #[allow(unused_variables)]
+            fn from_args(function_name: &str, args: &[Value]) -> Result<Self, RuntimeError> {
+                if args.len() > $count {
+                    return Err(arg_count_error(function_name, $count, args.len()));
+                }
+
+                Ok(($(typed_arg::<$type>(function_name, args, $index, $count)?,)*))
+            }
+        }
+
+        impl<F, R, $($type),*> TypedFunction<($($type,)*)> for F
+        where
+            F: Fn($($type),*) -> R + Send + Sync + 'static,
+            R: IntoRuntimeResult,
+            $($type: FromValue,)*
+        {
+            #[allow(unused_variables)]
+            fn call_typed(&self, function_name: &str, args: &[Value]) -> RuntimeResult {
+                let args = <($($type,)*)>::from_args(function_name, args)?;
+                self($(args.$index),*).into_runtime_result()
+            }
+        }
+    };
+}
+
+typed_function!(0;);
+typed_function!(1; A 0);
+typed_function!(2; A 0, B 1);
+typed_function!(3; A 0, B 1, C 2);
+typed_function!(4; A 0, B 1, C 2, D 3);
+typed_function!(5; A 0, B 1, C 2, D 3, E 4);
+typed_function!(6; A 0, B 1, C 2, D 3, E 4, G 5);
+typed_function!(7; A 0, B 1, C 2, D 3, E 4, G 5, H 6);
+typed_function!(8; A 0, B 1, C 2, D 3, E 4, G 5, H 6, I 7);
+
+// Converts an argument for a typed function
+//
+// Missing arguments are treated as Empty, so that trailing Option arguments can be omitted.
+fn typed_arg<T: FromValue>(
+    function_name: &str,
+    args: &[Value],
+    index: usize,
+    arg_count: usize,
+) -> Result<T, RuntimeError> {
+    match args.get(index) {
+        Some(value) => T::from_value(value).ok_or_else(|| {
+            make_external_error!(format!(
+                "{}: Expected {} as argument {}, found '{}'",
+                function_name,
+                T::type_name(),
+                index + 1,
+                type_as_string(value)
+            ))
+        }),
+        None => T::from_value(&Value::Empty)
+            .ok_or_else(|| arg_count_error(function_name, arg_count, args.len())),
+    }
+}
+
+fn arg_count_error(function_name: &str, expected: usize, found: usize) -> RuntimeError {
+    make_external_error!(format!(
+        "{}: Expected {} argument{}, found {}",
+        function_name,
+        expected,
+        if expected == 1 { "" } else { "s" },
+        found
+    ))
+}
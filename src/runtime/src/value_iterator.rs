@@ -112,13 +112,9 @@ impl Iterator for ValueIteratorInternals {
                 result
             }
             Iterable::Map(map) => {
-                let result = match map.data().get_index(self.index) {
-                    Some((key, value)) => Some(Ok(ValueIteratorOutput::ValuePair(
-                        key.clone(),
-                        value.clone(),
-                    ))),
-                    None => None,
-                };
+                let result = map.data().get_index(self.index).map(|(key, value)| {
+                    Ok(ValueIteratorOutput::ValuePair(key.clone(), value.clone()))
+                });
 
                 self.index += 1;
                 result
@@ -38,12 +38,12 @@ impl ValueList {
     }
 
     #[inline]
-    pub fn data(&self) -> RwLockReadGuard<ValueVec> {
+    pub fn data(&self) -> RwLockReadGuard<'_, ValueVec> {
         self.0.read()
     }
 
     #[inline]
-    pub fn data_mut(&self) -> RwLockWriteGuard<ValueVec> {
+    pub fn data_mut(&self) -> RwLockWriteGuard<'_, ValueVec> {
         self.0.write()
     }
 }
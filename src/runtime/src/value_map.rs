@@ -1,13 +1,16 @@
 use {
     crate::{
         external::{Args, ExternalFunction},
+        value_conversion::TypedFunction,
         RuntimeResult, Value, ValueList, ValueRef, Vm,
     },
     indexmap::IndexMap,
+    koto_parser::MetaKeyId,
     parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard},
     rustc_hash::FxHasher,
     std::{
         borrow::Borrow,
+        collections::HashMap,
         fmt,
         hash::{BuildHasherDefault, Hash, Hasher},
         iter::{FromIterator, IntoIterator},
@@ -17,7 +20,7 @@ use {
 };
 
 pub trait ValueMapKey {
-    fn to_value_ref(&self) -> ValueRef;
+    fn to_value_ref(&self) -> ValueRef<'_>;
 }
 
 impl<'a> Hash for dyn ValueMapKey + 'a {
@@ -35,13 +38,13 @@ impl<'a> PartialEq for dyn ValueMapKey + 'a {
 impl<'a> Eq for dyn ValueMapKey + 'a {}
 
 impl ValueMapKey for Value {
-    fn to_value_ref(&self) -> ValueRef {
+    fn to_value_ref(&self) -> ValueRef<'_> {
         self.as_ref()
     }
 }
 
-impl<'a> ValueMapKey for &'a str {
-    fn to_value_ref(&self) -> ValueRef {
+impl ValueMapKey for &str {
+    fn to_value_ref(&self) -> ValueRef<'_> {
         ValueRef::Str(self)
     }
 }
@@ -60,21 +63,27 @@ impl<'a> Borrow<dyn ValueMapKey + 'a> for &'a str {
 
 type ValueHashMapType = IndexMap<Value, Value, BuildHasherDefault<FxHasher>>;
 
+/// The meta entries of a map, e.g. operator overloads like `@+`
+pub type MetaMap = HashMap<MetaKeyId, Value, BuildHasherDefault<FxHasher>>;
+
 #[derive(Clone, Debug, Default)]
-pub struct ValueHashMap(ValueHashMapType);
+pub struct ValueHashMap {
+    data: ValueHashMapType,
+    meta: MetaMap,
+}
 
 impl ValueHashMap {
     #[inline]
     pub fn new() -> Self {
-        Self(ValueHashMapType::default())
+        Self::default()
     }
 
     #[inline]
     pub fn with_capacity(capacity: usize) -> Self {
-        Self(ValueHashMapType::with_capacity_and_hasher(
-            capacity,
-            Default::default(),
-        ))
+        Self {
+            data: ValueHashMapType::with_capacity_and_hasher(capacity, Default::default()),
+            meta: MetaMap::default(),
+        }
     }
 
     #[inline]
@@ -100,6 +109,13 @@ impl ValueHashMap {
         self.add_value(id, Value::ExternalFunction(ExternalFunction::new(f, true)));
     }
 
+    /// Adds a function with typed arguments, see [ValueMap::add_typed_fn]
+    #[inline]
+    pub fn add_typed_fn<Args>(&mut self, id: &str, f: impl TypedFunction<Args>) {
+        let name = id.to_string();
+        self.add_fn(id, move |vm, args| f.call_typed(&name, vm.get_args(args)));
+    }
+
     #[inline]
     pub fn add_list(&mut self, id: &str, list: ValueList) {
         #[allow(clippy::useless_conversion)]
@@ -120,17 +136,38 @@ impl ValueHashMap {
 
     #[inline]
     pub fn extend(&mut self, other: &ValueHashMap) {
-        self.0.extend(other.0.clone().into_iter());
+        self.data.extend(other.data.clone());
+        self.meta.extend(other.meta.clone());
     }
 
     #[inline]
     pub fn get_with_string(&self, key: &str) -> Option<&Value> {
-        self.0.get(&key as &dyn ValueMapKey)
+        self.data.get(&key as &dyn ValueMapKey)
     }
 
     #[inline]
     pub fn get_with_string_mut(&mut self, key: &str) -> Option<&mut Value> {
-        self.0.get_mut(&key as &dyn ValueMapKey)
+        self.data.get_mut(&key as &dyn ValueMapKey)
+    }
+
+    #[inline]
+    pub fn meta(&self) -> &MetaMap {
+        &self.meta
+    }
+
+    #[inline]
+    pub fn meta_mut(&mut self) -> &mut MetaMap {
+        &mut self.meta
+    }
+
+    #[inline]
+    pub fn get_meta_value(&self, key: MetaKeyId) -> Option<&Value> {
+        self.meta.get(&key)
+    }
+
+    #[inline]
+    pub fn insert_meta_value(&mut self, key: MetaKeyId, value: Value) -> Option<Value> {
+        self.meta.insert(key, value)
     }
 }
 
@@ -138,27 +175,30 @@ impl Deref for ValueHashMap {
     type Target = ValueHashMapType;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.data
     }
 }
 
 impl DerefMut for ValueHashMap {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.data
     }
 }
 
 impl FromIterator<(Value, Value)> for ValueHashMap {
     #[inline]
     fn from_iter<T: IntoIterator<Item = (Value, Value)>>(iter: T) -> ValueHashMap {
-        Self(ValueHashMapType::from_iter(iter))
+        Self {
+            data: ValueHashMapType::from_iter(iter),
+            meta: MetaMap::default(),
+        }
     }
 }
 
 impl PartialEq for ValueHashMap {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
+        self.data == other.data
     }
 }
 impl Eq for ValueHashMap {}
@@ -183,12 +223,12 @@ impl ValueMap {
     }
 
     #[inline]
-    pub fn data(&self) -> RwLockReadGuard<ValueHashMap> {
+    pub fn data(&self) -> RwLockReadGuard<'_, ValueHashMap> {
         self.0.read()
     }
 
     #[inline]
-    pub fn data_mut(&self) -> RwLockWriteGuard<ValueHashMap> {
+    pub fn data_mut(&self) -> RwLockWriteGuard<'_, ValueHashMap> {
         self.0.write()
     }
 
@@ -225,6 +265,34 @@ impl ValueMap {
         self.add_value(id, Value::ExternalFunction(ExternalFunction::new(f, true)));
     }
 
+    /// Adds a function that takes and returns ordinary Rust types
+    ///
+    /// The function's arguments are converted from Koto values with [FromValue], and its result
+    /// is converted back into a Koto value with [IntoValue]. Arguments with incompatible types
+    /// produce runtime errors that include the function's name. Missing arguments are treated
+    /// as Empty, so trailing `Option` arguments can be omitted by the caller.
+    ///
+    /// Functions that return a `Result` produce a runtime error when an error is returned.
+    ///
+    /// [FromValue]: crate::FromValue
+    /// [IntoValue]: crate::IntoValue
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use koto_runtime::ValueMap;
+    ///
+    /// let mut module = ValueMap::new();
+    /// module.add_typed_fn("repeat", |s: String, n: usize| -> Vec<String> {
+    ///     vec![s; n]
+    /// });
+    /// ```
+    #[inline]
+    pub fn add_typed_fn<Args>(&mut self, id: &str, f: impl TypedFunction<Args>) {
+        let name = id.to_string();
+        self.add_fn(id, move |vm, args| f.call_typed(&name, vm.get_args(args)));
+    }
+
     #[inline]
     pub fn add_list(&mut self, id: &str, list: ValueList) {
         self.add_value(id, Value::List(list));
@@ -240,11 +308,27 @@ impl ValueMap {
         self.insert(id.into(), value);
     }
 
+    // Adds an instance function to the map's meta entries, e.g. an implementation for @+
+    #[inline]
+    pub fn add_meta_fn(
+        &mut self,
+        key: MetaKeyId,
+        f: impl Fn(&mut Vm, &Args) -> RuntimeResult + Send + Sync + 'static,
+    ) {
+        self.data_mut()
+            .insert_meta_value(key, Value::ExternalFunction(ExternalFunction::new(f, true)));
+    }
+
+    #[inline]
+    pub fn get_meta_value(&self, key: MetaKeyId) -> Option<Value> {
+        self.data().get_meta_value(key).cloned()
+    }
+
     // An iterator that clones the map's keys and values
     //
     // Useful for avoiding holding on to the underlying RwLock while iterating
     #[inline]
-    pub fn cloned_iter(&self) -> ValueMapIter {
+    pub fn cloned_iter(&self) -> ValueMapIter<'_> {
         ValueMapIter::new(&self.0)
     }
 }
@@ -1,28 +1,39 @@
 use {
     crate::{
+        capabilities::{Capabilities, CoreModule, ModuleFileAccess},
         core::CoreLib,
+        coverage::Coverage,
+        debugger::{DebugFrame, DebugState, Debugger, SharedDebugger},
+        execution_limits::ExecutionLimiter,
         external::{self, Args, ExternalFunction},
         frame::Frame,
-        num2, num4, type_as_string,
+        import_cache::ImportCache,
+        koto_file::{DefaultStderr, DefaultStdout, KotoFile},
+        num2, num4,
+        profiler::Profiler,
+        type_as_string,
         value::{
             self, add_values, multiply_values, value_is_callable, value_size, RegisterSlice,
             RuntimeFunction,
         },
         value_iterator::{IntRange, Iterable, ValueIterator, ValueIteratorOutput},
-        vm_error, Loader, RuntimeError, RuntimeResult, Value, ValueList, ValueMap, ValueNumber,
-        ValueString, ValueVec,
+        vm_error, ExecutionLimit, ExecutionLimits, InterruptHandle, RuntimeError, RuntimeResult,
+        TestReport, TestResult, Value, ValueList, ValueMap, ValueNumber, ValueString, ValueVec,
     },
-    koto_bytecode::{Chunk, Instruction, InstructionReader, TypeId},
-    koto_parser::ConstantIndex,
-    parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+    koto_bytecode::{
+        Chunk, Instruction, InstructionReader, ModuleResolver, ResolvedModule, TypeId,
+    },
+    koto_parser::{ConstantIndex, MetaKeyId},
+    parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard},
     std::{
-        collections::HashMap,
         fmt,
-        path::PathBuf,
+        ops::Range,
+        path::{Path, PathBuf},
         sync::{
             atomic::{AtomicBool, Ordering},
             Arc,
         },
+        time::Instant,
     },
 };
 
@@ -36,31 +47,68 @@ pub enum ControlFlow {
 // Instructions will place their results in registers, there's no Ok type
 pub type InstructionResult = Result<(), RuntimeError>;
 
+/// Settings used when creating a [Vm]
+///
+/// The settings are shared with any VMs that are spawned by the VM.
+#[derive(Clone, Debug)]
+pub struct VmSettings {
+    /// The output used for printing, e.g. by `string.print` and `debug`
+    pub stdout: Arc<dyn KotoFile>,
+    /// The output used for printing errors, e.g. by external functions that report problems
+    pub stderr: Arc<dyn KotoFile>,
+    /// The core modules and module files that scripts are able to access
+    pub capabilities: Capabilities,
+    /// When enabled, imported module files are checked for changes whenever a module is imported
+    ///
+    /// Modules with files that have been modified since they were loaded are recompiled and run
+    /// again, along with any modules that import them. See [Vm::invalidate_changed_modules].
+    pub reload_changed_modules: bool,
+}
+
+impl Default for VmSettings {
+    fn default() -> Self {
+        Self {
+            stdout: Arc::new(DefaultStdout),
+            stderr: Arc::new(DefaultStderr),
+            capabilities: Capabilities::default(),
+            reload_changed_modules: false,
+        }
+    }
+}
+
+// The location of a VM's most recent debugger check
+struct DebuggerLocation {
+    // The address of the chunk that was being executed
+    chunk: usize,
+    // The call stack depth at the time of the check
+    depth: usize,
+    // The range of instructions covered by the source span that was checked
+    ips: Range<usize>,
+    // The debugger's check count following the check
+    check_count: u64,
+}
+
 /// Context shared by all VMs across modules
 struct SharedContext {
     pub prelude: ValueMap,
     core_lib: CoreLib,
+    settings: VmSettings,
 }
 
-impl Default for SharedContext {
-    fn default() -> Self {
+impl SharedContext {
+    fn with_settings(settings: VmSettings) -> Self {
         let core_lib = CoreLib::default();
 
         let mut prelude = ValueMap::default();
-        prelude.add_map("io", core_lib.io.clone());
-        prelude.add_map("iterator", core_lib.iterator.clone());
-        prelude.add_map("koto", core_lib.koto.clone());
-        prelude.add_map("list", core_lib.list.clone());
-        prelude.add_map("map", core_lib.map.clone());
-        prelude.add_map("os", core_lib.os.clone());
-        prelude.add_map("number", core_lib.number.clone());
-        prelude.add_map("range", core_lib.range.clone());
-        prelude.add_map("string", core_lib.string.clone());
-        prelude.add_map("test", core_lib.test.clone());
-        prelude.add_map("thread", core_lib.thread.clone());
-        prelude.add_map("tuple", core_lib.tuple.clone());
-
-        Self { prelude, core_lib }
+        for module in settings.capabilities.core_modules() {
+            prelude.add_map(module.name(), core_lib.module(*module).clone());
+        }
+
+        Self {
+            prelude,
+            core_lib,
+            settings,
+        }
     }
 }
 
@@ -68,16 +116,15 @@ impl Default for SharedContext {
 #[derive(Default)]
 pub struct ModuleContext {
     global: ValueMap,
-    loader: Loader,
-    modules: HashMap<PathBuf, Option<ValueMap>>,
+    // Shared with the contexts of imported modules
+    imports: Arc<Mutex<ImportCache>>,
     spawned_stop_flags: Vec<Arc<AtomicBool>>,
 }
 
 impl ModuleContext {
     fn spawn_new_context(&self) -> Self {
         Self {
-            loader: self.loader.clone(),
-            modules: self.modules.clone(),
+            imports: self.imports.clone(),
             global: Default::default(),
             spawned_stop_flags: Default::default(),
         }
@@ -85,7 +132,7 @@ impl ModuleContext {
 
     fn reset(&mut self) {
         // self.global.clear();
-        self.loader = Default::default();
+        self.imports.lock().loader.clear();
         self.stop_spawned_vms();
     }
 
@@ -110,22 +157,42 @@ pub struct Vm {
     value_stack: Vec<Value>,
     call_stack: Vec<Frame>,
     stop_flag: Option<Arc<AtomicBool>>,
+    limiter: Option<Arc<ExecutionLimiter>>,
+    debugger: Option<Arc<SharedDebugger>>,
+    // The location of the VM's most recent debugger check,
+    // used to avoid checking the debugger again until a new line is reached.
+    debugger_location: Option<DebuggerLocation>,
+    // The call stack depth of the VM that spawned this VM, used by the debugger
+    // to treat functions called by spawned VMs as nested calls.
+    debug_depth: usize,
+    profiler: Option<Profiler>,
+    coverage: Option<Coverage>,
 }
 
 impl Default for Vm {
     fn default() -> Self {
+        Self::with_settings(VmSettings::default())
+    }
+}
+
+impl Vm {
+    pub fn with_settings(settings: VmSettings) -> Self {
         Self {
             context: Arc::new(RwLock::new(ModuleContext::default())),
-            context_shared: Arc::new(SharedContext::default()),
+            context_shared: Arc::new(SharedContext::with_settings(settings)),
             reader: InstructionReader::default(),
             value_stack: Vec::with_capacity(32),
             call_stack: vec![],
             stop_flag: None,
+            limiter: None,
+            debugger: None,
+            debugger_location: None,
+            debug_depth: 0,
+            profiler: None,
+            coverage: None,
         }
     }
-}
 
-impl Vm {
     pub fn spawn_new_vm(&mut self) -> Self {
         Self {
             context: Arc::new(RwLock::new(self.context().spawn_new_context())),
@@ -134,6 +201,12 @@ impl Vm {
             value_stack: Vec::with_capacity(32),
             call_stack: vec![],
             stop_flag: None,
+            limiter: self.limiter.clone(),
+            debugger: self.debugger.clone(),
+            debugger_location: None,
+            debug_depth: self.debug_depth + self.call_stack.len(),
+            profiler: self.profiler.clone(),
+            coverage: self.coverage.clone(),
         }
     }
 
@@ -145,6 +218,12 @@ impl Vm {
             value_stack: Vec::with_capacity(8),
             call_stack: vec![],
             stop_flag: None,
+            limiter: self.limiter.clone(),
+            debugger: self.debugger.clone(),
+            debugger_location: None,
+            debug_depth: self.debug_depth + self.call_stack.len(),
+            profiler: self.profiler.clone(),
+            coverage: self.coverage.clone(),
         }
     }
 
@@ -161,6 +240,73 @@ impl Vm {
             value_stack: Vec::with_capacity(8),
             call_stack: vec![],
             stop_flag: Some(stop_flag),
+            limiter: self.limiter.clone(),
+            debugger: self.debugger.clone(),
+            debugger_location: None,
+            debug_depth: self.debug_depth + self.call_stack.len(),
+            profiler: self.profiler.clone(),
+            coverage: self.coverage.clone(),
+        }
+    }
+
+    /// Sets the limits that should be applied to the VM's execution
+    ///
+    /// The limits are shared with any VMs that are subsequently spawned by this VM,
+    /// and are measured from the point that this function is called.
+    ///
+    /// If an interrupt handle has already been created via [Vm::interrupt_handle] then it
+    /// remains valid, and any pending interrupt is kept until it's cleared with
+    /// [InterruptHandle::reset].
+    pub fn set_execution_limits(&mut self, limits: ExecutionLimits) {
+        let interrupt = self
+            .limiter
+            .as_ref()
+            .map(|limiter| limiter.interrupt_handle());
+
+        self.limiter = match interrupt {
+            Some(interrupt) => Some(Arc::new(ExecutionLimiter::new(limits, interrupt))),
+            None if limits.is_unlimited() => None,
+            None => Some(Arc::new(ExecutionLimiter::new(
+                limits,
+                InterruptHandle::default(),
+            ))),
+        };
+    }
+
+    /// Attaches a debugger to the VM, or detaches the current debugger if `None` is provided
+    ///
+    /// The debugger is shared with any VMs that are subsequently spawned by this VM.
+    pub fn set_debugger(&mut self, debugger: Option<Debugger>) {
+        self.debugger = debugger.map(|debugger| Arc::new(SharedDebugger::new(debugger)));
+        self.debugger_location = None;
+    }
+
+    /// Attaches a profiler to the VM, or detaches the current profiler if `None` is provided
+    ///
+    /// The profiler is shared with any VMs that are subsequently spawned by this VM.
+    pub fn set_profiler(&mut self, profiler: Option<Profiler>) {
+        self.profiler = profiler;
+    }
+
+    /// Attaches a coverage collector to the VM, or detaches the current collector
+    ///
+    /// The collector is shared with any VMs that are subsequently spawned by this VM.
+    pub fn set_coverage(&mut self, coverage: Option<Coverage>) {
+        self.coverage = coverage;
+    }
+
+    /// Returns a handle that can be used to interrupt the VM's execution from another thread
+    pub fn interrupt_handle(&mut self) -> InterruptHandle {
+        match &self.limiter {
+            Some(limiter) => limiter.interrupt_handle(),
+            None => {
+                let interrupt = InterruptHandle::default();
+                self.limiter = Some(Arc::new(ExecutionLimiter::new(
+                    ExecutionLimits::default(),
+                    interrupt.clone(),
+                )));
+                interrupt
+            }
         }
     }
 
@@ -168,11 +314,84 @@ impl Vm {
         self.context_shared.prelude.clone()
     }
 
-    fn context(&self) -> RwLockReadGuard<ModuleContext> {
+    /// The output that's used for printing
+    pub fn stdout(&self) -> &Arc<dyn KotoFile> {
+        &self.context_shared.settings.stdout
+    }
+
+    /// The output that's used for printing errors
+    pub fn stderr(&self) -> &Arc<dyn KotoFile> {
+        &self.context_shared.settings.stderr
+    }
+
+    /// Sets the resolver that's used to find imported modules
+    ///
+    /// See [Loader::set_module_resolver](crate::Loader::set_module_resolver).
+    pub fn set_module_resolver(&mut self, resolver: Arc<dyn ModuleResolver>) {
+        self.context()
+            .imports
+            .lock()
+            .loader
+            .set_module_resolver(resolver);
+    }
+
+    /// Sets a directory that compiled modules should be cached in
+    ///
+    /// See [Loader::set_cache_dir](crate::Loader::set_cache_dir).
+    pub fn set_module_cache_dir(&mut self, cache_dir: Option<PathBuf>) {
+        self.context()
+            .imports
+            .lock()
+            .loader
+            .set_cache_dir(cache_dir);
+    }
+
+    /// Enables constant folding when compiling imported modules
+    ///
+    /// See [Loader::set_fold_constants](crate::Loader::set_fold_constants).
+    pub fn set_fold_constants(&mut self, enabled: bool) {
+        self.context()
+            .imports
+            .lock()
+            .loader
+            .set_fold_constants(enabled);
+    }
+
+    /// Removes an imported module from the VM's module cache
+    ///
+    /// Any modules that import the module (directly or indirectly) are also invalidated,
+    /// so that the next time they're imported they'll be recompiled and run again.
+    ///
+    /// The ids of the invalidated modules are returned, along with the source paths of any
+    /// scripts that imported them. For modules loaded from files the id is the module's path.
+    pub fn invalidate_module(&mut self, id: &Path) -> Vec<PathBuf> {
+        let id = id.canonicalize().unwrap_or_else(|_| id.to_path_buf());
+        self.context().imports.lock().invalidate(&id)
+    }
+
+    /// Invalidates any imported modules with files that have changed since they were loaded
+    ///
+    /// The ids of the invalidated modules are returned, see [Vm::invalidate_module].
+    pub fn invalidate_changed_modules(&mut self) -> Vec<PathBuf> {
+        self.context().imports.lock().invalidate_changed()
+    }
+
+    /// Returns the ids of the modules and scripts that have imported the given module
+    pub fn module_dependents(&self, id: &Path) -> Vec<PathBuf> {
+        let id = id.canonicalize().unwrap_or_else(|_| id.to_path_buf());
+        self.context().imports.lock().dependents(&id)
+    }
+
+    /// The global values of the VM's module
+    pub fn globals(&self) -> ValueMap {
+        self.context().global.clone()
+    }
+
+    fn context(&self) -> RwLockReadGuard<'_, ModuleContext> {
         self.context.read()
     }
 
-    fn context_mut(&mut self) -> RwLockWriteGuard<ModuleContext> {
+    fn context_mut(&mut self) -> RwLockWriteGuard<'_, ModuleContext> {
         self.context.write()
     }
 
@@ -190,6 +409,13 @@ impl Vm {
     pub fn reset(&mut self) {
         self.context_mut().reset();
         self.value_stack = Default::default();
+        if let Some(profiler) = &self.profiler {
+            for frame in self.call_stack.iter().rev() {
+                if let Some(token) = frame.profile_token {
+                    profiler.exit(token);
+                }
+            }
+        }
         self.call_stack = Default::default();
     }
 
@@ -202,6 +428,9 @@ impl Vm {
         if self.call_stack.is_empty() {
             Ok(Value::Empty)
         } else {
+            if let Some(profiler) = &self.profiler {
+                profiler.resume(&self.profile_tokens());
+            }
             self.execute_instructions()
         }
     }
@@ -256,6 +485,7 @@ impl Vm {
             frame_base,
             args.len() as u8,
             instance_register,
+            None,
         )?;
 
         if self.call_stack.is_empty() {
@@ -275,79 +505,225 @@ impl Vm {
         }
     }
 
+    /// Runs the `test_` functions in the provided map, stopping at the first failing test
+    ///
+    /// See [Vm::run_tests_with_report] for a runner that runs all of the tests.
     pub fn run_tests(&mut self, tests: ValueMap) -> RuntimeResult {
-        use Value::*;
+        for (name, test) in test_functions(&tests) {
+            self.run_test(&tests, &name, test, true)?;
+        }
+
+        Ok(Value::Empty)
+    }
+
+    /// Runs the `test_` functions in the provided map, returning a report of the results
+    ///
+    /// Each test is run even if a previous test failed, unless the failure was caused by
+    /// execution being stopped, e.g. by an interrupt or an exceeded execution limit.
+    ///
+    /// If a filter is provided, then only the tests with names that contain the filter are run.
+    pub fn run_tests_with_report(
+        &mut self,
+        tests: ValueMap,
+        filter: Option<&str>,
+        report_name: &str,
+    ) -> TestReport {
+        let mut report = TestReport::new(report_name);
+
+        for (name, test) in test_functions(&tests) {
+            if matches!(filter, Some(filter) if !name.contains(filter)) {
+                continue;
+            }
 
+            let start_time = Instant::now();
+            // The report includes the test's name, so errors from the test itself are left as-is
+            let error = self.run_test(&tests, &name, test, false).err();
+            let stop = matches!(&error, Some(error) if !error.is_catchable());
+
+            report.results.push(TestResult {
+                name,
+                duration: start_time.elapsed(),
+                error,
+            });
+
+            if stop {
+                break;
+            }
+        }
+
+        report
+    }
+
+    // Runs a single test, along with the test map's pre_test and post_test functions
+    //
+    // Errors from the pre_test and post_test functions are prefixed with the test's name,
+    // and errors from the test itself are prefixed if `prefix_test_errors` is true.
+    fn run_test(
+        &mut self,
+        tests: &ValueMap,
+        name: &str,
+        test: Value,
+        prefix_test_errors: bool,
+    ) -> Result<(), RuntimeError> {
         // It's important here to make sure we don't hang on to any references to the internal
         // test map data while calling the test functions, otherwise we'll end up in deadlocks.
-        let self_arg = Map(tests.clone());
-
         let pre_test = tests.data().get_with_string("pre_test").cloned();
         let post_test = tests.data().get_with_string("post_test").cloned();
-        let pass_self_to_pre_test = match &pre_test {
-            Some(Function(f)) => f.arg_count == 1,
-            _ => false,
+
+        let make_test_error = |error: RuntimeError, message: &str| {
+            error.with_prefix(&format!("{} '{}'", message, name))
         };
-        let pass_self_to_post_test = match &post_test {
-            Some(Function(f)) => f.arg_count == 1,
+
+        if let Some(pre_test) = pre_test {
+            self.run_test_function(tests, pre_test)
+                .map_err(|error| make_test_error(error, "Error while preparing to run test"))?;
+        }
+
+        self.run_test_function(tests, test).map_err(|error| {
+            if prefix_test_errors {
+                make_test_error(error, "Error while running test")
+            } else {
+                error
+            }
+        })?;
+
+        if let Some(post_test) = post_test {
+            self.run_test_function(tests, post_test)
+                .map_err(|error| make_test_error(error, "Error after running test"))?;
+        }
+
+        Ok(())
+    }
+
+    // Runs a test function, passing in the test map as `self` if the function expects it
+    fn run_test_function(&mut self, tests: &ValueMap, function: Value) -> RuntimeResult {
+        if !value_is_callable(&function) {
+            return Ok(Value::Empty);
+        }
+
+        let pass_self = match &function {
+            Value::Function(f) => f.arg_count == 1,
             _ => false,
         };
 
-        for (key, value) in tests.cloned_iter() {
-            match (key, value) {
-                (Str(id), test) if id.starts_with("test_") && value_is_callable(&test) => {
-                    let make_test_error = |error: RuntimeError, message: &str| {
-                        Err(error.with_prefix(&format!("{} '{}'", message, &id[5..])))
-                    };
+        if pass_self {
+            self.run_instance_function(Value::Map(tests.clone()), function, &[])
+        } else {
+            self.run_function(function, &[])
+        }
+    }
 
-                    if let Some(pre_test) = &pre_test {
-                        if value_is_callable(pre_test) {
-                            let pre_test_result = if pass_self_to_pre_test {
-                                self.run_instance_function(self_arg.clone(), pre_test.clone(), &[])
-                            } else {
-                                self.run_function(pre_test.clone(), &[])
-                            };
+    #[inline]
+    fn check_debugger(&mut self, ip: usize) -> Result<(), RuntimeError> {
+        let shared_debugger = match &self.debugger {
+            Some(debugger) => debugger.clone(),
+            None => return Ok(()),
+        };
 
-                            if let Err(error) = pre_test_result {
-                                return make_test_error(error, "Error while preparing to run test");
-                            }
-                        }
-                    }
+        let chunk = Arc::as_ptr(&self.reader.chunk) as usize;
+        let depth = self.debug_depth + self.call_stack.len();
 
-                    let pass_self_to_test = match &test {
-                        Function(f) => f.arg_count == 1,
-                        _ => false,
-                    };
+        // The debugger only needs to be checked when a new line is reached, so the check can be
+        // skipped if the instruction is covered by the previous check's source span, as long as
+        // no other VM has checked the debugger in the meantime.
+        if let Some(location) = &self.debugger_location {
+            if location.chunk == chunk
+                && location.depth == depth
+                && location.ips.contains(&ip)
+                && location.check_count == shared_debugger.check_count.load(Ordering::Relaxed)
+            {
+                return Ok(());
+            }
+        }
 
-                    let test_result = if pass_self_to_test {
-                        self.run_instance_function(self_arg.clone(), test, &[])
-                    } else {
-                        self.run_function(test, &[])
-                    };
+        let (line, ips) = match self.chunk().debug_info.get_source_span_and_range(ip) {
+            Some((span, ips)) => (span.start.line, ips),
+            None => return Ok(()),
+        };
 
-                    if let Err(error) = test_result {
-                        return make_test_error(error, "Error while running test");
-                    }
+        let mut debugger = shared_debugger.debugger.lock();
+        self.debugger_location = Some(DebuggerLocation {
+            chunk,
+            depth,
+            ips,
+            check_count: shared_debugger.check_count.fetch_add(1, Ordering::Relaxed) + 1,
+        });
+        if let Some(reason) = debugger.check_pause(depth, &self.reader.chunk, line) {
+            let state = DebugState {
+                reason,
+                frames: self.debug_frames(ip),
+                globals: self.context().global.clone(),
+            };
+            if !debugger.on_pause(depth, &state) {
+                return Err(RuntimeError::ExecutionLimitExceeded(
+                    ExecutionLimit::StoppedByDebugger,
+                ));
+            }
+        }
 
-                    if let Some(post_test) = &post_test {
-                        if value_is_callable(post_test) {
-                            let post_test_result = if pass_self_to_post_test {
-                                self.run_instance_function(self_arg.clone(), post_test.clone(), &[])
-                            } else {
-                                self.run_function(post_test.clone(), &[])
-                            };
+        Ok(())
+    }
 
-                            if let Err(error) = post_test_result {
-                                return make_test_error(error, "Error after running test");
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            }
+    // The profiler tokens for the frames in the call stack, starting with the outermost frame
+    fn profile_tokens(&self) -> Vec<u64> {
+        self.call_stack
+            .iter()
+            .filter_map(|frame| frame.profile_token)
+            .collect()
+    }
+
+    // Captures the state of the call stack for the debugger, starting with the innermost frame
+    fn debug_frames(&self, ip: usize) -> Vec<DebugFrame> {
+        let mut result = Vec::with_capacity(self.call_stack.len());
+        let mut registers_end = self.value_stack.len();
+
+        for (index, frame) in self.call_stack.iter().enumerate().rev() {
+            let frame_ip = if index == self.call_stack.len() - 1 {
+                Some(ip)
+            } else {
+                // The return ip follows the frame's call instruction
+                frame
+                    .return_register_and_ip
+                    .map(|(_, return_ip)| return_ip.saturating_sub(1))
+            };
+
+            let registers_start = frame.register_base.min(registers_end);
+            let registers = self.value_stack[registers_start..registers_end].to_vec();
+            registers_end = registers_start;
+
+            let frame_info = frame_ip.and_then(|ip| frame.chunk.debug_info.get_frame_info(ip));
+            let function = frame_info
+                .and_then(|frame_info| frame_info.function_name)
+                .map(|name| frame.chunk.constants.get_str(name).to_string());
+            let locals = frame_info
+                .map(|frame_info| {
+                    frame_info
+                        .locals
+                        .iter()
+                        .map(|(register, id)| {
+                            (
+                                frame.chunk.constants.get_str(*id).to_string(),
+                                registers
+                                    .get(*register as usize)
+                                    .cloned()
+                                    .unwrap_or(Value::Empty),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            result.push(DebugFrame {
+                chunk: frame.chunk.clone(),
+                ip: frame_ip.unwrap_or_default(),
+                span: frame_ip.and_then(|ip| frame.chunk.debug_info.get_source_span(ip)),
+                function,
+                locals,
+                registers,
+            });
         }
 
-        Ok(Empty)
+        result
     }
 
     fn execute_instructions(&mut self) -> RuntimeResult {
@@ -361,13 +737,33 @@ impl Vm {
                     break;
                 }
             }
-            match self.execute_instruction(instruction, instruction_ip) {
+            if let Some(profiler) = &self.profiler {
+                profiler.record_instruction(&self.reader.chunk, instruction_ip);
+            }
+            if let Some(coverage) = &self.coverage {
+                coverage.record_instruction(&self.reader.chunk, instruction_ip);
+            }
+
+            let instruction_result = match self.check_debugger(instruction_ip) {
+                Ok(()) => match &self.limiter {
+                    Some(limiter) => limiter
+                        .check()
+                        .and_then(|_| self.execute_instruction(instruction, instruction_ip)),
+                    None => self.execute_instruction(instruction, instruction_ip),
+                },
+                Err(error) => Err(error),
+            };
+
+            match instruction_result {
                 Ok(ControlFlow::Continue) => {}
                 Ok(ControlFlow::Return(value)) => {
                     result = value;
                     break;
                 }
                 Ok(ControlFlow::Yield(value)) => {
+                    if let Some(profiler) = &self.profiler {
+                        profiler.suspend(&self.profile_tokens());
+                    }
                     result = value;
                     break;
                 }
@@ -377,7 +773,9 @@ impl Vm {
                     error.extend_trace(self.chunk(), instruction_ip);
 
                     while let Some(frame) = self.call_stack.last() {
-                        if let Some((error_register, catch_ip)) = frame.catch_stack.last() {
+                        if let (Some((error_register, catch_ip)), true) =
+                            (frame.catch_stack.last(), error.is_catchable())
+                        {
                             recover_register_and_ip = Some((*error_register, *catch_ip));
                             break;
                         } else {
@@ -394,7 +792,7 @@ impl Vm {
                     }
 
                     if let Some((register, ip)) = recover_register_and_ip {
-                        self.set_register(register, Value::Str(error.to_string().into()));
+                        self.set_register(register, catch_value(error));
                         self.set_ip(ip);
                     } else {
                         return Err(error);
@@ -476,6 +874,11 @@ impl Vm {
                 self.set_register(register, TemporaryTuple(RegisterSlice { start, count }));
                 Ok(())
             }
+            Instruction::MakeString {
+                register,
+                start,
+                count,
+            } => self.run_make_string(register, start, count),
             Instruction::MakeList {
                 register,
                 size_hint,
@@ -560,14 +963,8 @@ impl Vm {
             Instruction::GreaterOrEqual { register, lhs, rhs } => {
                 self.run_greater_or_equal(register, lhs, rhs, &instruction)
             }
-            Instruction::Equal { register, lhs, rhs } => {
-                self.run_equal(register, lhs, rhs);
-                Ok(())
-            }
-            Instruction::NotEqual { register, lhs, rhs } => {
-                self.run_not_equal(register, lhs, rhs);
-                Ok(())
-            }
+            Instruction::Equal { register, lhs, rhs } => self.run_equal(register, lhs, rhs),
+            Instruction::NotEqual { register, lhs, rhs } => self.run_not_equal(register, lhs, rhs),
             Instruction::Jump { offset } => {
                 self.jump_ip(offset);
                 Ok(())
@@ -586,17 +983,21 @@ impl Vm {
                 offset,
                 jump_condition,
             } => self.run_jump_back_if(register, offset, jump_condition),
+            Instruction::JumpIfArgProvided { register, offset } => {
+                if self.frame().omitted_args & (1 << register) == 0 {
+                    self.jump_ip(offset);
+                }
+                Ok(())
+            }
             Instruction::Call {
                 result,
                 function,
                 frame_base,
                 arg_count,
-            } => self.call_function(
-                result,
-                self.clone_register(function),
-                frame_base,
-                arg_count,
-                None,
+                tail_call,
+                named_args,
+            } => self.run_call(
+                result, function, frame_base, arg_count, None, tail_call, named_args,
             ),
             Instruction::CallChild {
                 result,
@@ -604,12 +1005,16 @@ impl Vm {
                 frame_base,
                 arg_count,
                 parent,
-            } => self.call_function(
+                tail_call,
+                named_args,
+            } => self.run_call(
                 result,
-                self.clone_register(function),
+                function,
                 frame_base,
                 arg_count,
                 Some(parent),
+                tail_call,
+                named_args,
             ),
             Instruction::Return { register } => {
                 if let Some(return_value) = self.pop_frame(self.clone_register(register))? {
@@ -671,7 +1076,7 @@ impl Vm {
                 values_start,
                 count,
             } => {
-                for value_register in values_start..(values_start + count) {
+                for value_register in values_start..(values_start + count as u16) {
                     self.run_list_push(list, value_register)?;
                 }
                 Ok(())
@@ -689,6 +1094,11 @@ impl Vm {
                 value,
                 key,
             } => self.run_map_insert(register, value, key),
+            Instruction::MetaInsert {
+                register,
+                value,
+                id,
+            } => self.run_meta_insert(register, value, id),
             Instruction::Access { register, map, key } => self.run_access(register, map, key),
             Instruction::TryStart {
                 arg_register,
@@ -702,9 +1112,9 @@ impl Vm {
                 self.frame_mut().catch_stack.pop();
                 Ok(())
             }
+            Instruction::Throw { register } => self.run_throw(register),
             Instruction::Debug { register, constant } => {
-                self.run_debug(register, constant, instruction_ip);
-                Ok(())
+                self.run_debug(register, constant, instruction_ip)
             }
             Instruction::CheckType { register, type_id } => self.run_check_type(register, type_id),
             Instruction::CheckSize { register, size } => self.run_check_size(register, size),
@@ -713,7 +1123,23 @@ impl Vm {
         Ok(control_flow)
     }
 
-    fn run_copy(&mut self, target: u8, source: u8) {
+    fn run_throw(&mut self, register: u16) -> InstructionResult {
+        let value = match self.clone_register(register) {
+            Value::TemporaryTuple(RegisterSlice { start, count }) => {
+                Value::Tuple(self.register_slice(start, count).into())
+            }
+            other => other,
+        };
+
+        let error = RuntimeError::ThrownValue {
+            value,
+            trace: Vec::new(),
+        };
+        crate::check_panic_on_runtime_error();
+        Err(error)
+    }
+
+    fn run_copy(&mut self, target: u16, source: u16) {
         let value = match self.clone_register(source) {
             Value::TemporaryTuple(RegisterSlice { start, count }) => {
                 // A temporary tuple shouldn't make it into a named value,
@@ -727,7 +1153,7 @@ impl Vm {
 
     fn run_load_global(
         &mut self,
-        register: u8,
+        register: u16,
         constant_index: ConstantIndex,
     ) -> InstructionResult {
         let global_name = self.get_constant_str(constant_index);
@@ -747,7 +1173,7 @@ impl Vm {
         }
     }
 
-    fn run_set_global(&mut self, constant_index: ConstantIndex, source_register: u8) {
+    fn run_set_global(&mut self, constant_index: ConstantIndex, source_register: u16) {
         let global_name = Value::Str(self.value_string_from_constant(constant_index));
         let value = self.clone_register(source_register);
         self.context_mut()
@@ -756,21 +1182,38 @@ impl Vm {
             .insert(global_name, value);
     }
 
-    fn run_make_tuple(&mut self, register: u8, start: u8, count: u8) {
+    fn run_make_tuple(&mut self, register: u16, start: u16, count: u8) {
         let mut copied = Vec::with_capacity(count as usize);
 
-        for register in start..start + count {
+        for register in start..start + count as u16 {
             copied.push(self.clone_register(register));
         }
 
         self.set_register(register, Value::Tuple(copied.into()));
     }
 
+    fn run_make_string(&mut self, register: u16, start: u16, count: u16) -> InstructionResult {
+        let mut result = String::new();
+
+        for value_register in start..start + count {
+            match self.get_register(value_register) {
+                Value::Str(s) => result.push_str(s),
+                other => {
+                    let other = other.clone();
+                    result.push_str(&self.value_to_string(&other)?);
+                }
+            }
+        }
+
+        self.set_register(register, Value::Str(result.into()));
+        Ok(())
+    }
+
     fn run_make_range(
         &mut self,
-        register: u8,
-        start_register: Option<u8>,
-        end_register: Option<u8>,
+        register: u16,
+        start_register: Option<u16>,
+        end_register: Option<u16>,
         inclusive: bool,
     ) -> InstructionResult {
         use Value::{IndexRange, Number, Range};
@@ -837,7 +1280,7 @@ impl Vm {
         Ok(())
     }
 
-    fn run_make_iterator(&mut self, register: u8, iterable_register: u8) -> InstructionResult {
+    fn run_make_iterator(&mut self, register: u16, iterable_register: u16) -> InstructionResult {
         use Value::*;
 
         let iterable = self.clone_register(iterable_register);
@@ -867,8 +1310,8 @@ impl Vm {
 
     fn run_iterator_next(
         &mut self,
-        result_register: Option<u8>,
-        iterator: u8,
+        result_register: Option<u16>,
+        iterator: u16,
         jump_offset: usize,
         output_is_temporary: bool,
     ) -> InstructionResult {
@@ -901,6 +1344,8 @@ impl Vm {
                     self.set_register(register, Tuple(vec![first, second].into()));
                 }
             }
+            (Some(Err(error @ RuntimeError::ThrownValue { .. })), _) => return Err(error),
+            (Some(Err(error)), _) if !error.is_catchable() => return Err(error),
             (Some(Err(error)), _) => return vm_error!(error.to_string()),
             (None, _) => self.jump_ip(jump_offset),
         };
@@ -908,7 +1353,7 @@ impl Vm {
         Ok(())
     }
 
-    fn run_value_index(&mut self, register: u8, value: u8, index: i8) -> InstructionResult {
+    fn run_value_index(&mut self, register: u16, value: u16, index: i8) -> InstructionResult {
         use Value::*;
 
         let result = match self.get_register(value) {
@@ -922,9 +1367,9 @@ impl Vm {
             }
             TemporaryTuple(RegisterSlice { start, count }) => {
                 let count = *count;
-                if (index.abs() as u8) < count {
+                if index.unsigned_abs() < count {
                     let index = signed_index_to_unsigned(index, count as usize);
-                    self.clone_register(start + index as u8)
+                    self.clone_register(start + index as u16)
                 } else {
                     Empty
                 }
@@ -958,8 +1403,8 @@ impl Vm {
 
     fn run_slice(
         &mut self,
-        register: u8,
-        value: u8,
+        register: u16,
+        value: u16,
         index: i8,
         is_slice_to: bool,
     ) -> InstructionResult {
@@ -1009,10 +1454,12 @@ impl Vm {
             Instruction::Function {
                 register,
                 arg_count,
+                optional_arg_count,
                 capture_count,
                 instance_function,
                 variadic,
                 generator,
+                arg_names_ip,
                 size,
             } => {
                 // Initialize the function's captures with Empty
@@ -1028,9 +1475,11 @@ impl Vm {
                     chunk: self.chunk(),
                     ip: self.ip(),
                     arg_count,
+                    optional_arg_count,
                     instance_function,
                     variadic,
                     captures,
+                    arg_names_ip,
                 };
 
                 let value = if generator {
@@ -1048,9 +1497,9 @@ impl Vm {
 
     fn run_capture_value(
         &mut self,
-        function: u8,
+        function: u16,
         capture_index: u8,
-        value: u8,
+        value: u16,
     ) -> InstructionResult {
         let capture_list = match self.get_register(function) {
             Value::Function(f) => &f.captures,
@@ -1062,7 +1511,11 @@ impl Vm {
 
         match capture_list {
             Some(capture_list) => {
-                capture_list.data_mut()[capture_index as usize] = self.clone_register(value)
+                let value = self.clone_register(value);
+                match capture_list.data_mut().get_mut(capture_index as usize) {
+                    Some(capture) => *capture = value,
+                    None => return vm_error!("Capture: invalid capture index ({})", capture_index),
+                }
             }
             None => return vm_error!("Capture: missing capture list for function"),
         }
@@ -1070,7 +1523,7 @@ impl Vm {
         Ok(())
     }
 
-    fn run_negate(&mut self, register: u8, value: u8) -> InstructionResult {
+    fn run_negate(&mut self, register: u16, value: u16) -> InstructionResult {
         use Value::*;
 
         let result = match &self.get_register(value) {
@@ -1078,6 +1531,10 @@ impl Vm {
             Number(n) => Number(-n),
             Num2(v) => Num2(-v),
             Num4(v) => Num4(-v),
+            Map(map) if map.data().get_meta_value(MetaKeyId::Negate).is_some() => {
+                let map = map.clone();
+                return self.call_overloaded_operator(register, MetaKeyId::Negate, map, &[]);
+            }
             unexpected => {
                 return self.unexpected_type_error("Negate: expected negatable value", unexpected);
             }
@@ -1089,28 +1546,41 @@ impl Vm {
 
     fn run_add(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
 
+        // Maps can be added together, so check for an overloaded @+ before using add_values
+        if let Value::Map(map) = lhs_value {
+            if map.data().get_meta_value(MetaKeyId::Add).is_some() {
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::Add,
+                    instruction,
+                );
+            }
+        }
+
         match add_values(lhs_value, rhs_value) {
             Some(result) => {
                 self.set_register(register, result);
                 Ok(())
             }
-            None => self.binary_op_error(lhs_value, rhs_value, instruction),
+            None => self.run_overloaded_binary_op(register, lhs, rhs, MetaKeyId::Add, instruction),
         }
     }
 
     fn run_subtract(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         use Value::*;
@@ -1126,7 +1596,13 @@ impl Vm {
             (Num4(a), Num4(b)) => Num4(a - b),
             (Num4(a), Number(b)) => Num4(a - b),
             _ => {
-                return self.binary_op_error(lhs_value, rhs_value, instruction);
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::Subtract,
+                    instruction,
+                );
             }
         };
         self.set_register(register, result);
@@ -1136,9 +1612,9 @@ impl Vm {
 
     fn run_multiply(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         let lhs_value = self.get_register(lhs);
@@ -1149,15 +1625,17 @@ impl Vm {
                 self.set_register(register, result);
                 Ok(())
             }
-            None => self.binary_op_error(lhs_value, rhs_value, instruction),
+            None => {
+                self.run_overloaded_binary_op(register, lhs, rhs, MetaKeyId::Multiply, instruction)
+            }
         }
     }
 
     fn run_divide(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         use Value::*;
@@ -1173,7 +1651,13 @@ impl Vm {
             (Num4(a), Num4(b)) => Num4(a / b),
             (Num4(a), Number(b)) => Num4(a / b),
             _ => {
-                return self.binary_op_error(lhs_value, rhs_value, instruction);
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::Divide,
+                    instruction,
+                );
             }
         };
         self.set_register(register, result);
@@ -1183,9 +1667,9 @@ impl Vm {
 
     fn run_modulo(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         use Value::*;
@@ -1201,7 +1685,13 @@ impl Vm {
             (Num4(a), Num4(b)) => Num4(a % b),
             (Num4(a), Number(b)) => Num4(a % b),
             _ => {
-                return self.binary_op_error(lhs_value, rhs_value, instruction);
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::Modulo,
+                    instruction,
+                );
             }
         };
         self.set_register(register, result);
@@ -1211,9 +1701,9 @@ impl Vm {
 
     fn run_less(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         use Value::*;
@@ -1224,7 +1714,13 @@ impl Vm {
             (Number(a), Number(b)) => Bool(a < b),
             (Str(a), Str(b)) => Bool(a.as_str() < b.as_str()),
             _ => {
-                return self.binary_op_error(lhs_value, rhs_value, instruction);
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::Less,
+                    instruction,
+                );
             }
         };
         self.set_register(register, result);
@@ -1234,9 +1730,9 @@ impl Vm {
 
     fn run_less_or_equal(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         use Value::*;
@@ -1247,7 +1743,13 @@ impl Vm {
             (Number(a), Number(b)) => Bool(a <= b),
             (Str(a), Str(b)) => Bool(a.as_str() <= b.as_str()),
             _ => {
-                return self.binary_op_error(lhs_value, rhs_value, instruction);
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::LessOrEqual,
+                    instruction,
+                );
             }
         };
         self.set_register(register, result);
@@ -1257,9 +1759,9 @@ impl Vm {
 
     fn run_greater(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         use Value::*;
@@ -1270,7 +1772,13 @@ impl Vm {
             (Number(a), Number(b)) => Bool(a > b),
             (Str(a), Str(b)) => Bool(a.as_str() > b.as_str()),
             _ => {
-                return self.binary_op_error(lhs_value, rhs_value, instruction);
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::Greater,
+                    instruction,
+                );
             }
         };
         self.set_register(register, result);
@@ -1280,9 +1788,9 @@ impl Vm {
 
     fn run_greater_or_equal(
         &mut self,
-        register: u8,
-        lhs: u8,
-        rhs: u8,
+        register: u16,
+        lhs: u16,
+        rhs: u16,
         instruction: &Instruction,
     ) -> InstructionResult {
         use Value::*;
@@ -1293,7 +1801,13 @@ impl Vm {
             (Number(a), Number(b)) => Bool(a >= b),
             (Str(a), Str(b)) => Bool(a.as_str() >= b.as_str()),
             _ => {
-                return self.binary_op_error(lhs_value, rhs_value, instruction);
+                return self.run_overloaded_binary_op(
+                    register,
+                    lhs,
+                    rhs,
+                    MetaKeyId::GreaterOrEqual,
+                    instruction,
+                );
             }
         };
         self.set_register(register, result);
@@ -1301,23 +1815,68 @@ impl Vm {
         Ok(())
     }
 
-    fn run_equal(&mut self, register: u8, lhs: u8, rhs: u8) {
+    fn run_equal(&mut self, register: u16, lhs: u16, rhs: u16) -> InstructionResult {
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
+
+        if let Value::Map(map) = lhs_value {
+            if map.data().get_meta_value(MetaKeyId::Equal).is_some() {
+                let map = map.clone();
+                let rhs_value = rhs_value.clone();
+                return self.call_overloaded_operator(
+                    register,
+                    MetaKeyId::Equal,
+                    map,
+                    &[rhs_value],
+                );
+            }
+        }
+
         let result = (lhs_value == rhs_value).into();
         self.set_register(register, result);
+        Ok(())
     }
 
-    fn run_not_equal(&mut self, register: u8, lhs: u8, rhs: u8) {
+    fn run_not_equal(&mut self, register: u16, lhs: u16, rhs: u16) -> InstructionResult {
         let lhs_value = self.get_register(lhs);
         let rhs_value = self.get_register(rhs);
+
+        if let Value::Map(map) = lhs_value {
+            if map.data().get_meta_value(MetaKeyId::NotEqual).is_some() {
+                let map = map.clone();
+                let rhs_value = rhs_value.clone();
+                return self.call_overloaded_operator(
+                    register,
+                    MetaKeyId::NotEqual,
+                    map,
+                    &[rhs_value],
+                );
+            }
+
+            // Fall back to negating @== when @!= isn't implemented
+            if map.data().get_meta_value(MetaKeyId::Equal).is_some() {
+                let map = map.clone();
+                let rhs_value = rhs_value.clone();
+                let result = match self.run_meta_function(MetaKeyId::Equal, map, &[rhs_value])? {
+                    Value::Bool(equal) => Value::Bool(!equal),
+                    unexpected => {
+                        return self
+                            .unexpected_type_error("@==: Expected Bool as result", &unexpected)
+                    }
+                };
+                self.set_register(register, result);
+                return Ok(());
+            }
+        }
+
         let result = (lhs_value != rhs_value).into();
         self.set_register(register, result);
+        Ok(())
     }
 
     fn run_jump_if(
         &mut self,
-        register: u8,
+        register: u16,
         offset: usize,
         jump_condition: bool,
     ) -> InstructionResult {
@@ -1336,7 +1895,7 @@ impl Vm {
 
     fn run_jump_back_if(
         &mut self,
-        register: u8,
+        register: u16,
         offset: usize,
         jump_condition: bool,
     ) -> InstructionResult {
@@ -1353,14 +1912,14 @@ impl Vm {
         Ok(())
     }
 
-    fn run_size(&mut self, register: u8, value: u8) {
+    fn run_size(&mut self, register: u16, value: u16) {
         let result = value_size(self.get_register(value));
         self.set_register(register, Value::Number(result.into()));
     }
 
     fn run_import(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         import_constant: ConstantIndex,
     ) -> InstructionResult {
         let import_name = self.value_string_from_constant(import_constant);
@@ -1383,22 +1942,57 @@ impl Vm {
             if let Some(value) = maybe_in_prelude {
                 self.set_register(result_register, value);
             } else {
+                if CoreModule::from_name(&import_name).is_some() {
+                    return vm_error!(
+                        "Failed to import '{}': the '{}' module has been disabled",
+                        import_name,
+                        import_name
+                    );
+                }
+
                 let source_path = self.reader.chunk.source_path.clone();
-                let compile_result = self
-                    .context_mut()
+                let imports = self.context().imports.clone();
+
+                let resolve_result = imports
+                    .lock()
                     .loader
-                    .compile_module(&import_name, source_path);
-                let (module_chunk, module_path) = match compile_result {
-                    Ok(chunk) => chunk,
+                    .resolve_module(&import_name, source_path.as_deref());
+                let module = match resolve_result {
+                    Ok(module) => module,
                     Err(e) => return vm_error!("Failed to import '{}': {}", import_name, e),
                 };
-                let maybe_module = self.context().modules.get(&module_path).cloned();
+
+                self.check_import_is_allowed(&import_name, &module)?;
+
+                let module_id = module.id.clone();
+                {
+                    let mut imports = imports.lock();
+
+                    if let Some(importing_path) = &source_path {
+                        imports.add_dependent(&module_id, importing_path);
+                    }
+
+                    if self.context_shared.settings.reload_changed_modules {
+                        imports.invalidate_changed();
+                    }
+                }
+
+                let maybe_module = imports.lock().get(&module_id);
                 match maybe_module {
                     Some(Some(module)) => self.set_register(result_register, Value::Map(module)),
                     Some(None) => return vm_error!("Recursive import of module '{}'", import_name),
                     None => {
+                        let file_path = module.file_path.clone();
+                        let load_result = imports.lock().loader.load_module(module);
+                        let module_chunk = match load_result {
+                            Ok((chunk, _)) => chunk,
+                            Err(e) => {
+                                return vm_error!("Failed to import '{}': {}", import_name, e)
+                            }
+                        };
+
                         // Insert a placeholder for the new module, preventing recursive imports
-                        self.context_mut().modules.insert(module_path.clone(), None);
+                        imports.lock().begin_import(&module_id, file_path);
 
                         // Run the module chunk
                         let mut vm = self.spawn_new_vm();
@@ -1406,22 +2000,22 @@ impl Vm {
                             Ok(_) => {
                                 if let Some(main) = vm.get_global_function("main") {
                                     if let Err(error) = vm.run_function(main, &[]) {
-                                        self.context_mut().modules.remove(&module_path);
+                                        imports.lock().cancel_import(&module_id);
                                         return Err(error);
                                     }
                                 }
                             }
                             Err(error) => {
-                                self.context_mut().modules.remove(&module_path);
+                                imports.lock().cancel_import(&module_id);
                                 return Err(error);
                             }
                         }
 
                         // Cache the resulting module's global map
                         let module_global = vm.context().global.clone();
-                        self.context_mut()
-                            .modules
-                            .insert(module_path, Some(module_global.clone()));
+                        imports
+                            .lock()
+                            .finish_import(&module_id, module_global.clone());
 
                         self.set_register(result_register, Value::Map(module_global));
                     }
@@ -1432,11 +2026,48 @@ impl Vm {
         Ok(())
     }
 
+    // Checks that the VM's capabilities allow a resolved module to be imported
+    fn check_import_is_allowed(
+        &self,
+        import_name: &str,
+        module: &ResolvedModule,
+    ) -> InstructionResult {
+        let file_path = match &module.file_path {
+            Some(file_path) => file_path,
+            // Virtual modules aren't affected by file access restrictions
+            None => return Ok(()),
+        };
+
+        match self
+            .context_shared
+            .settings
+            .capabilities
+            .module_file_access()
+        {
+            ModuleFileAccess::Unrestricted => Ok(()),
+            ModuleFileAccess::Disabled => vm_error!(
+                "Failed to import '{}': importing modules from files has been disabled",
+                import_name
+            ),
+            access @ ModuleFileAccess::AllowedDirectories(_) => {
+                if access.is_path_allowed(file_path) {
+                    Ok(())
+                } else {
+                    vm_error!(
+                        "Failed to import '{}': '{}' is outside of the allowed module directories",
+                        import_name,
+                        file_path.display()
+                    )
+                }
+            }
+        }
+    }
+
     fn run_make_num2(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         element_count: u8,
-        element_register: u8,
+        element_register: u16,
     ) -> InstructionResult {
         use Value::*;
 
@@ -1465,7 +2096,7 @@ impl Vm {
         } else {
             let mut result = num2::Num2::default();
             for i in 0..element_count {
-                match self.get_register(element_register + i) {
+                match self.get_register(element_register + i as u16) {
                     Number(n) => result[i as usize] = n.into(),
                     unexpected => {
                         return self.unexpected_type_error(
@@ -1484,9 +2115,9 @@ impl Vm {
 
     fn run_make_num4(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         element_count: u8,
-        element_register: u8,
+        element_register: u16,
     ) -> InstructionResult {
         use Value::*;
         let result = if element_count == 1 {
@@ -1518,7 +2149,7 @@ impl Vm {
         } else {
             let mut result = num4::Num4::default();
             for i in 0..element_count {
-                match self.get_register(element_register + i) {
+                match self.get_register(element_register + i as u16) {
                     Number(n) => result[i as usize] = n.into(),
                     unexpected => {
                         return self.unexpected_type_error(
@@ -1535,7 +2166,7 @@ impl Vm {
         Ok(())
     }
 
-    fn run_list_push(&mut self, list_register: u8, value_register: u8) -> InstructionResult {
+    fn run_list_push(&mut self, list_register: u16, value_register: u16) -> InstructionResult {
         use Value::*;
 
         let value = self.clone_register(value_register);
@@ -1562,9 +2193,9 @@ impl Vm {
 
     fn run_list_update(
         &mut self,
-        list_register: u8,
-        index_register: u8,
-        value_register: u8,
+        list_register: u16,
+        index_register: u16,
+        value_register: u16,
     ) -> InstructionResult {
         use Value::*;
 
@@ -1714,9 +2345,9 @@ impl Vm {
 
     fn run_index(
         &mut self,
-        result_register: u8,
-        value_register: u8,
-        index_register: u8,
+        result_register: u16,
+        value_register: u16,
+        index_register: u16,
     ) -> InstructionResult {
         use Value::*;
 
@@ -1773,10 +2404,13 @@ impl Vm {
             (Num4(n), Number(i)) => {
                 let i = usize::from(i);
                 match i {
-                    0 | 1 | 2 | 3 => self.set_register(result_register, Number(n[i].into())),
+                    0..=3 => self.set_register(result_register, Number(n[i].into())),
                     other => return vm_error!("Index out of bounds for Num4, {}", other),
                 }
             }
+            (Map(map), index) if map.data().get_meta_value(MetaKeyId::Index).is_some() => {
+                self.call_overloaded_operator(result_register, MetaKeyId::Index, map, &[index])?;
+            }
             (unexpected_value, unexpected_index) => {
                 return vm_error!(
                     "Unable to index '{}' with '{}'",
@@ -1791,8 +2425,8 @@ impl Vm {
 
     fn run_map_insert(
         &mut self,
-        map_register: u8,
-        value: u8,
+        map_register: u16,
+        value: u16,
         key: ConstantIndex,
     ) -> InstructionResult {
         let key_string = self.value_string_from_constant(key);
@@ -1805,15 +2439,35 @@ impl Vm {
             }
             unexpected => vm_error!(
                 "MapInsert: Expected Map, found '{}'",
-                type_as_string(&unexpected)
+                type_as_string(unexpected)
+            ),
+        }
+    }
+
+    fn run_meta_insert(
+        &mut self,
+        map_register: u16,
+        value: u16,
+        id: MetaKeyId,
+    ) -> InstructionResult {
+        let value = self.clone_register(value);
+
+        match self.get_register_mut(map_register) {
+            Value::Map(map) => {
+                map.data_mut().insert_meta_value(id, value);
+                Ok(())
+            }
+            unexpected => vm_error!(
+                "MetaInsert: Expected Map, found '{}'",
+                type_as_string(unexpected)
             ),
         }
     }
 
     fn run_access(
         &mut self,
-        result_register: u8,
-        map_register: u8,
+        result_register: u16,
+        map_register: u16,
         key: ConstantIndex,
     ) -> InstructionResult {
         use Value::*;
@@ -1831,10 +2485,10 @@ impl Vm {
                 )?;
                 self.set_register(result_register, op);
             }};
-        };
+        }
 
         match map_value {
-            Map(map) => match map.data().get_with_string(&key_string) {
+            Map(map) => match map.data().get_with_string(key_string) {
                 Some(value) => {
                     self.set_register(result_register, value.clone());
                 }
@@ -1871,7 +2525,7 @@ impl Vm {
                 .core_lib
                 .iterator
                 .data()
-                .get_with_string(&key)
+                .get_with_string(key)
                 .cloned(),
             maybe_op => maybe_op,
         };
@@ -1919,11 +2573,12 @@ impl Vm {
 
     fn call_external_function(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         external_function: ExternalFunction,
-        frame_base: u8,
+        frame_base: u16,
         call_arg_count: u8,
-        instance_register: Option<u8>,
+        instance_register: Option<u16>,
+        named_args: Option<ValueMap>,
     ) -> InstructionResult {
         let function = external_function.function.as_ref();
 
@@ -1944,21 +2599,23 @@ impl Vm {
             frame_base + 1
         };
 
-        let result = (&*function)(
-            self,
-            &Args {
-                register: adjusted_frame_base,
-                count: call_arg_count,
-            },
-        );
+        let args = Args::new(adjusted_frame_base, call_arg_count, named_args);
+        let result = (*function)(self, &args);
 
         match result {
             Ok(value) => {
+                if let Some(name) = args.unread_named_arg() {
+                    return vm_error!("The function has no argument named '{}'", name);
+                }
+
                 self.set_register(result_register, value);
                 // External function calls don't use the push/pop frame mechanism,
                 // so drop the function args here now that the call has been completed.
                 self.truncate_registers(frame_base);
             }
+            // Thrown values are passed through unchanged so that they can be caught by the caller
+            Err(error @ RuntimeError::ThrownValue { .. }) => return Err(error),
+            Err(error) if !error.is_catchable() => return Err(error),
             Err(error) => return vm_error!(error.to_string()),
         }
 
@@ -1967,11 +2624,12 @@ impl Vm {
 
     fn call_generator(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         function: RuntimeFunction,
-        frame_base: u8,
+        frame_base: u16,
         call_arg_count: u8,
-        instance_register: Option<u8>,
+        instance_register: Option<u16>,
+        omitted_args: u64,
     ) -> InstructionResult {
         let RuntimeFunction {
             chunk,
@@ -1980,6 +2638,7 @@ impl Vm {
             instance_function,
             variadic,
             captures,
+            ..
         } = function;
 
         // Spawn a VM for the generator
@@ -1990,6 +2649,11 @@ impl Vm {
             function_ip,
             0, // arguments will be copied starting in register 0
         );
+        generator_vm.frame_mut().omitted_args = omitted_args;
+        // The generator's frame isn't active until the generator is iterated
+        if let Some(profiler) = &generator_vm.profiler {
+            profiler.suspend(&generator_vm.profile_tokens());
+        }
 
         let expected_arg_count = match (instance_function, variadic) {
             (true, true) => function_arg_count - 2,
@@ -2016,11 +2680,11 @@ impl Vm {
             if call_arg_count >= expected_arg_count {
                 // Capture the varargs into a tuple and place them in the
                 // generator vm's last arg register
-                let varargs_start = frame_base + 1 + expected_arg_count;
+                let varargs_start = frame_base + 1 + expected_arg_count as u16;
                 let varargs_count = call_arg_count - expected_arg_count;
                 let varargs =
                     Value::Tuple(self.register_slice(varargs_start, varargs_count).into());
-                generator_vm.set_register(expected_arg_count + arg_offset, varargs);
+                generator_vm.set_register(expected_arg_count as u16 + arg_offset, varargs);
             } else {
                 return vm_error!(
                     "Insufficient arguments for function call, expected {}, found {}",
@@ -2043,14 +2707,14 @@ impl Vm {
             .cloned()
             .enumerate()
         {
-            generator_vm.set_register(arg_index as u8 + arg_offset, arg);
+            generator_vm.set_register(arg_index as u16 + arg_offset, arg);
         }
 
         if let Some(captures) = captures {
             // Copy the function's captures into the generator vm
-            let capture_offset = arg_offset + expected_arg_count;
+            let capture_offset = arg_offset + expected_arg_count as u16;
             for (capture_index, capture) in captures.data().iter().cloned().enumerate() {
-                generator_vm.set_register(capture_index as u8 + capture_offset, capture);
+                generator_vm.set_register(capture_index as u16 + capture_offset, capture);
             }
         }
 
@@ -2063,13 +2727,63 @@ impl Vm {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn run_call(
+        &mut self,
+        result_register: u16,
+        function_register: u16,
+        frame_base: u16,
+        arg_count: u8,
+        instance_register: Option<u16>,
+        tail_call: bool,
+        named_args: bool,
+    ) -> InstructionResult {
+        let function = self.clone_register(function_register);
+
+        // Named args are placed in a map in the register following the positional args
+        let named_args = if named_args {
+            match self.clone_register(frame_base + 1 + arg_count as u16) {
+                Value::Map(map) => Some(map),
+                unexpected => {
+                    return self.unexpected_type_error("Expected named args map", &unexpected)
+                }
+            }
+        } else {
+            None
+        };
+
+        // A tail call reuses the current frame when calling a Koto function,
+        // unless the frame is in a try block that needs to catch errors from the call.
+        // Otherwise the call is made as usual, with the result then being returned by the
+        // instructions that follow the call.
+        let reuse_frame = tail_call
+            && matches!(function, Value::Function(_))
+            && self.frame().catch_stack.is_empty();
+
+        self.call_function(
+            result_register,
+            function,
+            frame_base,
+            arg_count,
+            instance_register,
+            named_args,
+        )?;
+
+        if reuse_frame {
+            self.replace_frame_for_tail_call();
+        }
+
+        Ok(())
+    }
+
     fn call_function(
         &mut self,
-        result_register: u8,
+        result_register: u16,
         function: Value,
-        frame_base: u8,
+        frame_base: u16,
         call_arg_count: u8,
-        instance_register: Option<u8>,
+        instance_register: Option<u16>,
+        named_args: Option<ValueMap>,
     ) -> InstructionResult {
         use Value::*;
 
@@ -2080,22 +2794,42 @@ impl Vm {
                 frame_base,
                 call_arg_count,
                 instance_register,
+                named_args,
             ),
-            Generator(runtime_function) => self.call_generator(
-                result_register,
-                runtime_function,
-                frame_base,
-                call_arg_count,
-                instance_register,
-            ),
-            Function(RuntimeFunction {
-                chunk,
-                ip: function_ip,
-                arg_count: function_arg_count,
-                instance_function,
-                variadic,
-                captures,
-            }) => {
+            Generator(runtime_function) => {
+                let (call_arg_count, omitted_args) = self.prepare_call_args(
+                    &runtime_function,
+                    frame_base,
+                    call_arg_count,
+                    named_args,
+                )?;
+                self.call_generator(
+                    result_register,
+                    runtime_function,
+                    frame_base,
+                    call_arg_count,
+                    instance_register,
+                    omitted_args,
+                )
+            }
+            Function(runtime_function) => {
+                let (call_arg_count, omitted_args) = self.prepare_call_args(
+                    &runtime_function,
+                    frame_base,
+                    call_arg_count,
+                    named_args,
+                )?;
+
+                let RuntimeFunction {
+                    chunk,
+                    ip: function_ip,
+                    arg_count: function_arg_count,
+                    instance_function,
+                    variadic,
+                    captures,
+                    ..
+                } = runtime_function;
+
                 let expected_arg_count = match (instance_function, variadic) {
                     (true, true) => function_arg_count - 2,
                     (true, false) | (false, true) => function_arg_count - 1,
@@ -2125,7 +2859,7 @@ impl Vm {
                         // e.g. f = |x, y, z...|
                         // arg index 2 is the first vararg, and where the tuple will be placed
                         let arg_base = frame_base + 1;
-                        let varargs_start = arg_base + expected_arg_count;
+                        let varargs_start = arg_base + expected_arg_count as u16;
                         let varargs_count = call_arg_count - expected_arg_count;
                         let varargs =
                             Value::Tuple(self.register_slice(varargs_start, varargs_count).into());
@@ -2156,7 +2890,7 @@ impl Vm {
                     //    uninitialized, so using .extend() here for the captures would place them
                     //    in the wrong position.
                     let captures_start =
-                        self.register_index(adjusted_frame_base + function_arg_count);
+                        self.register_index(adjusted_frame_base + function_arg_count as u16);
                     self.value_stack.resize(captures_start, Value::Empty);
                     self.value_stack.extend(captures.data().iter().cloned());
                 }
@@ -2168,6 +2902,7 @@ impl Vm {
 
                 // Set up a new frame for the called function
                 self.push_frame(chunk, function_ip, adjusted_frame_base);
+                self.frame_mut().omitted_args = omitted_args;
 
                 Ok(())
             }
@@ -2175,7 +2910,105 @@ impl Vm {
         }
     }
 
-    fn run_debug(&self, register: u8, constant: ConstantIndex, instruction_ip: usize) {
+    // Places any named args in their arg registers, and fills in any omitted optional args
+    //
+    // Omitted optional args are set to Empty, and are included in the returned mask of omitted
+    // arg registers, which causes the function's default values to be used. The returned count
+    // is the number of args that the function will receive, excluding the instance.
+    fn prepare_call_args(
+        &mut self,
+        function: &RuntimeFunction,
+        frame_base: u16,
+        call_arg_count: u8,
+        named_args: Option<ValueMap>,
+    ) -> Result<(u8, u64), RuntimeError> {
+        let expected_arg_count =
+            function.arg_count - function.instance_function as u8 - function.variadic as u8;
+        let required_arg_count = expected_arg_count - function.optional_arg_count;
+        let arg_base = frame_base + 1;
+        // The register in the function's frame of the first arg, following the instance
+        let first_arg_register = function.instance_function as u16;
+        let omitted_arg_bit = |arg_index: u8| 1u64 << (first_arg_register + arg_index as u16);
+
+        let mut call_arg_count = call_arg_count;
+        let mut omitted_args = 0;
+
+        if let Some(named_args) = named_args {
+            let arg_name = |arg_index: u8| function.arg_name(first_arg_register + arg_index as u16);
+
+            let mut provided = vec![false; expected_arg_count as usize];
+            let mut named_arg_count = call_arg_count;
+            let mut placed_args = Vec::with_capacity(named_args.len());
+
+            for (name, value) in named_args.data().iter() {
+                let name = match name {
+                    Value::Str(name) => name.as_str(),
+                    unexpected => {
+                        return self
+                            .unexpected_type_error("Expected String as arg name", unexpected)
+                    }
+                };
+
+                let arg_index = match (0..expected_arg_count).find(|i| arg_name(*i) == Some(name)) {
+                    Some(arg_index) => arg_index,
+                    None => return vm_error!("The function has no argument named '{}'", name),
+                };
+
+                if arg_index < call_arg_count || provided[arg_index as usize] {
+                    return vm_error!("The argument '{}' was provided more than once", name);
+                }
+
+                provided[arg_index as usize] = true;
+                named_arg_count = named_arg_count.max(arg_index + 1);
+                placed_args.push((arg_index, value.clone()));
+            }
+
+            // Any gaps between the positional and named args must be optional args
+            for arg_index in call_arg_count..named_arg_count {
+                if !provided[arg_index as usize] {
+                    if arg_index < required_arg_count {
+                        return vm_error!(
+                            "Missing value for argument '{}'",
+                            arg_name(arg_index).unwrap_or("_")
+                        );
+                    }
+                    self.set_register(arg_base + arg_index as u16, Value::Empty);
+                    omitted_args |= omitted_arg_bit(arg_index);
+                }
+            }
+
+            // The named args map's register may be overwritten here, so the values are
+            // placed after the map has been read.
+            for (arg_index, value) in placed_args {
+                self.set_register(arg_base + arg_index as u16, value);
+            }
+
+            call_arg_count = named_arg_count;
+        }
+
+        if call_arg_count < expected_arg_count && call_arg_count >= required_arg_count {
+            for arg_index in call_arg_count..expected_arg_count {
+                self.set_register(arg_base + arg_index as u16, Value::Empty);
+                omitted_args |= omitted_arg_bit(arg_index);
+            }
+            call_arg_count = expected_arg_count;
+        } else if call_arg_count < required_arg_count && function.optional_arg_count > 0 {
+            return vm_error!(
+                "Insufficient arguments for function call, expected at least {}, found {}",
+                required_arg_count,
+                call_arg_count,
+            );
+        }
+
+        Ok((call_arg_count, omitted_args))
+    }
+
+    fn run_debug(
+        &mut self,
+        register: u16,
+        constant: ConstantIndex,
+        instruction_ip: usize,
+    ) -> InstructionResult {
         let prefix = match (
             self.reader.chunk.debug_info.get_source_span(instruction_ip),
             self.reader.chunk.source_path.as_ref(),
@@ -2185,28 +3018,34 @@ impl Vm {
             (None, Some(path)) => format!("[{}: #ERR] ", path.display()),
             (None, None) => "[#ERR] ".to_string(),
         };
-        let value = self.get_register(register);
-        println!("{}{}: {}", prefix, self.get_constant_str(constant), value);
+        let value = self.clone_register(register);
+        let value_string = self.value_to_string(&value)?;
+        self.stdout().write_line(&format!(
+            "{}{}: {}",
+            prefix,
+            self.get_constant_str(constant),
+            value_string
+        ))
     }
 
-    fn run_check_type(&self, register: u8, type_id: TypeId) -> Result<(), RuntimeError> {
+    fn run_check_type(&self, register: u16, type_id: TypeId) -> Result<(), RuntimeError> {
         let value = self.get_register(register);
         match type_id {
             TypeId::List => {
                 if !matches!(value, Value::List(_)) {
-                    return self.unexpected_type_error("Expected List", &value);
+                    return self.unexpected_type_error("Expected List", value);
                 }
             }
             TypeId::Tuple => {
                 if !matches!(value, Value::Tuple(_)) {
-                    return self.unexpected_type_error("Expected Tuple", &value);
+                    return self.unexpected_type_error("Expected Tuple", value);
                 }
             }
         }
         Ok(())
     }
 
-    fn run_check_size(&self, register: u8, expected_size: usize) -> Result<(), RuntimeError> {
+    fn run_check_size(&self, register: u16, expected_size: usize) -> Result<(), RuntimeError> {
         let value_size = value_size(self.get_register(register));
 
         if value_size == expected_size {
@@ -2252,7 +3091,7 @@ impl Vm {
         self.call_stack.last_mut().expect("Empty call stack")
     }
 
-    fn push_frame(&mut self, chunk: Arc<Chunk>, ip: usize, frame_base: u8) {
+    fn push_frame(&mut self, chunk: Arc<Chunk>, ip: usize, frame_base: u16) {
         let previous_frame_base = if let Some(frame) = self.call_stack.last() {
             frame.register_base
         } else {
@@ -2260,18 +3099,51 @@ impl Vm {
         };
         let new_frame_base = previous_frame_base + frame_base as usize;
 
-        self.call_stack
-            .push(Frame::new(chunk.clone(), new_frame_base));
+        let mut frame = Frame::new(chunk.clone(), new_frame_base);
+        if let Some(profiler) = &self.profiler {
+            frame.profile_token = Some(profiler.enter(&chunk, ip));
+        }
+        self.call_stack.push(frame);
         self.set_chunk_and_ip(chunk, ip);
     }
 
+    // Replaces the calling frame with the frame that was just pushed for a tail call
+    //
+    // The new frame's registers are moved down to the start of the caller's registers, so that
+    // recursive tail calls run in constant space. The caller's caller is left unchanged, so the
+    // new frame returns directly to it.
+    fn replace_frame_for_tail_call(&mut self) {
+        let mut frame = self.call_stack.pop().expect("Empty call stack");
+        let caller = self.call_stack.pop().expect("Missing caller for tail call");
+
+        let registers_end = frame.register_base.min(self.value_stack.len());
+        let registers_start = caller.register_base.min(registers_end);
+        self.value_stack.drain(registers_start..registers_end);
+
+        frame.register_base = caller.register_base;
+        frame.catch_barrier = caller.catch_barrier;
+        // The caller's profiled call exits along with the new frame's call
+        if let (Some(profiler), Some(token), Some(caller_token)) =
+            (&self.profiler, frame.profile_token, caller.profile_token)
+        {
+            frame.profile_token = Some(profiler.tail_call(token, caller_token));
+        }
+
+        self.call_stack.push(frame);
+    }
+
     fn pop_frame(&mut self, return_value: Value) -> Result<Option<Value>, RuntimeError> {
         self.truncate_registers(0);
 
-        if self.call_stack.pop().is_none() {
-            return vm_error!("pop_frame: Empty call stack");
+        let frame = match self.call_stack.pop() {
+            Some(frame) => frame,
+            None => return vm_error!("pop_frame: Empty call stack"),
         };
 
+        if let (Some(profiler), Some(token)) = (&self.profiler, frame.profile_token) {
+            profiler.exit(token);
+        }
+
         if !self.call_stack.is_empty() && self.frame().return_register_and_ip.is_some() {
             let (return_register, return_ip) = self.frame().return_register_and_ip.unwrap();
 
@@ -2291,11 +3163,11 @@ impl Vm {
         }
     }
 
-    fn register_index(&self, register: u8) -> usize {
+    fn register_index(&self, register: u16) -> usize {
         self.register_base() + register as usize
     }
 
-    fn set_register(&mut self, register: u8, value: Value) {
+    fn set_register(&mut self, register: u16, value: Value) {
         let index = self.register_index(register);
 
         if index >= self.value_stack.len() {
@@ -2305,11 +3177,11 @@ impl Vm {
         self.value_stack[index] = value;
     }
 
-    fn clone_register(&self, register: u8) -> Value {
+    fn clone_register(&self, register: u16) -> Value {
         self.get_register(register).clone()
     }
 
-    fn get_register(&self, register: u8) -> &Value {
+    fn get_register(&self, register: u16) -> &Value {
         let index = self.register_index(register);
         match self.value_stack.get(index) {
             Some(value) => value,
@@ -2324,12 +3196,12 @@ impl Vm {
         }
     }
 
-    fn get_register_mut(&mut self, register: u8) -> &mut Value {
+    fn get_register_mut(&mut self, register: u16) -> &mut Value {
         let index = self.register_index(register);
         &mut self.value_stack[index]
     }
 
-    pub fn register_slice(&self, register: u8, count: u8) -> &[Value] {
+    pub fn register_slice(&self, register: u16, count: u8) -> &[Value] {
         if count > 0 {
             let start = self.register_index(register);
             &self.value_stack[start..start + count as usize]
@@ -2338,7 +3210,7 @@ impl Vm {
         }
     }
 
-    fn truncate_registers(&mut self, len: u8) {
+    fn truncate_registers(&mut self, len: u16) {
         self.value_stack
             .truncate(self.register_base() + len as usize);
     }
@@ -2358,7 +3230,161 @@ impl Vm {
     }
 
     fn unexpected_type_error<T>(&self, message: &str, value: &Value) -> Result<T, RuntimeError> {
-        vm_error!("{}, found '{}'", message, type_as_string(&value))
+        vm_error!("{}, found '{}'", message, type_as_string(value))
+    }
+
+    // Calls the lhs value's overloaded implementation of a binary operator (e.g. @+),
+    // falling back to an error if no implementation is available.
+    fn run_overloaded_binary_op(
+        &mut self,
+        result_register: u16,
+        lhs: u16,
+        rhs: u16,
+        meta_key: MetaKeyId,
+        instruction: &Instruction,
+    ) -> InstructionResult {
+        match self.get_register(lhs) {
+            Value::Map(map) if map.data().get_meta_value(meta_key).is_some() => {
+                let map = map.clone();
+                let rhs_value = self.clone_register(rhs);
+                self.call_overloaded_operator(result_register, meta_key, map, &[rhs_value])
+            }
+            lhs_value => self.binary_op_error(lhs_value, self.get_register(rhs), instruction),
+        }
+    }
+
+    // Calls a map's implementation of an operator, placing the result in the result register
+    //
+    // The map is passed to the meta function as 'self', followed by any remaining operands.
+    fn call_overloaded_operator(
+        &mut self,
+        result_register: u16,
+        meta_key: MetaKeyId,
+        map: ValueMap,
+        args: &[Value],
+    ) -> InstructionResult {
+        let result = self.run_meta_function(meta_key, map, args)?;
+        self.set_register(result_register, result);
+        Ok(())
+    }
+
+    fn run_meta_function(
+        &mut self,
+        meta_key: MetaKeyId,
+        map: ValueMap,
+        args: &[Value],
+    ) -> RuntimeResult {
+        use Value::*;
+
+        let function = match map.data().get_meta_value(meta_key) {
+            Some(function) => function.clone(),
+            None => return vm_error!("Missing '{}' in map", meta_key),
+        };
+
+        let mut vm = self.spawn_shared_vm();
+        match &function {
+            Function(RuntimeFunction {
+                instance_function: true,
+                ..
+            })
+            | ExternalFunction(external::ExternalFunction {
+                is_instance_function: true,
+                ..
+            }) => vm.run_instance_function(Map(map), function, args),
+            _ => {
+                let mut function_args = Vec::with_capacity(args.len() + 1);
+                function_args.push(Map(map));
+                function_args.extend_from_slice(args);
+                vm.run_function(function, &function_args)
+            }
+        }
+    }
+
+    /// Converts a value to a string, using the value's @display implementation if available
+    ///
+    /// @display implementations are also used for values contained in lists, tuples, and maps.
+    pub fn value_to_string(&mut self, value: &Value) -> Result<String, RuntimeError> {
+        let mut result = String::new();
+        self.write_value_string(value, &mut result, false)?;
+        Ok(result)
+    }
+
+    // Writes the value's string representation to the output string
+    //
+    // Nested strings are quoted, matching the Display implementations for container values.
+    fn write_value_string(
+        &mut self,
+        value: &Value,
+        output: &mut String,
+        nested: bool,
+    ) -> Result<(), RuntimeError> {
+        use Value::*;
+
+        match value {
+            Map(map) if map.data().get_meta_value(MetaKeyId::Display).is_some() => {
+                match self.run_meta_function(MetaKeyId::Display, map.clone(), &[])? {
+                    Str(s) => output.push_str(&s),
+                    unexpected => {
+                        return self.unexpected_type_error(
+                            "@display: Expected String as result",
+                            &unexpected,
+                        )
+                    }
+                }
+            }
+            Map(map) => {
+                // The map's data is cloned to avoid holding its lock while calling @display
+                let entries: Vec<_> = map
+                    .data()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                output.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(", ");
+                    }
+                    output.push_str(&key.to_string());
+                    output.push_str(": ");
+                    self.write_value_string(value, output, true)?;
+                }
+                output.push('}');
+            }
+            List(list) => {
+                let values = list.data().clone();
+                self.write_sequence_string(&values, '[', ']', output)?;
+            }
+            Tuple(tuple) => {
+                let values = tuple.data().to_vec();
+                self.write_sequence_string(&values, '(', ')', output)?;
+            }
+            Str(s) if nested => {
+                output.push('"');
+                output.push_str(s);
+                output.push('"');
+            }
+            _ => output.push_str(&value.to_string()),
+        }
+
+        Ok(())
+    }
+
+    fn write_sequence_string(
+        &mut self,
+        values: &[Value],
+        start: char,
+        end: char,
+        output: &mut String,
+    ) -> Result<(), RuntimeError> {
+        output.push(start);
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                output.push_str(", ");
+            }
+            self.write_value_string(value, output, true)?;
+        }
+        output.push(end);
+        Ok(())
     }
 
     fn binary_op_error(&self, lhs: &Value, rhs: &Value, op: &Instruction) -> InstructionResult {
@@ -2379,8 +3405,60 @@ impl fmt::Debug for Vm {
 
 fn signed_index_to_unsigned(index: i8, size: usize) -> usize {
     if index < 0 {
-        size - (index.abs() as usize).min(size)
+        size - (index.unsigned_abs() as usize).min(size)
     } else {
         index as usize
     }
 }
+
+// Returns the test functions in a map of tests, with the `test_` prefix removed from their names
+fn test_functions(tests: &ValueMap) -> Vec<(String, Value)> {
+    tests
+        .cloned_iter()
+        .filter_map(|(key, value)| match key {
+            Value::Str(id) if id.starts_with("test_") && value_is_callable(&value) => {
+                Some((id[5..].to_string(), value))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// Returns the value that's passed to a catch block for the given error
+//
+// Thrown values are passed unchanged, other errors are converted into a map containing the
+// error's message and backtrace.
+fn catch_value(error: RuntimeError) -> Value {
+    match error {
+        RuntimeError::ThrownValue { value, .. } => value,
+        error => {
+            let backtrace = error
+                .backtrace()
+                .into_iter()
+                .map(|frame| {
+                    let mut frame_map = ValueMap::with_capacity(4);
+                    frame_map.add_value(
+                        "function",
+                        frame
+                            .function
+                            .map_or(Value::Empty, |name| Value::Str(name.into())),
+                    );
+                    frame_map.add_value(
+                        "path",
+                        frame.path.map_or(Value::Empty, |path| {
+                            Value::Str(path.to_string_lossy().to_string().into())
+                        }),
+                    );
+                    frame_map.add_value("line", Value::Number(frame.line.into()));
+                    frame_map.add_value("column", Value::Number(frame.column.into()));
+                    Value::Map(frame_map)
+                })
+                .collect::<ValueVec>();
+
+            let mut result = ValueMap::with_capacity(2);
+            result.add_value("message", Value::Str(format!("{:#}", error).into()));
+            result.add_value("backtrace", Value::Tuple(backtrace[..].into()));
+            Value::Map(result)
+        }
+    }
+}
@@ -0,0 +1,113 @@
+mod backtrace {
+    use {
+        koto_runtime::{Loader, RuntimeError, TraceFrame, Vm},
+        std::path::PathBuf,
+    };
+
+    fn run_script_with_error(script: &str, path: Option<&str>) -> RuntimeError {
+        let mut vm = Vm::default();
+
+        let mut loader = Loader::default();
+        let chunk = match loader.compile_script(script, &path.map(PathBuf::from)) {
+            Ok(chunk) => chunk,
+            Err(error) => panic!("Error while compiling script: {}", error),
+        };
+
+        match vm.run(chunk) {
+            Ok(result) => panic!("Unexpected success, result: {:#}", result),
+            Err(error) => error,
+        }
+    }
+
+    fn frame(function: Option<&str>, path: Option<&str>, line: u32, column: u32) -> TraceFrame {
+        TraceFrame {
+            function: function.map(String::from),
+            path: path.map(PathBuf::from),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn named_functions() {
+        let script = "
+bar = ||
+  1 + {}
+foo = |x|
+  x + bar()
+foo 42
+";
+        let error = run_script_with_error(script, Some("test.koto"));
+
+        assert_eq!(
+            error.backtrace(),
+            vec![
+                frame(Some("bar"), Some("test.koto"), 3, 8),
+                frame(Some("foo"), Some("test.koto"), 5, 11),
+                frame(None, Some("test.koto"), 6, 1),
+            ]
+        );
+
+        let message = error.to_string();
+        assert!(message.contains("test.koto - 3:8 in 'bar'"), "{}", message);
+        assert!(message.contains("test.koto - 5:11 in 'foo'"), "{}", message);
+    }
+
+    #[test]
+    fn functions_in_maps() {
+        let script = "
+m =
+  check: |n|
+    if n > 1
+      throw \"too big\"
+    n
+m.check 2
+";
+        let error = run_script_with_error(script, None);
+
+        assert_eq!(
+            error.backtrace(),
+            vec![frame(Some("check"), None, 5, 7), frame(None, None, 7, 3)]
+        );
+    }
+
+    #[test]
+    fn anonymous_functions() {
+        let script = "
+(0..3).each(|n| n / 0 + {}).to_tuple()
+";
+        let error = run_script_with_error(script, None);
+        let backtrace = error.backtrace();
+
+        assert!(!backtrace.is_empty());
+        assert!(backtrace.iter().all(|frame| frame.function.is_none()));
+    }
+
+    #[test]
+    fn tail_calls_replace_frames() {
+        let script = "
+bar = |n|
+  if n == 0
+    n + {}
+  else
+    bar n - 1
+foo = || bar 100
+foo()
+";
+        let error = run_script_with_error(script, None);
+
+        // The frames replaced by tail calls are omitted from the backtrace
+        assert_eq!(
+            error.backtrace(),
+            vec![frame(Some("bar"), None, 4, 10), frame(None, None, 8, 1)]
+        );
+    }
+
+    #[test]
+    fn external_error_has_no_backtrace() {
+        let error = RuntimeError::ExternalError {
+            message: "oops".into(),
+        };
+        assert!(error.backtrace().is_empty());
+    }
+}
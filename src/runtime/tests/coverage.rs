@@ -0,0 +1,103 @@
+mod coverage {
+    use {
+        koto_runtime::{Coverage, CoverageReport, Loader, Vm},
+        std::path::PathBuf,
+    };
+
+    const SCRIPT: &str = "
+f = |n|
+  if n > 0
+    n * 2
+  else
+    0
+x = 0
+for i in 0..3
+  x += f i
+";
+
+    fn run_with_coverage(coverage: &Coverage, script: &str, path: &str) {
+        let mut vm = Vm::default();
+        vm.set_coverage(Some(coverage.clone()));
+
+        let mut loader = Loader::default();
+        let chunk = match loader.compile_script(script, &Some(PathBuf::from(path))) {
+            Ok(chunk) => chunk,
+            Err(error) => panic!("Error while compiling script: {}", error),
+        };
+
+        if let Err(error) = vm.run(chunk) {
+            panic!("Error while running script: {}", error);
+        }
+    }
+
+    fn line_count(report: &CoverageReport, line: u32) -> Option<u64> {
+        report.files[0]
+            .lines
+            .iter()
+            .find(|(covered_line, _)| *covered_line == line)
+            .map(|(_, count)| *count)
+    }
+
+    #[test]
+    fn executed_lines() {
+        let coverage = Coverage::new();
+        run_with_coverage(&coverage, SCRIPT, "test.koto");
+        let report = coverage.report();
+
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(line_count(&report, 3), Some(3));
+        assert_eq!(line_count(&report, 4), Some(2));
+        assert_eq!(line_count(&report, 6), Some(1));
+        assert_eq!(line_count(&report, 7), Some(1));
+        // Lines without instructions aren't included
+        assert_eq!(line_count(&report, 5), None);
+    }
+
+    #[test]
+    fn lcov_output() {
+        let coverage = Coverage::new();
+        run_with_coverage(&coverage, "f = |n| n * 2\nx = 1", "test.koto");
+
+        let lcov = coverage.report().to_lcov();
+        assert!(lcov.starts_with("TN:\nSF:test.koto\n"));
+        assert!(lcov.contains("DA:2,1\n"));
+        assert!(lcov.ends_with("end_of_record\n"));
+    }
+
+    #[test]
+    fn uncalled_function() {
+        let coverage = Coverage::new();
+        let script = "
+f = ||
+  42
+x = 1
+";
+        run_with_coverage(&coverage, script, "test.koto");
+        let report = coverage.report();
+
+        assert_eq!(line_count(&report, 3), Some(0));
+        assert_eq!(line_count(&report, 4), Some(1));
+        assert!(report.to_lcov().contains("LH:2\n"));
+    }
+
+    #[test]
+    fn merged_scripts() {
+        let coverage = Coverage::new();
+        run_with_coverage(&coverage, SCRIPT, "test.koto");
+        run_with_coverage(&coverage, SCRIPT, "test.koto");
+        run_with_coverage(&coverage, "x = 1", "other.koto");
+        let report = coverage.report();
+
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].path, Some(PathBuf::from("other.koto")));
+        assert_eq!(report.files[1].path, Some(PathBuf::from("test.koto")));
+        assert_eq!(
+            report.files[1]
+                .lines
+                .iter()
+                .find(|(line, _)| *line == 3)
+                .map(|(_, count)| *count),
+            Some(6)
+        );
+    }
+}
@@ -0,0 +1,155 @@
+mod debugger {
+    use {
+        koto_runtime::{
+            Breakpoint, Breakpoints, DebugCommand, DebugState, Debugger, ExecutionLimit, Loader,
+            PauseReason, RuntimeError, Value, Vm,
+        },
+        parking_lot::Mutex,
+        std::sync::Arc,
+    };
+
+    const SCRIPT: &str = "
+f = |x|
+  y = x * 2
+  y + 1
+a = f 1
+b = f a
+a + b
+";
+
+    // A pause that was recorded by the test debugger, as (reason, line, locals)
+    type Pause = (PauseReason, u32, Vec<(String, Value)>);
+
+    // Runs the script with a debugger that responds to each pause with the given commands,
+    // continuing once the commands have been used up
+    fn run_with_debugger(
+        script: &str,
+        commands: Vec<DebugCommand>,
+        breakpoints: &[u32],
+        pause_on_entry: bool,
+    ) -> (Result<Value, RuntimeError>, Vec<Pause>) {
+        let pauses = Arc::new(Mutex::new(Vec::new()));
+
+        let mut debugger = {
+            let pauses = pauses.clone();
+            let mut commands = commands.into_iter();
+            Debugger::new(move |state: &DebugState, _: &mut Breakpoints| {
+                let frame = state.current_frame();
+                pauses.lock().push((
+                    state.reason,
+                    frame.line().expect("Missing line"),
+                    frame.locals.clone(),
+                ));
+                commands.next().unwrap_or(DebugCommand::Continue)
+            })
+        };
+        for line in breakpoints {
+            debugger.breakpoints_mut().add(Breakpoint {
+                path: None,
+                line: *line,
+            });
+        }
+        if pause_on_entry {
+            debugger.pause();
+        }
+
+        let mut vm = Vm::default();
+        vm.set_debugger(Some(debugger));
+
+        let mut loader = Loader::default();
+        let chunk = match loader.compile_script(script, &None) {
+            Ok(chunk) => chunk,
+            Err(error) => panic!("Error while compiling script: {}", error),
+        };
+
+        let result = vm.run(chunk);
+        let pauses = pauses.lock().clone();
+        (result, pauses)
+    }
+
+    fn paused_lines(pauses: &[Pause]) -> Vec<u32> {
+        pauses.iter().map(|(_, line, _)| *line).collect()
+    }
+
+    fn number(n: i64) -> Value {
+        Value::Number(n.into())
+    }
+
+    #[test]
+    fn breakpoints() {
+        let (result, pauses) = run_with_debugger(SCRIPT, vec![], &[3, 6], false);
+        assert_eq!(result.unwrap(), number(10));
+        assert_eq!(paused_lines(&pauses), vec![3, 6, 3]);
+        assert!(pauses
+            .iter()
+            .all(|(reason, _, _)| *reason == PauseReason::Breakpoint));
+    }
+
+    #[test]
+    fn step_in() {
+        use DebugCommand::StepIn;
+
+        let (_, pauses) = run_with_debugger(SCRIPT, vec![StepIn; 8], &[], true);
+        assert_eq!(paused_lines(&pauses), vec![2, 5, 3, 4, 6, 3, 4, 7]);
+    }
+
+    #[test]
+    fn step_over() {
+        use DebugCommand::StepOver;
+
+        let (_, pauses) = run_with_debugger(SCRIPT, vec![StepOver; 4], &[], true);
+        assert_eq!(paused_lines(&pauses), vec![2, 5, 6, 7]);
+    }
+
+    #[test]
+    fn step_out() {
+        use DebugCommand::{StepIn, StepOut};
+
+        let (_, pauses) =
+            run_with_debugger(SCRIPT, vec![StepIn, StepIn, StepOut, StepIn], &[], true);
+        assert_eq!(paused_lines(&pauses), vec![2, 5, 3, 6, 3]);
+    }
+
+    #[test]
+    fn step_in_to_generator() {
+        use DebugCommand::StepIn;
+
+        let script = "
+gen = ||
+  yield 1
+  yield 2
+x = 0
+for n in gen()
+  x += n
+x
+";
+        let (result, pauses) = run_with_debugger(script, vec![StepIn; 12], &[], true);
+        assert_eq!(result.unwrap(), number(3));
+        assert_eq!(paused_lines(&pauses), vec![2, 5, 6, 3, 7, 6, 4, 7, 6, 2, 7]);
+    }
+
+    #[test]
+    fn locals() {
+        let (_, pauses) = run_with_debugger(SCRIPT, vec![], &[4, 7], false);
+
+        let locals = &pauses[0].2;
+        assert!(locals.contains(&("x".to_string(), number(1))));
+        assert!(locals.contains(&("y".to_string(), number(2))));
+
+        let locals = &pauses[2].2;
+        assert!(locals.contains(&("a".to_string(), number(3))));
+        assert!(locals.contains(&("b".to_string(), number(7))));
+    }
+
+    #[test]
+    fn stop() {
+        let (result, pauses) = run_with_debugger(SCRIPT, vec![DebugCommand::Stop], &[3], false);
+        assert!(matches!(
+            result,
+            Err(RuntimeError::ExecutionLimitExceeded(
+                ExecutionLimit::StoppedByDebugger
+            ))
+        ));
+        assert_eq!(paused_lines(&pauses), vec![3]);
+    }
+}
@@ -0,0 +1,171 @@
+mod koto_object {
+    use {
+        koto_runtime::{
+            koto_methods, ExternalValue, IntoValue, KotoObject, Loader, RuntimeError, Value,
+            ValueMap, Vm,
+        },
+        std::fmt,
+    };
+
+    #[derive(Debug, KotoObject)]
+    struct Counter {
+        count: i64,
+    }
+
+    #[koto_methods]
+    impl Counter {
+        fn new(count: i64) -> Self {
+            Self { count }
+        }
+
+        fn count(&self) -> i64 {
+            self.count
+        }
+
+        fn increment(&mut self, amount: Option<i64>) -> i64 {
+            self.count += amount.unwrap_or(1);
+            self.count
+        }
+
+        #[koto(name = "reset")]
+        fn reset_to(&mut self, count: i64) {
+            self.count = count;
+        }
+
+        fn checked_decrement(&mut self) -> Result<i64, String> {
+            if self.count > 0 {
+                self.count -= 1;
+                Ok(self.count)
+            } else {
+                Err("Counter.checked_decrement: The counter is already at zero".into())
+            }
+        }
+
+        #[koto(skip)]
+        #[allow(dead_code)]
+        fn hidden(&self) -> i64 {
+            -1
+        }
+    }
+
+    #[derive(Debug, KotoObject)]
+    #[koto(type_name = "Greeter", display = false)]
+    struct KotoGreeter {
+        greeting: String,
+    }
+
+    #[koto_methods]
+    impl KotoGreeter {
+        fn greet(&self, name: String) -> String {
+            format!("{}, {}!", self.greeting, name)
+        }
+    }
+
+    impl fmt::Display for KotoGreeter {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "Greeter({})", self.greeting)
+        }
+    }
+
+    fn run_script(script: &str) -> Result<Value, RuntimeError> {
+        let mut module = ValueMap::new();
+        module.add_typed_fn("counter", Counter::new);
+        module.add_typed_fn("greeter", |greeting: String| KotoGreeter { greeting });
+
+        let mut vm = Vm::default();
+        vm.prelude().add_map("objects", module);
+
+        let script = format!("import objects\n{}", script);
+        let mut loader = Loader::default();
+        let chunk = match loader.compile_script(&script, &None) {
+            Ok(chunk) => chunk,
+            Err(error) => panic!("Error while compiling script: {}", error),
+        };
+
+        vm.run(chunk)
+    }
+
+    fn check_script_output(script: &str, expected: Value) {
+        match run_script(script) {
+            Ok(result) => assert_eq!(result, expected),
+            Err(error) => panic!("Error while running script: {}", error),
+        }
+    }
+
+    fn check_script_error(script: &str, expected_error: &str) {
+        match run_script(script) {
+            Ok(result) => panic!("Script didn't fail as expected, result: {}", result),
+            Err(error) => assert_eq!(format!("{:#}", error), expected_error),
+        }
+    }
+
+    #[test]
+    fn methods() {
+        let script = "
+c = objects.counter 10
+c.increment()
+c.increment 5
+c.count()
+";
+        check_script_output(script, 16.into_value());
+    }
+
+    #[test]
+    fn renamed_method() {
+        let script = "
+c = objects.counter 10
+c.reset 3
+c.count()
+";
+        check_script_output(script, 3.into_value());
+    }
+
+    #[test]
+    fn skipped_method() {
+        check_script_error(
+            "(objects.counter 1).hidden()",
+            "'hidden' not found in module 'map'",
+        );
+    }
+
+    #[test]
+    fn returned_error() {
+        let script = "
+c = objects.counter 1
+c.checked_decrement()
+c.checked_decrement()
+";
+        check_script_error(
+            script,
+            "Counter.checked_decrement: The counter is already at zero",
+        );
+    }
+
+    #[test]
+    fn argument_errors() {
+        check_script_error(
+            r#"(objects.counter 1).increment "x""#,
+            "Counter.increment: Expected Number (i64) or Empty as argument 1, found 'String'",
+        );
+        check_script_error(
+            "(objects.counter 1).reset()",
+            "Counter.reset: Expected 1 argument, found 0",
+        );
+    }
+
+    #[test]
+    fn type_name_and_display() {
+        check_script_output(
+            r#"(objects.greeter "Hi").greet "Koto""#,
+            "Hi, Koto!".into_value(),
+        );
+
+        let greeter = KotoGreeter {
+            greeting: "Hello".to_string(),
+        };
+        assert_eq!(greeter.value_type(), "Greeter");
+        assert_eq!(greeter.to_string(), "Greeter(Hello)");
+        assert_eq!(Counter::new(0).to_string(), "Counter");
+        assert_eq!(Counter::TYPE_NAME, "Counter");
+    }
+}
@@ -0,0 +1,194 @@
+mod profiler {
+    use koto_runtime::{FunctionProfile, Loader, ProfileReport, Profiler, Vm};
+
+    fn profile_script(script: &str) -> ProfileReport {
+        let profiler = Profiler::new();
+
+        let mut vm = Vm::default();
+        vm.set_profiler(Some(profiler.clone()));
+
+        let mut loader = Loader::default();
+        let chunk = match loader.compile_script(script, &None) {
+            Ok(chunk) => chunk,
+            Err(error) => panic!("Error while compiling script: {}", error),
+        };
+
+        if let Err(error) = vm.run(chunk) {
+            panic!("Error while running script: {}", error);
+        }
+
+        profiler.report()
+    }
+
+    fn function<'a>(report: &'a ProfileReport, name: &str) -> &'a FunctionProfile {
+        report
+            .functions
+            .iter()
+            .find(|function| function.name == name)
+            .unwrap_or_else(|| panic!("Missing function '{}'", name))
+    }
+
+    fn call_count(report: &ProfileReport, name: &str) -> u64 {
+        function(report, name).call_count
+    }
+
+    #[test]
+    fn function_calls() {
+        let script = "
+f = |n|
+  n * 2
+g = |n|
+  (f n) + (f n + 1)
+for i in 0..5
+  g i
+";
+        let report = profile_script(script);
+
+        assert_eq!(call_count(&report, "<script>"), 1);
+        assert_eq!(call_count(&report, "f (<script>:3)"), 10);
+        assert_eq!(call_count(&report, "g (<script>:5)"), 5);
+
+        for function in report.functions.iter() {
+            assert!(function.exclusive_time <= function.inclusive_time);
+        }
+    }
+
+    #[test]
+    fn recursive_calls() {
+        let script = "
+fib = |n|
+  if n < 2
+    n
+  else
+    (fib n - 1) + (fib n - 2)
+fib 15
+";
+        let report = profile_script(script);
+
+        let fib = function(&report, "fib (<script>:3)");
+        let total = function(&report, "<script>");
+        assert_eq!(fib.call_count, 1973);
+        // Nested recursive calls don't add to the inclusive time
+        assert!(fib.inclusive_time <= total.inclusive_time);
+        assert!(fib.exclusive_time <= fib.inclusive_time);
+    }
+
+    #[test]
+    fn tail_calls() {
+        let script = "
+fib = |n|
+  if n < 2
+    n
+  else
+    (fib n - 1) + (fib n - 2)
+work = ||
+  fib 15
+work()
+";
+        let report = profile_script(script);
+
+        // The tail call to fib is included in the time of the function that made the call
+        let fib = function(&report, "fib (<script>:3)");
+        let work = function(&report, "work (<script>:8)");
+        assert_eq!(work.call_count, 1);
+        assert!(work.inclusive_time >= fib.inclusive_time);
+
+        let folded = report.to_folded_stacks();
+        assert!(folded
+            .lines()
+            .any(|line| line.starts_with("<script>;work (<script>:8);fib (<script>:3) ")));
+    }
+
+    #[test]
+    fn repeated_tail_calls() {
+        let script = "
+fns = {}
+fns.is_even = |n|
+  if n == 0
+    true
+  else
+    fns.is_odd n - 1
+fns.is_odd = |n|
+  if n == 0
+    false
+  else
+    fns.is_even n - 1
+fns.is_even 100
+";
+        let report = profile_script(script);
+
+        assert_eq!(call_count(&report, "<script>:4"), 51);
+        assert_eq!(call_count(&report, "<script>:9"), 50);
+        // Repeated tail calls are merged rather than growing the call stack
+        assert!(report.stacks.iter().all(|(stack, _)| stack.len() <= 3));
+    }
+
+    #[test]
+    fn generators() {
+        let script = "
+gen = ||
+  for i in 0..3
+    yield i
+gen().to_tuple()
+";
+        let report = profile_script(script);
+
+        assert_eq!(call_count(&report, "gen (<script>:3)"), 1);
+
+        // The generator is called by to_tuple, so it appears as a nested call
+        let generator = report
+            .functions
+            .iter()
+            .position(|function| function.name == "gen (<script>:3)")
+            .unwrap();
+        assert!(report
+            .stacks
+            .iter()
+            .all(|(stack, _)| !stack.contains(&generator) || stack.len() == 2));
+    }
+
+    #[test]
+    fn line_counts() {
+        let script = "
+x = 0
+for i in 0..10
+  x += i
+x
+";
+        let report = profile_script(script);
+
+        let count = |line| {
+            report
+                .lines
+                .iter()
+                .find(|profile| profile.line == line)
+                .map(|profile| profile.instruction_count)
+                .unwrap_or(0)
+        };
+
+        assert_eq!(count(2), 1);
+        assert!(count(4) >= 10);
+        assert!(count(4) > count(2));
+    }
+
+    #[test]
+    fn export_formats() {
+        let script = "
+f = |n| n + 1
+f f 1
+";
+        let report = profile_script(script);
+
+        let folded = report.to_folded_stacks();
+        assert!(folded
+            .lines()
+            .any(|line| line.starts_with("<script>;f (<script>:2) ")));
+
+        let trace = report.to_chrome_trace();
+        assert!(trace.starts_with("{\"traceEvents\":["));
+        assert_eq!(trace.matches("\"name\":\"f (<script>:2)\"").count(), 2);
+
+        let text = report.to_text();
+        assert!(text.contains("f (<script>:2)"));
+    }
+}
@@ -0,0 +1,232 @@
+mod typed_functions {
+    use {
+        koto_runtime::{
+            FromValue, IntoValue, Loader, Num2, RuntimeError, Value, ValueMap, ValueNumber, Vm,
+        },
+        std::collections::HashMap,
+    };
+
+    fn make_module() -> ValueMap {
+        let mut module = ValueMap::new();
+
+        module.add_typed_fn("add", |a: f64, b: f64| a + b);
+        module.add_typed_fn("repeat", |s: String, n: usize| -> Vec<String> {
+            vec![s; n]
+        });
+        module.add_typed_fn("sum", |values: Vec<i64>| values.iter().sum::<i64>());
+        module.add_typed_fn("greet", |name: Option<String>| match name {
+            Some(name) => format!("Hello, {}!", name),
+            None => "Hello!".to_string(),
+        });
+        module.add_typed_fn("swap", |pair: (i64, String)| (pair.1, pair.0));
+        module.add_typed_fn("keys", |map: HashMap<String, Value>| {
+            let mut keys = map.keys().cloned().collect::<Vec<_>>();
+            keys.sort();
+            keys
+        });
+        module.add_typed_fn("scale", |n: Num2, factor: f64| {
+            Num2(n.0 * factor, n.1 * factor)
+        });
+        module.add_typed_fn("checked_div", |a: i64, b: i64| {
+            if b == 0 {
+                Err("checked_div: Division by zero")
+            } else {
+                Ok(a / b)
+            }
+        });
+        module.add_typed_fn("nothing", || {});
+
+        module
+    }
+
+    fn run_script(script: &str) -> Result<Value, RuntimeError> {
+        let mut vm = Vm::default();
+        vm.prelude().add_map("typed", make_module());
+
+        let script = format!("import typed\n{}", script);
+        let mut loader = Loader::default();
+        let chunk = match loader.compile_script(&script, &None) {
+            Ok(chunk) => chunk,
+            Err(error) => panic!("Error while compiling script: {}", error),
+        };
+
+        vm.run(chunk)
+    }
+
+    fn check_script_output(script: &str, expected: Value) {
+        match run_script(script) {
+            Ok(result) => assert_eq!(result, expected),
+            Err(error) => panic!("Error while running script: {}", error),
+        }
+    }
+
+    fn check_script_error(script: &str, expected_error: &str) {
+        match run_script(script) {
+            Ok(result) => panic!("Script didn't fail as expected, result: {}", result),
+            Err(error) => assert_eq!(format!("{:#}", error), expected_error),
+        }
+    }
+
+    fn number(n: i64) -> Value {
+        Value::Number(n.into())
+    }
+
+    fn string(s: &str) -> Value {
+        Value::Str(s.into())
+    }
+
+    mod calls {
+        use super::*;
+
+        #[test]
+        fn numbers() {
+            check_script_output("typed.add 1, 2.5", Value::Number(3.5.into()));
+        }
+
+        #[test]
+        fn strings_and_vecs() {
+            check_script_output(r#"typed.repeat("x", 3).size()"#, Value::Number(3.into()));
+        }
+
+        #[test]
+        fn vec_from_list_or_tuple() {
+            check_script_output("typed.sum [1, 2, 3]", number(6));
+            check_script_output("typed.sum (1, 2, 3, 4)", number(10));
+        }
+
+        #[test]
+        fn optional_arguments() {
+            check_script_output(r#"typed.greet "Koto""#, string("Hello, Koto!"));
+            check_script_output("typed.greet()", string("Hello!"));
+        }
+
+        #[test]
+        fn tuples() {
+            check_script_output(
+                r#"typed.swap (1, "x")"#,
+                Value::Tuple(vec![string("x"), number(1)].into()),
+            );
+        }
+
+        #[test]
+        fn hash_maps() {
+            check_script_output(
+                "typed.keys({foo: 1, bar: 2}).to_tuple()",
+                Value::Tuple(vec![string("bar"), string("foo")].into()),
+            );
+        }
+
+        #[test]
+        fn num2() {
+            check_script_output("typed.scale (num2 1, 2), 2", Value::Num2(Num2(2.0, 4.0)));
+        }
+
+        #[test]
+        fn result_ok() {
+            check_script_output("typed.checked_div 9, 3", number(3));
+        }
+
+        #[test]
+        fn empty_return_value() {
+            check_script_output("typed.nothing()", Value::Empty);
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        #[test]
+        fn wrong_argument_type() {
+            check_script_error(
+                "typed.add 1, true",
+                "add: Expected Number as argument 2, found 'Bool'",
+            );
+        }
+
+        #[test]
+        fn wrong_element_type() {
+            check_script_error(
+                r#"typed.sum [1, "two"]"#,
+                "sum: Expected List of Number (i64) as argument 1, found 'List'",
+            );
+        }
+
+        #[test]
+        fn missing_argument() {
+            check_script_error("typed.add 1", "add: Expected 2 arguments, found 1");
+        }
+
+        #[test]
+        fn too_many_arguments() {
+            check_script_error("typed.greet 1, 2", "greet: Expected 1 argument, found 2");
+        }
+
+        #[test]
+        fn integer_out_of_range() {
+            check_script_error(
+                r#"typed.repeat "x", -1"#,
+                "repeat: Expected Number (usize) as argument 2, found 'Int'",
+            );
+        }
+
+        #[test]
+        fn non_integral_number() {
+            check_script_error(
+                r#"typed.repeat "x", 2.9"#,
+                "repeat: Expected Number (usize) as argument 2, found 'Float'",
+            );
+        }
+
+        #[test]
+        fn result_error() {
+            check_script_error("typed.checked_div 1, 0", "checked_div: Division by zero");
+        }
+    }
+
+    mod conversions {
+        use super::*;
+
+        #[test]
+        fn round_trips() {
+            let values = vec![(1, "a".to_string()), (2, "b".to_string())];
+            let value = values.clone().into_value();
+            assert_eq!(Vec::<(i64, String)>::from_value(&value), Some(values));
+
+            let value = Some(42u8 as u32).into_value();
+            assert_eq!(Option::<u32>::from_value(&value), Some(Some(42)));
+            assert_eq!(Option::<u32>::from_value(&Value::Empty), Some(None));
+
+            let mut map = HashMap::new();
+            map.insert("x".to_string(), vec![true, false]);
+            let value = map.clone().into_value();
+            assert_eq!(HashMap::<String, Vec<bool>>::from_value(&value), Some(map));
+        }
+
+        #[test]
+        fn integers() {
+            let float = |n: f64| Value::Number(n.into());
+
+            assert_eq!(usize::from_value(&number(42)), Some(42));
+            assert_eq!(usize::from_value(&float(42.0)), Some(42));
+            assert_eq!(usize::from_value(&number(-1)), None);
+            assert_eq!(i64::from_value(&float(2.9)), None);
+            assert_eq!(i32::from_value(&number(i64::MAX)), None);
+            assert_eq!(i64::from_value(&float(1e20)), None);
+            assert_eq!(i64::from_value(&float(f64::NAN)), None);
+
+            // Integers that don't fit in an i64 are converted into floats rather than wrapping
+            assert_eq!(u64::MAX.into_value(), float(u64::MAX as f64));
+        }
+
+        #[test]
+        fn incompatible_values() {
+            assert_eq!(f64::from_value(&string("1")), None);
+            assert_eq!(String::from_value(&number(1)), None);
+            assert_eq!(<(i64, i64)>::from_value(&vec![1, 2, 3].into_value()), None);
+            assert_eq!(
+                ValueNumber::from_value(&number(1)),
+                Some(ValueNumber::from(1))
+            );
+        }
+    }
+}
@@ -2,8 +2,8 @@ mod vm {
     use {
         koto_bytecode::{chunk_to_string_annotated, Chunk},
         koto_runtime::{
-            external_error, num2, num4, type_as_string, IntRange, Loader, Value, Value::*,
-            ValueHashMap, ValueList, ValueMap, Vm,
+            external_error, num2, num4, type_as_string, IntRange, Loader, MetaKeyId, Value,
+            Value::*, ValueHashMap, ValueList, ValueMap, Vm,
         },
         std::sync::Arc,
     };
@@ -36,7 +36,7 @@ mod vm {
             println!("{}\n", script);
             let script_lines = script.lines().collect::<Vec<_>>();
 
-            println!("Constants\n---------\n{}\n", chunk.constants.to_string());
+            println!("Constants\n---------\n{}\n", chunk.constants);
             println!(
                 "Instructions\n------------\n{}",
                 chunk_to_string_annotated(chunk, &script_lines)
@@ -61,7 +61,7 @@ mod vm {
             }
             Err(e) => {
                 print_chunk(script, vm.chunk());
-                panic!(format!("Error while running script: {}", e.to_string()));
+                panic!("Error while running script: {}", e);
             }
         }
     }
@@ -91,7 +91,7 @@ mod vm {
     }
 
     fn value_list(values: &[Value]) -> Value {
-        List(ValueList::from_slice(&values))
+        List(ValueList::from_slice(values))
     }
 
     fn value_tuple(values: &[Value]) -> Value {
@@ -1930,6 +1930,79 @@ x[3]";
             test_script(r#""héllö42" >= "héllö11""#, Bool(true));
             test_script(r#""hello1" >= "hello42""#, Bool(false));
         }
+
+        #[test]
+        fn interpolated_string() {
+            let script = r#"
+x = 42
+"x: {x}, x * 2: {x * 2}"
+"#;
+            test_script(script, string("x: 42, x * 2: 84"));
+        }
+
+        #[test]
+        fn interpolated_string_with_nested_string() {
+            let script = r#"
+x = "abc"
+"{"{x}".size()} - {x}"
+"#;
+            test_script(script, string("3 - abc"));
+        }
+
+        #[test]
+        fn interpolated_string_with_many_segments() {
+            // More than 255 segments requires the segment count to be encoded with 2 bytes
+            let mut script = String::from("x = 1\n\"");
+            script.push_str(&"{x}".repeat(300));
+            script.push('"');
+            test_script(&script, string(&"1".repeat(300)));
+        }
+    }
+
+    mod meta_maps {
+        use super::*;
+
+        #[test]
+        fn overloaded_add() {
+            let script = "
+foo = |x|
+  x: x
+  @+: |self, other| foo self.x + other.x
+((foo 40) + (foo 2)).x
+";
+            test_script(script, Number(42.0.into()));
+        }
+
+        #[test]
+        fn overloaded_less_without_self_arg() {
+            let script = "
+foo = |x|
+  x: x
+  @<: |a, b| a.x < b.x
+(foo 1) < (foo 2)
+";
+            test_script(script, Bool(true));
+        }
+
+        #[test]
+        fn external_meta_function() {
+            let mut vm = Vm::default();
+
+            let mut foo = ValueMap::new();
+            foo.add_value("x", Number(42.0.into()));
+            foo.add_meta_fn(MetaKeyId::Negate, |vm, args| match vm.get_args(args) {
+                [Map(map)] => match map.data().get_with_string("x") {
+                    Some(Number(n)) => Ok(Number(-n)),
+                    _ => external_error!("Expected number for x"),
+                },
+                _ => external_error!("Expected map"),
+            });
+            vm.prelude().add_map("foo", foo);
+
+            let mut loader = Loader::default();
+            let chunk = loader.compile_script("import foo\n-foo", &None).unwrap();
+            assert_eq!(vm.run(chunk).unwrap(), Number((-42.0).into()));
+        }
     }
 
     mod error_recovery {
@@ -1978,5 +2051,661 @@ catch _
 ";
             test_script(script, Number(4.0.into()));
         }
+
+        #[test]
+        fn throw_string() {
+            let script = "
+try
+  throw \"oops\"
+catch e
+  e
+";
+            test_script(script, Str("oops".into()));
+        }
+
+        #[test]
+        fn throw_map() {
+            let script = "
+f = |n| throw {code: n * 2}
+try
+  f 21
+catch e
+  e.code
+";
+            test_script(script, Number(42.into()));
+        }
+
+        #[test]
+        fn throw_from_function_called_by_external_function() {
+            let script = "
+try
+  (1..5).each(|n| if n == 3 then throw n else n).to_tuple()
+catch e
+  e
+";
+            test_script(script, Number(3.into()));
+        }
+
+        #[test]
+        fn vm_error_is_caught_as_map() {
+            let script = "
+f = || x + 1
+try
+  f()
+catch e
+  e.message, e.backtrace[0].line, e.backtrace[1].line
+";
+            test_script(
+                script,
+                Tuple(
+                    vec![
+                        Str("'x' not found".into()),
+                        Number(2.into()),
+                        Number(4.into()),
+                    ]
+                    .into(),
+                ),
+            );
+        }
+
+        #[test]
+        fn uncaught_thrown_value() {
+            let script = "
+throw {code: 42}
+";
+            let mut vm = Vm::default();
+            let mut loader = Loader::default();
+            let chunk = loader.compile_script(script, &None).unwrap();
+
+            match vm.run(chunk) {
+                Err(koto_runtime::RuntimeError::ThrownValue {
+                    value: Map(map), ..
+                }) => {
+                    assert_eq!(map.data().get_with_string("code"), Some(&Number(42.into())));
+                }
+                other => panic!("Expected a thrown map, found {:?}", other),
+            }
+        }
+    }
+
+    mod execution_limits {
+        use {
+            super::*,
+            koto_runtime::{ExecutionLimit, ExecutionLimits, RuntimeError},
+            std::{thread, time::Duration},
+        };
+
+        fn run_with_limits(
+            script: &str,
+            limits: ExecutionLimits,
+            setup: impl FnOnce(&mut Vm),
+        ) -> Result<Value, RuntimeError> {
+            let mut vm = Vm::default();
+            let mut loader = Loader::default();
+            let chunk = loader.compile_script(script, &None).unwrap();
+            vm.set_execution_limits(limits);
+            setup(&mut vm);
+            vm.run(chunk)
+        }
+
+        fn check_limit_exceeded(result: Result<Value, RuntimeError>, expected: ExecutionLimit) {
+            match result {
+                Err(RuntimeError::ExecutionLimitExceeded(limit)) => assert_eq!(limit, expected),
+                other => panic!("Expected {:?} to be exceeded, found {:?}", expected, other),
+            }
+        }
+
+        #[test]
+        fn within_instruction_budget() {
+            let script = "
+x = 0
+for i in 0..10
+  x += i
+x
+";
+            let limits = ExecutionLimits {
+                instruction_budget: Some(1000),
+                ..Default::default()
+            };
+            assert_eq!(
+                run_with_limits(script, limits, |_| {}).unwrap(),
+                Number(45.0.into())
+            );
+        }
+
+        #[test]
+        fn instruction_budget_exceeded() {
+            let script = "
+loop
+  x = 1
+";
+            let limits = ExecutionLimits {
+                instruction_budget: Some(1000),
+                ..Default::default()
+            };
+            check_limit_exceeded(
+                run_with_limits(script, limits, |_| {}),
+                ExecutionLimit::InstructionBudget,
+            );
+        }
+
+        #[test]
+        fn instruction_budget_exceeded_in_external_call() {
+            let script = "
+(0..1000000).each(|x| x * x).consume()
+";
+            let limits = ExecutionLimits {
+                instruction_budget: Some(1000),
+                ..Default::default()
+            };
+            check_limit_exceeded(
+                run_with_limits(script, limits, |_| {}),
+                ExecutionLimit::InstructionBudget,
+            );
+        }
+
+        #[test]
+        fn timeout() {
+            let script = "
+loop
+  x = 1
+";
+            let limits = ExecutionLimits {
+                timeout: Some(Duration::from_millis(10)),
+                ..Default::default()
+            };
+            check_limit_exceeded(
+                run_with_limits(script, limits, |_| {}),
+                ExecutionLimit::Timeout,
+            );
+        }
+
+        #[test]
+        fn interrupted_from_another_thread() {
+            let script = "
+loop
+  x = 1
+";
+            let result = run_with_limits(script, ExecutionLimits::default(), |vm| {
+                let interrupt = vm.interrupt_handle();
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(10));
+                    interrupt.interrupt();
+                });
+            });
+            check_limit_exceeded(result, ExecutionLimit::Interrupted);
+        }
+
+        #[test]
+        fn pending_interrupt_is_kept_when_setting_limits() {
+            let result = run_with_limits("x = 1", ExecutionLimits::default(), |vm| {
+                vm.interrupt_handle().interrupt();
+                vm.set_execution_limits(ExecutionLimits {
+                    instruction_budget: Some(1000),
+                    ..Default::default()
+                });
+            });
+            check_limit_exceeded(result, ExecutionLimit::Interrupted);
+        }
+
+        #[test]
+        fn reset_interrupt() {
+            let result = run_with_limits("x = 1", ExecutionLimits::default(), |vm| {
+                let interrupt = vm.interrupt_handle();
+                interrupt.interrupt();
+                interrupt.reset();
+            });
+            assert_eq!(result.unwrap(), Value::Number(1.into()));
+        }
+
+        #[test]
+        fn limit_errors_cant_be_caught() {
+            let script = "
+try
+  loop
+    x = 1
+catch _
+  -1
+";
+            let limits = ExecutionLimits {
+                instruction_budget: Some(1000),
+                ..Default::default()
+            };
+            check_limit_exceeded(
+                run_with_limits(script, limits, |_| {}),
+                ExecutionLimit::InstructionBudget,
+            );
+        }
+    }
+
+    mod large_scripts {
+        use super::*;
+
+        // Returns a series of assignments to the given number of locals, x0, x1, x2, etc.
+        fn assign_locals(count: usize, indent: &str) -> String {
+            (0..count)
+                .map(|i| format!("{}x{} = {}\n", indent, i, i))
+                .collect()
+        }
+
+        #[test]
+        fn function_with_hundreds_of_locals() {
+            let script = format!(
+                "f = ||\n{}  x0 + x200 + x399\nf()",
+                assign_locals(400, "  ")
+            );
+            test_script(&script, Number(599.0.into()));
+        }
+
+        #[test]
+        fn top_level_with_hundreds_of_locals() {
+            let script = format!("{}x0 + x500 + x999", assign_locals(1000, ""));
+            test_script(&script, Number(1499.0.into()));
+        }
+
+        #[test]
+        fn branching_with_wide_registers() {
+            let script = format!(
+                "
+f = |n|
+{}  if n > x299
+    n
+  else
+    x299
+(f 1000) + (f 0)
+",
+                assign_locals(300, "  ")
+            );
+            test_script(&script, Number(1299.0.into()));
+        }
+
+        #[test]
+        fn loop_with_large_body() {
+            // Each iteration jumps over a large branch, and then jumps back to the start of the loop
+            let mut script = String::from(
+                "
+x = 0
+for i in 0..4
+  if i % 2 == 0
+",
+            );
+            for _ in 0..20_000 {
+                script.push_str("    x += 1\n");
+            }
+            script.push_str(
+                "  else
+    x += 1000
+x
+",
+            );
+            test_script(&script, Number(42_000.0.into()));
+        }
+
+        #[test]
+        fn function_with_large_body() {
+            let mut script = String::from(
+                "
+f = |n|
+  result = 0
+",
+            );
+            for i in 0..20_000 {
+                script.push_str(&format!("  result += n * {}\n", i % 10));
+            }
+            script.push_str(
+                "  result
+f(1) + f(2)
+",
+            );
+            test_script(&script, Number(270_000.0.into()));
+        }
+
+        #[test]
+        fn large_list() {
+            let elements = (0..1000).map(|n| n.to_string()).collect::<Vec<_>>();
+            let script = format!("[{}].size()", elements.join(", "));
+            test_script(&script, Number(1000.0.into()));
+        }
+    }
+
+    mod tail_calls {
+        use super::*;
+
+        #[test]
+        fn self_recursion_in_if() {
+            let script = "
+count = |n, acc|
+  if n == 0
+    acc
+  else
+    count n - 1, acc + 1
+count 10000, 0
+";
+            test_script(script, Number(10000.0.into()));
+        }
+
+        #[test]
+        fn return_expression() {
+            let script = "
+sum = |n, acc|
+  if n == 0
+    return acc
+  return sum n - 1, acc + n
+sum 100, 0
+";
+            test_script(script, Number(5050.0.into()));
+        }
+
+        #[test]
+        fn match_and_switch_arms() {
+            let script = "
+f = |n|
+  match n % 3
+    0 then
+      if n == 0 then \"done\" else f n - 1
+    x then
+      switch
+        x == 1 then f n - 1
+        else f(n - 1)
+f 100
+";
+            test_script(script, Str("done".into()));
+        }
+
+        #[test]
+        fn instance_functions() {
+            let script = "
+m =
+  offset: 10
+  count: |self, n|
+    if n == 0
+      self.offset
+    else
+      self.count n - 1
+m.count 1000
+";
+            test_script(script, Number(10.0.into()));
+        }
+
+        #[test]
+        fn mutual_recursion() {
+            let script = "
+m =
+  is_even: |self, n| if n == 0 then true else self.is_odd n - 1
+  is_odd: |self, n| if n == 0 then false else self.is_even n - 1
+m.is_even 1001
+";
+            test_script(script, Bool(false));
+        }
+
+        #[test]
+        fn variadic_function_with_captures() {
+            let script = "
+z = 100
+f = |n, xs...|
+  if n == 0
+    z + xs.size()
+  else
+    f n - 1, 1, 2, 3
+f 10
+";
+            test_script(script, Number(103.0.into()));
+        }
+
+        #[test]
+        fn call_to_external_function() {
+            let script = "
+f = |xs| xs.size()
+f [1, 2, 3]
+";
+            test_script(script, Number(3.0.into()));
+        }
+
+        #[test]
+        fn call_in_try_block() {
+            let script = "
+fail = || x
+f = ||
+  try
+    return fail()
+  catch _
+    -1
+f()
+";
+            test_script(script, Number((-1).into()));
+        }
+
+        #[test]
+        fn call_in_function_called_by_external_function() {
+            let script = "
+f = |n| if n == 0 then 1 else f n - 1
+(1..=3).each(|n| f n * 10).to_tuple()
+";
+            test_script(script, number_tuple(&[1, 1, 1]));
+        }
+    }
+
+    mod default_and_named_args {
+        use super::*;
+
+        #[test]
+        fn default_values() {
+            let script = "
+f = |a, b = 2, c = 3| a, b, c
+f(1), f(1, 20), f(1, 20, 30)
+";
+            test_script(
+                script,
+                value_tuple(&[
+                    number_tuple(&[1, 2, 3]),
+                    number_tuple(&[1, 20, 3]),
+                    number_tuple(&[1, 20, 30]),
+                ]),
+            );
+        }
+
+        #[test]
+        fn default_values_are_evaluated_at_call_time() {
+            let script = "
+f = |xs = []|
+  xs.push 1
+  xs
+f()
+f()
+";
+            test_script(script, number_list(&[1]));
+        }
+
+        #[test]
+        fn default_value_referring_to_earlier_arg() {
+            let script = "
+f = |a, b = a * 2| a + b
+f 10
+";
+            test_script(script, Number(30.into()));
+        }
+
+        #[test]
+        fn explicit_empty_value_doesnt_use_default() {
+            let script = "
+f = |a, b = 2, c = 3| a, b, c
+f 1, (), 30
+";
+            test_script(
+                script,
+                value_tuple(&[Number(1.into()), Empty, Number(30.into())]),
+            );
+        }
+
+        #[test]
+        fn default_value_after_named_arg_gap() {
+            let script = "
+f = |a, b = 2, c = 3| a, b, c
+f 1, c: ()
+";
+            test_script(
+                script,
+                value_tuple(&[Number(1.into()), Number(2.into()), Empty]),
+            );
+        }
+
+        #[test]
+        fn default_value_with_captures() {
+            let script = "
+x = 10
+f = |n = x| n * x
+f()
+";
+            test_script(script, Number(100.into()));
+        }
+
+        #[test]
+        fn default_value_with_varargs() {
+            let script = "
+f = |a, b = 2, rest...| a, b, rest.size()
+f(1), f(1, 20, 30, 40)
+";
+            test_script(
+                script,
+                value_tuple(&[number_tuple(&[1, 2, 0]), number_tuple(&[1, 20, 2])]),
+            );
+        }
+
+        #[test]
+        fn default_value_in_instance_function() {
+            let script = "
+m =
+  offset: 10
+  add: |self, n = 1| self.offset + n
+m.add(), m.add 5
+";
+            test_script(script, number_tuple(&[11, 15]));
+        }
+
+        #[test]
+        fn default_value_in_generator() {
+            let script = "
+gen = |n = 3|
+  for i in 0..n
+    yield i
+gen().to_tuple()
+";
+            test_script(script, number_tuple(&[0, 1, 2]));
+        }
+
+        #[test]
+        fn named_args() {
+            let script = "
+f = |a, b = 2, c = 3| a, b, c
+f 1, c: 30
+";
+            test_script(script, number_tuple(&[1, 2, 30]));
+        }
+
+        #[test]
+        fn named_args_only() {
+            let script = "
+f = |a, b| a - b
+f(b: 1, a: 10)
+";
+            test_script(script, Number(9.into()));
+        }
+
+        #[test]
+        fn named_args_in_instance_function() {
+            let script = "
+m =
+  offset: 10
+  add: |self, x = 1, y = 2| self.offset + x * y
+m.add y: 5
+";
+            test_script(script, Number(15.into()));
+        }
+
+        #[test]
+        fn named_args_in_generator() {
+            let script = "
+gen = |start = 0, end = 3|
+  for i in start..end
+    yield i
+gen(end: 2).to_tuple()
+";
+            test_script(script, number_tuple(&[0, 1]));
+        }
+
+        #[test]
+        fn named_args_errors() {
+            let script = "
+f = |a, b = 2| a, b
+error_message = |call|
+  try
+    call()
+  catch e
+    e.message
+error_message(|| f 1, c: 3),
+  error_message(|| f 1, a: 2),
+  error_message(|| f b: 2),
+  error_message(|| f())
+";
+            test_script(
+                script,
+                value_tuple(&[
+                    string("The function has no argument named 'c'"),
+                    string("The argument 'a' was provided more than once"),
+                    string("Missing value for argument 'a'"),
+                    string(
+                        "Insufficient arguments for function call, expected at least 1, found 0",
+                    ),
+                ]),
+            );
+        }
+
+        #[test]
+        fn named_args_in_external_function() {
+            let script = "
+import scale
+scale 1, 2, 3, factor: 10
+";
+            let mut vm = Vm::default();
+            vm.prelude().add_fn("scale", |vm, args| {
+                let factor = match args.named_arg("factor") {
+                    Some(Number(n)) => f64::from(n),
+                    _ => 1.0,
+                };
+                let sum: f64 = vm
+                    .get_args(args)
+                    .iter()
+                    .map(|arg| match arg {
+                        Number(n) => f64::from(n),
+                        _ => 0.0,
+                    })
+                    .sum();
+                Ok(Number((sum * factor).into()))
+            });
+
+            let mut loader = Loader::default();
+            let chunk = loader.compile_script(script, &None).unwrap();
+            assert_eq!(vm.run(chunk).unwrap(), Number(60.into()));
+        }
+
+        #[test]
+        fn unused_named_arg_in_external_function() {
+            let script = "
+import foo
+foo 1, bar: 2
+";
+            let mut vm = Vm::default();
+            vm.prelude().add_fn("foo", |vm, args| {
+                let _ = args.named_arg("baz");
+                Ok(vm.get_args(args)[0].clone())
+            });
+
+            let mut loader = Loader::default();
+            let chunk = loader.compile_script(script, &None).unwrap();
+            let error = vm.run(chunk).unwrap_err();
+            assert!(error
+                .to_string()
+                .contains("The function has no argument named 'bar'"));
+        }
     }
 }
@@ -0,0 +1,332 @@
+use {
+    crate::Error,
+    koto_runtime::{type_as_string, Value, ValueList, ValueMap, ValueVec},
+    serde::{
+        de::{
+            self,
+            value::{MapDeserializer, SeqDeserializer},
+            DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+            VariantAccess, Visitor,
+        },
+        forward_to_deserialize_any, Deserialize, Deserializer,
+    },
+    std::fmt,
+};
+
+/// Deserializes an instance of `T` from a Koto value
+///
+/// e.g.
+/// ```ignore
+/// let config: MyConfig = koto_serialize::from_value(value)?;
+/// ```
+pub fn from_value<T>(value: Value) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(ValueDeserializer::new(value))
+}
+
+/// A wrapper for Koto values that implements serde's Deserialize
+///
+/// This allows any serde data format to be deserialized into a Koto value, e.g.
+/// ```ignore
+/// let value = serde_json::from_str::<DeserializableValue>(input)?.0;
+/// ```
+pub struct DeserializableValue(pub Value);
+
+impl<'de> Deserialize<'de> for DeserializableValue {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(ValueVisitor).map(DeserializableValue)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a value that can be represented in Koto")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Value, E> {
+        Ok(Value::Number(n.into()))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Value, E> {
+        // Koto's integers are i64, so larger values are converted to floats
+        if n <= i64::MAX as u64 {
+            Ok(Value::Number((n as i64).into()))
+        } else {
+            Ok(Value::Number((n as f64).into()))
+        }
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Value, E> {
+        Ok(Value::Number(n.into()))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::Str(s.into()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Value, E> {
+        Ok(Value::Str(s.into()))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Empty)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Empty)
+    }
+
+    fn visit_some<D>(self, d: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(ValueVisitor)
+    }
+
+    fn visit_newtype_struct<D>(self, d: D) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        d.deserialize_any(ValueVisitor)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut result = ValueVec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(DeserializableValue(value)) = seq.next_element()? {
+            result.push(value);
+        }
+        Ok(Value::List(ValueList::with_data(result)))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut result = ValueMap::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((DeserializableValue(key), DeserializableValue(value))) = map.next_entry()? {
+            result.insert(key, value);
+        }
+        Ok(Value::Map(result))
+    }
+}
+
+/// A serde Deserializer that reads from a Koto value
+///
+/// Lists and tuples are deserialized as sequences, and maps as maps.
+/// Enums can be deserialized from strings (for unit variants),
+/// or from maps containing a single entry, with the variant name as key.
+pub struct ValueDeserializer {
+    value: Value,
+}
+
+impl ValueDeserializer {
+    pub fn new(value: Value) -> Self {
+        Self { value }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn visit_values<'de, V>(values: Vec<Value>, visitor: V) -> Result<V::Value, Error>
+where
+    V: Visitor<'de>,
+{
+    let mut seq = SeqDeserializer::new(values.into_iter().map(ValueDeserializer::new));
+    let result = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(result)
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        use Value::*;
+
+        match self.value {
+            Empty => visitor.visit_unit(),
+            Bool(b) => visitor.visit_bool(b),
+            Number(n) => {
+                if n.is_f64() {
+                    visitor.visit_f64(n.into())
+                } else {
+                    visitor.visit_i64(n.into())
+                }
+            }
+            Num2(n) => visit_values(vec![Number(n.0.into()), Number(n.1.into())], visitor),
+            Num4(n) => visit_values(
+                vec![
+                    Number(n.0.into()),
+                    Number(n.1.into()),
+                    Number(n.2.into()),
+                    Number(n.3.into()),
+                ],
+                visitor,
+            ),
+            Str(s) => visitor.visit_str(s.as_str()),
+            List(l) => visit_values(l.data().iter().cloned().collect(), visitor),
+            Tuple(t) => visit_values(t.data().to_vec(), visitor),
+            Map(m) => {
+                let entries = m
+                    .data()
+                    .iter()
+                    .map(|(key, value)| {
+                        (
+                            ValueDeserializer::new(key.clone()),
+                            ValueDeserializer::new(value.clone()),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let result = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(result)
+            }
+            unexpected => Err(de::Error::custom(format!(
+                "Unable to deserialize a value of type '{}'",
+                type_as_string(&unexpected)
+            ))),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Empty => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Value::Str(s) => visitor.visit_enum(s.as_str().to_string().into_deserializer()),
+            Value::Map(m) if m.len() == 1 => {
+                let (key, value) = m
+                    .data()
+                    .get_index(0)
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .unwrap();
+                match key {
+                    Value::Str(variant) => visitor.visit_enum(EnumDeserializer {
+                        variant: variant.to_string(),
+                        value,
+                    }),
+                    unexpected => Err(de::Error::custom(format!(
+                        "Expected a string as enum variant name, found '{}'",
+                        type_as_string(&unexpected)
+                    ))),
+                }
+            }
+            unexpected => Err(de::Error::custom(format!(
+                "Expected a string or a map with a single entry for an enum, found '{}'",
+                type_as_string(&unexpected)
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: Value,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer), Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, VariantDeserializer { value: self.value }))
+    }
+}
+
+struct VariantDeserializer {
+    value: Value,
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Deserialize::deserialize(ValueDeserializer::new(self.value))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(ValueDeserializer::new(self.value))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.value).deserialize_any(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: Visitor<'de>,
+    {
+        ValueDeserializer::new(self.value).deserialize_any(visitor)
+    }
+}
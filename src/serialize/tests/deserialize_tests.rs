@@ -0,0 +1,140 @@
+mod deserialize {
+    use {
+        koto_runtime::{Value, ValueList, ValueMap, ValueVec},
+        koto_serialize::{from_value, DeserializableValue},
+        serde::Deserialize,
+    };
+
+    fn number_list(values: &[i64]) -> Value {
+        Value::List(ValueList::with_data(
+            values
+                .iter()
+                .map(|n| Value::Number(n.into()))
+                .collect::<ValueVec>(),
+        ))
+    }
+
+    mod from_value {
+        use super::*;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Config {
+            name: String,
+            count: u8,
+            scale: f64,
+            enabled: bool,
+            values: Vec<i32>,
+            position: (f64, f64),
+            label: Option<String>,
+        }
+
+        #[test]
+        fn struct_from_map() {
+            let mut map = ValueMap::new();
+            map.add_value("name", Value::Str("test".into()));
+            map.add_value("count", Value::Number(42.into()));
+            map.add_value("scale", Value::Number(0.5.into()));
+            map.add_value("enabled", Value::Bool(true));
+            map.add_value("values", number_list(&[1, 2, 3]));
+            map.add_value(
+                "position",
+                Value::Tuple(vec![Value::Number(1.into()), Value::Number(2.into())].into()),
+            );
+
+            let config: Config = from_value(Value::Map(map)).unwrap();
+
+            assert_eq!(
+                config,
+                Config {
+                    name: "test".into(),
+                    count: 42,
+                    scale: 0.5,
+                    enabled: true,
+                    values: vec![1, 2, 3],
+                    position: (1.0, 2.0),
+                    label: None,
+                }
+            );
+        }
+
+        #[test]
+        fn num2_as_tuple() {
+            let value = Value::Num2(koto_runtime::Num2(1.0, 2.0));
+            let result: (f64, f64) = from_value(value).unwrap();
+            assert_eq!(result, (1.0, 2.0));
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Rect { width: f64, height: f64 },
+        }
+
+        #[test]
+        fn enums() {
+            let shape: Shape = from_value(Value::Str("Empty".into())).unwrap();
+            assert_eq!(shape, Shape::Empty);
+
+            let mut circle = ValueMap::new();
+            circle.add_value("Circle", Value::Number(2.0.into()));
+            let shape: Shape = from_value(Value::Map(circle)).unwrap();
+            assert_eq!(shape, Shape::Circle(2.0));
+
+            let mut size = ValueMap::new();
+            size.add_value("width", Value::Number(3.into()));
+            size.add_value("height", Value::Number(4.into()));
+            let mut rect = ValueMap::new();
+            rect.add_map("Rect", size);
+            let shape: Shape = from_value(Value::Map(rect)).unwrap();
+            assert_eq!(
+                shape,
+                Shape::Rect {
+                    width: 3.0,
+                    height: 4.0
+                }
+            );
+        }
+
+        #[test]
+        fn missing_field() {
+            let result = from_value::<Config>(Value::Map(ValueMap::new()));
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn integer_out_of_range() {
+            let result = from_value::<u8>(Value::Number(256.into()));
+            assert!(result.is_err());
+        }
+    }
+
+    mod deserializable_value {
+        use super::*;
+
+        #[test]
+        fn from_json() {
+            let input = r#"{"foo": [1, 2, 3], "bar": {"baz": null, "qux": 1.5}}"#;
+            let value = serde_json::from_str::<DeserializableValue>(input)
+                .unwrap()
+                .0;
+
+            let mut bar = ValueMap::new();
+            bar.add_value("baz", Value::Empty);
+            bar.add_value("qux", Value::Number(1.5.into()));
+            let mut expected = ValueMap::new();
+            expected.add_value("foo", number_list(&[1, 2, 3]));
+            expected.add_map("bar", bar);
+
+            assert_eq!(value, Value::Map(expected));
+        }
+
+        #[test]
+        fn large_unsigned_integer_as_float() {
+            let value = serde_json::from_str::<DeserializableValue>("18446744073709551615")
+                .unwrap()
+                .0;
+            assert_eq!(value, Value::Number((u64::MAX as f64).into()));
+        }
+    }
+}